@@ -23,6 +23,7 @@ async function main (options = {}) {
     gitSubdir   = env('_GIT_SUBDIR', ''),
     gitRemote   = env('_GIT_REMOTE', 'origin'),
     noFetch     = env('_NO_FETCH',   false),
+    noAudit     = env('_NO_AUDIT',   false),
     outputDir   = env('_OUTPUT', '/output'),
     docker      = env('RUNNING_IN_DOCKER', false), // are we running in a container?
     uid         = env('_BUILD_UID',  process.getuid()),
@@ -205,6 +206,11 @@ async function main (options = {}) {
         log(`Copying ${compiled} to ${optimized}...`)
         run(`cp ${compiled} ${optimized}`)
       }
+      if (noAudit) {
+        warn(`Skipping audit of ${optimized}`)
+      } else {
+        await audit(optimized)
+      }
       chown(optimized, uid, gid)
       // Output checksum to artifacts directory
       log(`Saving checksum for ${optimized} into ${checksum}...`)
@@ -213,8 +219,65 @@ async function main (options = {}) {
     }
   }
 
+  /** Fail the build if the artifact would be rejected by the chain when uploaded:
+    * if it imports host functions that the chain doesn't provide (e.g. the storage
+    * iterator, which isn't available on Secret Network), is missing the exports that
+    * the chain expects, or contains floating point instructions. */
+  async function audit (wasm) {
+    log(`Auditing imports, exports and instructions of ${wasm}...`)
+    const module   = await WebAssembly.compile(readFileSync(wasm))
+    const problems = []
+    for (const { module: from, name, kind } of WebAssembly.Module.imports(module)) {
+      if (from !== 'env' || kind !== 'function' || !allowedImports.has(name)) {
+        problems.push(`unexpected import: ${from}.${name} (${kind})`)
+      }
+    }
+    const exports = WebAssembly.Module.exports(module).map(({ name })=>name)
+    for (const name of requiredExports) {
+      if (!exports.includes(name)) problems.push(`missing export: ${name}`)
+    }
+    if (!exports.some(name=>interfaceVersion.test(name))) {
+      problems.push(`missing export: interface_version_* or cosmwasm_vm_version_*`)
+    }
+    if (context.wasmObjdump) {
+      const disassembly = execSync(`wasm-objdump -d ${wasm}`, { maxBuffer: 1024 * 1024 * 1024 })
+      const floats = new Set(String(disassembly).match(floatInstruction) ?? [])
+      if (floats.size > 0) {
+        problems.push(`floating point instructions: ${[...floats].sort().join(', ')}`)
+      }
+    } else {
+      warn('please install wabt, not checking for floating point instructions')
+    }
+    if (problems.length > 0) {
+      for (const problem of problems) warn(`${wasm}: ${problem}`)
+      throw new Error(`${wasm} failed the audit. Set FADROMA_BUILD_NO_AUDIT=1 to skip it.`)
+    }
+    log(`Audit passed`)
+  }
+
 }
 
+/** Host functions that contracts may import. These are the ones provided by
+  * Secret Network's CosmWasm 1.x runtime plus the ones of CosmWasm 0.10. */
+const allowedImports = new Set([
+  'db_read', 'db_write', 'db_remove',
+  'addr_validate', 'addr_canonicalize', 'addr_humanize',
+  'secp256k1_verify', 'secp256k1_recover_pubkey', 'secp256k1_sign',
+  'ed25519_verify', 'ed25519_batch_verify', 'ed25519_sign',
+  'debug', 'query_chain', 'abort', 'check_gas', 'gas_evaporate',
+  'canonicalize_address', 'humanize_address', 'debug_print',
+])
+
+/** Exports that the chain calls into regardless of the contract's entry points. */
+const requiredExports = [ 'memory', 'allocate', 'deallocate' ]
+
+/** Export that marks which version of the contract interface is implemented. */
+const interfaceVersion = /^(interface_version_|cosmwasm_vm_version_)\d+$/
+
+/** Matches floating point instructions in the output of wasm-objdump,
+  * including conversions between integers and floats (e.g. i32.trunc_f32_s). */
+const floatInstruction = /\bf(32|64)(x\d+)?\.\w+|\b[iv]\d+(x\d+)?\.\w*f(32|64)\w*/g
+
 function chown (path, uid, gid) {
   try {
     run(`chown ${uid} ${path}`)
@@ -1,4 +1,9 @@
-use syn::{Attribute, Meta, NestedMeta, MetaList, Ident, parse_quote};
+use std::mem;
+
+use syn::{
+    Attribute, Meta, NestedMeta, MetaList, Ident,
+    AttributeArgs, Lit, parse_quote
+};
 use proc_macro2::Span;
 
 use crate::err::ErrorSink;
@@ -27,10 +32,33 @@ pub const QUERY_FN: &str = "query";
 /// Name of the associated type that represents the error type in an interface.
 pub const ERROR_TYPE: &str = "Error";
 
+/// Meta arguments of the `#[contract]` attribute.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct ContractArgs {
+    /// The block size to pad all execute and query responses to.
+    pub pad: Option<usize>,
+    /// Whether to reject messages that contain unknown fields.
+    pub deny_unknown_fields: bool,
+    /// Whether to also accept camelCase field and variant names in messages.
+    pub accept_camel_case: bool,
+    /// The maximum size in bytes of incoming messages.
+    pub max_msg_size: Option<usize>,
+    /// The maximum JSON nesting depth of incoming messages.
+    pub max_msg_depth: Option<usize>
+}
+
+/// Meta arguments of the `#[interface]` attribute.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct InterfaceArgs {
+    /// Whether to generate a client for calling contracts that implement the interface.
+    pub client: bool
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum MsgAttr {
     Init { entry: Option<Entry> },
-    Execute,
+    /// Read-only handlers only receive [`Deps`] and thus can't write to storage.
+    Execute { read_only: bool },
     Query,
     Reply,
     ExecuteGuard
@@ -47,6 +75,8 @@ impl MsgAttr {
     pub const ENTRY_META: &str = "entry";
     /// Used as a meta tag in the `#[init(entry_wasm)]` attribute.
     pub const ENTRY_WASM_META: &str = "entry_wasm";
+    /// Used as a meta tag in the `#[execute(read_only)]` attribute.
+    pub const READ_ONLY_META: &str = "read_only";
 
     pub const INIT: &str = "init";
     pub const EXECUTE: &str = "execute";
@@ -79,9 +109,15 @@ impl MsgAttr {
                         Some(Self::Init { entry })
                     },
                     Self::EXECUTE => {
-                        assert_is_path_ident(sink, &meta);
+                        let mut read_only = false;
 
-                        Some(Self::Execute)
+                        if let Meta::List(list) = meta {
+                            read_only = validate_read_only_meta(sink, &list);
+                        } else {
+                            assert_is_path_ident(sink, &meta);
+                        }
+
+                        Some(Self::Execute { read_only })
                     }
                     Self::QUERY => {
                         assert_is_path_ident(sink, &meta);
@@ -114,7 +150,7 @@ impl MsgAttr {
     pub const fn as_str(&self) -> &'static str {
         match self {
             MsgAttr::Init { .. } => Self::INIT,
-            MsgAttr::Execute => Self::EXECUTE,
+            MsgAttr::Execute { .. } => Self::EXECUTE,
             MsgAttr::Query => Self::QUERY,
             MsgAttr::Reply => Self::REPLY,
             MsgAttr::ExecuteGuard => Self::EXECUTE_GUARD
@@ -122,6 +158,133 @@ impl MsgAttr {
     }
 }
 
+impl ContractArgs {
+    /// Used as `#[contract(pad)]` or `#[contract(pad = 512)]`.
+    pub const PAD_META: &str = "pad";
+    /// Used as `#[contract(deny_unknown_fields)]`.
+    pub const DENY_UNKNOWN_FIELDS_META: &str = "deny_unknown_fields";
+    /// Used as `#[contract(accept_camel_case)]`.
+    pub const ACCEPT_CAMEL_CASE_META: &str = "accept_camel_case";
+    /// Used as `#[contract(max_msg_size = 4096)]`.
+    pub const MAX_MSG_SIZE_META: &str = "max_msg_size";
+    /// Used as `#[contract(max_msg_depth = 16)]`.
+    pub const MAX_MSG_DEPTH_META: &str = "max_msg_depth";
+    /// Same as the default Secret Network message padding size.
+    pub const DEFAULT_BLOCK_SIZE: usize = 256;
+
+    pub fn parse(sink: &mut ErrorSink, args: AttributeArgs) -> Self {
+        let mut result = Self::default();
+
+        for arg in args {
+            let duplicate = match &arg {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident(Self::DENY_UNKNOWN_FIELDS_META) =>
+                    mem::replace(&mut result.deny_unknown_fields, true),
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident(Self::ACCEPT_CAMEL_CASE_META) =>
+                    mem::replace(&mut result.accept_camel_case, true),
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident(Self::PAD_META) =>
+                    result.pad.replace(Self::DEFAULT_BLOCK_SIZE).is_some(),
+                NestedMeta::Meta(Meta::NameValue(meta)) if meta.path.is_ident(Self::PAD_META) => {
+                    let Some(size) = parse_positive_int(sink, &meta.lit, "block size") else {
+                        continue;
+                    };
+
+                    result.pad.replace(size).is_some()
+                }
+                NestedMeta::Meta(Meta::NameValue(meta)) if meta.path.is_ident(Self::MAX_MSG_SIZE_META) => {
+                    let Some(size) = parse_positive_int(sink, &meta.lit, "message size") else {
+                        continue;
+                    };
+
+                    result.max_msg_size.replace(size).is_some()
+                }
+                NestedMeta::Meta(Meta::NameValue(meta)) if meta.path.is_ident(Self::MAX_MSG_DEPTH_META) => {
+                    let Some(depth) = parse_positive_int(sink, &meta.lit, "nesting depth") else {
+                        continue;
+                    };
+
+                    result.max_msg_depth.replace(depth).is_some()
+                }
+                _ => {
+                    sink.push_spanned(
+                        &arg,
+                        format!(
+                            "Expecting one of nested meta: \"{:?}\".",
+                            [
+                                Self::PAD_META,
+                                Self::DENY_UNKNOWN_FIELDS_META,
+                                Self::ACCEPT_CAMEL_CASE_META,
+                                Self::MAX_MSG_SIZE_META,
+                                Self::MAX_MSG_DEPTH_META
+                            ]
+                        )
+                    );
+
+                    continue;
+                }
+            };
+
+            if duplicate {
+                sink.push_spanned(&arg, "Duplicate meta.");
+            }
+        }
+
+        result
+    }
+
+    /// Whether any limits were set on incoming messages.
+    #[inline]
+    pub fn has_msg_limits(&self) -> bool {
+        self.max_msg_size.is_some() || self.max_msg_depth.is_some()
+    }
+}
+
+impl InterfaceArgs {
+    /// Used as `#[interface(client)]`.
+    pub const CLIENT_META: &str = "client";
+
+    pub fn parse(sink: &mut ErrorSink, args: AttributeArgs) -> Self {
+        let mut result = Self::default();
+
+        for arg in args {
+            match &arg {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident(Self::CLIENT_META) => {
+                    if mem::replace(&mut result.client, true) {
+                        sink.push_spanned(&arg, "Duplicate meta.");
+                    }
+                }
+                _ => sink.push_spanned(
+                    &arg,
+                    format!("Expecting one of nested meta: \"{:?}\".", [Self::CLIENT_META])
+                )
+            }
+        }
+
+        result
+    }
+}
+
+fn parse_positive_int(sink: &mut ErrorSink, lit: &Lit, name: &str) -> Option<usize> {
+    let Lit::Int(int) = lit else {
+        sink.push_spanned(lit, format!("Expecting an integer {}.", name));
+
+        return None;
+    };
+
+    match int.base10_parse::<usize>() {
+        Ok(value) if value > 0 => Some(value),
+        Ok(_) => {
+            sink.push_spanned(int, format!("The {} must be greater than zero.", name));
+
+            None
+        }
+        Err(err) => {
+            sink.push_err(err);
+
+            None
+        }
+    }
+}
+
 fn validate_entry_meta(sink: &mut ErrorSink, list: &MetaList) -> Option<Entry> {
     if list.nested.len() == 1 {
         let entry = Ident::new(MsgAttr::ENTRY_META, Span::call_site());
@@ -150,6 +313,23 @@ fn validate_entry_meta(sink: &mut ErrorSink, list: &MetaList) -> Option<Entry> {
     None
 }
 
+/// Returns `true` if the only nested meta is [`MsgAttr::READ_ONLY_META`].
+fn validate_read_only_meta(sink: &mut ErrorSink, list: &MetaList) -> bool {
+    let read_only = list.nested.len() == 1 && matches!(
+        &list.nested[0],
+        NestedMeta::Meta(Meta::Path(path)) if path.is_ident(MsgAttr::READ_ONLY_META)
+    );
+
+    if !read_only {
+        sink.push_spanned(
+            list,
+            format!("Expecting one of nested meta: \"{:?}\".", [MsgAttr::READ_ONLY_META])
+        );
+    }
+
+    read_only
+}
+
 #[inline]
 fn assert_is_path_ident(sink: &mut ErrorSink, meta: &Meta) {
     if !matches!(meta, Meta::Path(path) if path.segments.len() == 1) {
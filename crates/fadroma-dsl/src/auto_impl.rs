@@ -55,7 +55,7 @@ impl AutoImpl {
                     let args = fn_args_to_idents(&mut sink, &method.sig.inputs);
 
                     let stmt = match attr {
-                        MsgAttr::Init { .. } | MsgAttr::Execute => 
+                        MsgAttr::Init { .. } | MsgAttr::Execute { .. } => 
                             parse_quote!(<#impl_path as #trait_>::#fn_name(deps, env, info, #args)),
                         MsgAttr::Query =>
                             parse_quote!(<#impl_path as #trait_>::#fn_name(deps, env, #args)),
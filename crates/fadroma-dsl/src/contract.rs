@@ -1,19 +1,20 @@
 use syn::{
-    Item, ItemMod, ItemImpl, Type, TypePath,
+    Item, ItemMod, ItemImpl, ImplItem, Type, TypePath,
     Ident, ItemStruct, ItemEnum, ItemFn,
-    GenericArgument, parse_quote
+    GenericArgument, AttributeArgs, Attribute, parse_quote
 };
 use quote::quote;
 use proc_macro2::Span;
 
 use crate::{
-    attr::{MsgAttr, Entry, CONTRACT},
+    attr::{MsgAttr, ContractArgs, Entry, CONTRACT},
     err::{ErrorSink, CompileErrors},
     generate::{self, MsgType, ErrorEnum},
-    method::{Method, item_impl_methods}
+    deprecated::{self, Deprecations},
+    method::{Method, item_impl_methods, strip_arg_docs}
 };
 
-pub fn derive(mut item_mod: ItemMod) -> Result<proc_macro2::TokenStream, CompileErrors> {
+pub fn derive(args: AttributeArgs, mut item_mod: ItemMod) -> Result<proc_macro2::TokenStream, CompileErrors> {
     let Some((_, items)) = &mut item_mod.content else {
         return Err(vec![
             syn::Error::new_spanned(
@@ -25,8 +26,27 @@ pub fn derive(mut item_mod: ItemMod) -> Result<proc_macro2::TokenStream, Compile
 
     let mut sink = ErrorSink::default();
 
+    let args = ContractArgs::parse(&mut sink, args);
+    let mut deprecations = Deprecations::new();
+
+    for item in items.iter_mut() {
+        if let Item::Impl(item) = item {
+            if is_contract_impl(item) && item.trait_.is_none() {
+                deprecated::expand(&mut sink, item, &mut deprecations);
+            }
+        }
+    }
+
     let contract = Contract::parse(&mut sink, item_mod.ident.span(), items);
-    let g = contract.generate(&mut sink);
+    let g = contract.generate(&mut sink, args, &deprecations);
+
+    for item in items.iter_mut() {
+        if let Item::Impl(item) = item {
+            if is_contract_impl(item) {
+                strip_impl_arg_docs(item);
+            }
+        }
+    }
 
     items.push(Item::Struct(g.boilerplate.contract_struct));
     items.push(Item::Enum(g.boilerplate.error_enum.enum_def));
@@ -36,6 +56,7 @@ pub fn derive(mut item_mod: ItemMod) -> Result<proc_macro2::TokenStream, Compile
     if let Some(i) = g.interfaces {
         items.push(Item::Struct(i.init_msg));
         items.push(Item::Enum(i.execute_msg));
+        items.push(Item::Impl(i.execute_msg_name));
         items.push(Item::Enum(i.query_msg));
     
         items.push(Item::Fn(i.entry.init));
@@ -65,6 +86,7 @@ struct Generated {
 struct Interfaces {
     init_msg: ItemStruct,
     execute_msg: ItemEnum,
+    execute_msg_name: ItemImpl,
     query_msg: ItemEnum,
     entry: Entrypoints
 }
@@ -111,7 +133,12 @@ impl<'a> Contract<'a> {
         }
     }
 
-    fn generate(self, sink: &mut ErrorSink) -> Generated {
+    fn generate(
+        self,
+        sink: &mut ErrorSink,
+        args: ContractArgs,
+        deprecations: &Deprecations
+    ) -> Generated {
         let mut init: Option<Method> = None;
         let mut execute: Vec<Method> = vec![];
         let mut query: Vec<Method> = vec![];
@@ -154,7 +181,7 @@ impl<'a> Contract<'a> {
                             )
                         }
                     }
-                    MsgAttr::Execute => execute.push(method),
+                    MsgAttr::Execute { .. } => execute.push(method),
                     MsgAttr::Query => query.push(method),
                     MsgAttr::Reply => {
                         if reply.is_some() {
@@ -193,7 +220,7 @@ impl<'a> Contract<'a> {
                             has_init = true;
                         }
                     }
-                    MsgAttr::Execute => execute.push(method),
+                    MsgAttr::Execute { .. } => execute.push(method),
                     MsgAttr::Query => query.push(method),
                     unsupported => sink.unsupported_interface_attr(
                         &method.sig().ident,
@@ -212,36 +239,72 @@ impl<'a> Contract<'a> {
                 execute: generate::execute_fn(
                     sink,
                     &execute,
-                    execute_guard
+                    execute_guard,
+                    args.pad
                 ),
                 query: generate::query_fn(
                     sink,
-                    &query
+                    &query,
+                    args.pad
                 ),
                 wasm_ffi: if matches!(
                     init.ty(),
                     MsgAttr::Init { entry } if matches!(entry, Some(Entry::Wasm))
                 ) {
-                    Some(generate::wasm_entry(&reply))
+                    Some(generate::wasm_entry(&reply, args.max_msg_size, args.max_msg_depth))
                 } else {
+                    if args.has_msg_limits() {
+                        sink.push(
+                            Span::call_site(),
+                            format!(
+                                "\"{}\" and \"{}\" only have an effect when the entry point is defined with #[{}({})].",
+                                ContractArgs::MAX_MSG_SIZE_META,
+                                ContractArgs::MAX_MSG_DEPTH_META,
+                                MsgAttr::INIT,
+                                MsgAttr::ENTRY_WASM_META
+                            )
+                        );
+                    }
+
                     None
                 }
             };
     
-            Some(Interfaces {
+            let mut interfaces = Interfaces {
                 init_msg: generate::init_msg(sink, &init),
                 execute_msg: generate::messages(
                     sink,
                     MsgType::Execute,
                     &execute
                 ),
+                execute_msg_name: generate::msg_name_impl(
+                    MsgType::Execute,
+                    &execute,
+                    deprecations
+                ),
                 query_msg: generate::messages(
                     sink,
                     MsgType::Query,
                     &query
                 ),
                 entry
-            })
+            };
+
+            if args.deny_unknown_fields {
+                let attr: Attribute = parse_quote!(#[serde(deny_unknown_fields)]);
+
+                interfaces.init_msg.attrs.push(attr.clone());
+                interfaces.execute_msg.attrs.push(attr.clone());
+                interfaces.query_msg.attrs.push(attr);
+            }
+
+            if args.accept_camel_case {
+                generate::camel_case_field_aliases(&mut interfaces.init_msg.fields);
+                generate::camel_case_variant_aliases(&mut interfaces.execute_msg);
+                generate::camel_case_variant_aliases(&mut interfaces.query_msg);
+            }
+
+            Some(interfaces)
         } else {
             if let Some(guard) = execute_guard {
                 sink.attr_no_effect(guard.sig(), guard.ty());
@@ -251,6 +314,24 @@ impl<'a> Contract<'a> {
                 sink.attr_no_effect(reply.sig(), reply.ty());
             }
 
+            let meta_args = [
+                (args.pad.is_some(), ContractArgs::PAD_META),
+                (args.deny_unknown_fields, ContractArgs::DENY_UNKNOWN_FIELDS_META),
+                (args.accept_camel_case, ContractArgs::ACCEPT_CAMEL_CASE_META),
+                (args.max_msg_size.is_some(), ContractArgs::MAX_MSG_SIZE_META),
+                (args.max_msg_depth.is_some(), ContractArgs::MAX_MSG_DEPTH_META)
+            ];
+
+            for (_, meta) in meta_args.iter().filter(|(is_set, _)| *is_set) {
+                sink.push(
+                    Span::call_site(),
+                    format!(
+                        "\"{}\" has no effect when no entry point is defined. Either remove it or set an entry point for the contract.",
+                        meta
+                    )
+                );
+            }
+
             None
         };
 
@@ -275,6 +356,14 @@ fn create_contract_struct() -> ItemStruct {
     }
 }
 
+fn strip_impl_arg_docs(item: &mut ItemImpl) {
+    for item in &mut item.items {
+        if let ImplItem::Method(method) = item {
+            strip_arg_docs(&mut method.sig);
+        }
+    }
+}
+
 #[inline]
 fn is_contract_impl(item: &ItemImpl) -> bool {
     if let Type::Path(path) = &*item.self_ty {
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use syn::{
+    ItemImpl, ImplItem, ImplItemMethod, Meta, NestedMeta,
+    Lit, LitStr, Ident, Expr, Stmt, Attribute, parse_quote
+};
+
+use crate::{
+    attr::MsgAttr,
+    err::ErrorSink,
+    method::fn_args_to_idents
+};
+
+pub const DEPRECATED_VARIANT: &str = "deprecated_variant";
+
+const SINCE_META: &str = "since";
+const REPLACED_BY_META: &str = "replaced_by";
+
+/// Maps the names of deprecated methods to the names of the methods that replace them.
+pub type Deprecations = HashMap<String, String>;
+
+/// Fills in the body of each `impl Contract` method annotated with
+/// `#[deprecated_variant(since = "...", replaced_by = "...")]` so that it logs
+/// a warning and delegates to its replacement. The attribute is removed
+/// from the method since it is not an actual macro.
+pub fn expand(sink: &mut ErrorSink, item: &mut ItemImpl, deprecations: &mut Deprecations) {
+    for item in &mut item.items {
+        let ImplItem::Method(method) = item else {
+            continue;
+        };
+
+        let Some(index) = method.attrs.iter().position(|x| x.path.is_ident(DEPRECATED_VARIANT)) else {
+            continue;
+        };
+
+        let attr = method.attrs.remove(index);
+
+        let Some((since, replaced_by)) = parse_meta(sink, &attr) else {
+            continue;
+        };
+
+        if let Some(replaced_by) = fill_body(sink, method, &since, &replaced_by) {
+            deprecations.insert(method.sig.ident.to_string(), replaced_by);
+        }
+    }
+}
+
+fn fill_body(
+    sink: &mut ErrorSink,
+    method: &mut ImplItemMethod,
+    since: &LitStr,
+    replaced_by: &LitStr
+) -> Option<String> {
+    let Ok(new_method) = replaced_by.parse::<Ident>() else {
+        sink.push_spanned(replaced_by, "Expecting the name of a method.");
+
+        return None;
+    };
+
+    if !method.block.stmts.is_empty() {
+        sink.push_spanned(
+            &method.sig.ident,
+            format!("Methods marked with #[{}] must have an empty body.", DEPRECATED_VARIANT)
+        );
+
+        return None;
+    }
+
+    let old_method = method.sig.ident.to_string();
+    let args = fn_args_to_idents(sink, &method.sig.inputs);
+
+    let call: Expr = match MsgAttr::parse(sink, &method.attrs) {
+        Some(MsgAttr::Execute { .. }) => parse_quote!(Self::#new_method(deps, env, info, #args)),
+        Some(MsgAttr::Query) => parse_quote!(Self::#new_method(deps, env, #args)),
+        _ => {
+            sink.push_spanned(
+                &method.sig.ident,
+                format!(
+                    "#[{}] can only be used together with #[{}] or #[{}].",
+                    DEPRECATED_VARIANT,
+                    MsgAttr::EXECUTE,
+                    MsgAttr::QUERY
+                )
+            );
+
+            return None;
+        }
+    };
+
+    let warning = format!(
+        "\"{}\" is deprecated since {}. Use \"{}\" instead.",
+        old_method,
+        since.value(),
+        new_method
+    );
+
+    let doc = format!(" Deprecated since {}. Use `{}` instead.", since.value(), new_method);
+    let doc: Attribute = parse_quote!(#[doc = #doc]);
+    method.attrs.push(doc);
+
+    method.block.stmts.push(parse_quote!(deps.api.debug(#warning);));
+    method.block.stmts.push(Stmt::Expr(call));
+
+    Some(new_method.to_string())
+}
+
+fn parse_meta(sink: &mut ErrorSink, attr: &Attribute) -> Option<(LitStr, LitStr)> {
+    let expected = format!(
+        "Expecting #[{}({} = \"...\", {} = \"...\")].",
+        DEPRECATED_VARIANT,
+        SINCE_META,
+        REPLACED_BY_META
+    );
+
+    let list = match attr.parse_meta() {
+        Ok(Meta::List(list)) => list,
+        Ok(meta) => {
+            sink.push_spanned(meta, expected);
+
+            return None;
+        }
+        Err(err) => {
+            sink.push_err(err);
+
+            return None;
+        }
+    };
+
+    let mut since = None;
+    let mut replaced_by = None;
+
+    for nested in &list.nested {
+        match nested {
+            NestedMeta::Meta(Meta::NameValue(meta)) => {
+                let Lit::Str(value) = &meta.lit else {
+                    sink.push_spanned(&meta.lit, "Expecting a string literal.");
+
+                    continue;
+                };
+
+                if meta.path.is_ident(SINCE_META) && since.is_none() {
+                    since = Some(value.clone());
+                } else if meta.path.is_ident(REPLACED_BY_META) && replaced_by.is_none() {
+                    replaced_by = Some(value.clone());
+                } else {
+                    sink.push_spanned(nested, &expected);
+                }
+            }
+            _ => sink.push_spanned(nested, &expected)
+        }
+    }
+
+    match (since, replaced_by) {
+        (Some(since), Some(replaced_by)) => Some((since, replaced_by)),
+        _ => {
+            sink.push_spanned(list, expected);
+
+            None
+        }
+    }
+}
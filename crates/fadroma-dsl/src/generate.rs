@@ -3,7 +3,7 @@ use syn::{
     Visibility, parse_quote, FnArg, punctuated::Punctuated,
     ItemEnum, Variant, ItemFn, Expr, Stmt, ExprField, ExprMatch,
     ItemImpl, GenericArgument, ExprCall, ReturnType, Type, Item,
-    ItemMod, token::{Brace, Comma, Colon, RArrow}
+    ItemMod, Attribute, LitStr, ext::IdentExt, token::{Brace, Comma, Colon, RArrow}
 };
 use proc_macro2::Span;
 
@@ -15,8 +15,9 @@ use crate::{
         ERROR_ENUM, ERROR_TYPE, CONTRACT_ERR_VARIANT,
         BINARY_SERIALIZE_ERR_VARIANT
     },
-    method::{Method, fn_args_to_idents, fn_arg_ident, pat_ident},
-    utils::to_pascal
+    method::{Method, fn_args_to_idents, fn_arg_ident, pat_ident, docs},
+    deprecated::Deprecations,
+    utils::{to_pascal, to_camel}
 };
 
 #[derive(Clone, Copy)]
@@ -25,6 +26,11 @@ pub enum MsgType {
     Query
 }
 
+pub struct Client {
+    pub struct_def: ItemStruct,
+    pub impl_def: ItemImpl
+}
+
 pub struct ErrorEnum {
     pub enum_def: ItemEnum,
     pub display_impl: ItemImpl,
@@ -43,6 +49,7 @@ pub fn init_msg(sink: &mut ErrorSink, init: &Method<'_>) -> ItemStruct {
 
     let fields = extract_fields(sink, init.sig(), parse_quote!(pub));
     result.fields = Fields::Named(fields);
+    result.attrs.extend(init.docs());
 
     return result;
 }
@@ -68,7 +75,7 @@ pub fn messages(
         let fields = extract_fields(sink, sig, Visibility::Inherited);
 
         result.variants.push(Variant {
-            attrs: vec![],
+            attrs: method.docs(),
             ident: Ident::new(&variant_name, Span::call_site()),
             fields: Fields::Named(fields),
             discriminant: None
@@ -78,6 +85,52 @@ pub fn messages(
     result
 }
 
+pub fn msg_name_impl(
+    msg_type: MsgType,
+    methods: &[Method<'_>],
+    deprecations: &Deprecations
+) -> ItemImpl {
+    let enum_name: Ident = msg_type.into();
+
+    let mut match_expr: ExprMatch = parse_quote!(match *self {});
+    let mut read_only = Punctuated::<LitStr, Comma>::new();
+
+    for method in methods {
+        let method_name = method.sig().ident.to_string();
+
+        if method.is_read_only() {
+            read_only.push(LitStr::new(&method_name, Span::call_site()));
+        }
+
+        let variant = Ident::new(&to_pascal(&method_name), Span::call_site());
+
+        // Deprecated variants are handled by their replacement so they must
+        // have the same name in order to i.e. be paused together with it.
+        let name = deprecations.get(&method_name).unwrap_or(&method_name);
+
+        match_expr.arms.push(parse_quote!(Self::#variant { .. } => #name));
+    }
+
+    let mut result: ItemImpl = parse_quote! {
+        impl #enum_name {
+            /// Returns the name of the message variant as it appears in its JSON representation.
+            /// For variants marked with `#[deprecated_variant]`, returns the name of their replacement.
+            pub fn name(&self) -> &'static str {
+                #match_expr
+            }
+        }
+    };
+
+    if let MsgType::Execute = msg_type {
+        result.items.insert(0, parse_quote! {
+            /// The names of the messages whose handlers are marked with `#[execute(read_only)]`.
+            pub const READ_ONLY: &'static [&'static str] = &[#read_only];
+        });
+    }
+
+    result
+}
+
 pub fn init_fn(sink: &mut ErrorSink, method: &Method<'_>) -> ItemFn {
     let fn_name = Ident::new(INIT_FN, Span::call_site());
     let msg = Ident::new(INIT_MSG, Span::call_site());
@@ -132,7 +185,8 @@ pub fn init_fn(sink: &mut ErrorSink, method: &Method<'_>) -> ItemFn {
 pub fn execute_fn(
     sink: &mut ErrorSink,
     methods: &[Method<'_>],
-    execute_guard: Option<Method<'_>>
+    execute_guard: Option<Method<'_>>,
+    pad: Option<usize>
 ) -> ItemFn {
     let fn_name = Ident::new(EXECUTE_FN, Span::call_site());
     let msg = Ident::new(EXECUTE_MSG, Span::call_site());
@@ -172,12 +226,17 @@ pub fn execute_fn(
         }
     }
 
+    if let Some(block_size) = pad {
+        pad_result(&mut result, MsgType::Execute, block_size);
+    }
+
     result
 }
 
 pub fn query_fn(
     sink: &mut ErrorSink,
-    methods: &[Method<'_>]
+    methods: &[Method<'_>],
+    pad: Option<usize>
 ) -> ItemFn {
     let fn_name = Ident::new(QUERY_FN, Span::call_site());
     let msg = Ident::new(QUERY_MSG, Span::call_site());
@@ -206,14 +265,31 @@ pub fn query_fn(
         }
     }
 
+    if let Some(block_size) = pad {
+        pad_result(&mut result, MsgType::Query, block_size);
+    }
+
     result
 }
 
-pub fn wasm_entry(reply: &Option<Method<'_>>) -> ItemMod {
+pub fn wasm_entry(
+    reply: &Option<Method<'_>>,
+    max_msg_size: Option<usize>,
+    max_msg_depth: Option<usize>
+) -> ItemMod {
     let init_fn = Ident::new(INIT_FN, Span::call_site());
     let execute_fn = Ident::new(EXECUTE_FN, Span::call_site());
     let query_fn = Ident::new(QUERY_FN, Span::call_site());
 
+    let has_limits = max_msg_size.is_some() || max_msg_depth.is_some();
+
+    // Rejects messages that exceed the limits before they are deserialized.
+    let check: Option<Stmt> = has_limits.then(|| parse_quote! {
+        if let Some(result) = unsafe { ::fadroma::core::limits::wasm::check(&LIMITS, msg_ptr) } {
+            return result;
+        }
+    });
+
     let mut result: ItemMod = parse_quote! {
         #[cfg(target_arch = "wasm32")]
         mod wasm_entry {
@@ -221,21 +297,38 @@ pub fn wasm_entry(reply: &Option<Method<'_>>) -> ItemMod {
 
             #[no_mangle]
             extern "C" fn instantiate(env_ptr: u32, info_ptr: u32, msg_ptr: u32) -> u32 {
+                #check
                 do_instantiate(&super::#init_fn, env_ptr, info_ptr, msg_ptr)
             }
 
             #[no_mangle]
             extern "C" fn execute(env_ptr: u32, info_ptr: u32, msg_ptr: u32) -> u32 {
+                #check
                 do_execute(&super::#execute_fn, env_ptr, info_ptr, msg_ptr)
             }
 
             #[no_mangle]
             extern "C" fn query(env_ptr: u32, msg_ptr: u32) -> u32 {
+                #check
                 do_query(&super::#query_fn, env_ptr, msg_ptr)
             }
         }
     };
 
+    if has_limits {
+        // Emit the constant rather than its value since it differs between the host and WASM.
+        let unlimited: Expr = parse_quote!(usize::MAX);
+        let max_size: Expr = max_msg_size.map_or(unlimited.clone(), |x| parse_quote!(#x));
+        let max_depth: Expr = max_msg_depth.map_or(unlimited, |x| parse_quote!(#x));
+
+        let limits = parse_quote! {
+            const LIMITS: ::fadroma::core::limits::MsgLimits =
+                ::fadroma::core::limits::MsgLimits::new(#max_size, #max_depth);
+        };
+
+        result.content.as_mut().unwrap().1.push(Item::Const(limits));
+    }
+
     if let Some(reply) = reply {
         let contract = Ident::new(CONTRACT, Span::call_site());
         let reply_fn = &reply.sig().ident;
@@ -253,6 +346,101 @@ pub fn wasm_entry(reply: &Option<Method<'_>>) -> ItemMod {
     result
 }
 
+/// Generates the `I<Interface>` struct which builds the messages of the
+/// interface and sends them to a contract that implements it.
+pub fn client(
+    sink: &mut ErrorSink,
+    trait_ident: &Ident,
+    execute: &[Method<'_>],
+    query: &[Method<'_>]
+) -> Client {
+    let client = Ident::new(&format!("I{}", trait_ident), trait_ident.span());
+    let execute_msg = Ident::new(EXECUTE_MSG, Span::call_site());
+    let query_msg = Ident::new(QUERY_MSG, Span::call_site());
+
+    let doc = format!(" Calls the methods of contracts that implement [`{}`].", trait_ident);
+
+    let struct_def: ItemStruct = parse_quote! {
+        #[doc = #doc]
+        #[derive(Clone, Debug)]
+        pub struct #client {
+            pub address: cosmwasm_std::Addr,
+            pub code_hash: String,
+            funds: Vec<cosmwasm_std::Coin>
+        }
+    };
+
+    let mut impl_def: ItemImpl = parse_quote! {
+        impl #client {
+            #[inline]
+            pub fn new(address: cosmwasm_std::Addr, code_hash: String) -> Self {
+                Self {
+                    address,
+                    code_hash,
+                    funds: vec![]
+                }
+            }
+
+            /// Funds to send along with the next execute message.
+            #[inline]
+            pub fn add_funds(mut self, coin: cosmwasm_std::Coin) -> Self {
+                self.funds.push(coin);
+
+                self
+            }
+        }
+    };
+
+    for method in execute {
+        let sig = method.sig();
+        let method_name = &sig.ident;
+        let variant = Ident::new(&to_pascal(&method_name.to_string()), Span::call_site());
+        let fields = extract_fields(sink, sig, Visibility::Inherited);
+        let args = fields_to_idents(&fields);
+        let params = fields_to_params(fields);
+
+        impl_def.items.push(parse_quote! {
+            pub fn #method_name(self, #params) -> cosmwasm_std::StdResult<cosmwasm_std::CosmosMsg> {
+                Ok(cosmwasm_std::CosmosMsg::Wasm(cosmwasm_std::WasmMsg::Execute {
+                    contract_addr: self.address.into_string(),
+                    code_hash: self.code_hash,
+                    msg: cosmwasm_std::to_binary(&#execute_msg::#variant { #args })?,
+                    funds: self.funds
+                }))
+            }
+        });
+    }
+
+    for method in query {
+        let sig = method.sig();
+        let method_name = &sig.ident;
+        let variant = Ident::new(&to_pascal(&method_name.to_string()), Span::call_site());
+        let fields = extract_fields(sink, sig, Visibility::Inherited);
+        let args = fields_to_idents(&fields);
+        let params = fields_to_params(fields);
+        let result = method.return_ty().value;
+
+        impl_def.items.push(parse_quote! {
+            pub fn #method_name(
+                self,
+                querier: cosmwasm_std::QuerierWrapper,
+                #params
+            ) -> cosmwasm_std::StdResult<#result> {
+                querier.query_wasm_smart(
+                    self.code_hash,
+                    self.address,
+                    &#query_msg::#variant { #args }
+                )
+            }
+        });
+    }
+
+    Client {
+        struct_def,
+        impl_def
+    }
+}
+
 pub fn error_enum(
     sink: &mut ErrorSink,
     contract: Option<GenericArgument>,
@@ -334,7 +522,12 @@ pub fn error_enum(
 
 pub fn cw_arguments(sig: &mut Signature, attr: MsgAttr, has_block: bool) {
     match attr {
-        MsgAttr::Init { .. } | MsgAttr::Execute => {
+        MsgAttr::Execute { read_only: true } => {
+            sig.inputs.insert(0, parse_quote!(deps: cosmwasm_std::Deps));
+            sig.inputs.insert(1, parse_quote!(env: cosmwasm_std::Env));
+            sig.inputs.insert(2, parse_quote!(info: cosmwasm_std::MessageInfo));
+        },
+        MsgAttr::Init { .. } | MsgAttr::Execute { read_only: false } => {
             if has_block {
                 sig.inputs.insert(0, parse_quote!(mut deps: cosmwasm_std::DepsMut));
             } else {
@@ -360,6 +553,38 @@ pub fn cw_arguments(sig: &mut Signature, attr: MsgAttr, has_block: bool) {
     }
 }
 
+/// Wraps the expression returned by the generated `execute` or `query` function so that
+/// the response data is padded with spaces to a multiple of `block_size`. Trailing
+/// whitespace is valid JSON so clients can deserialize the data as usual. Data that
+/// isn't JSON, i.e. binary data set by an execute handler, is left as it is since
+/// padding would corrupt it.
+fn pad_result(item: &mut ItemFn, msg_type: MsgType, block_size: usize) {
+    let Some(Stmt::Expr(expr)) = item.block.stmts.pop() else {
+        return;
+    };
+
+    let wrapped: Expr = match msg_type {
+        MsgType::Execute => parse_quote! {
+            (#expr).map(|mut response| {
+                if let Some(data) = response.data.as_mut() {
+                    ::fadroma::scrt::space_pad_json(&mut data.0, #block_size);
+                }
+
+                response
+            })
+        },
+        MsgType::Query => parse_quote! {
+            (#expr).map(|mut data| {
+                ::fadroma::scrt::space_pad_json(&mut data.0, #block_size);
+
+                data
+            })
+        }
+    };
+
+    item.block.stmts.push(Stmt::Expr(wrapped));
+}
+
 fn create_match_expr(
     sink: &mut ErrorSink,
     methods: &[Method<'_>],
@@ -394,9 +619,15 @@ fn create_match_expr(
 
         match msg_type {
             MsgType::Execute => {
+                let deps: Expr = if method.is_read_only() {
+                    parse_quote!(deps.as_ref())
+                } else {
+                    parse_quote!(deps)
+                };
+
                 match_expr.arms.push(
                     parse_quote!(#enum_name::#variant { #args } =>
-                        #contract_ident::#method_name(deps, env, info, #args).#map_err
+                        #contract_ident::#method_name(#deps, env, info, #args).#map_err
                     )
                 );
             }
@@ -415,6 +646,64 @@ fn create_match_expr(
     Some(Expr::Match(match_expr))
 }
 
+/// Makes the given fields also accept their camelCase
+/// name on input while still emitting snake_case.
+pub fn camel_case_field_aliases(fields: &mut Fields) {
+    for field in fields.iter_mut() {
+        if let Some(ident) = &field.ident {
+            let name = ident.unraw().to_string();
+            let camel = to_camel(&name);
+
+            if camel != name {
+                push_alias(&mut field.attrs, &camel);
+            }
+        }
+    }
+}
+
+/// Same as [`camel_case_field_aliases`] but for all variants of
+/// a message enum, including the names of the variants themselves.
+pub fn camel_case_variant_aliases(msg: &mut ItemEnum) {
+    for variant in &mut msg.variants {
+        let pascal = variant.ident.to_string();
+        let mut chars = pascal.chars();
+
+        let camel = chars.next()
+            .map(|x| x.to_lowercase().chain(chars).collect::<String>())
+            .unwrap_or_default();
+
+        // Variants are renamed to snake_case so their camelCase
+        // form is only different if they have more than one word.
+        if camel.chars().any(|x| x.is_uppercase()) {
+            push_alias(&mut variant.attrs, &camel);
+        }
+
+        camel_case_field_aliases(&mut variant.fields);
+    }
+}
+
+#[inline]
+fn push_alias(attrs: &mut Vec<Attribute>, alias: &str) {
+    attrs.push(parse_quote!(#[serde(alias = #alias)]));
+}
+
+#[inline]
+fn fields_to_idents(fields: &FieldsNamed) -> Punctuated<Ident, Comma> {
+    fields.named
+        .iter()
+        .filter_map(|x| x.ident.clone())
+        .collect()
+}
+
+/// Doc comments aren't allowed on function arguments.
+#[inline]
+fn fields_to_params(fields: FieldsNamed) -> Punctuated<Field, Comma> {
+    let mut params = fields.named;
+    params.iter_mut().for_each(|x| x.attrs.clear());
+
+    params
+}
+
 fn extract_fields(
     sink: &mut ErrorSink,
     sig: &Signature,
@@ -431,7 +720,7 @@ fn extract_fields(
                 let ident = pat_ident(sink, *pat_type.pat.to_owned());
 
                 fields.named.push(Field {
-                    attrs: vec![],
+                    attrs: docs(&pat_type.attrs),
                     vis: vis.clone(),
                     ident,
                     ty: *pat_type.ty.to_owned(),
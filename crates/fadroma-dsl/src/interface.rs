@@ -1,13 +1,14 @@
 use syn::{
     ItemTrait, TraitItem, PathArguments, TypeParamBound,
-    TraitBoundModifier, punctuated::Punctuated, token::Add,
+    TraitBoundModifier, AttributeArgs, punctuated::Punctuated, token::Add,
 };
 use quote::{ToTokens, quote};
 
 use crate::{
-    attr::{MsgAttr, ERROR_TYPE},
+    attr::{MsgAttr, InterfaceArgs, ERROR_TYPE},
     err::{ErrorSink, CompileErrors},
     generate::{self, MsgType},
+    deprecated::Deprecations,
     method::{Method, trait_methods},
     validate
 };
@@ -18,8 +19,9 @@ pub const SUPPORTED_ATTRS: [&'static str; 3] = [
     MsgAttr::INIT
 ];
 
-pub fn derive(r#trait: ItemTrait) -> Result<proc_macro2::TokenStream, CompileErrors> {
+pub fn derive(args: AttributeArgs, r#trait: ItemTrait) -> Result<proc_macro2::TokenStream, CompileErrors> {
     let mut sink = ErrorSink::default();
+    let args = InterfaceArgs::parse(&mut sink, args);
     let interface = Interface::parse(&mut sink, &r#trait);
 
     let init_msg = interface.init.and_then(|x|
@@ -33,18 +35,41 @@ pub fn derive(r#trait: ItemTrait) -> Result<proc_macro2::TokenStream, CompileErr
         MsgType::Execute,
         &interface.execute
     );
+    let execute_msg_name = generate::msg_name_impl(
+        MsgType::Execute,
+        &interface.execute,
+        &Deprecations::new()
+    );
     let query_msg = generate::messages(
         &mut sink,
         MsgType::Query,
         &interface.query
     );
 
+    let client = if args.client {
+        let client = generate::client(
+            &mut sink,
+            &r#trait.ident,
+            &interface.execute,
+            &interface.query
+        );
+
+        let struct_def = client.struct_def;
+        let impl_def = client.impl_def;
+
+        quote!(#struct_def #impl_def)
+    } else {
+        proc_macro2::TokenStream::new()
+    };
+
     sink.check()?;
 
     Ok(quote! {
         #init_msg
         #execute_msg
+        #execute_msg_name
         #query_msg
+        #client
     })
 }
 
@@ -119,7 +144,7 @@ impl<'a> Interface<'a> {
 
                     init = Some(Method::Interface(method));
                 }
-                MsgAttr::Execute => execute.push(Method::Interface(method)),
+                MsgAttr::Execute { .. } => execute.push(Method::Interface(method)),
                 MsgAttr::Query => query.push(Method::Interface(method)),
                 unsupported => sink.unsupported_interface_attr(
                     &method.sig.ident,
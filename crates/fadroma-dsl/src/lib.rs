@@ -10,11 +10,12 @@ mod method;
 mod auto_impl;
 mod execute_guard;
 mod reply;
+mod deprecated;
 mod utils;
 
 use syn::{
-    AttributeArgs, Item, ItemTrait, TraitItemMethod, ItemFn,
-    ItemImpl, ItemMod, parse_macro_input, parse_quote
+    AttributeArgs, Item, ItemTrait, TraitItem, TraitItemMethod, ItemFn,
+    ItemImpl, ItemMod, NestedMeta, Meta, parse_macro_input, parse_quote
 };
 use proc_macro2::Span;
 use quote::quote;
@@ -24,17 +25,25 @@ use attr::MsgAttr;
 
 #[proc_macro_attribute]
 pub fn interface(
-    _args: proc_macro::TokenStream,
+    args: proc_macro::TokenStream,
     trait_: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
-    let item = parse_macro_input!(trait_ as ItemTrait);
-    let item_trait = quote!(#item);
+    let args = parse_macro_input!(args as AttributeArgs);
+    let mut item = parse_macro_input!(trait_ as ItemTrait);
 
-    let boilerplate = match interface::derive(item) {
+    let boilerplate = match interface::derive(args, item.clone()) {
         Ok(stream) => stream,
         Err(errors) => to_compile_errors(errors)
     };
 
+    for item in &mut item.items {
+        if let TraitItem::Method(method) = item {
+            method::strip_arg_docs(&mut method.sig);
+        }
+    }
+
+    let item_trait = quote!(#item);
+
     let result = quote! {
         #item_trait
         #boilerplate
@@ -45,12 +54,13 @@ pub fn interface(
 
 #[proc_macro_attribute]
 pub fn contract(
-    _args: proc_macro::TokenStream,
+    args: proc_macro::TokenStream,
     item: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
+    let args = parse_macro_input!(args as AttributeArgs);
     let item = parse_macro_input!(item as ItemMod);
 
-    let boilerplate = match contract::derive(item) {
+    let boilerplate = match contract::derive(args, item) {
         Ok(stream) => stream,
         Err(errors) => to_compile_errors(errors)
     };
@@ -92,11 +102,26 @@ pub fn init(
 
 #[proc_macro_attribute]
 pub fn execute(
-    _args: proc_macro::TokenStream,
+    args: proc_macro::TokenStream,
     item: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
+    let args = parse_macro_input!(args as AttributeArgs);
     let item = parse_macro_input!(item as Item);
-    let result = add_fn_args(item, MsgAttr::Execute);
+
+    let read_only = match args.as_slice() {
+        [] => false,
+        [NestedMeta::Meta(Meta::Path(path))] if path.is_ident(MsgAttr::READ_ONLY_META) => true,
+        _ => {
+            let err = syn::Error::new(
+                Span::call_site(),
+                format!("Expecting one of nested meta: \"{:?}\".", [MsgAttr::READ_ONLY_META])
+            );
+
+            return proc_macro::TokenStream::from(err.to_compile_error());
+        }
+    };
+
+    let result = add_fn_args(item, MsgAttr::Execute { read_only });
 
     proc_macro::TokenStream::from(result)
 }
@@ -2,7 +2,7 @@ use std::slice;
 
 use syn::{
     Signature, Path, Ident, FnArg, Pat, GenericArgument, ItemTrait,
-    TraitItem, ItemImpl, ImplItem, punctuated::Punctuated,
+    TraitItem, ItemImpl, ImplItem, Attribute, punctuated::Punctuated,
     token::Comma, parse_quote
 };
 use proc_macro2::Span;
@@ -21,12 +21,14 @@ pub enum Method<'a> {
 pub struct ContractMethod<'a> {
     ty: MsgAttr,
     sig: &'a Signature,
+    attrs: &'a [Attribute],
     return_ty: ResultType<'a>
 }
 
 pub struct InterfaceMethod<'a> {
     pub ty: MsgAttr,
     pub sig: &'a Signature,
+    pub attrs: &'a [Attribute],
     pub return_ty: ResultType<'a>,
     trait_: Path
 }
@@ -66,6 +68,7 @@ pub fn trait_methods<'a>(
             methods.push(InterfaceMethod {
                 ty,
                 sig: &method.sig,
+                attrs: &method.attrs,
                 trait_,
                 return_ty
             });
@@ -107,6 +110,7 @@ pub fn item_impl_methods<'a>(
                 methods.push(Method::Interface(InterfaceMethod {
                     ty,
                     sig: &method.sig,
+                    attrs: &method.attrs,
                     trait_: trait_.clone(),
                     return_ty
                 }));
@@ -124,6 +128,7 @@ pub fn item_impl_methods<'a>(
                 methods.push(Method::Contract(ContractMethod {
                     ty,
                     sig: &method.sig,
+                    attrs: &method.attrs,
                     return_ty
                 }));
             }
@@ -203,6 +208,25 @@ fn interface_method_return_ty<'a>(
     validate::result_type(sink, sig, (expected, err_args))
 }
 
+/// Returns only the `#[doc]` attributes i.e. doc comments.
+#[inline]
+pub fn docs(attrs: &[Attribute]) -> Vec<Attribute> {
+    attrs.iter()
+        .filter(|x| x.path.is_ident("doc"))
+        .cloned()
+        .collect()
+}
+
+/// Removes the doc comments from the arguments of the method since Rust doesn't allow
+/// them there. They are only used as the docs of the fields of the generated messages.
+pub fn strip_arg_docs(sig: &mut Signature) {
+    for input in &mut sig.inputs {
+        if let FnArg::Typed(pat_type) = input {
+            pat_type.attrs.retain(|x| !x.path.is_ident("doc"));
+        }
+    }
+}
+
 #[inline]
 fn contract_method_return_ty<'a>(
     sink: &mut ErrorSink,
@@ -222,7 +246,7 @@ fn contract_method_return_ty<'a>(
 #[inline]
 fn expected_value_type(ty: MsgAttr) -> Option<GenericArgument> {
     match ty {
-        MsgAttr::Init { .. } | MsgAttr::Execute | MsgAttr::Reply =>
+        MsgAttr::Init { .. } | MsgAttr::Execute { .. } | MsgAttr::Reply =>
             Some(parse_quote!(Response)),
         MsgAttr::Query => None,
         MsgAttr::ExecuteGuard => Some(parse_quote!(()))
@@ -246,6 +270,11 @@ impl<'a> Method<'a> {
         }
     }
 
+    #[inline]
+    pub fn is_read_only(&self) -> bool {
+        matches!(self.ty(), MsgAttr::Execute { read_only: true })
+    }
+
     #[inline]
     pub fn return_ty(&self) -> &ResultType<'_> {
         match self {
@@ -253,6 +282,17 @@ impl<'a> Method<'a> {
             Method::Interface(x) => &x.return_ty
         }
     }
+
+    /// The doc comments of the method.
+    #[inline]
+    pub fn docs(&self) -> Vec<Attribute> {
+        let attrs = match self {
+            Method::Contract(x) => x.attrs,
+            Method::Interface(x) => x.attrs
+        };
+
+        docs(attrs)
+    }
 }
 
 impl<'a> InterfaceMethod<'a> {
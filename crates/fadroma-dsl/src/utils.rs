@@ -22,6 +22,24 @@ pub fn to_pascal(s: &str) -> String {
     result
 }
 
+/// Converts a snake_case identifier to camelCase the same
+/// way that `#[serde(rename_all = "camelCase")]` would,
+/// except that leading underscores are preserved.
+pub fn to_camel(s: &str) -> String {
+    let trimmed = s.trim_start_matches('_');
+    let mut result = String::from(&s[..s.len() - trimmed.len()]);
+    let pascal = to_pascal(trimmed);
+    let mut chars = pascal.chars();
+
+    if let Some(first) = chars.next() {
+        result.extend(first.to_lowercase());
+    }
+
+    result.extend(chars);
+
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -32,4 +50,12 @@ mod tests {
         assert_eq!(to_pascal(&"__to_pascal"), String::from("ToPascal"));
         assert_eq!(to_pascal(&"Very_Long_string"), String::from("VeryLongString"));
     }
+
+    #[test]
+    fn test_to_camel() {
+        assert_eq!(to_camel(&"to_camel"), String::from("toCamel"));
+        assert_eq!(to_camel(&"__to_camel"), String::from("__toCamel"));
+        assert_eq!(to_camel(&"Very_Long_string"), String::from("veryLongString"));
+        assert_eq!(to_camel(&"amount"), String::from("amount"));
+    }
 }
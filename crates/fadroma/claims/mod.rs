@@ -0,0 +1,392 @@
+//! Delayed claims, i.e. for unbonding or withdrawal queues, with an optional deposit
+//! that discourages spamming them. You **MUST** implement [admin] in your contract if
+//! you want to use this module.
+//!
+//! The contract calls [`create`] from its own handler, i.e. when a user unbonds, which
+//! takes the configured deposit from the funds sent along. Once the delay has passed,
+//! the owner can [`complete`] the claim and gets the deposit back. A claim that isn't
+//! completed within the grace period after it matures is considered abandoned: anyone
+//! can remove it using [`slash_abandoned`] and the deposit is retained by the contract.
+//! The same happens if the owner cancels a claim using [`cancel`].
+//!
+//! Each of those returns a [`SettledClaim`] and it's up to the contract to act on it,
+//! i.e. transfer the claimed amount or restake it when the claim was abandoned.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    self as fadroma,
+    admin::{self, Admin, Mode},
+    dsl::*,
+    prelude::*
+};
+
+crate::namespace!(pub ClaimsConfigNs, b"Vb2nX7kQsR");
+pub const CONFIG: SingleItem<ClaimsConfig, ClaimsConfigNs> = SingleItem::new();
+
+crate::namespace!(pub ClaimsNs, b"e4LmH9tWcP");
+const CLAIMS: ItemSpace<Claim, ClaimsNs, TypedKey<u64>> = ItemSpace::new();
+
+crate::namespace!(pub PendingClaimsNs, b"J1yDq6sNfZ");
+const PENDING: ItemSpace<Vec<u64>, PendingClaimsNs, TypedKey<CanonicalAddr>> = ItemSpace::new();
+
+crate::namespace!(pub ClaimCountNs, b"u5GkR8wTbA");
+const COUNT: SingleItem<u64, ClaimCountNs> = SingleItem::new();
+
+/// Requires the admin component in order to check for admin.
+#[interface]
+pub trait Claims: Admin {
+    type Error: fmt::Display;
+
+    #[execute]
+    fn set_claims_config(config: ClaimsConfig) -> Result<Response, <Self as Claims>::Error>;
+
+    #[query]
+    fn claims_config() -> Result<ClaimsConfig, <Self as Claims>::Error>;
+
+    #[query]
+    fn pending_claims(account: String) -> Result<Vec<ClaimInfo>, <Self as Claims>::Error>;
+}
+
+#[derive(
+    Serialize, Deserialize, FadromaSerialize, FadromaDeserialize,
+    JsonSchema, PartialEq, Debug, Clone
+)]
+pub struct ClaimsConfig {
+    /// The number of seconds after which a claim can be completed.
+    pub delay: u64,
+    /// The number of seconds after a claim matures after which it is considered
+    /// abandoned if it hasn't been completed. Claims are never abandoned if [`None`].
+    pub grace_period: Option<u64>,
+    /// The deposit required in order to create a claim, if any.
+    pub deposit: Option<Coin>,
+    /// The maximum number of pending claims per account.
+    pub max_pending: u32
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct ClaimInfo {
+    pub id: u64,
+    pub amount: Uint128,
+    /// The block time in seconds at which the claim can be completed.
+    pub matures_at: u64,
+    /// The block time in seconds at which the claim is considered abandoned.
+    pub expires_at: Option<u64>,
+    pub deposit: Option<Coin>
+}
+
+/// A claim that has been removed from the queue.
+#[derive(PartialEq, Debug, Clone)]
+pub struct SettledClaim {
+    pub owner: Addr,
+    pub amount: Uint128,
+    /// The deposit that must be returned to the owner.
+    pub refund: Option<Coin>,
+    /// The deposit that was retained by the contract.
+    pub slashed: Option<Coin>
+}
+
+#[derive(FadromaSerialize, FadromaDeserialize, PartialEq, Debug, Clone)]
+struct Claim {
+    owner: CanonicalAddr,
+    amount: Uint128,
+    matures_at: u64,
+    expires_at: Option<u64>,
+    deposit: Option<Coin>
+}
+
+pub struct DefaultImpl;
+
+impl Admin for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn change_admin(mode: Option<Mode>) -> Result<Response, Self::Error> {
+        admin::DefaultImpl::change_admin(deps, env, info, mode)
+    }
+
+    #[query]
+    fn admin() -> Result<Option<Addr>, Self::Error> {
+        admin::DefaultImpl::admin(deps, env)
+    }
+}
+
+impl Claims for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn set_claims_config(config: ClaimsConfig) -> StdResult<Response> {
+        set_config(deps, info, config)?;
+
+        Ok(Response::new().add_attribute("action", "set_claims_config"))
+    }
+
+    #[query]
+    fn claims_config() -> StdResult<ClaimsConfig> {
+        load_config(deps.storage)
+    }
+
+    #[query]
+    fn pending_claims(account: String) -> StdResult<Vec<ClaimInfo>> {
+        let account = account.as_str().canonize(deps.api)?;
+        let ids = PENDING.load_or_default(deps.storage, &account)?;
+
+        ids.into_iter().map(|id| {
+            let claim = CLAIMS.load_or_error(deps.storage, &id)?;
+
+            Ok(ClaimInfo {
+                id,
+                amount: claim.amount,
+                matures_at: claim.matures_at,
+                expires_at: claim.expires_at,
+                deposit: claim.deposit
+            })
+        }).collect()
+    }
+}
+
+/// Sets the configuration that applies to all claims created after.
+/// Requires the admin component in order to check for admin.
+#[admin::require_admin]
+pub fn set_config(deps: DepsMut, info: MessageInfo, config: ClaimsConfig) -> StdResult<()> {
+    if config.max_pending == 0 {
+        return Err(StdError::generic_err("At least one pending claim must be allowed."));
+    }
+
+    if config.deposit.as_ref().is_some_and(|x| x.amount.is_zero()) {
+        return Err(StdError::generic_err("The deposit must be greater than zero."));
+    }
+
+    CONFIG.save(deps.storage, &config)
+}
+
+/// Returns `Err` if the configuration wasn't set.
+#[inline]
+pub fn load_config(storage: &dyn Storage) -> StdResult<ClaimsConfig> {
+    CONFIG.load(storage)?.ok_or_else(||
+        StdError::generic_err("Claims have not been configured.")
+    )
+}
+
+/// Creates a claim of `amount` for the sender, taking the deposit from the funds sent
+/// along if one is required. Any other funds are ignored. Returns the ID of the claim.
+pub fn create(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    amount: Uint128
+) -> StdResult<u64> {
+    let config = load_config(deps.storage)?;
+
+    if let Some(deposit) = &config.deposit {
+        let sent = info.funds.iter()
+            .find(|x| x.denom == deposit.denom)
+            .map(|x| x.amount)
+            .unwrap_or_default();
+
+        if sent != deposit.amount {
+            return Err(StdError::generic_err(format!(
+                "A deposit of exactly {} is required.",
+                deposit
+            )));
+        }
+    }
+
+    let owner = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let mut pending = PENDING.load_or_default(deps.storage, &owner)?;
+
+    if pending.len() >= config.max_pending as usize {
+        return Err(StdError::generic_err(format!(
+            "Cannot have more than {} pending claims.",
+            config.max_pending
+        )));
+    }
+
+    let id = COUNT.load_or_default(deps.storage)?;
+    COUNT.save(deps.storage, &(id + 1))?;
+
+    let matures_at = env.block.time.seconds().saturating_add(config.delay);
+
+    CLAIMS.save(deps.storage, &id, &Claim {
+        owner: owner.clone(),
+        amount,
+        matures_at,
+        expires_at: config.grace_period.map(|x| matures_at.saturating_add(x)),
+        deposit: config.deposit
+    })?;
+
+    pending.push(id);
+    PENDING.save(deps.storage, &owner, &pending)?;
+
+    Ok(id)
+}
+
+/// Completes the claim of `owner` once it has matured. The deposit is
+/// refunded unless the claim was completed after it had been abandoned.
+pub fn complete(deps: DepsMut, env: &Env, owner: &Addr, id: u64) -> StdResult<SettledClaim> {
+    let claim = load_owned(deps.as_ref(), owner, id)?;
+    let now = env.block.time.seconds();
+
+    if now < claim.matures_at {
+        return Err(StdError::generic_err(format!(
+            "The claim can only be completed after {}.",
+            claim.matures_at
+        )));
+    }
+
+    let abandoned = claim.expires_at.is_some_and(|x| now >= x);
+
+    settle(deps, id, claim, abandoned)
+}
+
+/// Cancels the claim of `owner`. The deposit is retained by the contract.
+pub fn cancel(deps: DepsMut, owner: &Addr, id: u64) -> StdResult<SettledClaim> {
+    let claim = load_owned(deps.as_ref(), owner, id)?;
+
+    settle(deps, id, claim, true)
+}
+
+/// Removes a claim that has been abandoned. Can be called by anyone.
+/// The deposit is retained by the contract.
+pub fn slash_abandoned(deps: DepsMut, env: &Env, id: u64) -> StdResult<SettledClaim> {
+    let claim = CLAIMS.load(deps.storage, &id)?
+        .ok_or_else(|| StdError::generic_err("Claim not found."))?;
+
+    let abandoned = claim.expires_at.is_some_and(|x| env.block.time.seconds() >= x);
+
+    if !abandoned {
+        return Err(StdError::generic_err("The claim has not been abandoned."));
+    }
+
+    settle(deps, id, claim, true)
+}
+
+impl SettledClaim {
+    /// The message that returns the deposit to the owner, if any.
+    #[inline]
+    pub fn refund_msg(&self) -> Option<BankMsg> {
+        self.refund.as_ref().map(|x| BankMsg::Send {
+            to_address: self.owner.to_string(),
+            amount: vec![x.clone()]
+        })
+    }
+}
+
+fn load_owned(deps: Deps, owner: &Addr, id: u64) -> StdResult<Claim> {
+    let claim = CLAIMS.load(deps.storage, &id)?
+        .ok_or_else(|| StdError::generic_err("Claim not found."))?;
+
+    if claim.owner != deps.api.addr_canonicalize(owner.as_str())? {
+        return Err(StdError::generic_err("Unauthorized"));
+    }
+
+    Ok(claim)
+}
+
+fn settle(deps: DepsMut, id: u64, claim: Claim, slash: bool) -> StdResult<SettledClaim> {
+    CLAIMS.remove(deps.storage, &id);
+
+    let mut pending = PENDING.load_or_default(deps.storage, &claim.owner)?;
+    pending.retain(|x| *x != id);
+
+    if pending.is_empty() {
+        PENDING.remove(deps.storage, &claim.owner);
+    } else {
+        PENDING.save(deps.storage, &claim.owner, &pending)?;
+    }
+
+    let (refund, slashed) = if slash {
+        (None, claim.deposit)
+    } else {
+        (claim.deposit, None)
+    };
+
+    Ok(SettledClaim {
+        owner: claim.owner.humanize(deps.api)?,
+        amount: claim.amount,
+        refund,
+        slashed
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cosmwasm_std::{coin, testing::{mock_dependencies, mock_env, mock_info}};
+
+    #[test]
+    fn deposits_are_refunded_or_slashed() {
+        let ref mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let alice = Addr::unchecked("alice");
+
+        admin::init(deps.as_mut(), None, &mock_info("admin", &[])).unwrap();
+
+        let config = ClaimsConfig {
+            delay: 100,
+            grace_period: Some(50),
+            deposit: Some(coin(10, "uscrt")),
+            max_pending: 2
+        };
+
+        let set_config = |deps: DepsMut, sender: &str, config: ClaimsConfig|
+            DefaultImpl::set_claims_config(deps, mock_env(), mock_info(sender, &[]), config);
+
+        assert!(set_config(deps.as_mut(), "alice", config.clone()).is_err());
+        assert!(create(deps.as_mut(), &env, &mock_info("alice", &[]), Uint128::new(1)).is_err());
+
+        set_config(deps.as_mut(), "admin", config).unwrap();
+
+        let info = mock_info("alice", &[coin(10, "uscrt")]);
+        let unbond = |deps: DepsMut, env: &Env, info: &MessageInfo| create(deps, env, info, Uint128::new(100));
+
+        assert_eq!(
+            unbond(deps.as_mut(), &env, &mock_info("alice", &[coin(9, "uscrt")])).unwrap_err(),
+            StdError::generic_err("A deposit of exactly 10uscrt is required.")
+        );
+
+        let first = unbond(deps.as_mut(), &env, &info).unwrap();
+        let second = unbond(deps.as_mut(), &env, &info).unwrap();
+        assert!(unbond(deps.as_mut(), &env, &info).is_err());
+
+        let pending = DefaultImpl::pending_claims(deps.as_ref(), env.clone(), "alice".into()).unwrap();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].matures_at, env.block.time.seconds() + 100);
+        assert_eq!(pending[0].expires_at, Some(env.block.time.seconds() + 150));
+
+        assert!(complete(deps.as_mut(), &env, &alice, first).is_err());
+        assert!(slash_abandoned(deps.as_mut(), &env, first).is_err());
+        assert_eq!(
+            cancel(deps.as_mut(), &Addr::unchecked("bob"), first).unwrap_err(),
+            StdError::generic_err("Unauthorized")
+        );
+
+        env.block.time = env.block.time.plus_seconds(100);
+
+        let settled = complete(deps.as_mut(), &env, &alice, first).unwrap();
+        assert_eq!(settled.amount, Uint128::new(100));
+        assert_eq!(settled.slashed, None);
+        assert_eq!(settled.refund_msg(), Some(BankMsg::Send {
+            to_address: "alice".into(),
+            amount: vec![coin(10, "uscrt")]
+        }));
+        assert!(complete(deps.as_mut(), &env, &alice, first).is_err());
+
+        let third = unbond(deps.as_mut(), &env, &info).unwrap();
+
+        // The second claim was abandoned.
+        env.block.time = env.block.time.plus_seconds(50);
+
+        let settled = slash_abandoned(deps.as_mut(), &env, second).unwrap();
+        assert_eq!(settled.owner, alice);
+        assert_eq!(settled.refund_msg(), None);
+        assert_eq!(settled.slashed, Some(coin(10, "uscrt")));
+
+        let settled = cancel(deps.as_mut(), &alice, third).unwrap();
+        assert_eq!(settled.slashed, Some(coin(10, "uscrt")));
+
+        assert_eq!(DefaultImpl::pending_claims(deps.as_ref(), env, "alice".into()).unwrap(), vec![]);
+    }
+}
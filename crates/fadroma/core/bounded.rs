@@ -0,0 +1,156 @@
+use std::fmt;
+
+use crate::cosmwasm_std::StdError;
+
+/// Enforces an explicit upper bound on the number of iterations of a loop
+/// so that a handler can never run out of gas by iterating over a collection
+/// that users can grow without limit.
+///
+/// # Examples
+///
+/// ```
+/// # use fadroma::prelude::*;
+/// # fn main() -> StdResult<()> {
+/// let mut guard = BoundedLoop::new(2);
+///
+/// guard.step()?;
+/// guard.step()?;
+/// assert_eq!(guard.step(), Err(LoopLimitExceeded { limit: 2 }));
+///
+/// let sum = (1..=3u32).bounded(3).sum::<Result<u32, _>>()?;
+/// assert_eq!(sum, 6);
+///
+/// let err = (1..=4u32).bounded(3).sum::<Result<u32, _>>().unwrap_err();
+/// assert_eq!(StdError::from(err), StdError::generic_err("Loop exceeded the limit of 3 iterations."));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BoundedLoop {
+    limit: u32,
+    count: u32
+}
+
+/// The error returned when a [`BoundedLoop`] exceeds its limit.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LoopLimitExceeded {
+    pub limit: u32
+}
+
+/// Iterator adapter returned by [`BoundedIterExt::bounded`].
+pub struct Bounded<I: Iterator> {
+    iter: I,
+    guard: BoundedLoop,
+    exceeded: bool
+}
+
+/// Adds the [`BoundedIterExt::bounded`] method to all iterators.
+pub trait BoundedIterExt: Iterator + Sized {
+    /// Yields the items of this iterator as `Ok` for up to `limit` items.
+    /// If there are any items left after that, yields a single `Err` and stops.
+    fn bounded(self, limit: u32) -> Bounded<Self>;
+}
+
+impl BoundedLoop {
+    #[inline]
+    pub const fn new(limit: u32) -> Self {
+        Self { limit, count: 0 }
+    }
+
+    /// Records a single iteration. Returns `Err` if the limit was already reached.
+    #[inline]
+    pub fn step(&mut self) -> Result<(), LoopLimitExceeded> {
+        if self.count >= self.limit {
+            return Err(LoopLimitExceeded { limit: self.limit });
+        }
+
+        self.count += 1;
+
+        Ok(())
+    }
+
+    /// The number of iterations recorded so far.
+    #[inline]
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    #[inline]
+    pub fn remaining(&self) -> u32 {
+        self.limit - self.count
+    }
+}
+
+impl<I: Iterator> BoundedIterExt for I {
+    #[inline]
+    fn bounded(self, limit: u32) -> Bounded<Self> {
+        Bounded {
+            iter: self,
+            guard: BoundedLoop::new(limit),
+            exceeded: false
+        }
+    }
+}
+
+impl<I: Iterator> Iterator for Bounded<I> {
+    type Item = Result<I::Item, LoopLimitExceeded>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.exceeded {
+            return None;
+        }
+
+        let item = self.iter.next()?;
+
+        match self.guard.step() {
+            Ok(()) => Some(Ok(item)),
+            Err(err) => {
+                self.exceeded = true;
+
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl fmt::Display for LoopLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Loop exceeded the limit of {} iterations.", self.limit)
+    }
+}
+
+impl std::error::Error for LoopLimitExceeded { }
+
+impl From<LoopLimitExceeded> for StdError {
+    #[inline]
+    fn from(err: LoopLimitExceeded) -> Self {
+        StdError::generic_err(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounded_iter_stops_after_error() {
+        let items: Vec<_> = (0..5u32).bounded(2).collect();
+        assert_eq!(items, vec![Ok(0), Ok(1), Err(LoopLimitExceeded { limit: 2 })]);
+
+        let items: Vec<_> = (0..2u32).bounded(2).collect();
+        assert_eq!(items, vec![Ok(0), Ok(1)]);
+
+        let items: Vec<_> = (0..0u32).bounded(0).collect();
+        assert!(items.is_empty());
+    }
+
+    #[test]
+    fn bounded_loop_counts() {
+        let mut guard = BoundedLoop::new(3);
+        assert_eq!(guard.remaining(), 3);
+
+        guard.step().unwrap();
+        assert_eq!(guard.count(), 1);
+        assert_eq!(guard.remaining(), 2);
+    }
+}
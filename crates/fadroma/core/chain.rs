@@ -0,0 +1,276 @@
+//! Address and denomination validation according to the chain that the
+//! contract is deployed to, so that the same code can be safely deployed
+//! to multiple Cosmos chains.
+//! *Feature flag: `chain`*
+//!
+//! The [`ChainProfile`] is configured once using [`init`], i.e. in the instantiate
+//! message, and then consulted by [`validate_address`] and [`validate_denom`].
+//! In addition to what [`cosmwasm_std::Api::addr_validate`] checks, addresses
+//! must be lowercase bech32 strings with a valid checksum, the expected prefix
+//! and a decoded length that is valid on the chain.
+
+use bech32::{FromBase32, Variant};
+use serde::{Serialize, Deserialize};
+
+use crate::{
+    self as fadroma,
+    bin_serde::{FadromaSerialize, FadromaDeserialize},
+    storage::SingleItem,
+    schemars::{self, JsonSchema},
+    cosmwasm_std::{Addr, Api, Coin, Deps, Storage, StdResult, StdError}
+};
+
+crate::namespace!(pub ChainProfileNs, b"Hs7dKq2VnW");
+pub const STORE: SingleItem<ChainProfile, ChainProfileNs> = SingleItem::new();
+
+/// The maximum length of a bech32 string as defined by BIP-173.
+const MAX_LEN: usize = 90;
+
+/// The address format and native denominations of a chain.
+///
+/// # Examples
+///
+/// ```
+/// use fadroma::{
+///     core::chain::ChainProfile,
+///     cosmwasm_std::testing::mock_dependencies
+/// };
+///
+/// let deps = mock_dependencies();
+/// let profile = ChainProfile::secret_network();
+///
+/// assert!(profile.validate_address(&deps.api, "secret1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnpn9nv9").is_ok());
+/// assert!(profile.validate_address(&deps.api, "cosmos1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnrk363e").is_err());
+///
+/// assert!(profile.validate_denom("uscrt").is_ok());
+/// assert!(profile.validate_denom("uatom").is_err());
+/// ```
+#[derive(
+    Serialize, Deserialize, FadromaSerialize, FadromaDeserialize,
+    JsonSchema, PartialEq, Debug, Clone
+)]
+pub struct ChainProfile {
+    /// The human readable part of addresses, i.e. `"secret"`.
+    pub bech32_prefix: String,
+    /// The valid lengths in bytes of canonical addresses. Usually 20 but
+    /// contract addresses are 32 bytes long on some chains.
+    pub canonical_lengths: Vec<u8>,
+    /// The native denominations that are accepted.
+    pub denoms: Vec<String>
+}
+
+impl ChainProfile {
+    pub fn new(
+        bech32_prefix: impl Into<String>,
+        canonical_lengths: Vec<u8>,
+        denoms: Vec<String>
+    ) -> Self {
+        Self {
+            bech32_prefix: bech32_prefix.into(),
+            canonical_lengths,
+            denoms
+        }
+    }
+
+    #[inline]
+    pub fn secret_network() -> Self {
+        Self::new("secret", vec![20], vec!["uscrt".into()])
+    }
+
+    /// Checks that `address` is valid on this chain and then
+    /// validates it using [`cosmwasm_std::Api::addr_validate`].
+    pub fn validate_address(&self, api: &dyn Api, address: &str) -> StdResult<Addr> {
+        let (prefix, data) = decode_bech32(address).ok_or_else(||
+            StdError::generic_err(format!("{} is not a valid bech32 address.", address))
+        )?;
+
+        if prefix != self.bech32_prefix {
+            return Err(StdError::generic_err(format!(
+                "Expected an address with the \"{}\" prefix but got \"{}\".",
+                self.bech32_prefix,
+                prefix
+            )));
+        }
+
+        if !self.canonical_lengths.iter().any(|x| *x as usize == data.len()) {
+            return Err(StdError::generic_err(format!(
+                "Invalid address length of {} bytes.",
+                data.len()
+            )));
+        }
+
+        api.addr_validate(address)
+    }
+
+    /// Returns `Err` if `denom` is not one of the native denominations of this chain.
+    pub fn validate_denom(&self, denom: &str) -> StdResult<()> {
+        if self.denoms.iter().any(|x| x == denom) {
+            Ok(())
+        } else {
+            Err(StdError::generic_err(format!("Unsupported denomination: {}", denom)))
+        }
+    }
+
+    /// Validates the denominations of all `funds`.
+    pub fn validate_funds(&self, funds: &[Coin]) -> StdResult<()> {
+        funds.iter().try_for_each(|x| self.validate_denom(&x.denom))
+    }
+}
+
+/// Configures the chain profile. You **must** call this in your
+/// instantiate message in order to use the validation functions.
+pub fn init(storage: &mut dyn Storage, profile: &ChainProfile) -> StdResult<()> {
+    if profile.bech32_prefix.is_empty() || profile.canonical_lengths.is_empty() {
+        return Err(StdError::generic_err(
+            "The chain profile must specify a prefix and at least one address length."
+        ));
+    }
+
+    STORE.save(storage, profile)
+}
+
+/// Returns `Err` if [`init`] wasn't called.
+#[inline]
+pub fn load(storage: &dyn Storage) -> StdResult<ChainProfile> {
+    STORE.load(storage)?.ok_or_else(||
+        StdError::generic_err("The chain profile has not been configured.")
+    )
+}
+
+/// Validates `address` using the configured [`ChainProfile`].
+#[inline]
+pub fn validate_address(deps: Deps, address: &str) -> StdResult<Addr> {
+    load(deps.storage)?.validate_address(deps.api, address)
+}
+
+/// Validates `denom` using the configured [`ChainProfile`].
+#[inline]
+pub fn validate_denom(storage: &dyn Storage, denom: &str) -> StdResult<()> {
+    load(storage)?.validate_denom(denom)
+}
+
+/// Returns the prefix and the decoded data of a lowercase bech32
+/// string or [`None`] if it isn't one or its checksum is invalid.
+fn decode_bech32(address: &str) -> Option<(String, Vec<u8>)> {
+    // The bech32 crate accepts uppercase strings as well and doesn't limit the length.
+    if address.len() > MAX_LEN || address.bytes().any(|x| x.is_ascii_uppercase()) {
+        return None;
+    }
+
+    let (prefix, data, variant) = bech32::decode(address).ok()?;
+
+    if variant != Variant::Bech32 {
+        return None;
+    }
+
+    Some((prefix, Vec::<u8>::from_base32(&data).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cosmwasm_std::{coin, testing::mock_dependencies};
+
+    const ADDRESS: &str = "secret1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnpn9nv9";
+    const CONTRACT: &str = "secret1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnzs23v9ccrydpk8qarc0sufhndj";
+
+    #[test]
+    fn decodes_bech32() {
+        let (prefix, data) = decode_bech32(ADDRESS).unwrap();
+        assert_eq!(prefix, "secret");
+        assert_eq!(data, (0..20).collect::<Vec<u8>>());
+
+        assert_eq!(decode_bech32(CONTRACT).unwrap().1.len(), 32);
+
+        // Changed a single character.
+        assert_eq!(decode_bech32("secret1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnpn9nv8"), None);
+        assert_eq!(decode_bech32(&ADDRESS.to_uppercase()), None);
+        assert_eq!(decode_bech32("secret1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnpn9nvb"), None);
+        assert_eq!(decode_bech32("secretqqqsyqcyq5rqwzqfpg9scrgwpugpzysnpn9nv9"), None);
+    }
+
+    #[test]
+    fn bip173_vectors() {
+        let valid = [
+            "a12uel5l",
+            "an83characterlonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1tt5tgs",
+            "abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw",
+            "11qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqc8247j",
+            "split1checkupstagehandshakeupstreamerranterredcaperred2y9e3w",
+            "?1ezyfcl"
+        ];
+
+        for address in valid {
+            assert!(decode_bech32(address).is_some(), "{}", address);
+        }
+
+        assert_eq!(decode_bech32("secret1qq45au0t"), Some(("secret".into(), vec![0])));
+
+        let invalid = [
+            // HRP character out of range.
+            "\u{20}1nwldj5",
+            "\u{7f}1axkwrx",
+            "\u{80}1eym55h",
+            // Overall max length exceeded.
+            "an84characterslonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1569pvx",
+            // No separator character.
+            "pzry9x8gf2tvdw0s3jn54khce6mua7l",
+            // Empty HRP.
+            "1pzry9x8gf2tvdw0s3jn54khce6mua7l",
+            "10a06t8",
+            "1qzzfhee",
+            // Invalid data character.
+            "x1b4n0q5v",
+            // Too short checksum.
+            "li1dgmt3",
+            // Invalid character in checksum.
+            "de1lg7wt\u{ff}",
+            // Checksum calculated with uppercase form of HRP.
+            "A1G7SGD8",
+            // Mixed case.
+            "a12UEL5L",
+            // Valid but uppercase, which addresses never are.
+            "A12UEL5L",
+            // Valid bech32m.
+            "a1lqfn3a",
+            // Non-zero padding.
+            "secret1qpgzffje"
+        ];
+
+        for address in invalid {
+            assert_eq!(decode_bech32(address), None, "{}", address);
+        }
+    }
+
+    #[test]
+    fn validates_using_configured_profile() {
+        let ref mut deps = mock_dependencies();
+
+        assert_eq!(
+            validate_address(deps.as_ref(), ADDRESS).unwrap_err(),
+            StdError::generic_err("The chain profile has not been configured.")
+        );
+
+        assert!(init(deps.as_mut().storage, &ChainProfile::new("secret", vec![], vec![])).is_err());
+        init(deps.as_mut().storage, &ChainProfile::secret_network()).unwrap();
+
+        assert_eq!(validate_address(deps.as_ref(), ADDRESS).unwrap(), Addr::unchecked(ADDRESS));
+        assert_eq!(
+            validate_address(deps.as_ref(), CONTRACT).unwrap_err(),
+            StdError::generic_err("Invalid address length of 32 bytes.")
+        );
+        assert_eq!(
+            validate_address(deps.as_ref(), "cosmos1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnrk363e").unwrap_err(),
+            StdError::generic_err("Expected an address with the \"secret\" prefix but got \"cosmos\".")
+        );
+
+        assert!(validate_denom(deps.as_ref().storage, "uscrt").is_ok());
+
+        let profile = load(deps.as_ref().storage).unwrap();
+        assert!(profile.validate_funds(&[coin(1, "uscrt")]).is_ok());
+        assert_eq!(
+            profile.validate_funds(&[coin(1, "uscrt"), coin(1, "uatom")]).unwrap_err(),
+            StdError::generic_err("Unsupported denomination: uatom")
+        );
+    }
+}
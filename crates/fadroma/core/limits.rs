@@ -0,0 +1,191 @@
+//! Limits on the size and nesting depth of incoming messages which are
+//! checked on the raw JSON before it is deserialized, protecting contracts
+//! from pathological inputs that cause excessive allocations.
+//!
+//! Use the `limits` argument of [`crate::entrypoint`] or the `max_msg_size` and
+//! `max_msg_depth` meta arguments of `#[contract]` to apply them to all
+//! messages that a contract receives.
+
+use serde::de::DeserializeOwned;
+
+use crate::cosmwasm_std::{StdResult, StdError, from_slice};
+
+/// The maximum serialized size in bytes and JSON nesting depth of a message.
+///
+/// # Examples
+///
+/// ```
+/// use fadroma::core::limits::MsgLimits;
+///
+/// let limits = MsgLimits { max_size: 64, max_depth: 2 };
+///
+/// assert!(limits.check(br#"{"transfer":{"amount":"100"}}"#).is_ok());
+/// assert!(limits.check(br#"{"transfer":{"amount":{"nested":"100"}}}"#).is_err());
+/// assert!(limits.check(&[b' '; 65]).is_err());
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MsgLimits {
+    pub max_size: usize,
+    /// Each JSON object or array adds one level of nesting.
+    pub max_depth: usize
+}
+
+impl MsgLimits {
+    #[inline]
+    pub const fn new(max_size: usize, max_depth: usize) -> Self {
+        Self { max_size, max_depth }
+    }
+
+    /// Returns an error if `msg` exceeds either limit. Doesn't
+    /// validate that `msg` is actually valid JSON.
+    pub fn check(&self, msg: &[u8]) -> StdResult<()> {
+        if msg.len() > self.max_size {
+            return Err(StdError::generic_err(format!(
+                "Message size of {} bytes exceeds the limit of {} bytes.",
+                msg.len(),
+                self.max_size
+            )));
+        }
+
+        if exceeds_depth(msg, self.max_depth) {
+            return Err(StdError::generic_err(format!(
+                "Message exceeds the nesting depth limit of {}.",
+                self.max_depth
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Checks the limits and only then deserializes `msg`.
+    #[inline]
+    pub fn from_slice<T: DeserializeOwned>(&self, msg: &[u8]) -> StdResult<T> {
+        self.check(msg)?;
+
+        from_slice(msg)
+    }
+}
+
+/// Scans the JSON without recursing so that deeply nested
+/// input can't overflow the stack here either.
+fn exceeds_depth(msg: &[u8], max_depth: usize) -> bool {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for byte in msg {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => { }
+            }
+
+            continue;
+        }
+
+        match byte {
+            b'"' => in_string = true,
+            b'{' | b'[' => {
+                depth += 1;
+
+                if depth > max_depth {
+                    return true;
+                }
+            }
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            _ => { }
+        }
+    }
+
+    false
+}
+
+#[doc(hidden)]
+#[cfg(target_arch = "wasm32")]
+pub mod wasm {
+    use crate::cosmwasm_std::{ContractResult, Empty, to_vec};
+    use super::MsgLimits;
+
+    /// Same layout as the region that `cosmwasm_std`
+    /// uses to exchange data with the host.
+    #[repr(C)]
+    struct Region {
+        offset: u32,
+        capacity: u32,
+        length: u32
+    }
+
+    /// Checks the message that the host passed to an entry point. If it exceeds the
+    /// limits, returns a pointer to the error result which the entry point must return
+    /// instead of dispatching the message. The regions of the arguments are not
+    /// freed in that case but they are discarded together with the instance anyway.
+    ///
+    /// # Safety
+    ///
+    /// `msg_ptr` must point to the message region that the host passed to the entry point.
+    pub unsafe fn check(limits: &MsgLimits, msg_ptr: u32) -> Option<u32> {
+        let region = &*(msg_ptr as *const Region);
+        let msg = std::slice::from_raw_parts(
+            region.offset as *const u8,
+            region.length as usize
+        );
+
+        let err = limits.check(msg).err()?;
+        let result = to_vec(&ContractResult::<Empty>::Err(err.to_string())).ok()?;
+
+        let region = Box::new(Region {
+            offset: result.as_ptr() as u32,
+            capacity: result.capacity() as u32,
+            length: result.len() as u32
+        });
+        std::mem::forget(result);
+
+        Some(Box::into_raw(region) as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    #[serde(rename_all = "snake_case")]
+    enum ExecuteMsg {
+        Batch { items: Vec<Vec<u8>> }
+    }
+
+    #[test]
+    fn rejects_messages_over_the_limits() {
+        let limits = MsgLimits::new(40, 4);
+
+        let msg = br#"{"batch":{"items":[[1],[2,3]]}}"#;
+        assert_eq!(
+            limits.from_slice::<ExecuteMsg>(msg).unwrap(),
+            ExecuteMsg::Batch { items: vec![vec![1], vec![2, 3]] }
+        );
+
+        let msg = br#"{"batch":{"items":[[1],[2,3],[4,5],[6]]}}"#;
+        let err = limits.from_slice::<ExecuteMsg>(msg).unwrap_err();
+        assert_eq!(err, StdError::generic_err("Message size of 41 bytes exceeds the limit of 40 bytes."));
+
+        let msg = br#"{"batch":{"items":[[[1]]]}}"#;
+        let err = limits.check(msg).unwrap_err();
+        assert_eq!(err, StdError::generic_err("Message exceeds the nesting depth limit of 4."));
+
+        let msg = "[".repeat(40);
+        assert!(limits.check(msg.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn ignores_brackets_in_strings() {
+        let limits = MsgLimits::new(1024, 1);
+
+        assert!(limits.check(br#"{"memo":"[[{{"}"#).is_ok());
+        assert!(limits.check(br#"{"memo":"\"[[{{\\"}"#).is_ok());
+        assert!(limits.check(br#"{"memo":"\\",[]}"#).is_err());
+    }
+}
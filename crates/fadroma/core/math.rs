@@ -0,0 +1,204 @@
+//! Arithmetic whose behaviour on overflow is chosen by each contract rather
+//! than by the components that it is made of.
+//!
+//! The policy is saved once, when the contract is instantiated, using
+//! [`MathConfig::save`]. Components load it with [`MathConfig::load`] which
+//! falls back to [`OverflowPolicy::Checked`] if the contract never set one.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    self as fadroma,
+    storage::SingleItem,
+    cosmwasm_std::{
+        Storage, StdResult, StdError, OverflowError, OverflowOperation,
+        Uint64, Uint128, Uint256
+    },
+    bin_serde::{FadromaSerialize, FadromaDeserialize},
+    schemars::{self, JsonSchema}
+};
+
+crate::namespace!(pub MathConfigNs, b"Tn4kW9cYpB");
+const CONFIG: SingleItem<MathConfig, MathConfigNs> = SingleItem::new();
+
+/// What to do when the result of an operation doesn't fit in its type.
+#[derive(
+    Serialize, Deserialize, FadromaSerialize, FadromaDeserialize,
+    JsonSchema, PartialEq, Eq, Debug, Clone, Copy, Default
+)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Return an [`StdError::Overflow`] error.
+    #[default]
+    Checked,
+    /// Clamp the result to the minimum or maximum value of the type.
+    Saturating,
+    /// Panic, which aborts the whole message. Use this if an overflow
+    /// can only be caused by a bug and must never be handled by the caller.
+    Strict
+}
+
+/// The math settings of a contract.
+///
+/// # Examples
+///
+/// ```
+/// # use fadroma::{
+/// #     core::math::{MathConfig, OverflowPolicy, Arithmetic},
+/// #     cosmwasm_std::{Uint128, StdResult, testing::mock_dependencies}
+/// # };
+/// # fn main() -> StdResult<()> {
+/// # let mut deps = mock_dependencies();
+/// # let storage = deps.as_mut().storage;
+/// // In the instantiate handler.
+/// MathConfig { overflow: OverflowPolicy::Saturating }.save(storage)?;
+///
+/// // In a component.
+/// let policy = MathConfig::load(storage)?.overflow;
+/// let balance = Uint128::MAX.add_with(Uint128::one(), policy)?;
+///
+/// assert_eq!(balance, Uint128::MAX);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(
+    Serialize, Deserialize, FadromaSerialize, FadromaDeserialize,
+    JsonSchema, PartialEq, Eq, Debug, Clone, Copy, Default
+)]
+pub struct MathConfig {
+    pub overflow: OverflowPolicy
+}
+
+impl MathConfig {
+    #[inline]
+    pub fn save(&self, storage: &mut dyn Storage) -> StdResult<()> {
+        CONFIG.save(storage, self)
+    }
+
+    /// Returns the default config if none has been saved.
+    #[inline]
+    pub fn load(storage: &dyn Storage) -> StdResult<Self> {
+        CONFIG.load_or(storage, Self::default())
+    }
+}
+
+/// Addition, subtraction and multiplication that overflow according to an [`OverflowPolicy`].
+pub trait Arithmetic: Sized {
+    fn add_with(self, rhs: Self, policy: OverflowPolicy) -> StdResult<Self>;
+    fn sub_with(self, rhs: Self, policy: OverflowPolicy) -> StdResult<Self>;
+    fn mul_with(self, rhs: Self, policy: OverflowPolicy) -> StdResult<Self>;
+}
+
+/// `$ok` converts the result of the `checked_*` methods of the types into an [`Option`].
+macro_rules! impl_arithmetic {
+    ($ok:path => $($ty:ty),+) => {
+        $(
+            impl Arithmetic for $ty {
+                #[inline]
+                fn add_with(self, rhs: Self, policy: OverflowPolicy) -> StdResult<Self> {
+                    apply(
+                        policy,
+                        OverflowOperation::Add,
+                        self,
+                        rhs,
+                        $ok(self.checked_add(rhs)),
+                        || self.saturating_add(rhs)
+                    )
+                }
+
+                #[inline]
+                fn sub_with(self, rhs: Self, policy: OverflowPolicy) -> StdResult<Self> {
+                    apply(
+                        policy,
+                        OverflowOperation::Sub,
+                        self,
+                        rhs,
+                        $ok(self.checked_sub(rhs)),
+                        || self.saturating_sub(rhs)
+                    )
+                }
+
+                #[inline]
+                fn mul_with(self, rhs: Self, policy: OverflowPolicy) -> StdResult<Self> {
+                    apply(
+                        policy,
+                        OverflowOperation::Mul,
+                        self,
+                        rhs,
+                        $ok(self.checked_mul(rhs)),
+                        || self.saturating_mul(rhs)
+                    )
+                }
+            }
+        )+
+    };
+}
+
+impl_arithmetic!(Result::ok => Uint64, Uint128, Uint256);
+impl_arithmetic!(std::convert::identity => u64, u128);
+
+fn apply<T: ToString>(
+    policy: OverflowPolicy,
+    operation: OverflowOperation,
+    lhs: T,
+    rhs: T,
+    checked: Option<T>,
+    saturating: impl FnOnce() -> T
+) -> StdResult<T> {
+    if let Some(result) = checked {
+        return Ok(result);
+    }
+
+    let err = OverflowError::new(operation, lhs, rhs);
+
+    match policy {
+        OverflowPolicy::Checked => Err(StdError::overflow(err)),
+        OverflowPolicy::Saturating => Ok(saturating()),
+        OverflowPolicy::Strict => panic!("{}", err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cosmwasm_std::testing::mock_dependencies;
+
+    #[test]
+    fn overflow_follows_policy() {
+        let max = Uint128::MAX;
+        let one = Uint128::one();
+
+        for policy in [OverflowPolicy::Checked, OverflowPolicy::Saturating, OverflowPolicy::Strict] {
+            assert_eq!(one.add_with(one, policy).unwrap(), Uint128::new(2));
+            assert_eq!(one.sub_with(one, policy).unwrap(), Uint128::zero());
+            assert_eq!(max.mul_with(one, policy).unwrap(), max);
+        }
+
+        let err = max.add_with(one, OverflowPolicy::Checked).unwrap_err();
+        assert!(matches!(err, StdError::Overflow { .. }));
+        assert!(Uint128::zero().sub_with(one, OverflowPolicy::Checked).is_err());
+        assert!(u64::MAX.mul_with(2, OverflowPolicy::Checked).is_err());
+
+        let policy = OverflowPolicy::Saturating;
+        assert_eq!(max.add_with(one, policy).unwrap(), max);
+        assert_eq!(Uint128::zero().sub_with(one, policy).unwrap(), Uint128::zero());
+        assert_eq!(Uint64::MAX.mul_with(Uint64::new(2), policy).unwrap(), Uint64::MAX);
+        assert_eq!(0u64.sub_with(1, policy).unwrap(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot Sub with 0 and 1")]
+    fn strict_policy_panics() {
+        let _ = 0u64.sub_with(1, OverflowPolicy::Strict);
+    }
+
+    #[test]
+    fn config_defaults_to_checked() {
+        let mut deps = mock_dependencies();
+
+        assert_eq!(MathConfig::load(&deps.storage).unwrap().overflow, OverflowPolicy::Checked);
+
+        MathConfig { overflow: OverflowPolicy::Strict }.save(&mut deps.storage).unwrap();
+        assert_eq!(MathConfig::load(&deps.storage).unwrap().overflow, OverflowPolicy::Strict);
+    }
+}
@@ -1,11 +1,20 @@
 pub mod addr;
+pub mod schema;
+pub mod num_str;
+pub mod limits;
+#[cfg(feature = "chain")]
+pub mod chain;
+pub mod panic;
+pub mod math;
 mod link;
 mod callback;
+mod bounded;
 
 pub use fadroma_derive_canonize::Canonize;
 pub use addr::{Humanize, Canonize, MaybeAddress, Address};
 pub use link::*;
 pub use callback::*;
+pub use bounded::*;
 
 pub(crate) mod sealed {
     pub trait Sealed { }
@@ -0,0 +1,203 @@
+//! Serde adapters that encode primitive integers as JSON strings,
+//! the same way that [`crate::cosmwasm_std::Uint128`] and friends are encoded.
+//! JavaScript clients cannot represent integers larger than 2^53 precisely
+//! so message fields should never be plain JSON numbers.
+//!
+//! Use [`crate::core::schema::assert_numbers_as_strings`] in tests to find
+//! any message fields that are still encoded as numbers.
+//!
+//! # Examples
+//!
+//! ```
+//! use fadroma::{
+//!     core::num_str,
+//!     schemars::{self, JsonSchema},
+//!     serde::{Serialize, Deserialize},
+//!     cosmwasm_std::{to_vec, from_slice}
+//! };
+//!
+//! #[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+//! struct Deposit {
+//!     #[serde(with = "num_str")]
+//!     #[schemars(with = "String")]
+//!     amount: u128,
+//!     #[serde(with = "num_str::option", default)]
+//!     #[schemars(with = "Option<String>")]
+//!     expires_at: Option<u64>
+//! }
+//!
+//! let deposit = Deposit { amount: 1_000_000, expires_at: Some(12345) };
+//! let json = to_vec(&deposit).unwrap();
+//!
+//! assert_eq!(json, br#"{"amount":"1000000","expires_at":"12345"}"#);
+//! assert_eq!(from_slice::<Deposit>(&json).unwrap(), deposit);
+//!
+//! // Numbers are rejected so that clients don't mix both encodings.
+//! assert!(from_slice::<Deposit>(br#"{"amount":1000000}"#).is_err());
+//! ```
+
+use std::{fmt, str::FromStr, marker::PhantomData};
+
+use serde::{Serializer, Deserializer, de::{self, Visitor}};
+
+/// Serializes `value` as a string using its [`fmt::Display`] implementation.
+#[inline]
+pub fn serialize<T: fmt::Display, S: Serializer>(
+    value: &T,
+    serializer: S
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&value.to_string())
+}
+
+/// Deserializes a value from a string using its [`FromStr`] implementation.
+/// JSON numbers are rejected.
+#[inline]
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+    D: Deserializer<'de>
+{
+    deserializer.deserialize_str(StrVisitor(PhantomData))
+}
+
+/// Same as the parent module but for optional values. Combine with
+/// `#[serde(default)]` to allow the field to be omitted entirely.
+pub mod option {
+    use super::*;
+
+    #[inline]
+    pub fn serialize<T: fmt::Display, S: Serializer>(
+        value: &Option<T>,
+        serializer: S
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.serialize_str(&value.to_string()),
+            None => serializer.serialize_none()
+        }
+    }
+
+    #[inline]
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+        D: Deserializer<'de>
+    {
+        deserializer.deserialize_option(OptionVisitor(PhantomData))
+    }
+
+    struct OptionVisitor<T>(PhantomData<T>);
+
+    impl<'de, T> Visitor<'de> for OptionVisitor<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display
+    {
+        type Value = Option<T>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a number encoded as a string or null")
+        }
+
+        #[inline]
+        fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        #[inline]
+        fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        #[inline]
+        fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+            super::deserialize(deserializer).map(Some)
+        }
+    }
+}
+
+struct StrVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for StrVisitor<T>
+where
+    T: FromStr,
+    T::Err: fmt::Display
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a number encoded as a string")
+    }
+
+    #[inline]
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        value.parse().map_err(E::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Serialize, Deserialize};
+
+    use crate::cosmwasm_std::{to_vec, from_slice, Uint128};
+    use super::*;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Amounts {
+        #[serde(with = "super")]
+        small: u8,
+        #[serde(with = "super")]
+        large: u128,
+        #[serde(with = "super")]
+        signed: i64,
+        #[serde(with = "option", default)]
+        maybe: Option<u64>,
+        uint: Uint128
+    }
+
+    #[test]
+    fn round_trip() {
+        let amounts = Amounts {
+            small: 255,
+            large: u128::MAX,
+            signed: i64::MIN,
+            maybe: Some(u64::MAX),
+            uint: Uint128::MAX
+        };
+
+        let json = to_vec(&amounts).unwrap();
+        assert_eq!(
+            String::from_utf8(json.clone()).unwrap(),
+            format!(
+                r#"{{"small":"255","large":"{0}","signed":"{1}","maybe":"{2}","uint":"{0}"}}"#,
+                u128::MAX,
+                i64::MIN,
+                u64::MAX
+            )
+        );
+        assert_eq!(from_slice::<Amounts>(&json).unwrap(), amounts);
+
+        let amounts = Amounts { maybe: None, ..amounts };
+        let json = to_vec(&amounts).unwrap();
+        assert_eq!(from_slice::<Amounts>(&json).unwrap(), amounts);
+
+        let json = br#"{"small":"1","large":"2","signed":"-3","uint":"4"}"#;
+        assert_eq!(from_slice::<Amounts>(json).unwrap().maybe, None);
+    }
+
+    #[test]
+    fn rejects_numbers_and_invalid_strings() {
+        let err = from_slice::<Amounts>(br#"{"small":1,"large":"2","signed":"3","uint":"4"}"#);
+        assert!(err.is_err());
+
+        let err = from_slice::<Amounts>(br#"{"small":"1","large":"2","signed":"3","maybe":4,"uint":"4"}"#);
+        assert!(err.is_err());
+
+        let err = from_slice::<Amounts>(br#"{"small":"256","large":"2","signed":"3","uint":"4"}"#);
+        assert!(err.is_err());
+
+        let err = from_slice::<Amounts>(br#"{"small":"1","large":"-2","signed":"3","uint":"4"}"#);
+        assert!(err.is_err());
+    }
+}
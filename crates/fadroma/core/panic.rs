@@ -0,0 +1,67 @@
+//! Converting panics in handlers into errors when running natively, i.e. in
+//! unit tests, so that a failing handler can be diagnosed from the error that
+//! the test receives, together with the message that it panicked with.
+//!
+//! Panics can't be caught on `wasm32` since contracts are built with `panic = "abort"`
+//! and a panic there always aborts the execution, which the chain reports as a
+//! generic failure. [`catch_panic`] simply calls the handler in that case.
+//!
+//! Use [`crate::ensemble::CatchPanics`] to apply it to all entry points of a
+//! contract running in the ensemble.
+
+use std::any::Any;
+
+use crate::cosmwasm_std::StdError;
+
+/// Calls `handler`, returning a [`StdError::GenericErr`] with the panic
+/// message if it panics. Anything that it wrote to storage before panicking
+/// is kept, so the caller must discard those writes like it does when the
+/// handler returns an error.
+///
+/// # Examples
+///
+/// ```
+/// use fadroma::{
+///     core::panic::catch_panic,
+///     cosmwasm_std::{StdResult, StdError}
+/// };
+///
+/// fn divide(a: u64, b: u64) -> StdResult<u64> {
+///     Ok(a / b)
+/// }
+///
+/// assert_eq!(catch_panic(|| divide(4, 2)), Ok(2));
+/// assert_eq!(
+///     catch_panic(|| divide(4, 0)),
+///     Err(StdError::generic_err("Handler panicked: attempt to divide by zero"))
+/// );
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+pub fn catch_panic<T, E: From<StdError>>(
+    handler: impl FnOnce() -> Result<T, E>
+) -> Result<T, E> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(handler))
+        .unwrap_or_else(|payload| Err(panic_error(payload.as_ref()).into()))
+}
+
+/// Calls `handler`. A panic aborts the execution on `wasm32` and can't be caught.
+#[cfg(target_arch = "wasm32")]
+#[inline]
+pub fn catch_panic<T, E: From<StdError>>(
+    handler: impl FnOnce() -> Result<T, E>
+) -> Result<T, E> {
+    handler()
+}
+
+/// Converts the payload of a panic into an error with its message.
+pub fn panic_error(payload: &(dyn Any + Send)) -> StdError {
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.as_str()
+    } else {
+        "unknown panic payload"
+    };
+
+    StdError::generic_err(format!("Handler panicked: {}", message))
+}
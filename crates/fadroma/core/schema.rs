@@ -0,0 +1,320 @@
+//! Assertions about contract messages based on their JSON schema.
+
+use schemars::{
+    JsonSchema, schema_for,
+    schema::{RootSchema, Schema, SchemaObject, InstanceType, SingleOrVec}
+};
+
+/// Returns the names of the messages described by `T` that silently ignore unknown
+/// fields i.e. aren't marked with `#[serde(deny_unknown_fields)]`. For enums, each
+/// variant is checked separately and is identified by its name as it appears in JSON.
+pub fn accepting_unknown_fields<T: JsonSchema>() -> Vec<String> {
+    let root = schema_for!(T);
+    let mut result = vec![];
+
+    let variants = root.schema.subschemas
+        .as_ref()
+        .and_then(|x| x.one_of.as_ref());
+
+    match variants {
+        Some(variants) => for variant in variants {
+            let Some(object) = resolve(&root, variant).and_then(|x| x.object.as_ref()) else {
+                continue;
+            };
+
+            for (name, fields) in &object.properties {
+                if resolve(&root, fields).is_some_and(|x| !denies_unknown_fields(x)) {
+                    result.push(name.clone());
+                }
+            }
+        },
+        None => if !denies_unknown_fields(&root.schema) {
+            let name = root.schema.metadata
+                .as_ref()
+                .and_then(|x| x.title.clone())
+                .unwrap_or_else(|| std::any::type_name::<T>().into());
+
+            result.push(name);
+        }
+    }
+
+    result
+}
+
+/// Panics if any of the messages described by `T` accept unknown fields.
+/// Use it in tests to make sure that all execute and query messages of a contract
+/// reject fields that it doesn't expect, i.e. when created with `#[contract(deny_unknown_fields)]`.
+///
+/// # Examples
+///
+/// ```
+/// use fadroma::{
+///     core::schema::assert_denies_unknown_fields,
+///     schemars::{self, JsonSchema},
+///     serde::{Serialize, Deserialize}
+/// };
+///
+/// #[derive(Serialize, Deserialize, JsonSchema)]
+/// #[serde(rename_all = "snake_case", deny_unknown_fields)]
+/// enum ExecuteMsg {
+///     Transfer { recipient: String, amount: u64 },
+///     Burn { amount: u64 }
+/// }
+///
+/// assert_denies_unknown_fields::<ExecuteMsg>();
+/// ```
+pub fn assert_denies_unknown_fields<T: JsonSchema>() {
+    let names = accepting_unknown_fields::<T>();
+
+    assert!(
+        names.is_empty(),
+        "The following messages accept unknown fields: {}",
+        names.join(", ")
+    );
+}
+
+/// Returns the paths of all fields in the messages described by `T` that are encoded
+/// as JSON numbers rather than strings. Each path starts with the name of the message
+/// and descends into nested structs, sequences (`[]`) and maps (`{}`), i.e. `"transfer.amount"`.
+pub fn numeric_fields<T: JsonSchema>() -> Vec<String> {
+    let root = schema_for!(T);
+    let mut result = vec![];
+
+    let variants = root.schema.subschemas
+        .as_ref()
+        .and_then(|x| x.one_of.as_ref());
+
+    match variants {
+        Some(variants) => for variant in variants {
+            find_numbers(&root, variant, String::new(), &mut vec![], &mut result);
+        },
+        None => {
+            let name = root.schema.metadata
+                .as_ref()
+                .and_then(|x| x.title.clone())
+                .unwrap_or_else(|| std::any::type_name::<T>().into());
+
+            find_numbers_in(&root, &root.schema, name, &mut vec![], &mut result);
+        }
+    }
+
+    result
+}
+
+/// Panics if any of the messages described by `T` contain fields that are
+/// encoded as JSON numbers. Use [`crate::cosmwasm_std::Uint128`] and the other
+/// `Uint` types or the [`super::num_str`] adapters for such fields instead.
+///
+/// # Examples
+///
+/// ```
+/// use fadroma::{
+///     core::{num_str, schema::assert_numbers_as_strings},
+///     schemars::{self, JsonSchema},
+///     serde::{Serialize, Deserialize},
+///     cosmwasm_std::Uint128
+/// };
+///
+/// #[derive(Serialize, Deserialize, JsonSchema)]
+/// #[serde(rename_all = "snake_case")]
+/// enum ExecuteMsg {
+///     Transfer { recipient: String, amount: Uint128 },
+///     Lock {
+///         #[serde(with = "num_str")]
+///         #[schemars(with = "String")]
+///         until: u64
+///     }
+/// }
+///
+/// assert_numbers_as_strings::<ExecuteMsg>();
+/// ```
+pub fn assert_numbers_as_strings<T: JsonSchema>() {
+    let paths = numeric_fields::<T>();
+
+    assert!(
+        paths.is_empty(),
+        "The following fields are encoded as JSON numbers: {}",
+        paths.join(", ")
+    );
+}
+
+fn find_numbers<'a>(
+    root: &'a RootSchema,
+    schema: &'a Schema,
+    path: String,
+    visited: &mut Vec<&'a str>,
+    result: &mut Vec<String>
+) {
+    let Schema::Object(object) = schema else {
+        return;
+    };
+
+    let Some(reference) = &object.reference else {
+        return find_numbers_in(root, object, path, visited, result);
+    };
+
+    // Recursive types would otherwise never terminate.
+    if visited.contains(&reference.as_str()) {
+        return;
+    }
+
+    if let Some(object) = resolve(root, schema) {
+        visited.push(reference);
+        find_numbers_in(root, object, path, visited, result);
+        visited.pop();
+    }
+}
+
+fn find_numbers_in<'a>(
+    root: &'a RootSchema,
+    object: &'a SchemaObject,
+    path: String,
+    visited: &mut Vec<&'a str>,
+    result: &mut Vec<String>
+) {
+    if let Some(types) = &object.instance_type {
+        if types.contains(&InstanceType::Integer) || types.contains(&InstanceType::Number) {
+            result.push(path.clone());
+        }
+    }
+
+    let join = |name: &str| if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    };
+
+    if let Some(object) = &object.object {
+        for (name, field) in &object.properties {
+            find_numbers(root, field, join(name), visited, result);
+        }
+
+        if let Some(values) = &object.additional_properties {
+            find_numbers(root, values, join("{}"), visited, result);
+        }
+    }
+
+    match object.array.as_ref().and_then(|x| x.items.as_ref()) {
+        Some(SingleOrVec::Single(item)) =>
+            find_numbers(root, item, join("[]"), visited, result),
+        Some(SingleOrVec::Vec(items)) => for item in items {
+            find_numbers(root, item, join("[]"), visited, result);
+        },
+        None => { }
+    }
+
+    if let Some(subschemas) = &object.subschemas {
+        let alternatives = [&subschemas.all_of, &subschemas.any_of, &subschemas.one_of];
+
+        for schema in alternatives.into_iter().flatten().flatten() {
+            find_numbers(root, schema, path.clone(), visited, result);
+        }
+    }
+}
+
+/// Object schemas that don't allow additional properties. Anything
+/// else, i.e. a newtype variant with a number, doesn't have any fields.
+#[inline]
+fn denies_unknown_fields(schema: &SchemaObject) -> bool {
+    match &schema.object {
+        Some(object) => matches!(
+            object.additional_properties.as_deref(),
+            Some(Schema::Bool(false))
+        ),
+        None => true
+    }
+}
+
+fn resolve<'a>(root: &'a RootSchema, schema: &'a Schema) -> Option<&'a SchemaObject> {
+    let Schema::Object(object) = schema else {
+        return None;
+    };
+
+    match &object.reference {
+        Some(reference) => {
+            let name = reference.strip_prefix("#/definitions/")?;
+
+            match root.definitions.get(name)? {
+                Schema::Object(object) => Some(object),
+                Schema::Bool(_) => None
+            }
+        },
+        None => Some(object)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::{Serialize, Deserialize};
+
+    use crate::cosmwasm_std::Uint128;
+    use super::*;
+
+    #[derive(Serialize, Deserialize, JsonSchema)]
+    #[serde(rename_all = "snake_case")]
+    enum QueryMsg {
+        Balance { address: String },
+        Config { },
+        Count(u64)
+    }
+
+    #[derive(Serialize, Deserialize, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    struct InstantiateMsg {
+        admin: Option<String>
+    }
+
+    #[derive(Serialize, Deserialize, JsonSchema)]
+    struct Config {
+        admin: Option<String>
+    }
+
+    #[derive(Serialize, Deserialize, JsonSchema)]
+    #[serde(rename_all = "snake_case")]
+    enum ExecuteMsg {
+        Transfer {
+            amount: Uint128,
+            memo: Option<String>
+        },
+        Batch {
+            actions: Vec<Action>,
+            deadline: Option<u64>
+        },
+        Lock(Lock)
+    }
+
+    #[derive(Serialize, Deserialize, JsonSchema)]
+    struct Action {
+        #[serde(with = "crate::core::num_str")]
+        #[schemars(with = "String")]
+        amount: u128,
+        weights: BTreeMap<String, u32>,
+        then: Option<Box<Action>>
+    }
+
+    #[derive(Serialize, Deserialize, JsonSchema)]
+    struct Lock {
+        until: u64
+    }
+
+    #[test]
+    fn finds_numeric_fields() {
+        assert_eq!(numeric_fields::<ExecuteMsg>(), vec![
+            "batch.actions.[].weights.{}",
+            "batch.deadline",
+            "lock.until"
+        ]);
+        assert_eq!(numeric_fields::<Lock>(), vec!["Lock.until"]);
+        assert!(numeric_fields::<QueryMsg>() == vec!["count"]);
+        assert!(numeric_fields::<Config>().is_empty());
+    }
+
+    #[test]
+    fn finds_messages_accepting_unknown_fields() {
+        assert_eq!(accepting_unknown_fields::<QueryMsg>(), vec!["balance", "config"]);
+        assert!(accepting_unknown_fields::<InstantiateMsg>().is_empty());
+        assert_eq!(accepting_unknown_fields::<Config>(), vec!["Config"]);
+    }
+}
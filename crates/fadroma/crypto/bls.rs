@@ -0,0 +1,253 @@
+//! BLS12-381 signature verification, using the proof of possession scheme
+//! with public keys in G1 and signatures in G2. This is the same scheme that
+//! is used by Ethereum consensus and most threshold signing committees.
+//! *Feature flag: `bls`*
+//!
+//! Enabling this feature adds around 80KB to the contract binary when built with
+//! `opt-level = "z"` and around 450KB with `opt-level = 3` (before running `wasm-opt`).
+//! Prefer the former for contracts that use it.
+//!
+//! # Rogue key attacks
+//!
+//! [`fast_aggregate_verify`] is only secure if every public key passed to it has had
+//! its proof of possession verified using [`verify_possession`] first. Do that when a
+//! committee member is registered and store only keys that passed the check.
+
+use bls12_381::{
+    G1Affine, G1Projective, G2Affine, G2Projective, G2Prepared, Gt,
+    multi_miller_loop,
+    hash_to_curve::{HashToCurve, ExpandMsgXmd}
+};
+
+use crate::cosmwasm_std::{StdResult, StdError, Binary};
+
+/// Domain separation tag used when hashing messages to sign.
+pub const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// Domain separation tag used when hashing public keys for proofs of possession.
+pub const POP_DST: &[u8] = b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+pub const PUBLIC_KEY_LEN: usize = 48;
+pub const SIGNATURE_LEN: usize = 96;
+
+/// Verifies the signature of a single public key over `msg`.
+///
+/// Returns `Err` if the public key or the signature are not valid
+/// compressed points rather than returning `Ok(false)`.
+pub fn verify(
+    public_key: &[u8],
+    msg: &[u8],
+    signature: &[u8]
+) -> StdResult<bool> {
+    let public_key = parse_public_key(public_key)?;
+    let signature = parse_signature(signature)?;
+
+    Ok(pairing_check(&[(public_key, hash(msg, DST))], &signature))
+}
+
+/// Verifies the proof that the owner of `public_key` also holds its secret key.
+pub fn verify_possession(
+    public_key: &[u8],
+    proof: &[u8]
+) -> StdResult<bool> {
+    let point = parse_public_key(public_key)?;
+    let proof = parse_signature(proof)?;
+
+    Ok(pairing_check(&[(point, hash(public_key, POP_DST))], &proof))
+}
+
+/// Verifies an aggregate signature by all of the `public_keys` over the same `msg`,
+/// which is what a committee attesting to a single message produces.
+///
+/// See the [module level docs](self) about rogue key attacks.
+pub fn fast_aggregate_verify(
+    public_keys: &[impl AsRef<[u8]>],
+    msg: &[u8],
+    signature: &[u8]
+) -> StdResult<bool> {
+    if public_keys.is_empty() {
+        return Ok(false);
+    }
+
+    let mut aggregate = G1Projective::identity();
+
+    for public_key in public_keys {
+        aggregate += parse_public_key(public_key.as_ref())?;
+    }
+
+    let signature = parse_signature(signature)?;
+
+    Ok(pairing_check(&[(G1Affine::from(aggregate), hash(msg, DST))], &signature))
+}
+
+/// Verifies an aggregate signature where each of the `public_keys` signed the
+/// message at the same index in `msgs`. All messages must be different.
+pub fn aggregate_verify(
+    public_keys: &[impl AsRef<[u8]>],
+    msgs: &[impl AsRef<[u8]>],
+    signature: &[u8]
+) -> StdResult<bool> {
+    if public_keys.len() != msgs.len() {
+        return Err(StdError::generic_err(
+            "The number of public keys and messages must be the same."
+        ));
+    }
+
+    if public_keys.is_empty() {
+        return Ok(false);
+    }
+
+    for (i, msg) in msgs.iter().enumerate() {
+        if msgs[..i].iter().any(|x| x.as_ref() == msg.as_ref()) {
+            return Err(StdError::generic_err("Aggregated messages must be distinct."));
+        }
+    }
+
+    let mut terms = Vec::with_capacity(public_keys.len());
+
+    for (public_key, msg) in public_keys.iter().zip(msgs) {
+        terms.push((parse_public_key(public_key.as_ref())?, hash(msg.as_ref(), DST)));
+    }
+
+    let signature = parse_signature(signature)?;
+
+    Ok(pairing_check(&terms, &signature))
+}
+
+/// Aggregates the given signatures into one.
+pub fn aggregate_signatures(signatures: &[impl AsRef<[u8]>]) -> StdResult<Binary> {
+    if signatures.is_empty() {
+        return Err(StdError::generic_err("No signatures to aggregate."));
+    }
+
+    let mut aggregate = G2Projective::identity();
+
+    for signature in signatures {
+        aggregate += parse_signature(signature.as_ref())?;
+    }
+
+    Ok(Binary::from(G2Affine::from(aggregate).to_compressed()))
+}
+
+/// Checks that `e(pk_1, H(m_1)) * ... * e(pk_n, H(m_n)) == e(g1, signature)`.
+fn pairing_check(terms: &[(G1Affine, G2Affine)], signature: &G2Affine) -> bool {
+    let generator = -G1Affine::generator();
+    let signature = G2Prepared::from(*signature);
+
+    let prepared: Vec<_> = terms.iter()
+        .map(|(public_key, msg)| (public_key, G2Prepared::from(*msg)))
+        .collect();
+
+    let mut pairs: Vec<_> = prepared.iter()
+        .map(|(public_key, msg)| (*public_key, msg))
+        .collect();
+
+    pairs.push((&generator, &signature));
+
+    multi_miller_loop(&pairs).final_exponentiation() == Gt::identity()
+}
+
+#[inline]
+fn hash(msg: &[u8], dst: &[u8]) -> G2Affine {
+    <G2Projective as HashToCurve<ExpandMsgXmd<sha2_09::Sha256>>>::hash_to_curve(msg, dst).into()
+}
+
+fn parse_public_key(bytes: &[u8]) -> StdResult<G1Affine> {
+    let bytes: &[u8; PUBLIC_KEY_LEN] = bytes.try_into().map_err(|_|
+        StdError::generic_err(format!("BLS public keys must be {} bytes long.", PUBLIC_KEY_LEN))
+    )?;
+
+    let point: Option<G1Affine> = G1Affine::from_compressed(bytes).into();
+
+    match point {
+        Some(point) if !bool::from(point.is_identity()) => Ok(point),
+        _ => Err(StdError::generic_err("Invalid BLS public key."))
+    }
+}
+
+fn parse_signature(bytes: &[u8]) -> StdResult<G2Affine> {
+    let bytes: &[u8; SIGNATURE_LEN] = bytes.try_into().map_err(|_|
+        StdError::generic_err(format!("BLS signatures must be {} bytes long.", SIGNATURE_LEN))
+    )?;
+
+    Option::from(G2Affine::from_compressed(bytes))
+        .ok_or_else(|| StdError::generic_err("Invalid BLS signature."))
+}
+
+#[cfg(test)]
+mod tests {
+    use bls12_381::Scalar;
+
+    use super::*;
+
+    fn keypair(secret: u64) -> (Scalar, [u8; PUBLIC_KEY_LEN]) {
+        let secret = Scalar::from(secret);
+        let public_key = G1Affine::from(G1Affine::generator() * secret);
+
+        (secret, public_key.to_compressed())
+    }
+
+    fn sign(secret: &Scalar, msg: &[u8], dst: &[u8]) -> [u8; SIGNATURE_LEN] {
+        G2Affine::from(hash(msg, dst) * secret).to_compressed()
+    }
+
+    #[test]
+    fn verifies_known_signature() {
+        let public_key = hex("a491d1b0ecd9bb917989f0e74f0dea0422eac4a873e5e2644f368dffb9a6e20fd6e10c1b77654d067c0618f6e5a7f79a");
+        let signature = hex("b6ed936746e01f8ecf281f020953fbf1f01debd5657c4a383940b020b26507f6076334f91e2366c96e9ab279fb5158090352ea1c5b0c9274504f4f0e7053af24802e51e4568d164fe986834f41e55c8e850ce1f98458c0cfc9ab380b55285a55");
+
+        assert!(verify(&public_key, &[0u8; 32], &signature).unwrap());
+        assert!(!verify(&public_key, &[1u8; 32], &signature).unwrap());
+    }
+
+    #[test]
+    fn single_and_aggregate() {
+        let msg = b"price: 100";
+        let committee: Vec<_> = (1..=3).map(keypair).collect();
+
+        let (secret, public_key) = &committee[0];
+        let signature = sign(secret, msg, DST);
+
+        assert!(verify(public_key, msg, &signature).unwrap());
+        assert!(!verify(&committee[1].1, msg, &signature).unwrap());
+
+        let proof = sign(secret, public_key, POP_DST);
+        assert!(verify_possession(public_key, &proof).unwrap());
+        assert!(!verify_possession(public_key, &signature).unwrap());
+
+        let public_keys: Vec<_> = committee.iter().map(|x| x.1).collect();
+        let signatures: Vec<_> = committee.iter().map(|x| sign(&x.0, msg, DST)).collect();
+        let aggregate = aggregate_signatures(&signatures).unwrap();
+
+        assert!(fast_aggregate_verify(&public_keys, msg, &aggregate).unwrap());
+        assert!(!fast_aggregate_verify(&public_keys[..2], msg, &aggregate).unwrap());
+
+        let msgs = [b"a", b"b", b"c"];
+        let signatures: Vec<_> = committee.iter()
+            .zip(msgs)
+            .map(|(x, msg)| sign(&x.0, msg, DST))
+            .collect();
+        let aggregate = aggregate_signatures(&signatures).unwrap();
+
+        assert!(aggregate_verify(&public_keys, &msgs, &aggregate).unwrap());
+        assert!(!aggregate_verify(&public_keys, &[b"a", b"c", b"b"], &aggregate).unwrap());
+        assert!(aggregate_verify(&public_keys, &[b"a", b"a", b"b"], &aggregate).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_points() {
+        let (secret, public_key) = keypair(1);
+        let signature = sign(&secret, b"msg", DST);
+
+        assert!(verify(&public_key[1..], b"msg", &signature).is_err());
+        assert!(verify(&public_key, b"msg", &[0u8; SIGNATURE_LEN]).is_err());
+        assert!(verify(&G1Affine::identity().to_compressed(), b"msg", &signature).is_err());
+    }
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+}
@@ -0,0 +1,231 @@
+//! Encryption of data to the X25519 public key of a recipient so that
+//! only they can decrypt it, i.e. off-chain. *Feature flag: `ecies`*
+//!
+//! A fresh ephemeral key pair is used for each message and combined with the public
+//! key of the recipient to derive the keys for encrypting and authenticating it:
+//!
+//! 1. `shared = X25519(ephemeral_secret, recipient)`
+//! 2. `enc_key || mac_key = HKDF-SHA256(ikm = shared, salt = "", info = INFO || ephemeral_public || recipient, len = 64)`
+//! 3. `ciphertext = plaintext XOR ChaCha20(enc_key, nonce = 0)` - the original variant with a 64-bit nonce
+//! 4. `tag = HMAC-SHA256(mac_key, ephemeral_public || ciphertext)`
+//!
+//! The result is `ephemeral_public || ciphertext || tag`. Since the keys
+//! are only ever used once, there is no need for a random nonce.
+
+use curve25519_dalek::{
+    constants::X25519_BASEPOINT,
+    montgomery::MontgomeryPoint,
+    scalar::Scalar
+};
+use hmac::{Hmac, Mac};
+use rand_chacha::ChaChaRng;
+use rand_core::{RngCore, SeedableRng};
+use sha2::Sha256;
+
+use crate::cosmwasm_std::{StdResult, StdError};
+
+pub const KEY_LEN: usize = 32;
+pub const TAG_LEN: usize = 32;
+/// The number of bytes that [`encrypt`] adds to the plaintext.
+pub const OVERHEAD: usize = KEY_LEN + TAG_LEN;
+
+/// Domain separation for the key derivation.
+pub const INFO: &[u8] = b"fadroma-ecies-x25519-chacha20-hmac-sha256";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Returns the public key corresponding to the given secret key.
+pub fn public_key(secret: &[u8; KEY_LEN]) -> [u8; KEY_LEN] {
+    (X25519_BASEPOINT * clamp(secret)).to_bytes()
+}
+
+/// Encrypts `plaintext` so that only the owner of the `recipient` public key can decrypt it.
+/// The `ephemeral_secret` must be secret and must never be reused, i.e. derived from
+/// `env.block.random` on Secret Network.
+///
+/// Returns `Err` if `recipient` is not a valid public key.
+pub fn encrypt(
+    recipient: &[u8],
+    plaintext: &[u8],
+    ephemeral_secret: [u8; KEY_LEN]
+) -> StdResult<Vec<u8>> {
+    let recipient = decode_key(recipient)?;
+    let ephemeral = public_key(&ephemeral_secret);
+    let shared = diffie_hellman(&ephemeral_secret, &recipient)?;
+
+    let (enc_key, mac_key) = derive_keys(&shared, &ephemeral, &recipient);
+
+    let mut result = Vec::with_capacity(plaintext.len() + OVERHEAD);
+    result.extend_from_slice(&ephemeral);
+    result.extend_from_slice(plaintext);
+    apply_keystream(&enc_key, &mut result[KEY_LEN..]);
+
+    let tag = tag(&mac_key, &result);
+    result.extend_from_slice(&tag);
+
+    Ok(result)
+}
+
+/// Decrypts data produced by [`encrypt`] using the secret key of the recipient.
+///
+/// Returns `Err` if the data was not encrypted to the given key or was tampered with.
+pub fn decrypt(secret: &[u8; KEY_LEN], data: &[u8]) -> StdResult<Vec<u8>> {
+    if data.len() < OVERHEAD {
+        return Err(invalid_data());
+    }
+
+    let (data, expected_tag) = data.split_at(data.len() - TAG_LEN);
+    let ephemeral: [u8; KEY_LEN] = data[..KEY_LEN].try_into().unwrap();
+    let recipient = public_key(secret);
+    let shared = diffie_hellman(secret, &ephemeral)?;
+
+    let (enc_key, mac_key) = derive_keys(&shared, &ephemeral, &recipient);
+
+    let mut mac = HmacSha256::new_from_slice(&mac_key).unwrap();
+    mac.update(data);
+    mac.verify_slice(expected_tag).map_err(|_| invalid_data())?;
+
+    let mut plaintext = data[KEY_LEN..].to_vec();
+    apply_keystream(&enc_key, &mut plaintext);
+
+    Ok(plaintext)
+}
+
+/// Returns `Err` if `key` is not the right length.
+pub fn decode_key(key: &[u8]) -> StdResult<[u8; KEY_LEN]> {
+    key.try_into().map_err(|_|
+        StdError::generic_err(format!("X25519 public keys must be {} bytes long.", KEY_LEN))
+    )
+}
+
+fn diffie_hellman(secret: &[u8; KEY_LEN], public: &[u8; KEY_LEN]) -> StdResult<[u8; KEY_LEN]> {
+    let shared = (MontgomeryPoint(*public) * clamp(secret)).to_bytes();
+
+    // Low order points result in a shared secret that anyone can compute.
+    if shared == [0u8; KEY_LEN] {
+        return Err(StdError::generic_err("Invalid X25519 public key."));
+    }
+
+    Ok(shared)
+}
+
+fn derive_keys(
+    shared: &[u8; KEY_LEN],
+    ephemeral: &[u8; KEY_LEN],
+    recipient: &[u8; KEY_LEN]
+) -> ([u8; 32], [u8; 32]) {
+    // HKDF-Extract with an empty salt which is the same as a zeroed one.
+    let mut mac = HmacSha256::new_from_slice(&[0u8; 32]).unwrap();
+    mac.update(shared);
+    let prk = mac.finalize().into_bytes();
+
+    // HKDF-Expand
+    let expand = |previous: &[u8], counter: u8| {
+        let mut mac = HmacSha256::new_from_slice(&prk).unwrap();
+        mac.update(previous);
+        mac.update(INFO);
+        mac.update(ephemeral);
+        mac.update(recipient);
+        mac.update(&[counter]);
+
+        <[u8; 32]>::from(mac.finalize().into_bytes())
+    };
+
+    let enc_key = expand(&[], 1);
+    let mac_key = expand(&enc_key, 2);
+
+    (enc_key, mac_key)
+}
+
+#[inline]
+fn apply_keystream(key: &[u8; 32], data: &mut [u8]) {
+    let mut keystream = vec![0u8; data.len()];
+    ChaChaRng::from_seed(*key).fill_bytes(&mut keystream);
+
+    data.iter_mut()
+        .zip(keystream)
+        .for_each(|(byte, key)| *byte ^= key);
+}
+
+#[inline]
+fn tag(mac_key: &[u8; 32], data: &[u8]) -> [u8; TAG_LEN] {
+    let mut mac = HmacSha256::new_from_slice(mac_key).unwrap();
+    mac.update(data);
+
+    mac.finalize().into_bytes().into()
+}
+
+#[inline]
+fn clamp(secret: &[u8; KEY_LEN]) -> Scalar {
+    let mut bytes = *secret;
+    bytes[0] &= 248;
+    bytes[31] &= 127;
+    bytes[31] |= 64;
+
+    Scalar::from_bits(bytes)
+}
+
+#[inline]
+fn invalid_data() -> StdError {
+    StdError::generic_err("Invalid encrypted data.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> [u8; 32] {
+        let mut result = [0u8; 32];
+
+        for (i, byte) in result.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).unwrap();
+        }
+
+        result
+    }
+
+    #[test]
+    fn x25519_test_vectors() {
+        // From RFC 7748, section 6.1
+        let alice = hex("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+        let bob = hex("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb");
+
+        assert_eq!(public_key(&alice), hex("8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a"));
+        assert_eq!(public_key(&bob), hex("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f"));
+
+        let shared = hex("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742");
+        assert_eq!(diffie_hellman(&alice, &public_key(&bob)).unwrap(), shared);
+        assert_eq!(diffie_hellman(&bob, &public_key(&alice)).unwrap(), shared);
+    }
+
+    #[test]
+    fn round_trip() {
+        let secret = [7u8; 32];
+        let recipient = public_key(&secret);
+        let plaintext = b"{\"amount\":\"100\"}";
+
+        let encrypted = encrypt(&recipient, plaintext, [1u8; 32]).unwrap();
+        assert_eq!(encrypted.len(), plaintext.len() + OVERHEAD);
+        assert_ne!(&encrypted[KEY_LEN..KEY_LEN + plaintext.len()], plaintext);
+        assert_eq!(decrypt(&secret, &encrypted).unwrap(), plaintext);
+
+        // Different ephemeral keys produce different ciphertexts.
+        let other = encrypt(&recipient, plaintext, [2u8; 32]).unwrap();
+        assert_ne!(other, encrypted);
+        assert_eq!(decrypt(&secret, &other).unwrap(), plaintext);
+
+        // Only the recipient can decrypt.
+        assert_eq!(decrypt(&[8u8; 32], &encrypted).unwrap_err(), invalid_data());
+
+        let mut tampered = encrypted.clone();
+        tampered[KEY_LEN] ^= 1;
+        assert_eq!(decrypt(&secret, &tampered).unwrap_err(), invalid_data());
+        assert_eq!(decrypt(&secret, &encrypted[..OVERHEAD - 1]).unwrap_err(), invalid_data());
+
+        assert!(encrypt(&recipient[..31], plaintext, [1u8; 32]).is_err());
+        assert!(encrypt(&[0u8; 32], plaintext, [1u8; 32]).is_err());
+
+        let empty = encrypt(&recipient, &[], [3u8; 32]).unwrap();
+        assert_eq!(decrypt(&secret, &empty).unwrap(), Vec::<u8>::new());
+    }
+}
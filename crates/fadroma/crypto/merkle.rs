@@ -0,0 +1,507 @@
+use std::marker::PhantomData;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    self as fadroma,
+    bin_serde::{FadromaSerialize, FadromaDeserialize},
+    cosmwasm_std::{Storage, StdResult, StdError, Binary},
+    storage::{self, Namespace},
+    schemars::JsonSchema
+};
+use super::sha_256;
+
+const HASH_BITS: u16 = 256;
+const EMPTY: [u8; 32] = [0u8; 32];
+
+const NODES_PREFIX: &[u8] = b"n";
+const VALUES_PREFIX: &[u8] = b"v";
+
+/// A sparse Merkle tree of key/value pairs stored under the given [`Namespace`].
+/// Every write goes through the tree so that its root always commits to all
+/// of the values stored in it. Expose [`MerkleTree::root`] and [`MerkleTree::prove`]
+/// via queries and off-chain consumers can verify any value (or its absence) against
+/// the root using [`MerkleProof::verify`], without having to trust the node they query.
+///
+/// Keys are placed in the tree by their SHA256 hash. Subtrees that contain a single
+/// leaf are collapsed into that leaf so that each write only touches as many nodes as
+/// it takes to tell the key apart from its neighbours, rather than all 256 levels.
+///
+/// # Examples
+///
+/// ```
+/// use fadroma::{
+///     cosmwasm_std::testing::mock_dependencies,
+///     crypto::MerkleTree
+/// };
+///
+/// fadroma::namespace!(BalancesNs, b"balances");
+/// const BALANCES: MerkleTree<u128, BalancesNs> = MerkleTree::new();
+///
+/// let mut deps = mock_dependencies();
+/// let storage = deps.as_mut().storage;
+///
+/// BALANCES.save(storage, b"alice", &100).unwrap();
+/// BALANCES.save(storage, b"bob", &50).unwrap();
+///
+/// let root = BALANCES.root(storage).unwrap();
+///
+/// let proof = BALANCES.prove(storage, b"alice").unwrap();
+/// assert!(proof.verify(&root, b"alice", Some(&100u128)).unwrap());
+/// assert!(!proof.verify(&root, b"alice", Some(&99u128)).unwrap());
+///
+/// let proof = BALANCES.prove(storage, b"charlie").unwrap();
+/// assert!(proof.verify::<u128>(&root, b"charlie", None).unwrap());
+/// ```
+pub struct MerkleTree<V: FadromaSerialize + FadromaDeserialize, N: Namespace> {
+    namespace_data: PhantomData<N>,
+    value_data: PhantomData<V>
+}
+
+/// Proof of inclusion or exclusion of a key in a [`MerkleTree`].
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Clone, Debug)]
+pub struct MerkleProof {
+    /// The leaf found at the position of the key, if any. If it belongs
+    /// to a different key, the proof is one of exclusion.
+    pub leaf: Option<MerkleLeaf>,
+    /// The hashes of the siblings along the path of the key,
+    /// ordered from the one closest to the root.
+    pub siblings: Vec<Binary>
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Clone, Debug)]
+pub struct MerkleLeaf {
+    /// The SHA256 hash of the key.
+    pub key: Binary,
+    /// The SHA256 hash of the serialized value.
+    pub value: Binary
+}
+
+#[derive(FadromaSerialize, FadromaDeserialize, Clone, Copy, PartialEq, Debug)]
+enum Node {
+    Leaf {
+        key: [u8; 32],
+        value: [u8; 32]
+    },
+    Internal {
+        hash: [u8; 32]
+    }
+}
+
+impl<V: FadromaSerialize + FadromaDeserialize, N: Namespace> MerkleTree<V, N> {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            namespace_data: PhantomData,
+            value_data: PhantomData
+        }
+    }
+
+    #[inline]
+    pub fn namespace(&self) -> &'static [u8] {
+        N::NAMESPACE
+    }
+
+    /// The root hash of the tree which commits to all of its values.
+    /// An empty tree has a root of all zeroes.
+    #[inline]
+    pub fn root(&self, storage: &dyn Storage) -> StdResult<Binary> {
+        self.node_hash(storage, 0, &EMPTY).map(Binary::from)
+    }
+
+    #[inline]
+    pub fn load(
+        &self,
+        storage: &dyn Storage,
+        key: impl AsRef<[u8]>
+    ) -> StdResult<Option<V>> {
+        let key = sha_256(key.as_ref());
+
+        storage::load(storage, self.value_key(&key))
+    }
+
+    #[inline]
+    pub fn load_or_default(
+        &self,
+        storage: &dyn Storage,
+        key: impl AsRef<[u8]>
+    ) -> StdResult<V> where V: Default {
+        let result = self.load(storage, key)?;
+
+        Ok(result.unwrap_or_default())
+    }
+
+    /// Saves the value and updates the hashes along its path to the root.
+    pub fn save(
+        &self,
+        storage: &mut dyn Storage,
+        key: impl AsRef<[u8]>,
+        value: &V
+    ) -> StdResult<()> {
+        let key = sha_256(key.as_ref());
+        let bytes = storage::serialize(value)?;
+
+        storage.set(&self.value_key(&key), &bytes);
+
+        let leaf = Node::Leaf { key, value: sha_256(&bytes) };
+        let mut depth = 0;
+
+        let bottom = loop {
+            match self.load_node(storage, depth, &key)? {
+                None => {
+                    self.save_node(storage, depth, &key, &leaf)?;
+
+                    break depth;
+                },
+                Some(Node::Internal { .. }) => depth += 1,
+                Some(Node::Leaf { key: other, value }) => {
+                    if other == key {
+                        self.save_node(storage, depth, &key, &leaf)?;
+
+                        break depth;
+                    }
+
+                    // Push the existing leaf down until the two keys diverge.
+                    let split = first_diff(&key, &other);
+
+                    self.save_node(storage, split + 1, &other, &Node::Leaf { key: other, value })?;
+                    self.save_node(storage, split + 1, &key, &leaf)?;
+
+                    break split + 1;
+                }
+            }
+        };
+
+        self.update_path(storage, &key, bottom)
+    }
+
+    /// Removes the value and collapses any subtrees that
+    /// are left with a single leaf as a result.
+    pub fn remove(
+        &self,
+        storage: &mut dyn Storage,
+        key: impl AsRef<[u8]>
+    ) -> StdResult<()> {
+        let key = sha_256(key.as_ref());
+        let mut depth = 0;
+
+        loop {
+            match self.load_node(storage, depth, &key)? {
+                Some(Node::Internal { .. }) => depth += 1,
+                Some(Node::Leaf { key: other, .. }) if other == key => break,
+                _ => return Ok(())
+            }
+        }
+
+        storage.remove(&self.value_key(&key));
+        storage.remove(&self.node_key(depth, &key));
+
+        while depth > 0 {
+            let sibling = flip_bit(&key, depth - 1);
+            let ours = self.load_node(storage, depth, &key)?;
+            let theirs = self.load_node(storage, depth, &sibling)?;
+
+            let (leaf, from) = match (ours, theirs) {
+                (Some(leaf @ Node::Leaf { .. }), None) => (leaf, &key),
+                (None, Some(leaf @ Node::Leaf { .. })) => (leaf, &sibling),
+                _ => break
+            };
+
+            storage.remove(&self.node_key(depth, from));
+            self.save_node(storage, depth - 1, &key, &leaf)?;
+
+            depth -= 1;
+        }
+
+        self.update_path(storage, &key, depth)
+    }
+
+    /// Creates a proof of the value (or the absence of one) for the given key.
+    pub fn prove(
+        &self,
+        storage: &dyn Storage,
+        key: impl AsRef<[u8]>
+    ) -> StdResult<MerkleProof> {
+        let key = sha_256(key.as_ref());
+        let mut siblings = vec![];
+        let mut depth = 0;
+
+        loop {
+            match self.load_node(storage, depth, &key)? {
+                None => return Ok(MerkleProof { leaf: None, siblings }),
+                Some(Node::Leaf { key, value }) => return Ok(MerkleProof {
+                    leaf: Some(MerkleLeaf {
+                        key: Binary::from(key),
+                        value: Binary::from(value)
+                    }),
+                    siblings
+                }),
+                Some(Node::Internal { .. }) => {
+                    depth += 1;
+
+                    let sibling = self.node_hash(storage, depth, &flip_bit(&key, depth - 1))?;
+                    siblings.push(Binary::from(sibling));
+                }
+            }
+        }
+    }
+
+    /// Recomputes the hashes of the internal nodes above `bottom`.
+    fn update_path(
+        &self,
+        storage: &mut dyn Storage,
+        key: &[u8; 32],
+        bottom: u16
+    ) -> StdResult<()> {
+        for depth in (0..bottom).rev() {
+            let (left, right) = (set_bit(key, depth, false), set_bit(key, depth, true));
+            let hash = hash_internal(
+                &self.node_hash(storage, depth + 1, &left)?,
+                &self.node_hash(storage, depth + 1, &right)?
+            );
+
+            self.save_node(storage, depth, key, &Node::Internal { hash })?;
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn node_hash(&self, storage: &dyn Storage, depth: u16, key: &[u8; 32]) -> StdResult<[u8; 32]> {
+        let node = self.load_node(storage, depth, key)?;
+
+        Ok(node.map(|x| x.hash()).unwrap_or(EMPTY))
+    }
+
+    #[inline]
+    fn load_node(&self, storage: &dyn Storage, depth: u16, key: &[u8; 32]) -> StdResult<Option<Node>> {
+        storage::load(storage, self.node_key(depth, key))
+    }
+
+    #[inline]
+    fn save_node(&self, storage: &mut dyn Storage, depth: u16, key: &[u8; 32], node: &Node) -> StdResult<()> {
+        storage::save(storage, self.node_key(depth, key), node)
+    }
+
+    fn node_key(&self, depth: u16, key: &[u8; 32]) -> Vec<u8> {
+        let mut result = Vec::with_capacity(N::NAMESPACE.len() + NODES_PREFIX.len() + 2 + 32);
+        result.extend_from_slice(N::NAMESPACE);
+        result.extend_from_slice(NODES_PREFIX);
+        result.extend_from_slice(&depth.to_be_bytes());
+        result.extend_from_slice(&prefix(key, depth));
+
+        result
+    }
+
+    fn value_key(&self, key: &[u8; 32]) -> Vec<u8> {
+        [N::NAMESPACE, VALUES_PREFIX, key].concat()
+    }
+}
+
+impl MerkleProof {
+    /// Checks that `value` is stored under `key` in the tree with the given `root`.
+    /// If `value` is `None`, checks that nothing is stored under `key` instead.
+    pub fn verify<V: FadromaSerialize>(
+        &self,
+        root: &Binary,
+        key: impl AsRef<[u8]>,
+        value: Option<&V>
+    ) -> StdResult<bool> {
+        if self.siblings.len() > HASH_BITS as usize {
+            return Err(StdError::generic_err("Merkle proof is too long."));
+        }
+
+        let key = sha_256(key.as_ref());
+        let leaf = match &self.leaf {
+            Some(leaf) => Some((to_hash(&leaf.key)?, to_hash(&leaf.value)?)),
+            None => None
+        };
+
+        match (value, leaf) {
+            (Some(value), Some((leaf_key, leaf_value))) => {
+                let value = sha_256(&storage::serialize(value)?);
+
+                if leaf_key != key || leaf_value != value {
+                    return Ok(false);
+                }
+            },
+            (Some(_), None) => return Ok(false),
+            (None, Some((leaf_key, _))) if leaf_key == key => return Ok(false),
+            (None, _) => { }
+        }
+
+        let mut hash = leaf.map(|(key, value)| hash_leaf(&key, &value)).unwrap_or(EMPTY);
+
+        for (depth, sibling) in self.siblings.iter().enumerate().rev() {
+            let sibling = to_hash(sibling)?;
+
+            hash = if get_bit(&key, depth as u16) {
+                hash_internal(&sibling, &hash)
+            } else {
+                hash_internal(&hash, &sibling)
+            };
+        }
+
+        Ok(hash.as_slice() == root.as_slice())
+    }
+}
+
+impl Node {
+    #[inline]
+    fn hash(&self) -> [u8; 32] {
+        match self {
+            Self::Leaf { key, value } => hash_leaf(key, value),
+            Self::Internal { hash } => *hash
+        }
+    }
+}
+
+#[inline]
+fn hash_leaf(key: &[u8; 32], value: &[u8; 32]) -> [u8; 32] {
+    sha_256(&[&[0u8][..], key, value].concat())
+}
+
+#[inline]
+fn hash_internal(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    sha_256(&[&[1u8][..], left, right].concat())
+}
+
+fn to_hash(bytes: &Binary) -> StdResult<[u8; 32]> {
+    bytes.as_slice().try_into().map_err(|_|
+        StdError::generic_err("Merkle proof hashes must be 32 bytes long.")
+    )
+}
+
+#[inline]
+fn get_bit(key: &[u8; 32], bit: u16) -> bool {
+    key[(bit / 8) as usize] & (0x80 >> (bit % 8)) != 0
+}
+
+#[inline]
+fn set_bit(key: &[u8; 32], bit: u16, value: bool) -> [u8; 32] {
+    let mut result = *key;
+    let mask = 0x80 >> (bit % 8);
+
+    if value {
+        result[(bit / 8) as usize] |= mask;
+    } else {
+        result[(bit / 8) as usize] &= !mask;
+    }
+
+    result
+}
+
+#[inline]
+fn flip_bit(key: &[u8; 32], bit: u16) -> [u8; 32] {
+    set_bit(key, bit, !get_bit(key, bit))
+}
+
+/// The first `depth` bits of the key, with the rest set to zero.
+fn prefix(key: &[u8; 32], depth: u16) -> [u8; 32] {
+    let mut result = [0u8; 32];
+
+    for bit in 0..depth {
+        if get_bit(key, bit) {
+            result = set_bit(&result, bit, true);
+        }
+    }
+
+    result
+}
+
+fn first_diff(a: &[u8; 32], b: &[u8; 32]) -> u16 {
+    (0..HASH_BITS)
+        .find(|&bit| get_bit(a, bit) != get_bit(b, bit))
+        .expect("keys must be different")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cosmwasm_std::testing::MockStorage;
+
+    crate::namespace!(TreeNs, b"tree");
+    const TREE: MerkleTree<u64, TreeNs> = MerkleTree::new();
+
+    #[test]
+    fn root_depends_only_on_contents() {
+        let mut a = MockStorage::new();
+        let mut b = MockStorage::new();
+
+        assert_eq!(TREE.root(&a).unwrap(), Binary::from(EMPTY));
+
+        for i in 0..50u64 {
+            TREE.save(&mut a, i.to_be_bytes(), &i).unwrap();
+        }
+
+        for i in (0..50u64).rev() {
+            TREE.save(&mut b, i.to_be_bytes(), &(i + 1)).unwrap();
+            TREE.save(&mut b, i.to_be_bytes(), &i).unwrap();
+        }
+
+        for i in 50..60u64 {
+            TREE.save(&mut b, i.to_be_bytes(), &i).unwrap();
+        }
+
+        assert_ne!(TREE.root(&a).unwrap(), TREE.root(&b).unwrap());
+
+        for i in 50..60u64 {
+            TREE.remove(&mut b, i.to_be_bytes()).unwrap();
+        }
+
+        assert_eq!(TREE.root(&a).unwrap(), TREE.root(&b).unwrap());
+        assert_eq!(TREE.load(&b, 60u64.to_be_bytes()).unwrap(), None);
+
+        for i in 0..50u64 {
+            TREE.remove(&mut b, i.to_be_bytes()).unwrap();
+        }
+
+        assert_eq!(TREE.root(&b).unwrap(), Binary::from(EMPTY));
+        assert_eq!(b.range(None, None, crate::cosmwasm_std::Order::Ascending).count(), 0);
+    }
+
+    #[test]
+    fn proofs() {
+        let mut storage = MockStorage::new();
+
+        for i in 0..20u64 {
+            TREE.save(&mut storage, i.to_be_bytes(), &(i * 2)).unwrap();
+        }
+
+        let root = TREE.root(&storage).unwrap();
+
+        for i in 0..20u64 {
+            let proof = TREE.prove(&storage, i.to_be_bytes()).unwrap();
+
+            assert!(proof.verify(&root, i.to_be_bytes(), Some(&(i * 2))).unwrap());
+            assert!(!proof.verify(&root, i.to_be_bytes(), Some(&(i * 2 + 1))).unwrap());
+            assert!(!proof.verify::<u64>(&root, i.to_be_bytes(), None).unwrap());
+            assert!(!proof.verify(&root, 100u64.to_be_bytes(), Some(&(i * 2))).unwrap());
+        }
+
+        for i in 20..40u64 {
+            let proof = TREE.prove(&storage, i.to_be_bytes()).unwrap();
+
+            assert!(proof.verify::<u64>(&root, i.to_be_bytes(), None).unwrap());
+            assert!(!proof.verify(&root, i.to_be_bytes(), Some(&0u64)).unwrap());
+        }
+
+        let mut proof = TREE.prove(&storage, 1u64.to_be_bytes()).unwrap();
+        proof.siblings[0] = Binary::from(EMPTY);
+
+        assert!(!proof.verify(&root, 1u64.to_be_bytes(), Some(&2u64)).unwrap());
+    }
+
+    #[test]
+    fn corrupted_nodes_are_errors() {
+        let mut storage = MockStorage::new();
+
+        TREE.save(&mut storage, 1u64.to_be_bytes(), &1).unwrap();
+        storage.set(&TREE.node_key(0, &EMPTY), b"garbage");
+
+        assert!(TREE.root(&storage).is_err());
+        assert!(TREE.prove(&storage, 1u64.to_be_bytes()).is_err());
+        assert!(TREE.save(&mut storage, 2u64.to_be_bytes(), &2).is_err());
+        assert!(TREE.remove(&mut storage, 1u64.to_be_bytes()).is_err());
+    }
+}
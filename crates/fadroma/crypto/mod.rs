@@ -1,6 +1,23 @@
-//! SHA256 hashing and pseudo rng.
+//! SHA256 hashing, pseudo rng, secret sharing and a sparse Merkle tree.
 //! *Feature flag: `crypto`*
 
+#[cfg(feature = "bls")]
+pub mod bls;
+
+#[cfg(feature = "vrf")]
+pub mod vrf;
+
+#[cfg(feature = "poseidon")]
+pub mod poseidon;
+
+#[cfg(feature = "ecies")]
+pub mod ecies;
+
+pub mod shamir;
+
+mod merkle;
+pub use merkle::*;
+
 use rand_chacha::ChaChaRng;
 use rand_core::{RngCore, SeedableRng};
 use sha2::{Digest, Sha256};
@@ -0,0 +1,89 @@
+//! The Poseidon hash function over the BN254 scalar field. Uses the same parameters
+//! as the `Poseidon` template of [circomlib](https://github.com/iden3/circomlib) so
+//! that contracts can verify commitments produced by circom circuits (and `circomlibjs`).
+//! *Feature flag: `poseidon`*
+
+use ark_bn254::Fr;
+use light_poseidon::{Poseidon, PoseidonBytesHasher, PoseidonError};
+
+use crate::cosmwasm_std::{StdResult, StdError, Uint256};
+
+/// The maximum number of inputs that can be hashed at once.
+pub const MAX_INPUTS: usize = 12;
+
+/// Hashes the given field elements, each one encoded as at most 32
+/// big-endian bytes. Returns the hash as 32 big-endian bytes.
+///
+/// Returns `Err` if there are no inputs or more than [`MAX_INPUTS`],
+/// or if any of them is not smaller than the field modulus.
+///
+/// # Examples
+///
+/// ```
+/// use fadroma::{
+///     cosmwasm_std::Uint256,
+///     crypto::poseidon::{poseidon, poseidon_uint}
+/// };
+///
+/// let hash = poseidon(&[&[1], &[2]]).unwrap();
+/// let expected = Uint256::from_be_bytes(hash);
+///
+/// assert_eq!(poseidon_uint(&[1u8.into(), 2u8.into()]).unwrap(), expected);
+/// ```
+pub fn poseidon(inputs: &[&[u8]]) -> StdResult<[u8; 32]> {
+    if inputs.is_empty() || inputs.len() > MAX_INPUTS {
+        return Err(StdError::generic_err(format!(
+            "Poseidon takes between 1 and {} inputs, got {}.",
+            MAX_INPUTS,
+            inputs.len()
+        )));
+    }
+
+    let mut hasher = Poseidon::<Fr>::new_circom(inputs.len()).map_err(into_std_err)?;
+
+    hasher.hash_bytes_be(inputs).map_err(into_std_err)
+}
+
+/// Same as [`poseidon`] but takes and returns numbers.
+/// Use it to hash the public signals of a circom circuit.
+pub fn poseidon_uint(inputs: &[Uint256]) -> StdResult<Uint256> {
+    let inputs: Vec<[u8; 32]> = inputs.iter().map(|x| x.to_be_bytes()).collect();
+    let inputs: Vec<&[u8]> = inputs.iter().map(|x| x.as_slice()).collect();
+
+    poseidon(&inputs).map(Uint256::from_be_bytes)
+}
+
+#[inline]
+fn into_std_err(err: PoseidonError) -> StdError {
+    StdError::generic_err(format!("Poseidon: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn matches_circomlib() {
+        let hash = poseidon_uint(&[Uint256::from(1u8)]).unwrap();
+        assert_eq!(hash, Uint256::from_str(
+            "18586133768512220936620570745912940619677854269274689475585506675881198879027"
+        ).unwrap());
+
+        let hash = poseidon_uint(&[Uint256::from(1u8), Uint256::from(2u8)]).unwrap();
+        assert_eq!(hash, Uint256::from_str(
+            "7853200120776062878684798364095072458815029376092732009249414926327459813530"
+        ).unwrap());
+    }
+
+    #[test]
+    fn rejects_invalid_inputs() {
+        assert!(poseidon(&[]).is_err());
+        assert!(poseidon(&[&[1u8][..]; MAX_INPUTS + 1]).is_err());
+        assert!(poseidon(&[&[1u8][..]; MAX_INPUTS]).is_ok());
+
+        assert!(poseidon_uint(&[Uint256::MAX]).is_err());
+        assert!(poseidon(&[&[1u8; 33]]).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+use crate::cosmwasm_std::{StdResult, StdError};
+use super::Prng;
+
+/// Splits `secret` into `count` shares so that any `threshold` of them can be
+/// used to recover it with [`combine`], while fewer reveal nothing about it.
+/// Each share is one byte longer than the secret.
+///
+/// # Examples
+///
+/// ```
+/// use fadroma::crypto::{Prng, shamir};
+///
+/// let mut prng = Prng::new(b"seed", b"entropy");
+/// let shares = shamir::split(b"secret", 2, 3, &mut prng).unwrap();
+///
+/// assert_eq!(shamir::combine(&shares[1..]).unwrap(), b"secret");
+/// assert_eq!(shamir::combine(&[&shares[0], &shares[2]]).unwrap(), b"secret");
+/// assert_ne!(shamir::combine(&shares[..1]).unwrap(), b"secret");
+/// ```
+pub fn split(
+    secret: &[u8],
+    threshold: u8,
+    count: u8,
+    prng: &mut Prng
+) -> StdResult<Vec<Vec<u8>>> {
+    if threshold == 0 || threshold > count {
+        return Err(StdError::generic_err(
+            "The threshold must be between 1 and the number of shares."
+        ));
+    }
+
+    let mut shares: Vec<Vec<u8>> = (1..=count).map(|x| {
+        let mut share = Vec::with_capacity(secret.len() + 1);
+        share.push(x);
+
+        share
+    }).collect();
+
+    let mut coefficients = vec![0u8; threshold as usize];
+
+    for byte in secret {
+        coefficients[0] = *byte;
+        prng.fill_bytes(&mut coefficients[1..]);
+
+        for share in shares.iter_mut() {
+            let x = share[0];
+            // Horner's method
+            let y = coefficients.iter()
+                .rev()
+                .fold(0, |acc, coefficient| mul(acc, x) ^ coefficient);
+
+            share.push(y);
+        }
+    }
+
+    Ok(shares)
+}
+
+/// Recovers the secret from shares created by [`split`]. Given fewer shares
+/// than the threshold used when splitting, it returns garbage rather than an error.
+pub fn combine(shares: &[impl AsRef<[u8]>]) -> StdResult<Vec<u8>> {
+    let first = match shares.first() {
+        Some(share) => share.as_ref(),
+        None => return Err(StdError::generic_err("No shares provided."))
+    };
+
+    if first.len() < 2 {
+        return Err(StdError::generic_err("Invalid share."));
+    }
+
+    for (i, share) in shares.iter().enumerate() {
+        let share = share.as_ref();
+
+        if share.len() != first.len() || share[0] == 0 {
+            return Err(StdError::generic_err("Invalid share."));
+        }
+
+        if shares[..i].iter().any(|x| x.as_ref()[0] == share[0]) {
+            return Err(StdError::generic_err("Duplicate share."));
+        }
+    }
+
+    let mut secret = vec![0u8; first.len() - 1];
+
+    for (i, share) in shares.iter().enumerate() {
+        let share = share.as_ref();
+
+        // Lagrange basis polynomial evaluated at 0.
+        let basis = shares.iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .fold(1, |acc, (_, other)| {
+                let x = other.as_ref()[0];
+
+                mul(acc, div(x, x ^ share[0]))
+            });
+
+        for (byte, y) in secret.iter_mut().zip(&share[1..]) {
+            *byte ^= mul(*y, basis);
+        }
+    }
+
+    Ok(secret)
+}
+
+/// Multiplication in GF(256) with the AES polynomial.
+fn mul(mut a: u8, mut b: u8) -> u8 {
+    let mut result = 0;
+
+    while b != 0 {
+        if b & 1 != 0 {
+            result ^= a;
+        }
+
+        let carry = a & 0x80;
+        a <<= 1;
+
+        if carry != 0 {
+            a ^= 0x1b;
+        }
+
+        b >>= 1;
+    }
+
+    result
+}
+
+#[inline]
+fn div(a: u8, b: u8) -> u8 {
+    // b^254 is the inverse of b.
+    let mut inverse = 1;
+
+    for _ in 0..254 {
+        inverse = mul(inverse, b);
+    }
+
+    mul(a, inverse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn any_threshold_subset_recovers_secret() {
+        let secret = b"the decryption key";
+        let mut prng = Prng::new(b"seed", b"entropy");
+
+        let shares = split(secret, 3, 5, &mut prng).unwrap();
+        assert!(shares.iter().all(|x| x.len() == secret.len() + 1));
+
+        for a in 0..5 {
+            for b in a + 1..5 {
+                for c in b + 1..5 {
+                    let subset = [&shares[a], &shares[b], &shares[c]];
+                    assert_eq!(combine(&subset).unwrap(), secret);
+                }
+            }
+        }
+
+        assert_eq!(combine(&shares).unwrap(), secret);
+        assert_ne!(combine(&shares[..2]).unwrap(), secret);
+
+        assert!(combine(&[&shares[0], &shares[0]]).is_err());
+        assert!(split(secret, 0, 5, &mut prng).is_err());
+        assert!(split(secret, 6, 5, &mut prng).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+//! Verification of verifiable random function (VRF) proofs using the
+//! `ECVRF-EDWARDS25519-SHA512-TAI` suite as specified in
+//! [RFC 9381](https://www.rfc-editor.org/rfc/rfc9381).
+//! *Feature flag: `vrf`*
+//!
+//! A VRF lets an off-chain party holding a secret key produce randomness for a given
+//! input (`alpha`) together with a proof that the output is the only one possible for that
+//! input and their public key. As long as the contract chooses the input (i.e. the round
+//! number of a lottery), the party producing the randomness can't influence the outcome.
+
+use curve25519_dalek::{
+    edwards::{CompressedEdwardsY, EdwardsPoint},
+    scalar::Scalar
+};
+use rand_chacha::ChaChaRng;
+use rand_core::{CryptoRng, SeedableRng};
+use sha2_09::{Digest, Sha512};
+
+use crate::cosmwasm_std::{StdResult, StdError};
+
+pub use rand_core::RngCore;
+
+const SUITE: u8 = 0x03;
+
+pub const PUBLIC_KEY_LEN: usize = 32;
+pub const PROOF_LEN: usize = 80;
+pub const OUTPUT_LEN: usize = 64;
+
+/// Verifies that `proof` was produced by the owner of `public_key` for the input `alpha`.
+/// Returns the VRF output if the proof is valid and `None` otherwise.
+///
+/// Returns `Err` if the public key or the proof are malformed.
+pub fn verify(
+    public_key: &[u8],
+    alpha: &[u8],
+    proof: &[u8]
+) -> StdResult<Option<[u8; OUTPUT_LEN]>> {
+    let public_key: &[u8; PUBLIC_KEY_LEN] = public_key.try_into().map_err(|_|
+        StdError::generic_err(format!("VRF public keys must be {} bytes long.", PUBLIC_KEY_LEN))
+    )?;
+    let y = match decode_point(public_key) {
+        Some(y) if !y.is_small_order() => y,
+        _ => return Err(StdError::generic_err("Invalid VRF public key."))
+    };
+
+    if proof.len() != PROOF_LEN {
+        return Err(StdError::generic_err(format!("VRF proofs must be {} bytes long.", PROOF_LEN)));
+    }
+
+    let gamma = decode_point(&proof[..32])
+        .ok_or_else(|| StdError::generic_err("Invalid VRF proof."))?;
+
+    let mut c = [0u8; 32];
+    c[..16].copy_from_slice(&proof[32..48]);
+    let c = Scalar::from_bytes_mod_order(c);
+
+    let s: [u8; 32] = proof[48..].try_into().unwrap();
+    let s = Scalar::from_canonical_bytes(s)
+        .ok_or_else(|| StdError::generic_err("Invalid VRF proof."))?;
+
+    let h = match hash_to_curve(public_key, alpha) {
+        Some(h) => h,
+        None => return Ok(None)
+    };
+
+    let u = EdwardsPoint::vartime_double_scalar_mul_basepoint(&-c, &y, &s);
+    let v = s * h - c * gamma;
+
+    if challenge(&[&y, &h, &gamma, &u, &v]) != proof[32..48] {
+        return Ok(None);
+    }
+
+    let output = Sha512::new()
+        .chain([SUITE, 0x03])
+        .chain(gamma.mul_by_cofactor().compress().as_bytes())
+        .chain([0x00])
+        .finalize();
+
+    Ok(Some(output.into()))
+}
+
+/// Randomness produced off-chain by a VRF and verified by the contract. Can
+/// be used as a source of random numbers through its [`RngCore`] implementation.
+///
+/// # Examples
+///
+/// ```
+/// # use fadroma::{prelude::*, crypto::vrf::{VerifiedRandomness, RngCore}};
+/// # fn hex(s: &str) -> Vec<u8> {
+/// #     (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+/// # }
+/// # fn main() -> StdResult<()> {
+/// // Normally the public key of the oracle would be loaded from storage and the proof would
+/// // come from the message sent by it, while the input would be decided by the contract.
+/// let public_key = hex("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a");
+/// let proof = hex("8657106690b5526245a92b003bb079ccd1a92130477671f6fc01ad16f26f723f26f8a57ccaed74ee1b190bed1f479d9727d2d0f9b005a6e456a35d4fb0daab1268a1b0db10836d9826a528ca76567805");
+///
+/// let mut randomness = VerifiedRandomness::new(&public_key, b"", &proof)?;
+/// let winner = randomness.next_u64() % 10;
+///
+/// assert!(VerifiedRandomness::new(&public_key, b"round 2", &proof).is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct VerifiedRandomness {
+    output: [u8; OUTPUT_LEN],
+    rng: ChaChaRng
+}
+
+impl VerifiedRandomness {
+    /// Verifies the proof using [`verify`] and returns `Err` if it isn't valid.
+    pub fn new(public_key: &[u8], alpha: &[u8], proof: &[u8]) -> StdResult<Self> {
+        match verify(public_key, alpha, proof)? {
+            Some(output) => {
+                let mut seed = [0u8; 32];
+                seed.copy_from_slice(&output[..32]);
+
+                Ok(Self {
+                    output,
+                    rng: ChaChaRng::from_seed(seed)
+                })
+            },
+            None => Err(StdError::generic_err("Invalid VRF proof."))
+        }
+    }
+
+    /// The VRF output as specified by the RFC, for
+    /// compatibility with other implementations.
+    #[inline]
+    pub fn output(&self) -> &[u8; OUTPUT_LEN] {
+        &self.output
+    }
+}
+
+impl RngCore for VerifiedRandomness {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    #[inline]
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    #[inline]
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+impl CryptoRng for VerifiedRandomness { }
+
+/// The try and increment method of encoding to the curve.
+fn hash_to_curve(public_key: &[u8; PUBLIC_KEY_LEN], alpha: &[u8]) -> Option<EdwardsPoint> {
+    (0..=u8::MAX).find_map(|ctr| {
+        let hash = Sha512::new()
+            .chain([SUITE, 0x01])
+            .chain(public_key)
+            .chain(alpha)
+            .chain([ctr, 0x00])
+            .finalize();
+
+        decode_point(&hash[..32]).map(|x| x.mul_by_cofactor())
+    })
+}
+
+fn challenge(points: &[&EdwardsPoint]) -> [u8; 16] {
+    let mut hasher = Sha512::new().chain([SUITE, 0x02]);
+
+    for point in points {
+        hasher.update(point.compress().as_bytes());
+    }
+
+    let hash = hasher.chain([0x00]).finalize();
+
+    hash[..16].try_into().unwrap()
+}
+
+/// Decodes a point, rejecting non-canonical encodings as required by RFC 8032.
+fn decode_point(bytes: &[u8]) -> Option<EdwardsPoint> {
+    let compressed = CompressedEdwardsY::from_slice(bytes);
+    let point = compressed.decompress()?;
+
+    if point.compress() != compressed {
+        return None;
+    }
+
+    Some(point)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUBLIC_KEY: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    const PROOF: &str = "8657106690b5526245a92b003bb079ccd1a92130477671f6fc01ad16f26f723f26f8a57ccaed74ee1b190bed1f479d9727d2d0f9b005a6e456a35d4fb0daab1268a1b0db10836d9826a528ca76567805";
+    const OUTPUT: &str = "90cf1df3b703cce59e2a35b925d411164068269d7b2d29f3301c03dd757876ff66b71dda49d2de59d03450451af026798e8f81cd2e333de5cdf4f3e140fdd8ae";
+
+    #[test]
+    fn verifies_rfc_test_vector() {
+        let output = verify(&hex(PUBLIC_KEY), b"", &hex(PROOF)).unwrap().unwrap();
+        assert_eq!(output.to_vec(), hex(OUTPUT));
+
+        assert_eq!(verify(&hex(PUBLIC_KEY), b"r", &hex(PROOF)).unwrap(), None);
+
+        let mut proof = hex(PROOF);
+        proof[40] ^= 1;
+        assert_eq!(verify(&hex(PUBLIC_KEY), b"", &proof).unwrap(), None);
+
+        assert!(verify(&hex(PUBLIC_KEY), b"", &hex(PROOF)[1..]).is_err());
+        assert!(verify(&[0u8; PUBLIC_KEY_LEN], b"", &hex(PROOF)).is_err());
+    }
+
+    #[test]
+    fn verified_randomness_is_deterministic() {
+        let mut a = VerifiedRandomness::new(&hex(PUBLIC_KEY), b"", &hex(PROOF)).unwrap();
+        let mut b = a.clone();
+
+        assert_eq!(a.output().to_vec(), hex(OUTPUT));
+        assert_eq!(a.next_u64(), b.next_u64());
+        assert_ne!(a.next_u64(), a.next_u64());
+    }
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+}
@@ -0,0 +1,121 @@
+use std::cell::RefCell;
+
+use crate::cosmwasm_std::{Storage, Record, Order, Binary};
+use super::{EnsembleResult, EnsembleError};
+
+/// A single storage read performed by a query.
+#[derive(Clone, PartialEq, Debug)]
+pub enum StorageRead {
+    /// A call to [`Storage::get`] with the given key.
+    Get(Binary),
+    /// A call to [`Storage::range`] with the given bounds.
+    Range {
+        start: Option<Binary>,
+        end: Option<Binary>
+    },
+    /// The key of a record returned while iterating a range.
+    Next(Binary)
+}
+
+/// All storage reads that a query performed, in the order that they happened.
+///
+/// Returned by [`super::ContractEnsemble::query_access_pattern`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct AccessPattern {
+    pub msg: Binary,
+    pub reads: Vec<StorageRead>,
+    /// Whether the query returned `Ok`.
+    pub succeeded: bool
+}
+
+impl AccessPattern {
+    /// Returns `Err` with the index of the first read where the
+    /// two patterns diverge if they don't read the same keys.
+    pub(crate) fn compare(&self, other: &Self) -> Result<(), usize> {
+        let diverges_at = self.reads
+            .iter()
+            .zip(other.reads.iter())
+            .position(|(a, b)| a != b);
+
+        match diverges_at {
+            Some(index) => Err(index),
+            None if self.reads.len() != other.reads.len() =>
+                Err(self.reads.len().min(other.reads.len())),
+            None => Ok(())
+        }
+    }
+}
+
+pub(crate) fn check(patterns: &[AccessPattern]) -> EnsembleResult<()> {
+    let Some((first, rest)) = patterns.split_first() else {
+        return Ok(());
+    };
+
+    for (i, pattern) in rest.iter().enumerate() {
+        if let Err(index) = first.compare(pattern) {
+            return Err(EnsembleError::AccessPattern(format!(
+                "queries 0 and {} diverge at read {}: {:?} and {:?}",
+                i + 1,
+                index,
+                first.reads.get(index),
+                pattern.reads.get(index)
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) struct RecordingStorage<'a> {
+    storage: &'a dyn Storage,
+    reads: RefCell<Vec<StorageRead>>
+}
+
+impl<'a> RecordingStorage<'a> {
+    #[inline]
+    pub fn new(storage: &'a dyn Storage) -> Self {
+        Self {
+            storage,
+            reads: RefCell::new(vec![])
+        }
+    }
+
+    #[inline]
+    pub fn into_reads(self) -> Vec<StorageRead> {
+        self.reads.into_inner()
+    }
+}
+
+impl<'a> Storage for RecordingStorage<'a> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.reads.borrow_mut().push(StorageRead::Get(key.into()));
+
+        self.storage.get(key)
+    }
+
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        self.reads.borrow_mut().push(StorageRead::Range {
+            start: start.map(Binary::from),
+            end: end.map(Binary::from)
+        });
+
+        let iter = self.storage.range(start, end, order);
+
+        Box::new(iter.inspect(|(key, _)|
+            self.reads.borrow_mut().push(StorageRead::Next(key.as_slice().into()))
+        ))
+    }
+
+    fn set(&mut self, _key: &[u8], _value: &[u8]) {
+        unreachable!("Queries cannot write to storage.")
+    }
+
+    fn remove(&mut self, _key: &[u8]) {
+        unreachable!("Queries cannot write to storage.")
+    }
+}
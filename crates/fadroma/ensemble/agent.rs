@@ -0,0 +1,118 @@
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    prelude::{ContractCode, ContractLink},
+    cosmwasm_std::{Addr, Binary, Coin}
+};
+use super::{
+    ensemble::{ContractEnsemble, EnsembleResult},
+    env::MockEnv
+};
+
+/// An account that can instantiate, execute and query contracts.
+/// Integration tests written against this trait, rather than against
+/// [`ContractEnsemble`] directly, don't depend on how the transactions
+/// are carried out and can be run by any backend that implements it.
+pub trait Agent {
+    type Error;
+
+    /// The address of the account that sends the messages.
+    fn address(&self) -> &str;
+
+    /// Creates a new instance of `code` using `msg` as the init message.
+    fn instantiate<T: Serialize>(
+        &mut self,
+        code: &ContractCode,
+        label: &str,
+        msg: &T,
+        funds: Vec<Coin>
+    ) -> Result<ContractLink<Addr>, Self::Error>;
+
+    /// Executes the contract at `contract` and returns the data
+    /// field of its response, if any.
+    fn execute<T: Serialize + ?Sized>(
+        &mut self,
+        contract: &str,
+        msg: &T,
+        funds: Vec<Coin>
+    ) -> Result<Option<Binary>, Self::Error>;
+
+    /// Queries the contract at `contract` and deserializes the result.
+    fn query<T: Serialize + ?Sized, R: DeserializeOwned>(
+        &self,
+        contract: &str,
+        msg: &T
+    ) -> Result<R, Self::Error>;
+}
+
+/// An [`Agent`] which sends all of its messages through a [`ContractEnsemble`].
+/// Obtained by calling [`ContractEnsemble::agent`].
+pub struct EnsembleAgent<'a> {
+    ensemble: &'a mut ContractEnsemble,
+    address: String
+}
+
+impl ContractEnsemble {
+    /// Returns an [`Agent`] that sends messages as `address`.
+    /// Messages are sent using [`MockEnv::new`] and so the same
+    /// address restrictions apply.
+    #[inline]
+    pub fn agent(&mut self, address: impl Into<String>) -> EnsembleAgent<'_> {
+        EnsembleAgent {
+            ensemble: self,
+            address: address.into()
+        }
+    }
+}
+
+impl<'a> EnsembleAgent<'a> {
+    /// Provides access to the underlying ensemble.
+    #[inline]
+    pub fn ensemble(&mut self) -> &mut ContractEnsemble {
+        self.ensemble
+    }
+}
+
+impl<'a> Agent for EnsembleAgent<'a> {
+    type Error = super::EnsembleError;
+
+    #[inline]
+    fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// The ensemble uses `label` as the address of the new instance.
+    fn instantiate<T: Serialize>(
+        &mut self,
+        code: &ContractCode,
+        label: &str,
+        msg: &T,
+        funds: Vec<Coin>
+    ) -> EnsembleResult<ContractLink<Addr>> {
+        let env = MockEnv::new(self.address.as_str(), label).sent_funds(funds);
+        let resp = self.ensemble.instantiate(code.id, msg, env)?;
+
+        Ok(resp.instance)
+    }
+
+    fn execute<T: Serialize + ?Sized>(
+        &mut self,
+        contract: &str,
+        msg: &T,
+        funds: Vec<Coin>
+    ) -> EnsembleResult<Option<Binary>> {
+        let env = MockEnv::new(self.address.as_str(), contract).sent_funds(funds);
+        let resp = self.ensemble.execute(msg, env)?;
+
+        Ok(resp.response.data)
+    }
+
+    #[inline]
+    fn query<T: Serialize + ?Sized, R: DeserializeOwned>(
+        &self,
+        contract: &str,
+        msg: &T
+    ) -> EnsembleResult<R> {
+        self.ensemble.query(contract, msg)
+    }
+}
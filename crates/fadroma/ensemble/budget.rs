@@ -0,0 +1,104 @@
+use std::cell::Cell;
+
+use crate::cosmwasm_std::{Storage, Record, Order, Binary};
+use super::{EnsembleResult, EnsembleError};
+
+/// The maximum number of storage reads and writes that a single
+/// execution of a message is expected to perform. Each record returned
+/// when iterating storage counts as a separate read.
+///
+/// Set for a message using [`super::ContractEnsemble::set_storage_budget`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct StorageBudget {
+    pub reads: u64,
+    pub writes: u64
+}
+
+impl StorageBudget {
+    #[inline]
+    pub fn new(reads: u64, writes: u64) -> Self {
+        Self { reads, writes }
+    }
+
+    pub(crate) fn check(
+        &self,
+        msg: &str,
+        storage: &CountingStorage
+    ) -> EnsembleResult<()> {
+        let reads = storage.reads.get();
+
+        if reads > self.reads || storage.writes > self.writes {
+            return Err(EnsembleError::StorageBudget(format!(
+                "\"{}\" performed {} reads and {} writes, expected at most {} reads and {} writes",
+                msg,
+                reads,
+                storage.writes,
+                self.reads,
+                self.writes
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the name of the message if it's a JSON string or the first key if
+/// it's a JSON object, which is how `enum` variants are serialized.
+pub(crate) fn msg_name(msg: &Binary) -> Option<String> {
+    let msg = std::str::from_utf8(msg.as_slice()).ok()?.trim_start();
+    let msg = match msg.strip_prefix('{') {
+        Some(rest) => rest.trim_start(),
+        None => msg
+    };
+
+    let name = msg.strip_prefix('"')?;
+    let end = name.find('"')?;
+
+    Some(name[..end].to_string())
+}
+
+pub(crate) struct CountingStorage<'a> {
+    storage: &'a mut dyn Storage,
+    reads: Cell<u64>,
+    writes: u64
+}
+
+impl<'a> CountingStorage<'a> {
+    #[inline]
+    pub fn new(storage: &'a mut dyn Storage) -> Self {
+        Self {
+            storage,
+            reads: Cell::new(0),
+            writes: 0
+        }
+    }
+}
+
+impl<'a> Storage for CountingStorage<'a> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.reads.set(self.reads.get() + 1);
+
+        self.storage.get(key)
+    }
+
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        let iter = self.storage.range(start, end, order);
+
+        Box::new(iter.inspect(|_| self.reads.set(self.reads.get() + 1)))
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.writes += 1;
+        self.storage.set(key, value);
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.writes += 1;
+        self.storage.remove(key);
+    }
+}
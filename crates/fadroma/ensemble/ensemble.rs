@@ -1,6 +1,7 @@
 use std::{
     fmt::Debug,
-    convert::TryFrom
+    convert::TryFrom,
+    collections::HashMap
 };
 use serde::{
     Serialize,
@@ -10,10 +11,12 @@ use oorandom::Rand64;
 
 use crate::{
     prelude::{ContractCode, ContractLink},
+    core::panic::catch_panic,
     cosmwasm_std::{
         SubMsg, Deps, DepsMut, Env, Response, MessageInfo, Binary, Coin, Empty,
         CosmosMsg, WasmMsg, BlockInfo, ContractInfo, BankMsg, Timestamp, Addr,
         SubMsgResponse, SubMsgResult, Reply, Storage, Api, Querier, QuerierWrapper,
+        Record, Order,
         from_binary, to_binary, testing::MockApi
     }
 };
@@ -32,7 +35,10 @@ use super::{
     state::State,
     execution_state::{ExecutionState, MessageType},
     error::{EnsembleError, RegistryError},
-    event::ProcessedEvents
+    event::ProcessedEvents,
+    budget::{self, StorageBudget, CountingStorage},
+    access::{self, AccessPattern, StorageRead, RecordingStorage},
+    migration::MigrationCheck
 };
 
 #[cfg(feature = "ensemble-staking")]
@@ -60,6 +66,37 @@ pub trait ContractHarness {
     }
 }
 
+/// Wraps a [`ContractHarness`] and turns panics in any of its entry points
+/// into errors that carry the panic message, using [`catch_panic`]. The
+/// transaction is then reverted like for any other error, instead of the
+/// panic unwinding through the ensemble and failing the entire test.
+/// 
+/// Useful for tests which expect a handler to fail and to diagnose ones that
+/// panic. Note that a panic aborts the execution of the contract on chain.
+/// 
+/// ```ignore
+/// let contract = ensemble.register(Box::new(CatchPanics(Counter)));
+/// ```
+pub struct CatchPanics<H: ContractHarness>(pub H);
+
+impl<H: ContractHarness> ContractHarness for CatchPanics<H> {
+    fn instantiate(&self, deps: DepsMut, env: Env, info: MessageInfo, msg: Binary) -> AnyResult<Response> {
+        catch_panic(|| self.0.instantiate(deps, env, info, msg))
+    }
+
+    fn execute(&self, deps: DepsMut, env: Env, info: MessageInfo, msg: Binary) -> AnyResult<Response> {
+        catch_panic(|| self.0.execute(deps, env, info, msg))
+    }
+
+    fn query(&self, deps: Deps, env: Env, msg: Binary) -> AnyResult<Binary> {
+        catch_panic(|| self.0.query(deps, env, msg))
+    }
+
+    fn reply(&self, deps: DepsMut, env: Env, reply: Reply) -> AnyResult<Response> {
+        catch_panic(|| self.0.reply(deps, env, reply))
+    }
+}
+
 /// This the main type in the system that takes care of registering and executing contracts,
 /// keeping the blockchain simulation state and allowing the manipulation of particular parameters
 /// such as account funds, blocks or contract state in order to efficiently simulate testing scenarios.
@@ -149,13 +186,32 @@ pub(crate) struct Context {
     #[cfg(feature = "ensemble-staking")]
     pub delegations: Delegations,
     pub state: State,
+    executed: Vec<ExecutedMsg>,
+    invariants: Vec<Invariant>,
     block: Block,
     chain_id: String
 }
 
+/// A message that was successfully executed by calling [`ContractEnsemble::execute`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct ExecutedMsg {
+    pub sender: String,
+    pub contract: String,
+    pub msg: Binary,
+    pub funds: Vec<Coin>
+}
+
+type InvariantCheck = Box<dyn Fn(&ContractEnsemble) -> AnyResult<()>>;
+
+struct Invariant {
+    name: String,
+    check: InvariantCheck
+}
+
 pub(crate) struct ContractUpload {
     code_hash: String,
-    code: Box<dyn ContractHarness>
+    code: Box<dyn ContractHarness>,
+    budgets: HashMap<String, StorageBudget>
 }
 
 impl ContractEnsemble {
@@ -195,7 +251,8 @@ impl ContractEnsemble {
 
         self.ctx.contracts.push(ContractUpload {
             code_hash: code_hash.clone(),
-            code
+            code,
+            budgets: HashMap::new()
         });
 
         ContractCode {
@@ -204,6 +261,76 @@ impl ContractEnsemble {
         }
     }
 
+    /// Sets the maximum number of storage reads and writes that executing
+    /// the message with the given name is allowed to perform for all instances
+    /// of the contract with the given code id. Executions that exceed it fail
+    /// with [`EnsembleError::StorageBudget`], which makes regressions in the
+    /// storage access patterns of a handler show up as test failures.
+    /// 
+    /// The name of a message is the single key of its JSON representation,
+    /// which is what `ExecuteMsg::name` returns for messages generated by the DSL.
+    /// 
+    /// Returns `Err` if a contract with `code_id` wasn't registered.
+    pub fn set_storage_budget(
+        &mut self,
+        code_id: u64,
+        msg: impl Into<String>,
+        budget: StorageBudget
+    ) -> EnsembleResult<()> {
+        let contract = self
+            .ctx
+            .contracts
+            .get_mut(code_id as usize)
+            .ok_or_else(|| EnsembleError::registry(RegistryError::IdNotFound(code_id)))?;
+
+        contract.budgets.insert(msg.into(), budget);
+
+        Ok(())
+    }
+
+    /// Makes executing any of the messages with the given names fail with
+    /// [`EnsembleError::StorageBudget`] if it writes to storage, for all instances
+    /// of the contract with the given code id. Any reads budget previously set
+    /// with [`ContractEnsemble::set_storage_budget`] is kept.
+    /// 
+    /// Pass `ExecuteMsg::READ_ONLY` for contracts generated by the DSL in order to
+    /// check all handlers marked with `#[execute(read_only)]`.
+    /// 
+    /// Returns `Err` if a contract with `code_id` wasn't registered.
+    pub fn set_read_only(&mut self, code_id: u64, msgs: &[&str]) -> EnsembleResult<()> {
+        let contract = self
+            .ctx
+            .contracts
+            .get_mut(code_id as usize)
+            .ok_or_else(|| EnsembleError::registry(RegistryError::IdNotFound(code_id)))?;
+
+        for msg in msgs {
+            contract.budgets
+                .entry(msg.to_string())
+                .or_insert_with(|| StorageBudget::new(u64::MAX, 0))
+                .writes = 0;
+        }
+
+        Ok(())
+    }
+
+    /// Registers a check that is run after every transaction initiated by
+    /// [`ContractEnsemble::instantiate`] or [`ContractEnsemble::execute`],
+    /// including all sub-messages that it dispatched. If the check returns
+    /// `Err`, the transaction is reverted and fails with [`EnsembleError::Invariant`].
+    /// 
+    /// Use this to assert properties that must always hold for the contracts
+    /// being tested (e.g. total supply equals the sum of all balances) for every
+    /// possible sequence of messages that a test suite executes.
+    pub fn add_invariant<F>(&mut self, name: impl Into<String>, check: F)
+        where F: Fn(&ContractEnsemble) -> AnyResult<()> + 'static
+    {
+        self.ctx.invariants.push(Invariant {
+            name: name.into(),
+            check: Box::new(check)
+        });
+    }
+
     /// Returns a reference to the current block state.
     #[inline]
     pub fn block(&self) -> &Block {
@@ -394,6 +521,93 @@ impl ContractEnsemble {
         result
     }
 
+    /// Writes the given key/value pairs to the storage of the contract with
+    /// the given address, overwriting any existing values. Can be used to seed
+    /// an instance with state exported from a live contract, so that bugs and
+    /// migrations can be reproduced against real data.
+    /// 
+    /// Returns `Err` if a contract with `address` wasn't found.
+    pub fn import_storage<K, V>(
+        &mut self,
+        address: impl AsRef<str>,
+        entries: impl IntoIterator<Item = (K, V)>
+    ) -> EnsembleResult<()>
+        where K: AsRef<[u8]>, V: AsRef<[u8]>
+    {
+        self.contract_storage_mut(address, |storage| {
+            for (key, value) in entries {
+                storage.set(key.as_ref(), value.as_ref());
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Returns all key/value pairs in the storage of the contract with
+    /// the given address in ascending key order.
+    /// 
+    /// Returns `Err` if a contract with `address` wasn't found.
+    pub fn export_storage(&self, address: impl AsRef<str>) -> EnsembleResult<Vec<Record>> {
+        let instance = self.ctx.state.instance(address.as_ref())?;
+        let records = instance.storage.range(None, None, Order::Ascending).collect();
+
+        Ok(records)
+    }
+
+    /// Returns the number of keys starting with `prefix` in the storage
+    /// of the contract with the given address.
+    /// 
+    /// Returns `Err` if a contract with `address` wasn't found.
+    pub fn count_keys(&self, address: impl AsRef<str>, prefix: &[u8]) -> EnsembleResult<usize> {
+        let instance = self.ctx.state.instance(address.as_ref())?;
+        let count = instance.storage
+            .range(Some(prefix), None, Order::Ascending)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .count();
+
+        Ok(count)
+    }
+
+    /// Rehearses a migration, i.e. against state seeded with [`ContractEnsemble::import_storage`].
+    /// Measures each of the `checks` and then calls `migrate` which should execute all of the
+    /// messages that the migration consists of, be it a state version upgrade of a single contract
+    /// or the emigration of its data to a new one. Once it returns, the checks are measured again
+    /// and the migration fails with [`EnsembleError::Migration`] for the first one that changed.
+    /// 
+    /// The ensemble is left in the state that the migration produced, even if it failed,
+    /// so that it can be inspected further.
+    pub fn rehearse_migration<F>(
+        &mut self,
+        checks: Vec<MigrationCheck>,
+        migrate: F
+    ) -> EnsembleResult<()>
+        where F: FnOnce(&mut ContractEnsemble) -> EnsembleResult<()>
+    {
+        let mut pending = Vec::with_capacity(checks.len());
+
+        for check in checks {
+            let compare = (check.measure)(self).map_err(|err|
+                EnsembleError::Migration(format!("{} (before): {}", check.name, err))
+            )?;
+
+            pending.push((check.name, compare));
+        }
+
+        migrate(self)?;
+
+        for (name, compare) in pending {
+            let mismatch = compare(self).map_err(|err|
+                EnsembleError::Migration(format!("{} (after): {}", name, err))
+            )?;
+
+            if let Some(msg) = mismatch {
+                return Err(EnsembleError::Migration(format!("{}: {}", name, msg)));
+            }
+        }
+
+        Ok(())
+    }
+
     /// Creates a new contract instance using the given code id. The code id
     /// must be obtained by calling the [`ContractEnsemble::register`] method first.
     /// 
@@ -422,7 +636,7 @@ impl ContractEnsemble {
             label: env.contract.into_string()
         });
 
-        match self.ctx.execute_messages(sub_msg, env.sender.into_string())? {
+        match self.dispatch(sub_msg, env.sender.into_string())? {
             ResponseVariants::Instantiate(resp) => Ok(resp),
             _ => unreachable!()
         }
@@ -439,19 +653,86 @@ impl ContractEnsemble {
         let instance = self.ctx.state.instance(&address)?;
         let code_hash = self.ctx.contracts[instance.index].code_hash.clone();
 
-        let sub_msg = SubMsg::new(WasmMsg::Execute {
-            contract_addr: address,
-            code_hash,
+        let executed = ExecutedMsg {
+            sender: env.sender.into_string(),
+            contract: address,
             msg: to_binary(msg)?,
             funds: env.sent_funds
+        };
+
+        let sub_msg = SubMsg::new(WasmMsg::Execute {
+            contract_addr: executed.contract.clone(),
+            code_hash,
+            msg: executed.msg.clone(),
+            funds: executed.funds.clone()
         });
 
-        match self.ctx.execute_messages(sub_msg, env.sender.into_string())? {
-            ResponseVariants::Execute(resp) => Ok(resp),
+        match self.dispatch(sub_msg, executed.sender.clone())? {
+            ResponseVariants::Execute(resp) => {
+                self.ctx.executed.push(executed);
+
+                Ok(resp)
+            },
             _ => unreachable!()
         }
     }
 
+    /// Returns all messages that were successfully executed by
+    /// calling [`ContractEnsemble::execute`] in the order of execution.
+    #[inline]
+    pub fn executed(&self) -> &[ExecutedMsg] {
+        &self.ctx.executed
+    }
+
+    /// Executes every message in [`ContractEnsemble::executed`] that was sent by
+    /// `privileged` again but this time sent by `unprivileged`, which is given
+    /// any funds that the original message sent. The state is reverted after
+    /// each message regardless of the outcome.
+    /// 
+    /// Returns the messages that didn't fail. Used to assert that privileged
+    /// messages (e.g. admin only) still check the sender.
+    /// Note that the messages are executed against the current state rather
+    /// than the state at the time of their original execution.
+    pub fn replay_unprivileged(
+        &mut self,
+        privileged: impl AsRef<str>,
+        unprivileged: impl AsRef<str>
+    ) -> Vec<ExecutedMsg> {
+        let unprivileged = unprivileged.as_ref();
+        let replays: Vec<ExecutedMsg> = self.ctx.executed
+            .iter()
+            .filter(|x| x.sender == privileged.as_ref())
+            .cloned()
+            .collect();
+
+        let mut succeeded = vec![];
+
+        for executed in replays {
+            let Ok(instance) = self.ctx.state.instance(&executed.contract) else {
+                continue;
+            };
+
+            let sub_msg = SubMsg::new(WasmMsg::Execute {
+                contract_addr: executed.contract.clone(),
+                code_hash: self.ctx.contracts[instance.index].code_hash.clone(),
+                msg: executed.msg.clone(),
+                funds: executed.funds.clone()
+            });
+
+            self.ctx.state.push_scope();
+            self.ctx.state.add_funds(unprivileged, executed.funds.clone());
+
+            let result = self.ctx.execute_messages(sub_msg, unprivileged.to_string());
+            self.ctx.state.revert();
+
+            if result.is_ok() {
+                succeeded.push(executed);
+            }
+        }
+
+        succeeded
+    }
+
     /// Queries the contract associated with the given address and
     /// attempts to deserialize its response to the given type parameter.
     #[inline]
@@ -476,6 +757,75 @@ impl ContractEnsemble {
     ) -> EnsembleResult<Binary> {
         self.ctx.query(address.as_ref(), to_binary(msg)?)
     }
+
+    /// Queries the contract associated with the given address and records
+    /// every storage read that it performs. The query is allowed to fail,
+    /// in which case the reads performed up to the failure are returned.
+    pub fn query_access_pattern<T: Serialize + ?Sized>(
+        &self,
+        address: impl AsRef<str>,
+        msg: &T
+    ) -> EnsembleResult<AccessPattern> {
+        let msg = to_binary(msg)?;
+        let (result, reads) = self.ctx.query_recorded(address.as_ref(), msg.clone())?;
+
+        Ok(AccessPattern {
+            msg,
+            reads,
+            succeeded: result.is_ok()
+        })
+    }
+
+    /// Runs each of the given queries and checks that they all read exactly the same
+    /// storage keys in the same order. The queries should be the same message with
+    /// different values for its private inputs (e.g. different addresses or viewing keys,
+    /// including invalid ones). Otherwise, anyone able to observe the storage access of
+    /// the contract could learn something about those inputs.
+    /// 
+    /// Returns [`EnsembleError::AccessPattern`] describing the first divergence found.
+    /// Use [`ContractEnsemble::query_access_pattern`] to inspect the reads of a single query.
+    pub fn check_access_patterns<T: Serialize>(
+        &self,
+        address: impl AsRef<str>,
+        msgs: &[T]
+    ) -> EnsembleResult<Vec<AccessPattern>> {
+        let address = address.as_ref();
+        let patterns = msgs
+            .iter()
+            .map(|msg| self.query_access_pattern(address, msg))
+            .collect::<EnsembleResult<Vec<_>>>()?;
+
+        access::check(&patterns)?;
+
+        Ok(patterns)
+    }
+}
+
+impl ContractEnsemble {
+    fn dispatch(
+        &mut self,
+        msg: SubMsg,
+        sender: String
+    ) -> EnsembleResult<ResponseVariants> {
+        let response = self.ctx.execute_messages(msg, sender)?;
+
+        let violation = self.ctx.invariants.iter().find_map(|invariant|
+            (invariant.check)(self)
+                .err()
+                .map(|err| format!("{}: {}", invariant.name, err))
+        );
+
+        if let Some(msg) = violation {
+            self.ctx.state.revert();
+
+            return Err(EnsembleError::Invariant(msg));
+        }
+
+        self.ctx.block.next();
+        self.ctx.state.commit();
+
+        Ok(response)
+    }
 }
 
 impl Context {
@@ -484,6 +834,8 @@ impl Context {
         Self {
             contracts: vec![],
             state: State::new(),
+            executed: vec![],
+            invariants: vec![],
             block: Block::default(),
             chain_id: "fadroma-ensemble-testnet".into()
         }
@@ -495,6 +847,8 @@ impl Context {
             contracts: vec![],
             state: State::new(),
             delegations: Delegations::new(native_denom),
+            executed: vec![],
+            invariants: vec![],
             block: Block::default(),
             chain_id: "fadroma-ensemble-testnet".into()
         }
@@ -559,17 +913,29 @@ impl Context {
         let sender = msg_info.sender.to_string();
         
         let contract = &self.contracts[index];
+        let budget = if contract.budgets.is_empty() {
+            None
+        } else {
+            budget::msg_name(&msg).and_then(|name| {
+                contract.budgets.get(&name).map(|budget| (name, *budget))
+            })
+        };
 
         let querier = EnsembleQuerier::new(&self);
         let response = self.state.borrow_storage_mut(&address, |storage| {
+            let mut storage = CountingStorage::new(storage);
             let deps = DepsMut::<Empty> {
-                storage,
+                storage: &mut storage,
                 api: &MockApi::default() as &dyn Api,
                 querier: QuerierWrapper::new(&querier as &dyn Querier)
             };
 
             let result = contract.code.execute(deps, env, msg_info, msg.clone())?;
 
+            if let Some((name, budget)) = budget {
+                budget.check(&name, &storage)?;
+            }
+
             Ok(result)
         })?;
 
@@ -584,7 +950,36 @@ impl Context {
 
     pub(crate) fn query(&self, address: &str, msg: Binary) -> EnsembleResult<Binary> {
         let instance = self.state.instance(address)?;
-        let contract = &self.contracts[instance.index];
+        let result = self.query_with_storage(
+            address,
+            instance.index,
+            &instance.storage as &dyn Storage,
+            msg
+        )?;
+
+        Ok(result)
+    }
+
+    fn query_recorded(
+        &self,
+        address: &str,
+        msg: Binary
+    ) -> EnsembleResult<(EnsembleResult<Binary>, Vec<StorageRead>)> {
+        let instance = self.state.instance(address)?;
+        let storage = RecordingStorage::new(&instance.storage as &dyn Storage);
+        let result = self.query_with_storage(address, instance.index, &storage, msg);
+
+        Ok((result, storage.into_reads()))
+    }
+
+    fn query_with_storage(
+        &self,
+        address: &str,
+        index: usize,
+        storage: &dyn Storage,
+        msg: Binary
+    ) -> EnsembleResult<Binary> {
+        let contract = &self.contracts[index];
 
         let env = self.create_env(ContractLink {
             address: Addr::unchecked(address),
@@ -593,7 +988,7 @@ impl Context {
 
         let querier = EnsembleQuerier::new(&self);
         let deps = Deps::<Empty> {
-            storage: &instance.storage as &dyn Storage,
+            storage,
             api: &MockApi::default() as &dyn Api,
             querier: QuerierWrapper::new(&querier as &dyn Querier)
         };
@@ -702,9 +1097,8 @@ impl Context {
             }
         }
 
-        self.block.next();
-        self.state.commit();
-
+        // Committing and advancing the block is left to the
+        // caller so that invariants can still revert the state.
         Ok(state.finalize())
     }
 
@@ -918,6 +1312,7 @@ impl Debug for Context {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Context")
             .field("contracts_len", &self.contracts.len())
+            .field("invariants_len", &self.invariants.len())
             .field("block", &self.block)
             .field("chain_id", &self.chain_id)
             .finish()
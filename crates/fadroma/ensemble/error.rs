@@ -9,6 +9,10 @@ pub enum EnsembleError {
     AttributeValidation(String),
     Bank(String),
     Staking(String),
+    StorageBudget(String),
+    Invariant(String),
+    AccessPattern(String),
+    Migration(String),
     Std(StdError)
 }
 
@@ -71,12 +75,18 @@ impl Display for EnsembleError {
             Self::Staking(msg) => f.write_fmt(format_args!("Ensemble error - Staking: {}", msg)),
             Self::ContractRegistry(err) => f.write_fmt(format_args!("Ensemble error - Contract registry: {}", err.to_string())),
             Self::AttributeValidation(msg) => f.write_fmt(format_args!("Ensemble error - Event attribute validation: {}", msg)),
+            Self::Invariant(msg) => f.write_fmt(format_args!("Ensemble error - Invariant violated: {}", msg)),
+            Self::AccessPattern(msg) => f.write_fmt(format_args!("Ensemble error - Storage access depends on query input: {}", msg)),
+            Self::Migration(msg) => f.write_fmt(format_args!("Ensemble error - Migration check failed: {}", msg)),
+            Self::StorageBudget(msg) => f.write_fmt(format_args!("Ensemble error - Storage budget exceeded: {}", msg)),
             Self::Std(err) => Display::fmt(err, f),
             Self::ContractError(err) => Display::fmt(err, f)
         }
     }
 }
 
+impl std::error::Error for EnsembleError { }
+
 impl Display for RegistryError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -0,0 +1,70 @@
+use std::{fmt::Debug, rc::Rc};
+
+use super::{ContractEnsemble, AnyResult};
+
+type Measure = Box<dyn FnOnce(&ContractEnsemble) -> AnyResult<Compare>>;
+type Compare = Box<dyn FnOnce(&ContractEnsemble) -> AnyResult<Option<String>>>;
+
+/// A property of the contract state that a migration must preserve, i.e. the sum
+/// of all balances or the number of stored accounts. It is measured before and after
+/// the migration by [`ContractEnsemble::rehearse_migration`] and the two results must be equal.
+pub struct MigrationCheck {
+    pub(crate) name: String,
+    pub(crate) measure: Measure
+}
+
+impl MigrationCheck {
+    /// Measures the state using `measure` both before and after the migration.
+    pub fn unchanged<T, F>(name: impl Into<String>, measure: F) -> Self
+        where
+            T: PartialEq + Debug + 'static,
+            F: Fn(&ContractEnsemble) -> AnyResult<T> + 'static
+    {
+        let measure = Rc::new(measure);
+        let after = measure.clone();
+
+        Self::moved(name, move |ensemble| measure(ensemble), move |ensemble| after(ensemble))
+    }
+
+    /// Measures the state using `before` prior to the migration and using `after` once it's done.
+    /// Use this when the data is moved to a different place, i.e. to compare the balances of the
+    /// old contract with the ones of the new contract that it emigrated to.
+    pub fn moved<T, B, A>(name: impl Into<String>, before: B, after: A) -> Self
+        where
+            T: PartialEq + Debug + 'static,
+            B: FnOnce(&ContractEnsemble) -> AnyResult<T> + 'static,
+            A: FnOnce(&ContractEnsemble) -> AnyResult<T> + 'static
+    {
+        Self {
+            name: name.into(),
+            measure: Box::new(move |ensemble| {
+                let expected = before(ensemble)?;
+
+                Ok(Box::new(move |ensemble| {
+                    let actual = after(ensemble)?;
+
+                    Ok((actual != expected).then(||
+                        format!("expected {:?} but was {:?}", expected, actual)
+                    ))
+                }))
+            })
+        }
+    }
+
+    /// Checks that the number of keys starting with `prefix` in the storage
+    /// of the contract with the given address doesn't change.
+    pub fn key_count(address: impl Into<String>, prefix: impl Into<Vec<u8>>) -> Self {
+        let address = address.into();
+        let prefix = prefix.into();
+
+        let name = format!(
+            "number of keys under \"{}\" in {}",
+            prefix.escape_ascii(),
+            address
+        );
+
+        Self::unchanged(name, move |ensemble| {
+            Ok(ensemble.count_keys(&address, &prefix)?)
+        })
+    }
+}
@@ -1,7 +1,11 @@
 //! Test multiple contract interactions using unit tests.
 //! *Feature flag: `ensemble`*
 
+mod access;
+mod agent;
 mod bank;
+mod budget;
+mod migration;
 mod ensemble;
 mod env;
 mod querier;
@@ -14,17 +18,24 @@ mod state;
 mod execution_state;
 mod error;
 mod event;
+mod model;
 
 #[cfg(test)]
 mod tests;
 
 pub use ensemble::*;
+pub use agent::*;
 pub use env::*;
 pub use querier::*;
 pub use block::Block;
+pub use budget::StorageBudget;
+pub use access::{AccessPattern, StorageRead};
+pub use migration::MigrationCheck;
 pub use response::*;
 pub use error::*;
+pub use model::*;
 pub use anyhow;
+pub use oorandom;
 
 /// Generate a struct and implement [`ContractHarness`] for the given struct identifier,
 /// using the provided entry point functions.
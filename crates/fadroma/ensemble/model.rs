@@ -0,0 +1,77 @@
+use std::fmt::Debug;
+
+use serde::Serialize;
+use oorandom::Rand64;
+use anyhow::{bail, Context};
+
+use super::{
+    ensemble::{ContractEnsemble, AnyResult},
+    env::MockEnv
+};
+
+/// A message to be executed by both the [`Model`] and the ensemble.
+#[derive(Clone, Debug)]
+pub struct Step<M> {
+    pub msg: M,
+    pub env: MockEnv
+}
+
+/// A pure Rust reference implementation of the behaviour of one or more contracts.
+/// Used with [`check_model`] to execute random sequences of messages against both the
+/// model and the actual contracts in order to find where their behaviour diverges.
+pub trait Model {
+    type Msg: Serialize + Debug;
+
+    /// Generates the next message to be executed.
+    fn next_step(&mut self, rng: &mut Rand64) -> Step<Self::Msg>;
+
+    /// Applies the message to the model. Returns `false` if the
+    /// contract is expected to return an error for this message,
+    /// in which case the model must be left unchanged.
+    fn apply(&mut self, step: &Step<Self::Msg>) -> bool;
+
+    /// Compares the state of the model against the observable
+    /// state of the contracts, usually by querying them.
+    fn compare(&self, ensemble: &ContractEnsemble) -> AnyResult<()>;
+}
+
+/// Executes `steps` messages generated by `model` both on the model and the
+/// ensemble and compares them after each step. The same `seed` always generates
+/// the same sequence of messages so that any failure can be reproduced.
+///
+/// Returns `Err` describing the step at which the model and the
+/// contracts diverged, if that happened.
+pub fn check_model<M: Model>(
+    ensemble: &mut ContractEnsemble,
+    model: &mut M,
+    steps: u32,
+    seed: u128
+) -> AnyResult<()> {
+    let mut rng = Rand64::new(seed);
+
+    for i in 0..steps {
+        let step = model.next_step(&mut rng);
+
+        let expected = model.apply(&step);
+        let result = ensemble.execute(&step.msg, step.env.clone());
+
+        match (expected, result) {
+            (true, Err(err)) => bail!(
+                "Step {} (seed {}): {:?} was expected to succeed but failed with: {}",
+                i, seed, step.msg, err
+            ),
+            (false, Ok(_)) => bail!(
+                "Step {} (seed {}): {:?} was expected to fail but succeeded.",
+                i, seed, step.msg
+            ),
+            _ => { }
+        }
+
+        model.compare(ensemble).with_context(|| format!(
+            "Step {} (seed {}): state diverged after {:?}",
+            i, seed, step.msg
+        ))?;
+    }
+
+    Ok(())
+}
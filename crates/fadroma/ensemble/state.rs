@@ -33,7 +33,6 @@ pub enum Op {
         key: Vec<u8>,
         old: Option<Vec<u8>>
     },
-    #[allow(dead_code)]
     BankAddFunds {
         address: String,
         coin: Coin
@@ -158,7 +157,6 @@ impl State {
         }
     }
 
-    #[allow(dead_code)]
     pub fn add_funds(
         &mut self,
         address: impl Into<String>, 
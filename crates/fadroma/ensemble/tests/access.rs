@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ensemble::{ContractEnsemble, ContractHarness, MockEnv, AnyResult, StorageRead, EnsembleError},
+    storage
+};
+use crate::prelude::*;
+
+const CONTRACT: &str = "contract";
+
+/// Stores a balance and a password for each account.
+struct Accounts;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum QueryMsg {
+    /// Reads the balance only if the password matches.
+    Balance { address: String, password: String },
+    /// Scans all accounts regardless of the address.
+    HasAccount { address: String }
+}
+
+impl ContractHarness for Accounts {
+    fn instantiate(&self, deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Binary) -> AnyResult<Response> {
+        for (address, password) in [("alice", "a"), ("bob", "b")] {
+            storage::save(deps.storage, [b"balance:", address.as_bytes()].concat(), &100u64)?;
+            storage::save(deps.storage, [b"password:", address.as_bytes()].concat(), &password.to_string())?;
+        }
+
+        Ok(Response::default())
+    }
+
+    fn execute(&self, _deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Binary) -> AnyResult<Response> {
+        Ok(Response::default())
+    }
+
+    fn query(&self, deps: Deps, _env: Env, msg: Binary) -> AnyResult<Binary> {
+        match from_binary(&msg)? {
+            QueryMsg::Balance { address, password } => {
+                let expected: Option<String> = storage::load(
+                    deps.storage,
+                    [b"password:", address.as_bytes()].concat()
+                )?;
+
+                if expected != Some(password) {
+                    return Err(StdError::generic_err("Unauthorized").into());
+                }
+
+                let balance: u64 = storage::load(
+                    deps.storage,
+                    [b"balance:", address.as_bytes()].concat()
+                )?.unwrap_or_default();
+
+                Ok(to_binary(&balance)?)
+            }
+            QueryMsg::HasAccount { address } => {
+                let key = [b"balance:", address.as_bytes()].concat();
+                let found = deps.storage
+                    .range(Some(b"balance:"), Some(b"balance;"), Order::Ascending)
+                    .fold(false, |found, (k, _)| found | (k == key));
+
+                Ok(to_binary(&found)?)
+            }
+        }
+    }
+}
+
+#[test]
+fn flags_input_dependent_access() {
+    let mut ensemble = ContractEnsemble::new();
+    let code = ensemble.register(Box::new(Accounts));
+    ensemble.instantiate(code.id, &(), MockEnv::new("admin", CONTRACT)).unwrap();
+
+    let balance = |address: &str, password: &str| QueryMsg::Balance {
+        address: address.into(),
+        password: password.into()
+    };
+
+    let pattern = ensemble.query_access_pattern(CONTRACT, &balance("alice", "a")).unwrap();
+    assert!(pattern.succeeded);
+    assert_eq!(pattern.reads, vec![
+        StorageRead::Get(b"password:alice".into()),
+        StorageRead::Get(b"balance:alice".into())
+    ]);
+
+    let pattern = ensemble.query_access_pattern(CONTRACT, &balance("alice", "wrong")).unwrap();
+    assert!(!pattern.succeeded);
+    assert_eq!(pattern.reads, vec![StorageRead::Get(b"password:alice".into())]);
+
+    // Whether the password is correct changes the number of reads.
+    let err = ensemble.check_access_patterns(
+        CONTRACT,
+        &[balance("alice", "a"), balance("alice", "wrong")]
+    ).unwrap_err();
+    assert!(matches!(err, EnsembleError::AccessPattern(_)));
+    assert!(err.to_string().contains("queries 0 and 1 diverge at read 1"));
+
+    // The address determines which keys are read.
+    let err = ensemble.check_access_patterns(
+        CONTRACT,
+        &[balance("alice", "a"), balance("bob", "b")]
+    ).unwrap_err();
+    assert!(err.to_string().contains("diverge at read 0"));
+
+    let has_account = |address: &str| QueryMsg::HasAccount { address: address.into() };
+    let patterns = ensemble.check_access_patterns(
+        CONTRACT,
+        &[has_account("alice"), has_account("bob"), has_account("charlie")]
+    ).unwrap();
+    assert_eq!(patterns.len(), 3);
+    assert_eq!(patterns[0].reads.len(), 3);
+
+    ensemble.check_access_patterns("missing", &[has_account("alice")]).unwrap_err();
+}
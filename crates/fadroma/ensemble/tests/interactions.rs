@@ -6,7 +6,7 @@ use crate::{
     ensemble::{
         ContractEnsemble, ContractHarness,
         MockEnv, EnsembleResult, EnsembleError,
-        ResponseVariants
+        ResponseVariants, Agent, StorageBudget
     }
 };
 use crate::prelude::*;
@@ -749,3 +749,166 @@ fn remove_funds() {
         _ => panic!("No error message"),
     };
 }
+
+#[test]
+fn agent_executes_through_ensemble() {
+    fn set_and_read<A: Agent>(agent: &mut A, code: &ContractCode) -> Result<Block, A::Error> {
+        let instance = agent.instantiate(code, "block_height", &Empty {}, vec![])?;
+        agent.execute(instance.address.as_str(), &BlockHeightHandle::Set, vec![])?;
+
+        agent.query(instance.address.as_str(), &Empty {})
+    }
+
+    let mut ensemble = ContractEnsemble::new();
+    ensemble.block_mut().exact_increments(10, 7);
+    ensemble.block_mut().height = 0;
+    ensemble.block_mut().time = 0;
+
+    let code = ensemble.register(Box::new(BlockHeight));
+
+    let mut agent = ensemble.agent("admin");
+    assert_eq!(agent.address(), "admin");
+
+    let block = set_and_read(&mut agent, &code).unwrap();
+    assert_eq!(block, Block { height: 10, time: 70 });
+
+    let err = agent.execute("missing", &BlockHeightHandle::Set, vec![]).unwrap_err();
+    assert!(matches!(err, EnsembleError::ContractRegistry(_)));
+
+    let block: Block = agent.ensemble().query("block_height", &Empty {}).unwrap();
+    assert_eq!(block.height, 10);
+}
+
+#[test]
+fn import_exported_storage() {
+    let admin = "admin";
+
+    let mut ensemble = ContractEnsemble::new();
+    ensemble.block_mut().height = 5;
+
+    let code = ensemble.register(Box::new(BlockHeight));
+
+    let original = ensemble
+        .instantiate(code.id, &Empty {}, MockEnv::new(admin, "original"))
+        .unwrap()
+        .instance;
+
+    ensemble.block_mut().height = 100;
+
+    let copy = ensemble
+        .instantiate(code.id, &Empty {}, MockEnv::new(admin, "copy"))
+        .unwrap()
+        .instance;
+
+    let state = ensemble.export_storage(&original.address).unwrap();
+    assert_eq!(state.len(), 1);
+
+    ensemble.import_storage(&copy.address, state).unwrap();
+
+    let a: Block = ensemble.query(&original.address, &Empty {}).unwrap();
+    let b: Block = ensemble.query(&copy.address, &Empty {}).unwrap();
+    assert_eq!(a, b);
+    assert_eq!(b.height, 5);
+
+    let err = ensemble.import_storage("missing", [(b"key", b"value")]).unwrap_err();
+    assert!(matches!(err, EnsembleError::ContractRegistry(_)));
+    ensemble.export_storage("missing").unwrap_err();
+}
+
+#[test]
+fn storage_budget() {
+    let mut ensemble = ContractEnsemble::new();
+    let InitResult { counter, multiplier: _ } = init(&mut ensemble, false, false).unwrap();
+
+    // Increment reads and writes the counter exactly once.
+    ensemble.set_storage_budget(0, "increment", StorageBudget::new(1, 1)).unwrap();
+    ensemble.execute(&CounterHandle::Increment, MockEnv::new("admin", counter.address.clone())).unwrap();
+
+    ensemble.set_storage_budget(0, "increment", StorageBudget::new(1, 0)).unwrap();
+    let err = ensemble.execute(
+        &CounterHandle::Increment,
+        MockEnv::new("admin", counter.address.clone())
+    ).unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "Ensemble error - Storage budget exceeded: \"increment\" performed 1 reads and 1 writes, expected at most 1 reads and 0 writes"
+    );
+
+    // The failed execution is reverted.
+    let number: u8 = ensemble.query(&counter.address, &CounterQuery::Number).unwrap();
+    assert_eq!(number, 1);
+
+    // Only the message with the given name is affected.
+    ensemble.execute(
+        &CounterHandle::IncrementAndMultiply { by: 2 },
+        MockEnv::new("admin", counter.address.clone())
+    ).unwrap();
+
+    ensemble.set_storage_budget(10, "increment", StorageBudget::new(1, 1)).unwrap_err();
+}
+
+#[test]
+fn read_only_messages() {
+    let mut ensemble = ContractEnsemble::new();
+    let InitResult { counter, multiplier: _ } = init(&mut ensemble, false, false).unwrap();
+
+    // The reads budget is kept.
+    ensemble.set_storage_budget(0, "increment", StorageBudget::new(5, 5)).unwrap();
+    ensemble.set_read_only(0, &["increment"]).unwrap();
+
+    let err = ensemble.execute(
+        &CounterHandle::Increment,
+        MockEnv::new("admin", counter.address.clone())
+    ).unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "Ensemble error - Storage budget exceeded: \"increment\" performed 1 reads and 1 writes, expected at most 5 reads and 0 writes"
+    );
+
+    let number: u8 = ensemble.query(&counter.address, &CounterQuery::Number).unwrap();
+    assert_eq!(number, 0);
+
+    ensemble.set_read_only(10, &["increment"]).unwrap_err();
+}
+
+#[test]
+fn invariants_revert_violating_transactions() {
+    let mut ensemble = ContractEnsemble::new();
+    let InitResult { counter, multiplier: _ } = init(&mut ensemble, false, false).unwrap();
+
+    let address = counter.address.clone();
+    ensemble.add_invariant("counter is at most 2", move |ensemble| {
+        let number: u8 = ensemble.query(&address, &CounterQuery::Number)?;
+
+        if number > 2 {
+            bail!("counter is {}", number);
+        }
+
+        Ok(())
+    });
+
+    for _ in 0..2 {
+        ensemble.execute(
+            &CounterHandle::Increment,
+            MockEnv::new("admin", counter.address.clone())
+        ).unwrap();
+    }
+
+    let height = ensemble.block().height;
+
+    let err = ensemble.execute(
+        &CounterHandle::Increment,
+        MockEnv::new("admin", counter.address.clone())
+    ).unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "Ensemble error - Invariant violated: counter is at most 2: counter is 3"
+    );
+
+    let number: u8 = ensemble.query(&counter.address, &CounterQuery::Number).unwrap();
+    assert_eq!(number, 2);
+    assert_eq!(ensemble.block().height, height);
+}
@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+
+use crate::ensemble::{
+    ContractEnsemble, ContractHarness, MockEnv, AnyResult,
+    MigrationCheck, EnsembleError
+};
+use crate::prelude::*;
+
+const V1_PREFIX: &[u8] = b"v1:";
+const V2_PREFIX: &[u8] = b"v2:";
+
+/// Stores balances as u64 under `V1_PREFIX` and migrates them to u128 under `V2_PREFIX`.
+struct Ledger {
+    /// Loses the last account when migrating.
+    buggy: bool
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ExecuteMsg {
+    Migrate {}
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum QueryMsg {
+    TotalBalance {}
+}
+
+impl ContractHarness for Ledger {
+    fn instantiate(&self, _deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Binary) -> AnyResult<Response> {
+        Ok(Response::default())
+    }
+
+    fn execute(&self, deps: DepsMut, _env: Env, _info: MessageInfo, msg: Binary) -> AnyResult<Response> {
+        match from_binary(&msg)? {
+            ExecuteMsg::Migrate {} => {
+                let mut accounts = deps.storage
+                    .range(Some(V1_PREFIX), None, Order::Ascending)
+                    .take_while(|(key, _)| key.starts_with(V1_PREFIX))
+                    .collect::<Vec<_>>();
+
+                for (key, _) in accounts.iter() {
+                    deps.storage.remove(key);
+                }
+
+                if self.buggy {
+                    accounts.pop();
+                }
+
+                for (key, value) in accounts {
+                    let balance = u64::from_be_bytes(value.try_into().unwrap()) as u128;
+                    let key = [V2_PREFIX, &key[V1_PREFIX.len()..]].concat();
+
+                    deps.storage.set(&key, &balance.to_be_bytes());
+                }
+
+                Ok(Response::default())
+            }
+        }
+    }
+
+    fn query(&self, deps: Deps, _env: Env, msg: Binary) -> AnyResult<Binary> {
+        match from_binary(&msg)? {
+            QueryMsg::TotalBalance {} => {
+                let v1: u128 = deps.storage
+                    .range(Some(V1_PREFIX), Some(V2_PREFIX), Order::Ascending)
+                    .map(|(_, value)| u64::from_be_bytes(value.try_into().unwrap()) as u128)
+                    .sum();
+
+                let v2: u128 = deps.storage
+                    .range(Some(V2_PREFIX), None, Order::Ascending)
+                    .take_while(|(key, _)| key.starts_with(V2_PREFIX))
+                    .map(|(_, value)| u128::from_be_bytes(value.try_into().unwrap()))
+                    .sum();
+
+                Ok(to_binary(&Uint128::new(v1 + v2))?)
+            }
+        }
+    }
+}
+
+fn setup(buggy: bool) -> ContractEnsemble {
+    let mut ensemble = ContractEnsemble::new();
+    let ledger = ensemble.register(Box::new(Ledger { buggy }));
+
+    ensemble.instantiate(ledger.id, &(), MockEnv::new("admin", "ledger")).unwrap();
+
+    // Production-shaped data, as exported from a live contract.
+    let accounts = [("alice", 100u64), ("bob", 250), ("carol", 7)].map(|(address, balance)|
+        ([V1_PREFIX, address.as_bytes()].concat(), balance.to_be_bytes())
+    );
+
+    ensemble.import_storage("ledger", accounts).unwrap();
+
+    ensemble
+}
+
+fn checks() -> Vec<MigrationCheck> {
+    vec![
+        MigrationCheck::unchanged("total balance", |ensemble| {
+            let total: Uint128 = ensemble.query("ledger", &QueryMsg::TotalBalance {})?;
+
+            Ok(total)
+        }),
+        MigrationCheck::moved(
+            "accounts",
+            |ensemble| Ok(ensemble.count_keys("ledger", V1_PREFIX)?),
+            |ensemble| Ok(ensemble.count_keys("ledger", V2_PREFIX)?)
+        ),
+        MigrationCheck::key_count("ledger", b"config".to_vec())
+    ]
+}
+
+fn migrate(ensemble: &mut ContractEnsemble) -> crate::ensemble::EnsembleResult<()> {
+    ensemble.execute(&ExecuteMsg::Migrate {}, MockEnv::new("admin", "ledger"))?;
+
+    Ok(())
+}
+
+#[test]
+fn migration_preserves_state() {
+    let mut ensemble = setup(false);
+
+    ensemble.rehearse_migration(checks(), migrate).unwrap();
+
+    assert_eq!(ensemble.count_keys("ledger", V1_PREFIX).unwrap(), 0);
+    assert_eq!(ensemble.count_keys("ledger", V2_PREFIX).unwrap(), 3);
+}
+
+#[test]
+fn migration_that_loses_data_fails() {
+    let mut ensemble = setup(true);
+
+    let err = ensemble.rehearse_migration(checks(), migrate).unwrap_err();
+
+    match err {
+        EnsembleError::Migration(msg) => assert_eq!(
+            msg,
+            "total balance: expected Uint128(357) but was Uint128(350)"
+        ),
+        err => panic!("Expected EnsembleError::Migration, got: {}", err)
+    }
+
+    // The state that the migration produced is kept for inspection.
+    assert_eq!(ensemble.count_keys("ledger", V2_PREFIX).unwrap(), 2);
+
+    let err = ensemble.rehearse_migration(
+        vec![MigrationCheck::key_count("missing", V1_PREFIX)],
+        |_| Ok(())
+    ).unwrap_err();
+
+    assert!(err.to_string().contains("(before)"));
+}
@@ -1,4 +1,9 @@
+mod access;
 mod interactions;
+mod migration;
+mod model;
+mod panics;
+mod replay;
 #[cfg(feature = "ensemble-staking")]
 mod staking;
 mod submsg;
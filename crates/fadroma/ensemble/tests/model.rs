@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    storage::{ItemSpace, TypedKey},
+    ensemble::{
+        ContractEnsemble, ContractHarness, MockEnv, AnyResult,
+        Model, Step, check_model,
+        anyhow::{bail, ensure},
+        oorandom::Rand64
+    }
+};
+use crate::prelude::*;
+
+const VAULT: &str = "vault";
+const USERS: [&str; 3] = ["alice", "bob", "charlie"];
+
+/// Withdrawals that exceed the balance are supposed to fail.
+/// When `buggy` is `true`, the balance is set to zero instead.
+struct Vault {
+    buggy: bool
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+enum ExecuteMsg {
+    Deposit { amount: u64 },
+    Withdraw { amount: u64 }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum QueryMsg {
+    Balance { address: String }
+}
+
+crate::namespace!(BalancesNs, b"balances");
+const BALANCES: ItemSpace<u64, BalancesNs, TypedKey<String>> = ItemSpace::new();
+
+impl ContractHarness for Vault {
+    fn instantiate(&self, _deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Binary) -> AnyResult<Response> {
+        Ok(Response::default())
+    }
+
+    fn execute(&self, deps: DepsMut, _env: Env, info: MessageInfo, msg: Binary) -> AnyResult<Response> {
+        let msg: ExecuteMsg = from_binary(&msg)?;
+        let address = info.sender.into_string();
+        let balance = BALANCES.load_or_default(deps.storage, &address)?;
+
+        let balance = match msg {
+            ExecuteMsg::Deposit { amount } => balance + amount,
+            ExecuteMsg::Withdraw { amount } if self.buggy => balance.saturating_sub(amount),
+            ExecuteMsg::Withdraw { amount } => match balance.checked_sub(amount) {
+                Some(balance) => balance,
+                None => bail!("Insufficient balance.")
+            }
+        };
+
+        BALANCES.save(deps.storage, &address, &balance)?;
+
+        Ok(Response::default())
+    }
+
+    fn query(&self, deps: Deps, _env: Env, msg: Binary) -> AnyResult<Binary> {
+        let QueryMsg::Balance { address } = from_binary(&msg)?;
+        let balance = BALANCES.load_or_default(deps.storage, &address)?;
+
+        Ok(to_binary(&balance)?)
+    }
+}
+
+#[derive(Default)]
+struct VaultModel {
+    balances: HashMap<String, u64>
+}
+
+impl Model for VaultModel {
+    type Msg = ExecuteMsg;
+
+    fn next_step(&mut self, rng: &mut Rand64) -> Step<Self::Msg> {
+        let sender = USERS[rng.rand_range(0..USERS.len() as u64) as usize];
+        let amount = rng.rand_range(1..100);
+
+        let msg = if rng.rand_range(0..2) == 0 {
+            ExecuteMsg::Deposit { amount }
+        } else {
+            ExecuteMsg::Withdraw { amount }
+        };
+
+        Step {
+            msg,
+            env: MockEnv::new(sender, VAULT)
+        }
+    }
+
+    fn apply(&mut self, step: &Step<Self::Msg>) -> bool {
+        let balance = self.balances
+            .entry(step.env.sender().to_string())
+            .or_default();
+
+        match step.msg {
+            ExecuteMsg::Deposit { amount } => {
+                *balance += amount;
+
+                true
+            },
+            ExecuteMsg::Withdraw { amount } => match balance.checked_sub(amount) {
+                Some(new) => {
+                    *balance = new;
+
+                    true
+                }
+                None => false
+            }
+        }
+    }
+
+    fn compare(&self, ensemble: &ContractEnsemble) -> AnyResult<()> {
+        for user in USERS {
+            let actual: u64 = ensemble.query(VAULT, &QueryMsg::Balance {
+                address: user.into()
+            })?;
+            let expected = self.balances.get(user).copied().unwrap_or_default();
+
+            ensure!(actual == expected, "{}: expected {}, got {}", user, expected, actual);
+        }
+
+        Ok(())
+    }
+}
+
+fn init(buggy: bool) -> ContractEnsemble {
+    let mut ensemble = ContractEnsemble::new();
+    let vault = ensemble.register(Box::new(Vault { buggy }));
+
+    ensemble.instantiate(vault.id, &Empty { }, MockEnv::new("admin", VAULT)).unwrap();
+
+    ensemble
+}
+
+#[test]
+fn model_matches_contract() {
+    let mut ensemble = init(false);
+    let mut model = VaultModel::default();
+
+    check_model(&mut ensemble, &mut model, 200, 42).unwrap();
+}
+
+#[test]
+fn model_finds_divergence() {
+    let mut ensemble = init(true);
+    let mut model = VaultModel::default();
+
+    let err = check_model(&mut ensemble, &mut model, 200, 42).unwrap_err();
+    let err = err.to_string();
+
+    assert!(err.contains("(seed 42)"));
+    assert!(err.contains("was expected to fail but succeeded"));
+}
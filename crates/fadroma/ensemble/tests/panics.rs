@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+use crate::ensemble::{
+    ContractEnsemble, ContractHarness, CatchPanics,
+    MockEnv, AnyResult, EnsembleError
+};
+use crate::prelude::*;
+
+const NUMBER_KEY: &[u8] = b"number";
+
+struct Divider;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ExecuteMsg {
+    /// Stores the result before dividing by zero panics.
+    Divide { by: u64 }
+}
+
+impl ContractHarness for Divider {
+    fn instantiate(&self, deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Binary) -> AnyResult<Response> {
+        deps.storage.set(NUMBER_KEY, &100u64.to_be_bytes());
+
+        Ok(Response::default())
+    }
+
+    fn execute(&self, deps: DepsMut, _env: Env, _info: MessageInfo, msg: Binary) -> AnyResult<Response> {
+        match from_binary(&msg)? {
+            ExecuteMsg::Divide { by } => {
+                deps.storage.set(NUMBER_KEY, &0u64.to_be_bytes());
+                deps.storage.set(NUMBER_KEY, &(100 / by).to_be_bytes());
+
+                Ok(Response::default())
+            }
+        }
+    }
+
+    fn query(&self, deps: Deps, _env: Env, _msg: Binary) -> AnyResult<Binary> {
+        let number = deps.storage.get(NUMBER_KEY).unwrap();
+
+        Ok(to_binary(&u64::from_be_bytes(number.try_into().unwrap()))?)
+    }
+}
+
+#[test]
+fn panics_are_converted_to_errors() {
+    let mut ensemble = ContractEnsemble::new();
+    let divider = ensemble.register(Box::new(CatchPanics(Divider)));
+
+    ensemble.instantiate(divider.id, &(), MockEnv::new("admin", "divider")).unwrap();
+    ensemble.execute(&ExecuteMsg::Divide { by: 4 }, MockEnv::new("admin", "divider")).unwrap();
+
+    let err = ensemble.execute(
+        &ExecuteMsg::Divide { by: 0 },
+        MockEnv::new("admin", "divider")
+    ).unwrap_err();
+
+    match err {
+        EnsembleError::ContractError(err) => assert_eq!(
+            err.to_string(),
+            "Generic error: Handler panicked: attempt to divide by zero"
+        ),
+        err => panic!("Expected EnsembleError::ContractError, got: {}", err)
+    }
+
+    // The writes made before panicking are reverted.
+    let number: u64 = ensemble.query("divider", &()).unwrap();
+    assert_eq!(number, 25);
+}
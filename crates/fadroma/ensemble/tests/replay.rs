@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    admin,
+    ensemble::{ContractEnsemble, ContractHarness, MockEnv, AnyResult},
+    storage
+};
+use crate::prelude::*;
+
+const ADMIN: &str = "admin";
+const USER: &str = "user";
+const CONTRACT: &str = "contract";
+
+/// `SetLimit` is admin only but `SetFee` is missing its check.
+struct Config;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ExecuteMsg {
+    SetLimit { value: u64 },
+    SetFee { value: u64 },
+    Deposit
+}
+
+impl ContractHarness for Config {
+    fn instantiate(&self, deps: DepsMut, _env: Env, info: MessageInfo, _msg: Binary) -> AnyResult<Response> {
+        admin::init(deps, None, &info)?;
+
+        Ok(Response::default())
+    }
+
+    fn execute(&self, deps: DepsMut, _env: Env, info: MessageInfo, msg: Binary) -> AnyResult<Response> {
+        match from_binary(&msg)? {
+            ExecuteMsg::SetLimit { value } => {
+                admin::assert(deps.as_ref(), &info)?;
+                storage::save(deps.storage, b"limit", &value)?;
+            }
+            ExecuteMsg::SetFee { value } => {
+                storage::save(deps.storage, b"fee", &value)?;
+            }
+            ExecuteMsg::Deposit => {
+                if info.funds.is_empty() {
+                    return Err(StdError::generic_err("No funds sent.").into());
+                }
+            }
+        }
+
+        Ok(Response::default())
+    }
+
+    fn query(&self, deps: Deps, _env: Env, _msg: Binary) -> AnyResult<Binary> {
+        let fee: u64 = storage::load(deps.storage, b"fee")?.unwrap_or_default();
+
+        Ok(to_binary(&fee)?)
+    }
+}
+
+#[test]
+fn replay_finds_unguarded_messages() {
+    let mut ensemble = ContractEnsemble::new();
+    ensemble.add_funds(ADMIN, vec![coin(100, "uscrt")]);
+
+    let config = ensemble.register(Box::new(Config));
+    ensemble.instantiate(config.id, &Empty { }, MockEnv::new(ADMIN, CONTRACT)).unwrap();
+
+    ensemble.execute(&ExecuteMsg::SetLimit { value: 10 }, MockEnv::new(ADMIN, CONTRACT)).unwrap();
+    ensemble.execute(&ExecuteMsg::SetFee { value: 5 }, MockEnv::new(ADMIN, CONTRACT)).unwrap();
+    ensemble.execute(
+        &ExecuteMsg::Deposit,
+        MockEnv::new(ADMIN, CONTRACT).sent_funds(vec![coin(100, "uscrt")])
+    ).unwrap();
+    ensemble.execute(&ExecuteMsg::SetFee { value: 1 }, MockEnv::new(USER, CONTRACT)).unwrap();
+
+    assert_eq!(ensemble.executed().len(), 4);
+
+    let unguarded = ensemble.replay_unprivileged(ADMIN, USER);
+    assert_eq!(unguarded.len(), 2);
+
+    assert_eq!(unguarded[0].msg, to_binary(&ExecuteMsg::SetFee { value: 5 }).unwrap());
+    // The user is given the funds that the admin sent.
+    assert_eq!(unguarded[1].msg, to_binary(&ExecuteMsg::Deposit).unwrap());
+    assert_eq!(unguarded[1].funds, vec![coin(100, "uscrt")]);
+
+    // Replaying doesn't change the state.
+    let fee: u64 = ensemble.query(CONTRACT, &()).unwrap();
+    assert_eq!(fee, 1);
+    assert!(ensemble.balances(USER).unwrap().values().all(|x| x.is_zero()));
+    assert_eq!(ensemble.executed().len(), 4);
+}
@@ -0,0 +1,411 @@
+//! Fixed-length epochs derived from the block time or height and per-epoch accounting
+//! shared by components that distribute rewards or fees periodically.
+//!
+//! Epochs are never advanced by a transaction of their own. Instead, [`advance`] should
+//! be called at the start of every handler that depends on them, which detects that one
+//! or more epochs have passed since it was last called and runs the [`EpochHook`]s of the
+//! components once per rollover.
+//!
+//! Use an [`EpochAccumulator`] to record per-account amounts during an epoch, i.e. the
+//! stake or the volume of an account, and settle them lazily once the epoch is over,
+//! i.e. when the account next interacts with the contract.
+
+use std::marker::PhantomData;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    self as fadroma,
+    core::math::{MathConfig, Arithmetic},
+    prelude::*
+};
+
+crate::namespace!(pub EpochConfigNs, b"Zq8mT3vHcL");
+pub const CONFIG: SingleItem<EpochConfig, EpochConfigNs> = SingleItem::new();
+
+crate::namespace!(pub LastEpochNs, b"c6XnW1pRkF");
+const LAST_EPOCH: SingleItem<u64, LastEpochNs> = SingleItem::new();
+
+const TOTAL_NS: &[u8] = b"total";
+const AMOUNT_NS: &[u8] = b"amount";
+const SETTLED_NS: &[u8] = b"settled";
+
+#[derive(
+    Serialize, Deserialize, FadromaSerialize, FadromaDeserialize,
+    JsonSchema, PartialEq, Debug, Clone, Copy
+)]
+#[serde(rename_all = "snake_case")]
+pub enum EpochClock {
+    /// Epochs are measured in seconds of block time.
+    Time,
+    /// Epochs are measured in blocks.
+    Height
+}
+
+/// Epoch `0` begins at `start` and each epoch lasts for `length` units of the `clock`.
+///
+/// # Examples
+///
+/// ```
+/// use fadroma::{epochs::{EpochConfig, EpochClock}, cosmwasm_std::testing::mock_env};
+///
+/// let mut block = mock_env().block;
+/// block.height = 250;
+///
+/// let config = EpochConfig { clock: EpochClock::Height, start: 100, length: 100 };
+///
+/// assert_eq!(config.epoch_at(&block), 1);
+/// assert_eq!(config.epoch_start(1), 200);
+/// ```
+#[derive(
+    Serialize, Deserialize, FadromaSerialize, FadromaDeserialize,
+    JsonSchema, PartialEq, Debug, Clone
+)]
+pub struct EpochConfig {
+    pub clock: EpochClock,
+    pub start: u64,
+    pub length: u64
+}
+
+/// One or more epochs have passed. Epochs between
+/// `from` and `to` may have passed without any activity.
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone, Copy)]
+pub struct Rollover {
+    /// The epoch that has ended.
+    pub from: u64,
+    /// The current epoch.
+    pub to: u64
+}
+
+/// Implemented by components that need to act on epoch rollovers, i.e.
+/// to snapshot the reward rate of the epoch that has just ended.
+pub trait EpochHook {
+    fn on_rollover(&self, deps: DepsMut, env: &Env, rollover: &Rollover) -> StdResult<()>;
+}
+
+/// The amount of an account in a single completed epoch, passed to
+/// the callback of [`EpochAccumulator::settle`].
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct EpochShare {
+    pub epoch: u64,
+    pub amount: Uint128,
+    /// The sum of the amounts of all accounts in the epoch.
+    pub total: Uint128
+}
+
+/// Accumulates per-account amounts for each epoch, which are settled lazily.
+/// Each instance must use its own namespace.
+///
+/// # Examples
+///
+/// ```
+/// # use fadroma::{
+/// #     epochs::{EpochAccumulator, EpochShare},
+/// #     cosmwasm_std::{CanonicalAddr, Uint128, StdResult, testing::mock_dependencies}
+/// # };
+/// # fn main() -> StdResult<()> {
+/// # let mut deps = mock_dependencies();
+/// # let storage = deps.as_mut().storage;
+/// fadroma::namespace!(VolumeNs, b"volume");
+/// const VOLUME: EpochAccumulator<VolumeNs> = EpochAccumulator::new();
+///
+/// let alice = CanonicalAddr::from(b"alice".as_slice());
+/// let bob = CanonicalAddr::from(b"bob".as_slice());
+///
+/// VOLUME.add(storage, 0, &alice, Uint128::new(30))?;
+/// VOLUME.add(storage, 0, &bob, Uint128::new(10))?;
+/// VOLUME.add(storage, 1, &alice, Uint128::new(5))?;
+///
+/// let mut shares = vec![];
+///
+/// // Only the epochs before the current one are settled.
+/// let settled = VOLUME.settle(storage, &alice, 1, 10, |_, share| {
+///     shares.push(share);
+///
+///     Ok(())
+/// })?;
+///
+/// assert!(settled);
+/// assert_eq!(shares, vec![
+///     EpochShare { epoch: 0, amount: Uint128::new(30), total: Uint128::new(40) }
+/// ]);
+/// # Ok(())
+/// # }
+/// ```
+pub struct EpochAccumulator<N: Namespace> {
+    ns: PhantomData<N>
+}
+
+impl EpochConfig {
+    /// Returns the epoch that `block` falls in. Blocks before
+    /// the start of the first epoch are considered part of it.
+    #[inline]
+    pub fn epoch_at(&self, block: &BlockInfo) -> u64 {
+        let now = match self.clock {
+            EpochClock::Time => block.time.seconds(),
+            EpochClock::Height => block.height
+        };
+
+        now.saturating_sub(self.start) / self.length
+    }
+
+    /// Returns the time or height at which `epoch` begins.
+    #[inline]
+    pub fn epoch_start(&self, epoch: u64) -> u64 {
+        self.start.saturating_add(epoch.saturating_mul(self.length))
+    }
+}
+
+/// Configures the epochs. You **must** call this in your instantiate
+/// message in order to use [`current`] and [`advance`].
+pub fn init(storage: &mut dyn Storage, block: &BlockInfo, config: &EpochConfig) -> StdResult<()> {
+    if config.length == 0 {
+        return Err(StdError::generic_err("The epoch length must be greater than zero."));
+    }
+
+    CONFIG.save(storage, config)?;
+    LAST_EPOCH.save(storage, &config.epoch_at(block))
+}
+
+/// Returns `Err` if [`init`] wasn't called.
+#[inline]
+pub fn load_config(storage: &dyn Storage) -> StdResult<EpochConfig> {
+    CONFIG.load(storage)?.ok_or_else(||
+        StdError::generic_err("Epochs have not been configured.")
+    )
+}
+
+/// Returns the current epoch.
+#[inline]
+pub fn current(storage: &dyn Storage, block: &BlockInfo) -> StdResult<u64> {
+    Ok(load_config(storage)?.epoch_at(block))
+}
+
+/// Checks whether the epoch has changed since the last call and if so, calls
+/// each of the `hooks` in order with the same [`Rollover`], which is also returned.
+pub fn advance(
+    mut deps: DepsMut,
+    env: &Env,
+    hooks: &[&dyn EpochHook]
+) -> StdResult<Option<Rollover>> {
+    let to = current(deps.storage, &env.block)?;
+    let from = LAST_EPOCH.load_or_default(deps.storage)?;
+
+    if to <= from {
+        return Ok(None);
+    }
+
+    LAST_EPOCH.save(deps.storage, &to)?;
+
+    let rollover = Rollover { from, to };
+
+    for hook in hooks {
+        hook.on_rollover(deps.branch(), env, &rollover)?;
+    }
+
+    Ok(Some(rollover))
+}
+
+impl<N: Namespace> EpochAccumulator<N> {
+    #[inline]
+    pub const fn new() -> Self {
+        Self { ns: PhantomData }
+    }
+
+    /// Adds `amount` to the amount of `account` and to the total in `epoch`.
+    /// Amounts must only be added to the current epoch since epochs that
+    /// have already been settled for `account` are not settled again.
+    /// Overflows according to the [`MathConfig`] of the contract.
+    pub fn add(
+        &self,
+        storage: &mut dyn Storage,
+        epoch: u64,
+        account: &CanonicalAddr,
+        amount: Uint128
+    ) -> StdResult<()> {
+        let policy = MathConfig::load(storage)?.overflow;
+
+        let key = Self::amount_key(epoch, account);
+        let current: Uint128 = storage::load(storage, &key)?.unwrap_or_default();
+        storage::save(storage, &key, &current.add_with(amount, policy)?)?;
+
+        let key = Self::total_key(epoch);
+        let total: Uint128 = storage::load(storage, &key)?.unwrap_or_default();
+        storage::save(storage, &key, &total.add_with(amount, policy)?)?;
+
+        // The first epoch that needs to be settled for the account.
+        let key = Self::settled_key(account);
+
+        if !storage::exists(storage, &key) {
+            storage::save(storage, &key, &epoch)?;
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn total(&self, storage: &dyn Storage, epoch: u64) -> StdResult<Uint128> {
+        Ok(storage::load(storage, Self::total_key(epoch))?.unwrap_or_default())
+    }
+
+    #[inline]
+    pub fn amount(
+        &self,
+        storage: &dyn Storage,
+        epoch: u64,
+        account: &CanonicalAddr
+    ) -> StdResult<Uint128> {
+        Ok(storage::load(storage, Self::amount_key(epoch, account))?.unwrap_or_default())
+    }
+
+    /// Calls `settle` once for each epoch before `current_epoch` in which `account`
+    /// has a non-zero amount and that hasn't been settled yet, in order. At most
+    /// `max_epochs` epochs are visited in order to bound the gas used by accounts
+    /// that have been inactive for a long time.
+    ///
+    /// Returns `true` if all completed epochs have been settled for `account`.
+    pub fn settle<F>(
+        &self,
+        storage: &mut dyn Storage,
+        account: &CanonicalAddr,
+        current_epoch: u64,
+        max_epochs: u64,
+        mut settle: F
+    ) -> StdResult<bool>
+        where F: FnMut(&mut dyn Storage, EpochShare) -> StdResult<()>
+    {
+        let key = Self::settled_key(account);
+        let Some(next) = storage::load::<u64>(storage, &key)? else {
+            return Ok(true);
+        };
+
+        let end = current_epoch.min(next.saturating_add(max_epochs));
+
+        for epoch in next..end {
+            let amount = self.amount(storage, epoch, account)?;
+
+            if amount.is_zero() {
+                continue;
+            }
+
+            let total = self.total(storage, epoch)?;
+            settle(storage, EpochShare { epoch, amount, total })?;
+        }
+
+        if end > next {
+            storage::save(storage, &key, &end)?;
+        }
+
+        Ok(end == current_epoch)
+    }
+
+    #[inline]
+    fn total_key(epoch: u64) -> Vec<u8> {
+        [N::NAMESPACE, TOTAL_NS, &epoch.to_be_bytes()].concat()
+    }
+
+    #[inline]
+    fn amount_key(epoch: u64, account: &CanonicalAddr) -> Vec<u8> {
+        [N::NAMESPACE, AMOUNT_NS, account.as_slice(), &epoch.to_be_bytes()].concat()
+    }
+
+    #[inline]
+    fn settled_key(account: &CanonicalAddr) -> Vec<u8> {
+        [N::NAMESPACE, SETTLED_NS, account.as_slice()].concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::cosmwasm_std::testing::{mock_dependencies, mock_env};
+
+    crate::namespace!(RewardsNs, b"rewards");
+    const REWARDS: EpochAccumulator<RewardsNs> = EpochAccumulator::new();
+
+    struct Recorder(RefCell<Vec<Rollover>>);
+
+    impl EpochHook for Recorder {
+        fn on_rollover(&self, _deps: DepsMut, _env: &Env, rollover: &Rollover) -> StdResult<()> {
+            self.0.borrow_mut().push(*rollover);
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn advances_epochs() {
+        let ref mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let start = env.block.time.seconds();
+
+        let config = EpochConfig { clock: EpochClock::Time, start, length: 0 };
+        assert!(init(deps.as_mut().storage, &env.block, &config).is_err());
+        assert!(current(deps.as_ref().storage, &env.block).is_err());
+
+        let config = EpochConfig { length: 60, ..config };
+        init(deps.as_mut().storage, &env.block, &config).unwrap();
+
+        let recorder = Recorder(RefCell::new(vec![]));
+        let hooks: [&dyn EpochHook; 1] = [&recorder];
+
+        assert_eq!(advance(deps.as_mut(), &env, &hooks).unwrap(), None);
+
+        env.block.time = env.block.time.plus_seconds(59);
+        assert_eq!(advance(deps.as_mut(), &env, &hooks).unwrap(), None);
+
+        env.block.time = env.block.time.plus_seconds(1);
+        assert_eq!(advance(deps.as_mut(), &env, &hooks).unwrap(), Some(Rollover { from: 0, to: 1 }));
+        assert_eq!(advance(deps.as_mut(), &env, &hooks).unwrap(), None);
+
+        // Multiple epochs without any activity result in a single rollover.
+        env.block.time = env.block.time.plus_seconds(180);
+        assert_eq!(current(deps.as_ref().storage, &env.block).unwrap(), 4);
+        advance(deps.as_mut(), &env, &hooks).unwrap();
+
+        assert_eq!(
+            recorder.0.into_inner(),
+            vec![Rollover { from: 0, to: 1 }, Rollover { from: 1, to: 4 }]
+        );
+        assert_eq!(config.epoch_start(4), start + 240);
+    }
+
+    #[test]
+    fn settles_completed_epochs_lazily() {
+        let ref mut deps = mock_dependencies();
+        let storage = deps.as_mut().storage;
+        let alice = CanonicalAddr::from(b"alice".as_slice());
+        let bob = CanonicalAddr::from(b"bob".as_slice());
+
+        let mut settled = vec![];
+        let mut settle = |storage: &mut dyn Storage, current: u64, max: u64| {
+            REWARDS.settle(storage, &alice, current, max, |_, share| {
+                settled.push(share.epoch);
+
+                Ok(())
+            }).unwrap()
+        };
+
+        assert!(settle(storage, 5, 10));
+
+        REWARDS.add(storage, 2, &alice, Uint128::new(10)).unwrap();
+        REWARDS.add(storage, 2, &bob, Uint128::new(30)).unwrap();
+        REWARDS.add(storage, 2, &alice, Uint128::new(10)).unwrap();
+        REWARDS.add(storage, 5, &alice, Uint128::new(1)).unwrap();
+        REWARDS.add(storage, 7, &alice, Uint128::new(1)).unwrap();
+
+        assert_eq!(REWARDS.amount(storage, 2, &alice).unwrap(), Uint128::new(20));
+        assert_eq!(REWARDS.total(storage, 2).unwrap(), Uint128::new(50));
+
+        // The current epoch isn't settled.
+        assert!(settle(storage, 2, 10));
+
+        assert!(!settle(storage, 8, 3));
+        assert!(settle(storage, 8, 3));
+        assert!(settle(storage, 8, 3));
+
+        assert_eq!(settled, vec![2, 5, 7]);
+    }
+}
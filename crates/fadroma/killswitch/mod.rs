@@ -17,6 +17,9 @@ use serde::{Deserialize, Serialize};
 crate::namespace!(pub KillswitchNs, b"zK5CBApPlV");
 pub const STORE: SingleItem<ContractStatus<CanonicalAddr>, KillswitchNs> = SingleItem::new();
 
+crate::namespace!(pub RestrictionsNs, b"9hQ7vJtrzB");
+pub const RESTRICTIONS: SingleItem<Vec<Restriction>, RestrictionsNs> = SingleItem::new();
+
 /// Possible states of a contract.
 #[derive(Serialize, Deserialize, Canonize, FadromaSerialize, FadromaDeserialize, JsonSchema, PartialEq, Debug, Clone)]
 pub enum ContractStatus<A: Address> {
@@ -33,6 +36,15 @@ pub enum ContractStatus<A: Address> {
     }
 }
 
+/// A single message that has been paused independently of the contract status.
+#[derive(Serialize, Deserialize, FadromaSerialize, FadromaDeserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct Restriction {
+    /// The name of the paused message as it appears in its JSON
+    /// representation i.e the value returned by `ExecuteMsg::name`.
+    pub msg: String,
+    pub reason: String
+}
+
 /// Requires the admin component in order to check for admin.
 #[interface]
 pub trait Killswitch: Admin {
@@ -43,8 +55,19 @@ pub trait Killswitch: Admin {
         status: ContractStatus<Addr>
     ) -> Result<Response, <Self as Killswitch>::Error>;
 
+    /// Pauses the message with the given name if `reason` is `Some`
+    /// or lifts any existing restriction on it otherwise.
+    #[execute]
+    fn set_restriction(
+        msg: String,
+        reason: Option<String>
+    ) -> Result<Response, <Self as Killswitch>::Error>;
+
     #[query]
     fn status() -> Result<ContractStatus<Addr>, <Self as Killswitch>::Error>;
+
+    #[query]
+    fn restrictions() -> Result<Vec<Restriction>, <Self as Killswitch>::Error>;
 }
 
 pub struct DefaultImpl;
@@ -79,10 +102,29 @@ impl Killswitch for DefaultImpl {
         )
     }
 
+    #[execute]
+    fn set_restriction(
+        msg: String,
+        reason: Option<String>
+    ) -> StdResult<Response> {
+        let action = if reason.is_some() { "pause" } else { "unpause" };
+        set_restriction(deps, info, msg.clone(), reason)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_restriction")
+            .add_attribute(action, msg)
+        )
+    }
+
     #[query]
     fn status() -> StdResult<ContractStatus<Addr>> {
         STORE.load_humanize_or_default(deps)
     }
+
+    #[query]
+    fn restrictions() -> StdResult<Vec<Restriction>> {
+        RESTRICTIONS.load_or_default(deps.storage)
+    }
 }
 
 /// Returns `false` if the current contract status level is other than [`ContractStatus::Operational`].
@@ -109,6 +151,23 @@ pub fn assert_is_operational(deps: Deps) -> StdResult<()> {
     Ok(())
 }
 
+/// Fail if the message with the given name has been paused using [`set_restriction`].
+/// This doesn't check the contract status, use [`assert_is_operational`] for that.
+/// 
+/// When using the DSL, call this from the `#[execute_guard]` with `msg.name()`.
+#[inline]
+pub fn assert_not_restricted(deps: Deps, msg: &str) -> StdResult<()> {
+    let restrictions = RESTRICTIONS.load_or_default(deps.storage)?;
+
+    if let Some(restriction) = restrictions.iter().find(|x| x.msg == msg) {
+        return Err(StdError::generic_err(
+            format!("{} is paused\nReason: {}", msg, restriction.reason)
+        ));
+    }
+
+    Ok(())
+}
+
 /// Fail if trying to return from [`ContractStatus::Migrating`] status.
 #[inline]
 pub fn assert_can_set_status(deps: Deps, new: &ContractStatus<Addr>) -> StdResult<()> {
@@ -139,6 +198,25 @@ pub fn set_status(
     STORE.canonize_and_save(deps, status)
 }
 
+/// Pause the message with the given name if `reason` is `Some`, otherwise lift
+/// the restriction on it. Requires the admin component in order to check for admin.
+#[admin::require_admin]
+pub fn set_restriction(
+    deps: DepsMut,
+    info: MessageInfo,
+    msg: String,
+    reason: Option<String>
+) -> StdResult<()> {
+    let mut restrictions = RESTRICTIONS.load_or_default(deps.storage)?;
+    restrictions.retain(|x| x.msg != msg);
+
+    if let Some(reason) = reason {
+        restrictions.push(Restriction { msg, reason });
+    }
+
+    RESTRICTIONS.save(deps.storage, &restrictions)
+}
+
 impl<A: Address> Default for ContractStatus<A> {
     fn default() -> Self {
         Self::Operational
@@ -266,4 +344,61 @@ mod tests {
         let current = STORE.load_humanize_or_default(deps.as_ref()).unwrap();
         assert_eq!(current, migrating);
     }
+
+    #[test]
+    fn test_restrictions() {
+        let mut deps = mock_dependencies();
+        let admin = "admin";
+
+        admin::init(deps.as_mut(), None, &mock_info(admin, &[])).unwrap();
+
+        assert_not_restricted(deps.as_ref(), "deposit").unwrap();
+        assert!(RESTRICTIONS.load_or_default(&deps.storage).unwrap().is_empty());
+
+        let err = set_restriction(
+            deps.as_mut(),
+            mock_info("not_admin", &[]),
+            "deposit".into(),
+            Some("Reason".into())
+        ).unwrap_err();
+
+        assert_eq!(err, StdError::generic_err("Unauthorized"));
+
+        set_restriction(
+            deps.as_mut(),
+            mock_info(admin, &[]),
+            "deposit".into(),
+            Some("Reason".into())
+        ).unwrap();
+
+        let err = assert_not_restricted(deps.as_ref(), "deposit").unwrap_err();
+        assert_eq!(err, StdError::generic_err("deposit is paused\nReason: Reason"));
+
+        assert_not_restricted(deps.as_ref(), "withdraw").unwrap();
+        assert_is_operational(deps.as_ref()).unwrap();
+
+        // Setting it again replaces the reason rather than adding a duplicate.
+        set_restriction(
+            deps.as_mut(),
+            mock_info(admin, &[]),
+            "deposit".into(),
+            Some("Other".into())
+        ).unwrap();
+
+        let restrictions = RESTRICTIONS.load_or_default(&deps.storage).unwrap();
+        assert_eq!(restrictions, vec![Restriction {
+            msg: "deposit".into(),
+            reason: "Other".into()
+        }]);
+
+        set_restriction(
+            deps.as_mut(),
+            mock_info(admin, &[]),
+            "deposit".into(),
+            None
+        ).unwrap();
+
+        assert_not_restricted(deps.as_ref(), "deposit").unwrap();
+        assert!(RESTRICTIONS.load_or_default(&deps.storage).unwrap().is_empty());
+    }
 }
@@ -21,6 +21,13 @@ pub mod tokens;
 pub use fadroma_dsl as dsl;
 pub mod killswitch;
 pub mod admin;
+pub mod token_registry;
+pub mod price_guard;
+pub mod epochs;
+pub mod claims;
+pub mod tiers;
+pub mod metrics;
+pub mod trusted_contracts;
 #[cfg(feature = "crypto")]
 pub mod crypto;
 
@@ -54,7 +61,8 @@ pub mod prelude {
 
     pub use crate::storage::{
         self, Key, Namespace, CompositeKey, StaticKey, FixedSegmentSizeKey,
-        TypedKey, TypedKey2, TypedKey3, TypedKey4, SingleItem, ItemSpace
+        TypedKey, TypedKey2, TypedKey3, TypedKey4, PrefixedKey2, PrefixedKey3,
+        PrefixedKey4, SingleItem, ItemSpace
     };
 
     #[cfg(feature = "vk")]
@@ -69,6 +77,10 @@ pub mod prelude {
 /// 
 /// Supports `init`, `execute` and `query` **or**
 /// `init`, `execute`, `query` and `reply`.
+///
+/// Optionally, `limits: { max_size: 4096, max_depth: 16 }` can be
+/// given last in order to reject any `init`, `execute` or `query` messages
+/// that exceed them before they are deserialized. See [`core::limits`].
 /// 
 /// Note that Fadroma DSL already handles this for you and
 /// as such this macro is not needed when using it.
@@ -118,23 +130,32 @@ pub mod prelude {
 /// ```
 #[macro_export]
 macro_rules! entrypoint {
-    (@init $($init:ident)::+) => {
+    (@check $limits:ident, $msg_ptr:ident) => {
+        if let Some(result) = unsafe { $crate::core::limits::wasm::check(&$limits, $msg_ptr) } {
+            return result;
+        }
+    };
+
+    (@init $($init:ident)::+ $(; $limits:ident)?) => {
         #[no_mangle]
         extern "C" fn instantiate(env_ptr: u32, info_ptr: u32, msg_ptr: u32) -> u32 {
+            $($crate::entrypoint!(@check $limits, msg_ptr);)?
             $crate::cosmwasm_std::do_instantiate(&super::$($init)::+, env_ptr, info_ptr, msg_ptr)
         }
     };
 
-    (@execute $($execute:ident)::+) => {
+    (@execute $($execute:ident)::+ $(; $limits:ident)?) => {
         #[no_mangle]
         extern "C" fn execute(env_ptr: u32, info_ptr: u32, msg_ptr: u32) -> u32 {
+            $($crate::entrypoint!(@check $limits, msg_ptr);)?
             $crate::cosmwasm_std::do_execute(&super::$($execute)::+, env_ptr, info_ptr, msg_ptr)
         }
     };
 
-    (@query $($query:ident)::+) => {
+    (@query $($query:ident)::+ $(; $limits:ident)?) => {
         #[no_mangle]
         extern "C" fn query(env_ptr: u32, msg_ptr: u32) -> u32 {
+            $($crate::entrypoint!(@check $limits, msg_ptr);)?
             $crate::cosmwasm_std::do_query(&super::$($query)::+, env_ptr, msg_ptr)
         }
     };
@@ -167,4 +188,23 @@ macro_rules! entrypoint {
             $($crate::entrypoint!(@reply $($reply)::+);)?
         }
     };
+
+    (
+        init: $($init:ident)::+,
+        execute: $($execute:ident)::+,
+        query: $($query:ident)::+,
+        $(reply: $($reply:ident)::+,)?
+        limits: { max_size: $max_size:expr, max_depth: $max_depth:expr $(,)? }
+    ) => {
+        $crate::entrypoint! {
+            @wasm_mod
+            const LIMITS: $crate::core::limits::MsgLimits =
+                $crate::core::limits::MsgLimits::new($max_size, $max_depth);
+
+            $crate::entrypoint!(@init    $($init)::+; LIMITS);
+            $crate::entrypoint!(@execute $($execute)::+; LIMITS);
+            $crate::entrypoint!(@query   $($query)::+; LIMITS);
+            $($crate::entrypoint!(@reply $($reply)::+);)?
+        }
+    };
 }
@@ -0,0 +1,169 @@
+//! Contract wide usage counters that anyone can query, so that dashboards can
+//! show how a contract is used without running an off-chain indexer.
+//!
+//! The contract calls [`record`] from the handlers of the messages that should be
+//! counted, passing a message class, i.e. `0` for swaps and `1` for providing
+//! liquidity, and the volume involved. This keeps the number of transactions of
+//! each class, the total volume and an estimate of the number of unique accounts.
+//!
+//! Only totals are exposed, but since they are public, an observer who queries
+//! them before and after a transaction can tell its class and volume. Don't record
+//! anything that the contract must keep private. The `ActivityTracking` component
+//! keeps per-account statistics that are only visible to the account itself.
+//!
+//! Unique accounts are counted using a [`HyperLogLog`] which takes a fixed
+//! 256 bytes of storage no matter how many accounts there are, at the cost
+//! of a standard error of about 6.5%.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    self as fadroma,
+    dsl::*,
+    prelude::*,
+    storage::sketch::HyperLogLog
+};
+
+crate::namespace!(pub MetricsNs, b"Vq7nB3xKtJ");
+const TOTALS: SingleItem<Totals, MetricsNs> = SingleItem::new();
+
+crate::namespace!(pub UniqueAccountsNs, b"f5LwR8cHmZ");
+const UNIQUE_ACCOUNTS: HyperLogLog<UniqueAccountsNs> = HyperLogLog::new();
+
+/// The number of message classes that can be counted.
+pub const MAX_CLASSES: u8 = 16;
+
+#[interface]
+pub trait Metrics {
+    type Error: fmt::Display;
+
+    #[query]
+    fn metrics() -> Result<UsageMetrics, Self::Error>;
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct UsageMetrics {
+    /// The number of transactions of each message class, indexed by the class.
+    pub tx_counts: Vec<Uint64>,
+    /// The total volume of all recorded transactions.
+    pub volume: Uint128,
+    /// The estimated number of unique accounts that made the recorded transactions.
+    pub unique_accounts: Uint64
+}
+
+#[derive(FadromaSerialize, FadromaDeserialize, Default)]
+struct Totals {
+    tx_counts: Vec<u64>,
+    volume: Uint128
+}
+
+pub struct DefaultImpl;
+
+impl Metrics for DefaultImpl {
+    type Error = StdError;
+
+    #[query]
+    fn metrics() -> StdResult<UsageMetrics> {
+        load(deps.storage)
+    }
+}
+
+/// Counts a transaction of `class` with the given `volume` made by `account`.
+pub fn record(
+    deps: DepsMut,
+    account: &Addr,
+    class: u8,
+    volume: Uint128
+) -> StdResult<()> {
+    if class >= MAX_CLASSES {
+        return Err(StdError::generic_err(format!(
+            "The message class must be less than {}.",
+            MAX_CLASSES
+        )));
+    }
+
+    let account = deps.api.addr_canonicalize(account.as_str())?;
+
+    TOTALS.update(deps.storage, |totals| {
+        let mut totals = totals.unwrap_or_default();
+        let class = class as usize;
+
+        if totals.tx_counts.len() <= class {
+            totals.tx_counts.resize(class + 1, 0);
+        }
+
+        totals.tx_counts[class] = totals.tx_counts[class].saturating_add(1);
+        totals.volume = totals.volume.saturating_add(volume);
+
+        Ok(totals)
+    })?;
+
+    UNIQUE_ACCOUNTS.insert(deps.storage, account.as_slice())?;
+
+    Ok(())
+}
+
+pub fn load(storage: &dyn Storage) -> StdResult<UsageMetrics> {
+    let totals = TOTALS.load_or_default(storage)?;
+
+    Ok(UsageMetrics {
+        tx_counts: totals.tx_counts.into_iter().map(Uint64::new).collect(),
+        volume: totals.volume,
+        unique_accounts: Uint64::new(UNIQUE_ACCOUNTS.estimate(storage)?)
+    })
+}
+
+impl UsageMetrics {
+    /// The number of transactions of `class`.
+    #[inline]
+    pub fn tx_count(&self, class: u8) -> u64 {
+        self.tx_counts.get(class as usize).map(|x| x.u64()).unwrap_or_default()
+    }
+
+    /// The number of transactions of all classes.
+    #[inline]
+    pub fn total_tx_count(&self) -> u64 {
+        self.tx_counts.iter().map(|x| x.u64()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cosmwasm_std::testing::{mock_dependencies, mock_env};
+
+    const SWAP: u8 = 0;
+    const PROVIDE_LIQUIDITY: u8 = 2;
+
+    #[test]
+    fn counts_transactions() {
+        let ref mut deps = mock_dependencies();
+        let alice = Addr::unchecked("alice");
+        let bob = Addr::unchecked("bob");
+
+        let metrics = |deps: Deps| DefaultImpl::metrics(deps, mock_env()).unwrap();
+
+        assert_eq!(metrics(deps.as_ref()), UsageMetrics {
+            tx_counts: vec![],
+            volume: Uint128::zero(),
+            unique_accounts: Uint64::zero()
+        });
+
+        record(deps.as_mut(), &alice, SWAP, Uint128::new(100)).unwrap();
+        record(deps.as_mut(), &alice, SWAP, Uint128::new(25)).unwrap();
+        record(deps.as_mut(), &bob, PROVIDE_LIQUIDITY, Uint128::new(50)).unwrap();
+        assert!(record(deps.as_mut(), &bob, MAX_CLASSES, Uint128::new(50)).is_err());
+
+        let recorded = metrics(deps.as_ref());
+        assert_eq!(recorded, UsageMetrics {
+            tx_counts: vec![Uint64::new(2), Uint64::zero(), Uint64::new(1)],
+            volume: Uint128::new(175),
+            unique_accounts: Uint64::new(2)
+        });
+        assert_eq!(recorded.tx_count(PROVIDE_LIQUIDITY), 1);
+        assert_eq!(recorded.tx_count(MAX_CLASSES - 1), 0);
+        assert_eq!(recorded.total_tx_count(), 3);
+    }
+}
@@ -0,0 +1,417 @@
+//! Sanity checks for prices reported by oracles, protecting components that consume
+//! them, i.e. lending markets and AMMs, from bad or manipulated feeds. You **MUST**
+//! implement [admin] in your contract if you want to use this module.
+//!
+//! Each feed has a [`PriceBand`] configured by the admin. A reported price is rejected
+//! if it falls outside the absolute bounds of the band or if it deviates from the time
+//! weighted average (TWAP) of the previously accepted prices by more than the allowed
+//! number of basis points. Only accepted prices are included in the TWAP.
+//!
+//! The TWAP is updated on each accepted price by weighing the new price by the time
+//! elapsed since the previous one relative to the configured window. Once a full window
+//! has passed without any accepted price, the TWAP is considered stale: the next price
+//! is only checked against the absolute bounds and the TWAP is reset to it. This way a
+//! genuine move of the market by more than the allowed deviation only blocks the feed
+//! for a single window. Changing the band of a feed discards its TWAP as well.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    self as fadroma,
+    admin::{self, Admin, Mode},
+    dsl::*,
+    prelude::*
+};
+
+crate::namespace!(pub PriceBandsNs, b"N3sVq7LbXe");
+const BANDS: ItemSpace<PriceBand, PriceBandsNs, TypedKey<String>> = ItemSpace::new();
+
+crate::namespace!(pub TwapsNs, b"fG9kZ2pRwM");
+const TWAPS: ItemSpace<Twap, TwapsNs, TypedKey<String>> = ItemSpace::new();
+
+const BPS: u64 = 10_000;
+
+/// Requires the admin component in order to check for admin.
+#[interface]
+pub trait PriceGuard: Admin {
+    type Error: fmt::Display;
+
+    #[execute]
+    fn set_price_band(
+        feed: String,
+        band: PriceBand
+    ) -> Result<Response, <Self as PriceGuard>::Error>;
+
+    #[query]
+    fn price_band(feed: String) -> Result<Option<PriceBand>, <Self as PriceGuard>::Error>;
+
+    #[query]
+    fn twap(feed: String) -> Result<Option<Twap>, <Self as PriceGuard>::Error>;
+}
+
+/// The range of prices that are accepted for a feed.
+#[derive(
+    Serialize, Deserialize, FadromaSerialize, FadromaDeserialize,
+    JsonSchema, PartialEq, Debug, Clone
+)]
+pub struct PriceBand {
+    /// The maximum deviation from the TWAP in basis points.
+    pub max_deviation_bps: u16,
+    /// The period in seconds over which prices are averaged.
+    pub twap_window: u64,
+    pub min_price: Option<Decimal>,
+    pub max_price: Option<Decimal>
+}
+
+#[derive(
+    Serialize, Deserialize, FadromaSerialize, FadromaDeserialize,
+    JsonSchema, PartialEq, Debug, Clone
+)]
+pub struct Twap {
+    pub price: Decimal,
+    /// The block time in seconds of the last accepted price.
+    pub updated_at: u64
+}
+
+/// The outcome of [`check_price`].
+#[derive(PartialEq, Debug, Clone)]
+pub enum PriceCheck {
+    Accepted {
+        twap: Decimal
+    },
+    Rejected {
+        reason: PriceRejection
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum PriceRejection {
+    BelowMin { min: Decimal },
+    AboveMax { max: Decimal },
+    Deviation { twap: Decimal, bps: u64 }
+}
+
+pub struct DefaultImpl;
+
+impl Admin for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn change_admin(mode: Option<Mode>) -> Result<Response, Self::Error> {
+        admin::DefaultImpl::change_admin(deps, env, info, mode)
+    }
+
+    #[query]
+    fn admin() -> Result<Option<Addr>, Self::Error> {
+        admin::DefaultImpl::admin(deps, env)
+    }
+}
+
+impl PriceGuard for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn set_price_band(
+        feed: String,
+        band: PriceBand
+    ) -> StdResult<Response> {
+        set_price_band(deps, info, &feed, band)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_price_band")
+            .add_attribute("feed", feed)
+        )
+    }
+
+    #[query]
+    fn price_band(feed: String) -> StdResult<Option<PriceBand>> {
+        BANDS.load(deps.storage, &feed)
+    }
+
+    #[query]
+    fn twap(feed: String) -> StdResult<Option<Twap>> {
+        TWAPS.load(deps.storage, &feed)
+    }
+}
+
+/// Configures the band of accepted prices for `feed`.
+/// Requires the admin component in order to check for admin.
+#[admin::require_admin]
+pub fn set_price_band(
+    deps: DepsMut,
+    info: MessageInfo,
+    feed: &str,
+    band: PriceBand
+) -> StdResult<()> {
+    if band.twap_window == 0 {
+        return Err(StdError::generic_err("The TWAP window must be greater than zero."));
+    }
+
+    if let (Some(min), Some(max)) = (band.min_price, band.max_price) {
+        if min > max {
+            return Err(StdError::generic_err("The minimum price must not exceed the maximum price."));
+        }
+    }
+
+    let feed = feed.to_string();
+
+    // The TWAP may have been accumulated under a very different band.
+    TWAPS.remove(deps.storage, &feed);
+
+    BANDS.save(deps.storage, &feed, &band)
+}
+
+/// Checks `price` against the band configured for `feed` and includes it in the TWAP if
+/// it is accepted. A rejected price doesn't result in an error so that the caller can
+/// decide whether to fail the transaction or i.e. skip the update and emit [`PriceCheck::event`].
+/// The first price reported for a feed, as well as the first one after a full window has
+/// passed without an accepted price, is only checked against the absolute bounds.
+///
+/// Returns `Err` if no band is configured for `feed`.
+pub fn check_price(
+    storage: &mut dyn Storage,
+    env: &Env,
+    feed: &str,
+    price: Decimal
+) -> StdResult<PriceCheck> {
+    let feed = feed.to_string();
+    let band = BANDS.load(storage, &feed)?.ok_or_else(||
+        StdError::generic_err(format!("No price band configured for {}.", feed))
+    )?;
+
+    let now = env.block.time.seconds();
+    let twap = TWAPS.load(storage, &feed)?.filter(|x|
+        now.saturating_sub(x.updated_at) < band.twap_window
+    );
+
+    if let Some(reason) = band.rejects(price, twap.as_ref().map(|x| x.price)) {
+        return Ok(PriceCheck::Rejected { reason });
+    }
+
+    let price = match twap {
+        Some(twap) => {
+            let elapsed = now.saturating_sub(twap.updated_at).min(band.twap_window);
+            let weight = Decimal::from_ratio(elapsed, band.twap_window);
+
+            // Weighted sum of both prices so that it doesn't underflow.
+            twap.price.checked_mul(Decimal::one() - weight)?
+                .checked_add(price.checked_mul(weight)?)?
+        },
+        None => price
+    };
+
+    TWAPS.save(storage, &feed, &Twap { price, updated_at: now })?;
+
+    Ok(PriceCheck::Accepted { twap: price })
+}
+
+impl PriceBand {
+    /// Returns the reason why `price` would be rejected given the current `twap`, if any.
+    pub fn rejects(&self, price: Decimal, twap: Option<Decimal>) -> Option<PriceRejection> {
+        if let Some(min) = self.min_price {
+            if price < min {
+                return Some(PriceRejection::BelowMin { min });
+            }
+        }
+
+        if let Some(max) = self.max_price {
+            if price > max {
+                return Some(PriceRejection::AboveMax { max });
+            }
+        }
+
+        let twap = twap.filter(|x| !x.is_zero())?;
+
+        let diff = if price > twap { price - twap } else { twap - price };
+
+        // Only overflows for deviations far beyond any sensible limit.
+        let bps = diff.checked_div(twap)
+            .ok()
+            .and_then(|x| x.checked_mul(Decimal::from_ratio(BPS, 1u64)).ok())
+            .map(|x| (Uint128::one() * x).u128().min(u64::MAX as u128) as u64)
+            .unwrap_or(u64::MAX);
+
+        if bps > self.max_deviation_bps as u64 {
+            return Some(PriceRejection::Deviation { twap, bps });
+        }
+
+        None
+    }
+}
+
+impl PriceCheck {
+    #[inline]
+    pub fn is_accepted(&self) -> bool {
+        matches!(self, Self::Accepted { .. })
+    }
+
+    /// Returns an `Err` if the price was rejected.
+    pub fn into_result(self) -> StdResult<Decimal> {
+        match self {
+            Self::Accepted { twap } => Ok(twap),
+            Self::Rejected { reason } => Err(StdError::generic_err(reason.to_string()))
+        }
+    }
+
+    /// A `price_accepted` or a `price_rejected` event for `feed` and `price`.
+    pub fn event(&self, feed: &str, price: Decimal) -> Event {
+        let event = match self {
+            Self::Accepted { twap } => Event::new("price_accepted")
+                .add_attribute("twap", twap.to_string()),
+            Self::Rejected { reason } => Event::new("price_rejected")
+                .add_attribute("reason", reason.to_string())
+        };
+
+        event
+            .add_attribute("feed", feed)
+            .add_attribute("price", price.to_string())
+    }
+}
+
+impl fmt::Display for PriceRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BelowMin { min } => write!(f, "Price is below the minimum of {}.", min),
+            Self::AboveMax { max } => write!(f, "Price is above the maximum of {}.", max),
+            Self::Deviation { twap, bps } => write!(
+                f,
+                "Price deviates from the TWAP of {} by {} bps.",
+                twap,
+                bps
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+
+    const FEED: &str = "SCRT/USD";
+
+    fn price(x: u64) -> Decimal {
+        Decimal::from_ratio(x, 100u64)
+    }
+
+    #[test]
+    fn rejects_prices_outside_of_band() {
+        let ref mut deps = mock_dependencies();
+        let mut env = mock_env();
+
+        admin::init(deps.as_mut(), None, &mock_info("admin", &[])).unwrap();
+
+        let band = PriceBand {
+            max_deviation_bps: 500,
+            twap_window: 100,
+            min_price: Some(price(10)),
+            max_price: Some(price(1000))
+        };
+
+        let set_band = |deps: DepsMut, sender: &str, band: PriceBand| DefaultImpl::set_price_band(
+            deps,
+            mock_env(),
+            mock_info(sender, &[]),
+            FEED.into(),
+            band
+        );
+
+        assert!(set_band(deps.as_mut(), "user", band.clone()).is_err());
+        assert!(check_price(deps.as_mut().storage, &env, FEED, price(100)).is_err());
+
+        set_band(deps.as_mut(), "admin", band.clone()).unwrap();
+
+        let check = |deps: &mut OwnedDeps<_, _, _>, env: &Env, x: u64|
+            check_price(deps.as_mut().storage, env, FEED, price(x)).unwrap();
+
+        assert_eq!(
+            check(deps, &env, 5),
+            PriceCheck::Rejected { reason: PriceRejection::BelowMin { min: price(10) } }
+        );
+        assert_eq!(
+            check(deps, &env, 1001),
+            PriceCheck::Rejected { reason: PriceRejection::AboveMax { max: price(1000) } }
+        );
+        assert_eq!(DefaultImpl::twap(deps.as_ref(), env.clone(), FEED.into()).unwrap(), None);
+
+        // The first price is only checked against the bounds.
+        assert_eq!(check(deps, &env, 100), PriceCheck::Accepted { twap: price(100) });
+
+        let rejected = check(deps, &env, 106);
+        assert_eq!(
+            rejected,
+            PriceCheck::Rejected {
+                reason: PriceRejection::Deviation { twap: price(100), bps: 600 }
+            }
+        );
+        assert!(rejected.clone().into_result().is_err());
+
+        let event = rejected.event(FEED, price(106));
+        assert_eq!(event.ty, "price_rejected");
+        assert_eq!(event.attributes[0].value, "Price deviates from the TWAP of 1 by 600 bps.");
+
+        // Half of the window has passed so the TWAP moves halfway.
+        env.block.time = env.block.time.plus_seconds(50);
+        assert_eq!(check(deps, &env, 104), PriceCheck::Accepted { twap: price(102) });
+
+        let twap = DefaultImpl::twap(deps.as_ref(), env.clone(), FEED.into()).unwrap().unwrap();
+        assert_eq!(twap, Twap { price: price(102), updated_at: env.block.time.seconds() });
+
+        // Prices within the same block don't move the TWAP.
+        assert_eq!(check(deps, &env, 106), PriceCheck::Accepted { twap: price(102) });
+        assert!(!check(deps, &env, 108).is_accepted());
+
+        // A full window resets the TWAP.
+        env.block.time = env.block.time.plus_seconds(200);
+        assert_eq!(check(deps, &env, 105), PriceCheck::Accepted { twap: price(105) });
+
+        // Changing the band discards the TWAP.
+        set_band(deps.as_mut(), "admin", band).unwrap();
+        assert_eq!(DefaultImpl::twap(deps.as_ref(), env.clone(), FEED.into()).unwrap(), None);
+        assert_eq!(check(deps, &env, 200), PriceCheck::Accepted { twap: price(200) });
+    }
+
+    #[test]
+    fn step_change_is_accepted_after_a_window() {
+        let ref mut deps = mock_dependencies();
+        let mut env = mock_env();
+
+        admin::init(deps.as_mut(), None, &mock_info("admin", &[])).unwrap();
+
+        let band = PriceBand {
+            max_deviation_bps: 500,
+            twap_window: 100,
+            min_price: None,
+            max_price: Some(price(1000))
+        };
+        set_price_band(deps.as_mut(), mock_info("admin", &[]), FEED, band).unwrap();
+
+        let check = |deps: &mut OwnedDeps<_, _, _>, env: &Env, x: u64|
+            check_price(deps.as_mut().storage, env, FEED, price(x)).unwrap();
+
+        assert!(check(deps, &env, 100).is_accepted());
+
+        // The market moves by 50% and stays there.
+        for _ in 0..9 {
+            env.block.time = env.block.time.plus_seconds(10);
+            assert!(!check(deps, &env, 150).is_accepted());
+        }
+
+        // The TWAP is stale once a full window passes without an accepted price.
+        env.block.time = env.block.time.plus_seconds(10);
+        assert_eq!(check(deps, &env, 150), PriceCheck::Accepted { twap: price(150) });
+
+        env.block.time = env.block.time.plus_seconds(10);
+        assert!(check(deps, &env, 152).is_accepted());
+        assert!(!check(deps, &env, 100).is_accepted());
+
+        // The bounds still apply to a stale TWAP.
+        env.block.time = env.block.time.plus_seconds(500);
+        assert_eq!(
+            check(deps, &env, 1001),
+            PriceCheck::Rejected { reason: PriceRejection::AboveMax { max: price(1000) } }
+        );
+    }
+}
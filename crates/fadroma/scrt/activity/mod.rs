@@ -0,0 +1,234 @@
+//! Opt-in per-account activity statistics, i.e. for loyalty programs or tiers.
+//! *Feature flag: `activity`*
+//!
+//! Nothing is recorded for an account unless it enables tracking using
+//! [`ActivityTracking::set_activity_tracking`]. The contract calls [`record`] from the
+//! handlers of the messages that should count towards the statistics, passing a message
+//! class, i.e. `0` for swaps and `1` for providing liquidity, and the volume involved.
+//!
+//! The statistics are private to the account and can only be queried
+//! with its viewing key, so the contract must also implement [`crate::scrt::vk`].
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    self as fadroma,
+    dsl::*,
+    prelude::*,
+    scrt::vk::{ViewingKey, auth::authenticate}
+};
+
+crate::namespace!(pub ActivityTrackingNs, b"Gx3pL9mQzC");
+const TRACKING: ItemSpace<bool, ActivityTrackingNs, TypedKey<CanonicalAddr>> = ItemSpace::new();
+
+crate::namespace!(pub ActivityStatsNs, b"y7RdV2kNwH");
+const STATS: ItemSpace<ActivityStats, ActivityStatsNs, TypedKey<CanonicalAddr>> = ItemSpace::new();
+
+/// The number of message classes that can be tracked.
+pub const MAX_CLASSES: u8 = 16;
+
+#[interface]
+pub trait ActivityTracking {
+    type Error: fmt::Display;
+
+    /// Enables or disables tracking for the sender.
+    /// Disabling it also deletes the recorded statistics.
+    #[execute]
+    fn set_activity_tracking(
+        enabled: bool,
+        _padding: Option<String>
+    ) -> Result<Response, Self::Error>;
+
+    #[query]
+    fn activity_stats(
+        address: String,
+        key: String
+    ) -> Result<Option<ActivityStats>, Self::Error>;
+}
+
+#[derive(
+    Serialize, Deserialize, FadromaSerialize, FadromaDeserialize,
+    JsonSchema, PartialEq, Debug, Clone
+)]
+pub struct ActivityStats {
+    /// The block time in seconds of the first recorded transaction.
+    pub first_seen: u64,
+    /// The block time in seconds of the last recorded transaction.
+    pub last_seen: u64,
+    /// The number of transactions of each message class, indexed by the class.
+    pub tx_counts: Vec<u32>,
+    /// The total volume of all recorded transactions.
+    pub volume: Uint128
+}
+
+pub struct DefaultImpl;
+
+impl ActivityTracking for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn set_activity_tracking(
+        enabled: bool,
+        _padding: Option<String>
+    ) -> StdResult<Response> {
+        set_tracking(deps, &info.sender, enabled)?;
+
+        Ok(Response::new().add_attribute("action", "set_activity_tracking"))
+    }
+
+    #[query]
+    fn activity_stats(address: String, key: String) -> StdResult<Option<ActivityStats>> {
+        let address = address.as_str().canonize(deps.api)?;
+        authenticate(deps.storage, &ViewingKey(key), &address)?;
+
+        STATS.load(deps.storage, &address)
+    }
+}
+
+pub fn set_tracking(deps: DepsMut, account: &Addr, enabled: bool) -> StdResult<()> {
+    let account = deps.api.addr_canonicalize(account.as_str())?;
+
+    if enabled {
+        TRACKING.save(deps.storage, &account, &true)
+    } else {
+        TRACKING.remove(deps.storage, &account);
+        STATS.remove(deps.storage, &account);
+
+        Ok(())
+    }
+}
+
+/// Records a transaction of `class` with the given `volume` for `account`
+/// if it has enabled tracking. Returns whether anything was recorded.
+pub fn record(
+    deps: DepsMut,
+    env: &Env,
+    account: &Addr,
+    class: u8,
+    volume: Uint128
+) -> StdResult<bool> {
+    if class >= MAX_CLASSES {
+        return Err(StdError::generic_err(format!(
+            "The message class must be less than {}.",
+            MAX_CLASSES
+        )));
+    }
+
+    let account = deps.api.addr_canonicalize(account.as_str())?;
+
+    if !TRACKING.exists(deps.storage, &account) {
+        return Ok(false);
+    }
+
+    let now = env.block.time.seconds();
+
+    STATS.update(deps.storage, &account, |stats| {
+        let mut stats = stats.unwrap_or(ActivityStats {
+            first_seen: now,
+            last_seen: now,
+            tx_counts: vec![],
+            volume: Uint128::zero()
+        });
+
+        let class = class as usize;
+
+        if stats.tx_counts.len() <= class {
+            stats.tx_counts.resize(class + 1, 0);
+        }
+
+        stats.tx_counts[class] = stats.tx_counts[class].saturating_add(1);
+        stats.volume = stats.volume.saturating_add(volume);
+        stats.last_seen = now;
+
+        Ok(stats)
+    })?;
+
+    Ok(true)
+}
+
+impl ActivityStats {
+    /// The number of transactions of `class`.
+    #[inline]
+    pub fn tx_count(&self, class: u8) -> u32 {
+        self.tx_counts.get(class as usize).copied().unwrap_or_default()
+    }
+
+    /// The number of transactions of all classes.
+    #[inline]
+    pub fn total_tx_count(&self) -> u64 {
+        self.tx_counts.iter().map(|x| *x as u64).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        scrt::vk::auth::{self, VkAuth},
+        cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info}
+    };
+
+    const SWAP: u8 = 0;
+    const PROVIDE_LIQUIDITY: u8 = 2;
+
+    #[test]
+    fn records_only_when_enabled() {
+        let ref mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let alice = Addr::unchecked("alice");
+
+        auth::DefaultImpl::set_viewing_key(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            "key".into(),
+            None
+        ).unwrap();
+
+        let set_tracking = |deps: DepsMut, enabled: bool| DefaultImpl::set_activity_tracking(
+            deps,
+            mock_env(),
+            mock_info("alice", &[]),
+            enabled,
+            None
+        );
+        let stats = |deps: Deps, key: &str| DefaultImpl::activity_stats(
+            deps,
+            mock_env(),
+            "alice".into(),
+            key.into()
+        );
+
+        assert!(!record(deps.as_mut(), &env, &alice, SWAP, Uint128::new(100)).unwrap());
+        assert_eq!(stats(deps.as_ref(), "key").unwrap(), None);
+
+        set_tracking(deps.as_mut(), true).unwrap();
+        let start = env.block.time.seconds();
+
+        assert!(record(deps.as_mut(), &env, &alice, SWAP, Uint128::new(100)).unwrap());
+        assert!(record(deps.as_mut(), &env, &alice, MAX_CLASSES, Uint128::new(100)).is_err());
+
+        env.block.time = env.block.time.plus_seconds(10);
+        record(deps.as_mut(), &env, &alice, PROVIDE_LIQUIDITY, Uint128::new(50)).unwrap();
+        record(deps.as_mut(), &env, &alice, SWAP, Uint128::new(25)).unwrap();
+
+        assert_eq!(stats(deps.as_ref(), "wrong").unwrap_err(), StdError::generic_err("Unauthorized"));
+
+        let recorded = stats(deps.as_ref(), "key").unwrap().unwrap();
+        assert_eq!(recorded, ActivityStats {
+            first_seen: start,
+            last_seen: start + 10,
+            tx_counts: vec![2, 0, 1],
+            volume: Uint128::new(175)
+        });
+        assert_eq!(recorded.tx_count(PROVIDE_LIQUIDITY), 1);
+        assert_eq!(recorded.tx_count(MAX_CLASSES - 1), 0);
+        assert_eq!(recorded.total_tx_count(), 3);
+
+        set_tracking(deps.as_mut(), false).unwrap();
+        assert_eq!(stats(deps.as_ref(), "key").unwrap(), None);
+        assert!(!record(deps.as_mut(), &env, &alice, SWAP, Uint128::new(100)).unwrap());
+    }
+}
@@ -0,0 +1,301 @@
+//! Threshold release of secrets held in the private state of the contract.
+//! *Feature flag: `escrow`*
+//!
+//! The depositor splits the decryption key of some payload into shares (i.e. using
+//! [`crate::crypto::shamir::split`]) and creates an escrow with one share per authorized party.
+//! The share of each party is released once they approve and the recipient can claim the
+//! released shares in order to recover the key once `threshold` parties have approved.
+//!
+//! The contract receives every share in plaintext and keeps all of them in its own state. They
+//! are only hidden from everyone else because contract state is encrypted on Secret Network, and
+//! the threshold is only enforced by the code of the contract, which releases the shares to the
+//! recipient once `threshold` parties have approved. A contract with a bug or one that was written
+//! to do so can release all of the shares at any time, so the guarantee is only as good as the
+//! code of the contract and the state encryption of the network.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    self as fadroma,
+    dsl::*,
+    prelude::*,
+    scrt::ResponseExt
+};
+
+crate::namespace!(pub EscrowsNs, b"Hf6sZcDQ1x");
+const ESCROWS: ItemSpace<Escrow, EscrowsNs, TypedKey<u64>> = ItemSpace::new();
+
+crate::namespace!(pub EscrowCountNs, b"2JpWq0aLvT");
+const COUNT: SingleItem<u64, EscrowCountNs> = SingleItem::new();
+
+#[interface]
+pub trait SecretEscrow {
+    type Error: fmt::Display;
+
+    /// Creates a new escrow with the given terms.
+    /// The ID of the escrow is returned as the response data.
+    #[execute]
+    fn create_escrow(
+        terms: EscrowTerms,
+        _padding: Option<String>
+    ) -> Result<Response, Self::Error>;
+
+    /// Releases the share of the sender to the recipient.
+    #[execute]
+    fn approve_release(
+        id: u64,
+        _padding: Option<String>
+    ) -> Result<Response, Self::Error>;
+
+    /// Returns the released shares as the response data if at least
+    /// `threshold` parties have approved. Can only be called by the recipient.
+    #[execute]
+    fn claim_shares(
+        id: u64,
+        _padding: Option<String>
+    ) -> Result<Response, Self::Error>;
+
+    #[query]
+    fn escrow_status(id: u64) -> Result<EscrowStatus, Self::Error>;
+}
+
+/// The terms of a new escrow where `shares[i]` is released when `parties[i]` approves.
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct EscrowTerms {
+    pub recipient: String,
+    pub parties: Vec<String>,
+    pub shares: Vec<Binary>,
+    /// The number of parties that must approve before the recipient can claim the shares.
+    pub threshold: u32
+}
+
+/// The publicly visible state of an escrow.
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct EscrowStatus {
+    pub threshold: u32,
+    pub approvals: u32,
+    pub parties: u32
+}
+
+#[derive(FadromaSerialize, FadromaDeserialize, PartialEq, Debug, Clone)]
+struct Escrow {
+    recipient: CanonicalAddr,
+    threshold: u32,
+    shares: Vec<Share>
+}
+
+#[derive(FadromaSerialize, FadromaDeserialize, PartialEq, Debug, Clone)]
+struct Share {
+    party: CanonicalAddr,
+    share: Binary,
+    approved: bool
+}
+
+pub struct DefaultImpl;
+
+impl SecretEscrow for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn create_escrow(
+        terms: EscrowTerms,
+        _padding: Option<String>
+    ) -> StdResult<Response> {
+        let EscrowTerms { recipient, parties, shares, threshold } = terms;
+
+        if parties.len() != shares.len() {
+            return Err(StdError::generic_err(
+                "The number of parties and shares must be the same."
+            ));
+        }
+
+        if threshold == 0 || threshold as usize > parties.len() {
+            return Err(StdError::generic_err(
+                "The threshold must be between 1 and the number of parties."
+            ));
+        }
+
+        let mut escrow = Escrow {
+            recipient: recipient.as_str().canonize(deps.api)?,
+            threshold,
+            shares: Vec::with_capacity(shares.len())
+        };
+
+        for (party, share) in parties.into_iter().zip(shares) {
+            let party = party.as_str().canonize(deps.api)?;
+
+            if escrow.shares.iter().any(|x| x.party == party) {
+                return Err(StdError::generic_err("Duplicate party."));
+            }
+
+            escrow.shares.push(Share {
+                party,
+                share,
+                approved: false
+            });
+        }
+
+        let id = COUNT.load_or_default(deps.storage)?;
+        COUNT.save(deps.storage, &(id + 1))?;
+        ESCROWS.save(deps.storage, &id, &escrow)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "create_escrow")
+            .set_data(to_binary(&id)?)
+            .pad()
+        )
+    }
+
+    #[execute]
+    fn approve_release(
+        id: u64,
+        _padding: Option<String>
+    ) -> StdResult<Response> {
+        let mut escrow = ESCROWS.load_or_error(deps.storage, &id)?;
+        let sender = info.sender.canonize(deps.api)?;
+
+        let share = escrow.shares
+            .iter_mut()
+            .find(|x| x.party == sender)
+            .ok_or_else(|| StdError::generic_err("Unauthorized"))?;
+
+        if share.approved {
+            return Err(StdError::generic_err("Already approved."));
+        }
+
+        share.approved = true;
+        ESCROWS.save(deps.storage, &id, &escrow)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "approve_release")
+            .pad()
+        )
+    }
+
+    #[execute]
+    fn claim_shares(
+        id: u64,
+        _padding: Option<String>
+    ) -> StdResult<Response> {
+        let escrow = ESCROWS.load_or_error(deps.storage, &id)?;
+
+        if escrow.recipient != info.sender.canonize(deps.api)? {
+            return Err(StdError::generic_err("Unauthorized"));
+        }
+
+        let released: Vec<Binary> = escrow.shares
+            .into_iter()
+            .filter(|x| x.approved)
+            .map(|x| x.share)
+            .collect();
+
+        if (released.len() as u32) < escrow.threshold {
+            return Err(StdError::generic_err(format!(
+                "Only {} out of the required {} parties have approved.",
+                released.len(),
+                escrow.threshold
+            )));
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "claim_shares")
+            .set_data(to_binary(&released)?)
+            .pad()
+        )
+    }
+
+    #[query]
+    fn escrow_status(id: u64) -> StdResult<EscrowStatus> {
+        let escrow = ESCROWS.load_or_error(deps.storage, &id)?;
+
+        Ok(EscrowStatus {
+            threshold: escrow.threshold,
+            approvals: escrow.shares.iter().filter(|x| x.approved).count() as u32,
+            parties: escrow.shares.len() as u32
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        crypto::{Prng, shamir},
+        cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info}
+    };
+
+    const PARTIES: [&str; 3] = ["alice", "bob", "charlie"];
+    const RECIPIENT: &str = "recipient";
+
+    #[test]
+    fn releases_shares_after_threshold() {
+        let ref mut deps = mock_dependencies();
+        let env = mock_env();
+
+        let key = b"decryption key";
+        let shares = shamir::split(key, 2, 3, &mut Prng::new(b"seed", b"entropy")).unwrap();
+
+        let create = |deps: DepsMut, threshold: u32| DefaultImpl::create_escrow(
+            deps,
+            mock_env(),
+            mock_info("depositor", &[]),
+            EscrowTerms {
+                recipient: RECIPIENT.into(),
+                parties: PARTIES.iter().map(|x| x.to_string()).collect(),
+                shares: shares.iter().cloned().map(Binary::from).collect(),
+                threshold
+            },
+            None
+        );
+
+        assert!(create(deps.as_mut(), 4).is_err());
+
+        let resp = create(deps.as_mut(), 2).unwrap();
+        let id: u64 = from_binary(&resp.data.unwrap()).unwrap();
+
+        let approve = |deps: DepsMut, sender: &str| DefaultImpl::approve_release(
+            deps,
+            mock_env(),
+            mock_info(sender, &[]),
+            id,
+            None
+        );
+
+        let claim = |deps: DepsMut, sender: &str| DefaultImpl::claim_shares(
+            deps,
+            mock_env(),
+            mock_info(sender, &[]),
+            id,
+            None
+        );
+
+        assert_eq!(
+            approve(deps.as_mut(), RECIPIENT).unwrap_err(),
+            StdError::generic_err("Unauthorized")
+        );
+
+        approve(deps.as_mut(), "alice").unwrap();
+        assert!(approve(deps.as_mut(), "alice").is_err());
+
+        assert!(claim(deps.as_mut(), RECIPIENT).is_err());
+
+        approve(deps.as_mut(), "charlie").unwrap();
+
+        assert_eq!(
+            claim(deps.as_mut(), "alice").unwrap_err(),
+            StdError::generic_err("Unauthorized")
+        );
+
+        let status = DefaultImpl::escrow_status(deps.as_ref(), env, id).unwrap();
+        assert_eq!(status, EscrowStatus { threshold: 2, approvals: 2, parties: 3 });
+
+        let resp = claim(deps.as_mut(), RECIPIENT).unwrap();
+        let released: Vec<Binary> = from_binary(&resp.data.unwrap()).unwrap();
+
+        assert_eq!(released, vec![Binary::from(shares[0].clone()), Binary::from(shares[2].clone())]);
+        let released: Vec<&[u8]> = released.iter().map(|x| x.as_slice()).collect();
+        assert_eq!(shamir::combine(&released).unwrap(), key);
+    }
+}
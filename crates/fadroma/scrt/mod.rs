@@ -8,8 +8,30 @@ pub mod permit;
 pub mod vk;
 #[cfg(feature = "snip20")]
 pub mod snip20;
+#[cfg(feature = "escrow")]
+pub mod escrow;
+#[cfg(feature = "voting")]
+pub mod voting;
+#[cfg(feature = "orderbook")]
+pub mod orderbook;
+#[cfg(feature = "receipts")]
+pub mod receipts;
+#[cfg(feature = "session-keys")]
+pub mod session_keys;
+#[cfg(feature = "state-export")]
+pub mod state_export;
+#[cfg(feature = "activity")]
+pub mod activity;
+#[cfg(feature = "oracle")]
+pub mod oracle;
+#[cfg(feature = "nft-staking")]
+pub mod nft_staking;
 
-use crate::cosmwasm_std::{StdResult, CosmosMsg, WasmMsg, Response, to_binary};
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor, SeqAccess, MapAccess};
+
+use crate::cosmwasm_std::{StdResult, CosmosMsg, WasmMsg, Response, to_binary, from_slice};
 
 /// Default Secret Network message padding size.
 pub const BLOCK_SIZE: usize = 256;
@@ -57,6 +79,70 @@ pub fn space_pad (
     message
 }
 
+/// Pads `data` the same way as [`space_pad`] but only if it is valid JSON,
+/// since the trailing spaces would corrupt any other kind of data. Returns
+/// `true` if it was padded.
+pub fn space_pad_json(data: &mut Vec<u8>, block_size: usize) -> bool {
+    if from_slice::<AnyJson>(data).is_err() {
+        return false;
+    }
+
+    space_pad(data, block_size);
+
+    true
+}
+
+/// Accepts any JSON value, including nested ones, without allocating.
+struct AnyJson;
+
+impl<'de> Deserialize<'de> for AnyJson {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(AnyJsonVisitor)
+    }
+}
+
+struct AnyJsonVisitor;
+
+impl<'de> Visitor<'de> for AnyJsonVisitor {
+    type Value = AnyJson;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(AnyJson)
+    }
+
+    fn visit_bool<E: de::Error>(self, _: bool) -> Result<Self::Value, E> {
+        Ok(AnyJson)
+    }
+
+    fn visit_i64<E: de::Error>(self, _: i64) -> Result<Self::Value, E> {
+        Ok(AnyJson)
+    }
+
+    fn visit_u64<E: de::Error>(self, _: u64) -> Result<Self::Value, E> {
+        Ok(AnyJson)
+    }
+
+    fn visit_str<E: de::Error>(self, _: &str) -> Result<Self::Value, E> {
+        Ok(AnyJson)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        while seq.next_element::<AnyJson>()?.is_some() { }
+
+        Ok(AnyJson)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        while map.next_entry::<AnyJson, AnyJson>()?.is_some() { }
+
+        Ok(AnyJson)
+    }
+}
+
 impl ResponseExt for Response {
     fn pad(mut self) -> Self {
         if let Some(data) = self.data.as_mut() {
@@ -66,3 +152,26 @@ impl ResponseExt for Response {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_pads_json() {
+        for json in [&br#"{"a":[1,-2,"b\"}",null,true,{}]}"#[..], b"5", b"\"x\"", b" [] "] {
+            let mut data = json.to_vec();
+
+            assert!(space_pad_json(&mut data, 16), "{}", String::from_utf8_lossy(json));
+            assert_eq!(data.len() % 16, 0);
+            assert!(from_slice::<AnyJson>(&data).is_ok());
+        }
+
+        for binary in [&b"\x00\x01\x02"[..], b"{\"a\":}", b"[1,\x01]", b"5 5", b""] {
+            let mut data = binary.to_vec();
+
+            assert!(!space_pad_json(&mut data, 16));
+            assert_eq!(data, binary);
+        }
+    }
+}
@@ -0,0 +1,475 @@
+//! Staking SNIP-721 tokens of a single collection in exchange for rewards, i.e. for
+//! GameFi or membership NFTs. You **MUST** implement [admin] and [crate::scrt::vk]
+//! in your contract if you want to use this module.
+//! *Feature flag: `nft-staking`*
+//!
+//! Tokens are staked by sending them to the contract, which must have registered itself
+//! as a receiver with the collection using the message returned by [`init`]. Each staked
+//! token accrues the admin configured reward rate every second. The contract decides
+//! what the rewards are paid out in: it calls [`claim_rewards`] from its own handler and
+//! transfers the returned amount to the owner.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::{
+    self as fadroma,
+    admin::{self, Admin, Mode},
+    dsl::*,
+    prelude::*,
+    scrt::{to_cosmos_msg, vk::{ViewingKey, auth::authenticate}},
+    storage::{iterable::Page, indexed_map::{IndexedMap, MultiIndex}}
+};
+
+crate::namespace!(pub NftCollectionNs, b"p3ZfK7tWmB");
+pub const COLLECTION: SingleItem<ContractLink<Addr>, NftCollectionNs> = SingleItem::new();
+
+crate::namespace!(pub NftRewardsNs, b"Lw6cQ2hVxS");
+const REWARDS: SingleItem<RewardState, NftRewardsNs> = SingleItem::new();
+
+crate::namespace!(pub StakedNftsNs, b"d9RyM4gJnE");
+const STAKED: IndexedMap<StakedNft, StakedNftsNs, TypedKey<String>> = IndexedMap::new(&[
+    MultiIndex::new(OWNER_INDEX, |x| x.owner.to_vec())
+]);
+
+crate::namespace!(pub NftStakersNs, b"Vs1tH8kCqA");
+const STAKERS: ItemSpace<Staker, NftStakersNs, TypedKey<CanonicalAddr>> = ItemSpace::new();
+
+const OWNER_INDEX: &[u8] = b"owner";
+
+/// The maximum number of token IDs that [`DefaultImpl`] returns in a single page.
+pub const MAX_PAGE_SIZE: u32 = 50;
+
+/// Requires the admin component in order to check for admin.
+#[interface]
+pub trait NftStaking: Admin {
+    type Error: fmt::Display;
+
+    /// Called by the collection when a single token is sent to the contract.
+    #[execute]
+    fn receive_nft(
+        sender: String,
+        token_id: String,
+        msg: Option<Binary>
+    ) -> Result<Response, <Self as NftStaking>::Error>;
+
+    /// Called by the collection when multiple tokens are sent to the contract.
+    #[execute]
+    fn batch_receive_nft(
+        sender: String,
+        from: String,
+        token_ids: Vec<String>,
+        msg: Option<Binary>
+    ) -> Result<Response, <Self as NftStaking>::Error>;
+
+    /// Returns the given tokens staked by the sender.
+    #[execute]
+    fn unstake_nfts(
+        token_ids: Vec<String>,
+        _padding: Option<String>
+    ) -> Result<Response, <Self as NftStaking>::Error>;
+
+    /// Sets the amount of rewards that each staked token accrues per second.
+    #[execute]
+    fn set_nft_reward_rate(rate: Uint128) -> Result<Response, <Self as NftStaking>::Error>;
+
+    #[query]
+    fn staked_nfts(
+        address: String,
+        key: String,
+        cursor: Option<u64>,
+        limit: u32
+    ) -> Result<Page<String>, <Self as NftStaking>::Error>;
+
+    #[query]
+    fn nft_staking_rewards(
+        address: String,
+        key: String
+    ) -> Result<Uint128, <Self as NftStaking>::Error>;
+}
+
+#[derive(FadromaSerialize, FadromaDeserialize, PartialEq, Debug, Clone)]
+struct StakedNft {
+    token_id: String,
+    owner: CanonicalAddr
+}
+
+#[derive(FadromaSerialize, FadromaDeserialize, Default, PartialEq, Debug, Clone)]
+struct Staker {
+    count: u32,
+    /// The value of [`RewardState::index`] when the rewards were last settled.
+    index: Uint128,
+    pending: Uint128
+}
+
+#[derive(FadromaSerialize, FadromaDeserialize, Default, PartialEq, Debug, Clone)]
+struct RewardState {
+    /// The amount of rewards per token per second.
+    rate: Uint128,
+    /// The total amount of rewards that a single token
+    /// staked since the start would have accrued.
+    index: Uint128,
+    last_update: u64
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Snip721ExecuteMsg {
+    RegisterReceiveNft {
+        code_hash: String,
+        also_implements_batch_receive_nft: bool
+    },
+    TransferNft {
+        recipient: String,
+        token_id: String
+    }
+}
+
+pub struct DefaultImpl;
+
+impl Admin for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn change_admin(mode: Option<Mode>) -> Result<Response, Self::Error> {
+        admin::DefaultImpl::change_admin(deps, env, info, mode)
+    }
+
+    #[query]
+    fn admin() -> Result<Option<Addr>, Self::Error> {
+        admin::DefaultImpl::admin(deps, env)
+    }
+}
+
+impl NftStaking for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn receive_nft(
+        sender: String,
+        token_id: String,
+        _msg: Option<Binary>
+    ) -> StdResult<Response> {
+        stake(deps, &env, &info, &sender, vec![token_id])?;
+
+        Ok(Response::new().add_attribute("action", "stake_nfts"))
+    }
+
+    #[execute]
+    fn batch_receive_nft(
+        _sender: String,
+        from: String,
+        token_ids: Vec<String>,
+        _msg: Option<Binary>
+    ) -> StdResult<Response> {
+        stake(deps, &env, &info, &from, token_ids)?;
+
+        Ok(Response::new().add_attribute("action", "stake_nfts"))
+    }
+
+    #[execute]
+    fn unstake_nfts(
+        token_ids: Vec<String>,
+        _padding: Option<String>
+    ) -> StdResult<Response> {
+        let msgs = unstake(deps, &env, &info.sender, token_ids)?;
+
+        Ok(Response::new()
+            .add_messages(msgs)
+            .add_attribute("action", "unstake_nfts"))
+    }
+
+    #[execute]
+    fn set_nft_reward_rate(rate: Uint128) -> StdResult<Response> {
+        set_reward_rate(deps, &env, info, rate)?;
+
+        Ok(Response::new().add_attribute("action", "set_nft_reward_rate"))
+    }
+
+    #[query]
+    fn staked_nfts(
+        address: String,
+        key: String,
+        cursor: Option<u64>,
+        limit: u32
+    ) -> StdResult<Page<String>> {
+        let address = address.as_str().canonize(deps.api)?;
+        authenticate(deps.storage, &ViewingKey(key), &address)?;
+
+        let page = STAKED.find(
+            deps.storage,
+            OWNER_INDEX,
+            address.as_slice(),
+            cursor,
+            limit.min(MAX_PAGE_SIZE) as u64
+        )?;
+
+        Ok(Page {
+            items: page.items.into_iter().map(|x| x.token_id).collect(),
+            cursor: page.cursor
+        })
+    }
+
+    #[query]
+    fn nft_staking_rewards(address: String, key: String) -> StdResult<Uint128> {
+        let address = address.as_str().canonize(deps.api)?;
+        authenticate(deps.storage, &ViewingKey(key), &address)?;
+
+        let state = accrue(deps.storage, &env)?;
+        let staker = settle(STAKERS.load_or_default(deps.storage, &address)?, &state)?;
+
+        Ok(staker.pending)
+    }
+}
+
+/// Sets the collection whose tokens can be staked and the initial reward rate. You **must**
+/// call this in your instantiate message and send the returned message, which registers
+/// the contract as a receiver with the collection.
+pub fn init(
+    deps: DepsMut,
+    env: &Env,
+    collection: ContractLink<Addr>,
+    reward_rate: Uint128
+) -> StdResult<CosmosMsg> {
+    COLLECTION.save(deps.storage, &collection)?;
+    REWARDS.save(deps.storage, &RewardState {
+        rate: reward_rate,
+        index: Uint128::zero(),
+        last_update: env.block.time.seconds()
+    })?;
+
+    to_cosmos_msg(
+        collection.address.into_string(),
+        collection.code_hash,
+        &Snip721ExecuteMsg::RegisterReceiveNft {
+            code_hash: env.contract.code_hash.clone(),
+            also_implements_batch_receive_nft: true
+        }
+    )
+}
+
+/// Requires the admin component in order to check for admin.
+#[admin::require_admin]
+pub fn set_reward_rate(
+    deps: DepsMut,
+    env: &Env,
+    info: MessageInfo,
+    rate: Uint128
+) -> StdResult<()> {
+    let mut state = accrue(deps.storage, env)?;
+    state.rate = rate;
+
+    REWARDS.save(deps.storage, &state)
+}
+
+/// Stakes `token_ids` on behalf of `owner`. Must be called with the [`MessageInfo`]
+/// of a receive message, which is checked to have been sent by the collection.
+pub fn stake(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    owner: &str,
+    token_ids: Vec<String>
+) -> StdResult<()> {
+    let collection = COLLECTION.load_or_error(deps.storage)?;
+
+    if info.sender != collection.address {
+        return Err(StdError::generic_err("Only tokens of the staked collection are accepted."));
+    }
+
+    let owner = owner.canonize(deps.api)?;
+    let state = accrue(deps.storage, env)?;
+    let mut staker = settle(STAKERS.load_or_default(deps.storage, &owner)?, &state)?;
+
+    for token_id in token_ids {
+        // The collection guarantees that the token was owned by the sender. A token
+        // can't be received while staked since the contract itself owns it.
+        STAKED.save(deps.storage, &token_id, &StakedNft {
+            token_id: token_id.clone(),
+            owner: owner.clone()
+        })?;
+
+        staker.count += 1;
+    }
+
+    STAKERS.save(deps.storage, &owner, &staker)?;
+    REWARDS.save(deps.storage, &state)
+}
+
+/// Removes `token_ids` from the stake of `owner` and returns the messages
+/// that transfer them back. Fails if any of them isn't staked by `owner`.
+pub fn unstake(
+    deps: DepsMut,
+    env: &Env,
+    owner: &Addr,
+    token_ids: Vec<String>
+) -> StdResult<Vec<CosmosMsg>> {
+    let collection = COLLECTION.load_or_error(deps.storage)?;
+    let canonical = deps.api.addr_canonicalize(owner.as_str())?;
+
+    let state = accrue(deps.storage, env)?;
+    let mut staker = settle(STAKERS.load_or_default(deps.storage, &canonical)?, &state)?;
+
+    let mut msgs = Vec::with_capacity(token_ids.len());
+
+    for token_id in token_ids {
+        let is_owner = STAKED.load(deps.storage, &token_id)?
+            .is_some_and(|x| x.owner == canonical);
+
+        if !is_owner {
+            return Err(StdError::generic_err(format!(
+                "Token {} is not staked by {}.",
+                token_id,
+                owner
+            )));
+        }
+
+        STAKED.remove(deps.storage, &token_id)?;
+        staker.count -= 1;
+
+        msgs.push(to_cosmos_msg(
+            collection.address.to_string(),
+            collection.code_hash.clone(),
+            &Snip721ExecuteMsg::TransferNft {
+                recipient: owner.to_string(),
+                token_id
+            }
+        )?);
+    }
+
+    STAKERS.save(deps.storage, &canonical, &staker)?;
+    REWARDS.save(deps.storage, &state)?;
+
+    Ok(msgs)
+}
+
+/// Resets the rewards accrued by `owner` and returns their amount,
+/// which the contract is responsible for transferring to it.
+pub fn claim_rewards(deps: DepsMut, env: &Env, owner: &Addr) -> StdResult<Uint128> {
+    let owner = deps.api.addr_canonicalize(owner.as_str())?;
+
+    let state = accrue(deps.storage, env)?;
+    let mut staker = settle(STAKERS.load_or_default(deps.storage, &owner)?, &state)?;
+
+    let amount = staker.pending;
+    staker.pending = Uint128::zero();
+
+    STAKERS.save(deps.storage, &owner, &staker)?;
+    REWARDS.save(deps.storage, &state)?;
+
+    Ok(amount)
+}
+
+/// Returns the reward state updated up to the current block.
+fn accrue(storage: &dyn Storage, env: &Env) -> StdResult<RewardState> {
+    let mut state = REWARDS.load_or_error(storage)?;
+    let now = env.block.time.seconds();
+
+    let elapsed = now.saturating_sub(state.last_update);
+    state.index = state.index.checked_add(state.rate.checked_mul(elapsed.into())?)?;
+    state.last_update = now;
+
+    Ok(state)
+}
+
+#[inline]
+fn settle(mut staker: Staker, state: &RewardState) -> StdResult<Staker> {
+    let accrued = state.index.checked_sub(staker.index)?
+        .checked_mul(staker.count.into())?;
+
+    staker.pending = staker.pending.checked_add(accrued)?;
+    staker.index = state.index;
+
+    Ok(staker)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        scrt::vk::auth::{self, VkAuth},
+        cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info}
+    };
+
+    #[test]
+    fn stakes_and_accrues_rewards() {
+        let ref mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let collection = ContractLink {
+            address: Addr::unchecked("collection"),
+            code_hash: "collection_hash".into()
+        };
+
+        admin::init(deps.as_mut(), None, &mock_info("admin", &[])).unwrap();
+        init(deps.as_mut(), &env, collection, Uint128::new(10)).unwrap();
+
+        auth::DefaultImpl::set_viewing_key(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            "key".into(),
+            None
+        ).unwrap();
+
+        let receive = |deps: DepsMut, env: &Env, sender: &str, token_ids: &[&str]| DefaultImpl::batch_receive_nft(
+            deps,
+            env.clone(),
+            mock_info(sender, &[]),
+            "alice".into(),
+            "alice".into(),
+            token_ids.iter().map(|x| x.to_string()).collect(),
+            None
+        );
+        let rewards = |deps: Deps, env: &Env| DefaultImpl::nft_staking_rewards(
+            deps,
+            env.clone(),
+            "alice".into(),
+            "key".into()
+        ).unwrap();
+        let staked = |deps: Deps| DefaultImpl::staked_nfts(
+            deps,
+            mock_env(),
+            "alice".into(),
+            "key".into(),
+            None,
+            10
+        ).unwrap().items;
+
+        assert!(receive(deps.as_mut(), &env, "impostor", &["1"]).is_err());
+        receive(deps.as_mut(), &env, "collection", &["1", "2"]).unwrap();
+        assert_eq!(staked(deps.as_ref()), vec!["1", "2"]);
+
+        env.block.time = env.block.time.plus_seconds(5);
+        assert_eq!(rewards(deps.as_ref(), &env), Uint128::new(100));
+
+        DefaultImpl::set_nft_reward_rate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            Uint128::new(1)
+        ).unwrap();
+
+        let unstake = |deps: DepsMut, env: &Env, sender: &str| DefaultImpl::unstake_nfts(
+            deps,
+            env.clone(),
+            mock_info(sender, &[]),
+            vec!["1".into()],
+            None
+        );
+
+        env.block.time = env.block.time.plus_seconds(5);
+        assert!(unstake(deps.as_mut(), &env, "bob").is_err());
+
+        let resp = unstake(deps.as_mut(), &env, "alice").unwrap();
+        assert_eq!(resp.messages.len(), 1);
+        assert_eq!(staked(deps.as_ref()), vec!["2"]);
+        assert!(unstake(deps.as_mut(), &env, "alice").is_err());
+
+        env.block.time = env.block.time.plus_seconds(5);
+        assert_eq!(rewards(deps.as_ref(), &env), Uint128::new(115));
+
+        assert_eq!(claim_rewards(deps.as_mut(), &env, &Addr::unchecked("alice")).unwrap(), Uint128::new(115));
+        assert_eq!(rewards(deps.as_ref(), &env), Uint128::zero());
+    }
+}
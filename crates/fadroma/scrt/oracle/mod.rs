@@ -0,0 +1,405 @@
+//! Prices pushed by a set of trusted feeders, allowing a contract to act as its own
+//! lightweight oracle. You **MUST** implement [admin] in your contract if you want to
+//! use this module.
+//! *Feature flag: `oracle`*
+//!
+//! Each feeder signs its prices off-chain with a secp256k1 key configured by the admin.
+//! Anyone, i.e. a relayer or one of the feeders, can then submit the signed prices using
+//! [`Oracle::submit_prices`]. Only the latest price of each feeder is kept and the price
+//! of a feed is the median of the prices that aren't older than the configured maximum
+//! age, as long as enough feeders have reported one. This way, no single feeder can move
+//! the price on its own and feeders that stop reporting are eventually ignored.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    self as fadroma,
+    admin::{self, Admin, Mode},
+    dsl::*,
+    prelude::*,
+    crypto::sha_256,
+    storage::series
+};
+
+crate::namespace!(pub OracleConfigNs, b"Tn5wF1qXbM");
+pub const CONFIG: SingleItem<OracleConfig, OracleConfigNs> = SingleItem::new();
+
+crate::namespace!(pub FeederPricesNs, b"a8KdS3jVpL");
+const PRICES: ItemSpace<
+    FeederPrice,
+    FeederPricesNs,
+    TypedKey2<String, String>
+> = ItemSpace::new();
+
+/// The maximum number of feeders that can be configured.
+pub const MAX_FEEDERS: usize = 32;
+
+/// Requires the admin component in order to check for admin.
+#[interface]
+pub trait Oracle: Admin {
+    type Error: fmt::Display;
+
+    #[execute]
+    fn set_oracle_config(config: OracleConfig) -> Result<Response, <Self as Oracle>::Error>;
+
+    /// Accepts prices signed by any of the configured feeders.
+    #[execute]
+    fn submit_prices(prices: Vec<SignedPrice>) -> Result<Response, <Self as Oracle>::Error>;
+
+    #[query]
+    fn oracle_config() -> Result<OracleConfig, <Self as Oracle>::Error>;
+
+    #[query]
+    fn oracle_price(feed: String) -> Result<Option<OraclePrice>, <Self as Oracle>::Error>;
+}
+
+#[derive(
+    Serialize, Deserialize, FadromaSerialize, FadromaDeserialize,
+    JsonSchema, PartialEq, Debug, Clone
+)]
+pub struct OracleConfig {
+    /// The compressed secp256k1 public keys of the feeders.
+    pub feeders: Vec<Binary>,
+    /// The minimum number of feeders that must have reported a
+    /// price that isn't stale in order for the price to be valid.
+    pub quorum: u32,
+    /// The number of seconds after which a price is considered stale.
+    pub max_age: u64
+}
+
+/// A price signed by a feeder.
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct SignedPrice {
+    pub feed: String,
+    pub price: Decimal,
+    /// The time in seconds at which the price was observed.
+    pub timestamp: u64,
+    pub pubkey: Binary,
+    /// The signature of the SHA-256 hash of the JSON encoded [`PriceSignDoc`].
+    pub signature: Binary
+}
+
+/// The data that is signed by a feeder. Binds the price to the
+/// contract and chain so that it can't be replayed anywhere else.
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct PriceSignDoc {
+    pub chain_id: String,
+    pub contract: String,
+    pub feed: String,
+    pub price: Decimal,
+    pub timestamp: u64
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct OraclePrice {
+    /// The median of the prices reported by the feeders.
+    pub price: Decimal,
+    /// The timestamp of the oldest price included in the median.
+    pub timestamp: u64,
+    /// The number of feeders included in the median.
+    pub feeders: u32
+}
+
+#[derive(FadromaSerialize, FadromaDeserialize, PartialEq, Debug, Clone)]
+struct FeederPrice {
+    price: Decimal,
+    timestamp: u64
+}
+
+pub struct DefaultImpl;
+
+impl Admin for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn change_admin(mode: Option<Mode>) -> Result<Response, Self::Error> {
+        admin::DefaultImpl::change_admin(deps, env, info, mode)
+    }
+
+    #[query]
+    fn admin() -> Result<Option<Addr>, Self::Error> {
+        admin::DefaultImpl::admin(deps, env)
+    }
+}
+
+impl Oracle for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn set_oracle_config(config: OracleConfig) -> StdResult<Response> {
+        set_config(deps, info, config)?;
+
+        Ok(Response::new().add_attribute("action", "set_oracle_config"))
+    }
+
+    #[execute]
+    fn submit_prices(prices: Vec<SignedPrice>) -> StdResult<Response> {
+        for price in &prices {
+            submit(deps.branch(), &env, price)?;
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "submit_prices")
+            .add_attribute("count", prices.len().to_string()))
+    }
+
+    #[query]
+    fn oracle_config() -> StdResult<OracleConfig> {
+        load_config(deps.storage)
+    }
+
+    #[query]
+    fn oracle_price(feed: String) -> StdResult<Option<OraclePrice>> {
+        price(deps.storage, &env, &feed)
+    }
+}
+
+/// Requires the admin component in order to check for admin.
+#[admin::require_admin]
+pub fn set_config(deps: DepsMut, info: MessageInfo, config: OracleConfig) -> StdResult<()> {
+    if config.feeders.is_empty() || config.feeders.len() > MAX_FEEDERS {
+        return Err(StdError::generic_err(format!(
+            "Between 1 and {} feeders must be configured.",
+            MAX_FEEDERS
+        )));
+    }
+
+    if config.feeders.iter().any(|x| x.len() != 33) {
+        return Err(StdError::generic_err("Feeders must be compressed secp256k1 public keys."));
+    }
+
+    for (i, feeder) in config.feeders.iter().enumerate() {
+        if config.feeders[i + 1..].contains(feeder) {
+            return Err(StdError::generic_err(format!("Duplicate feeder: {}", feeder)));
+        }
+    }
+
+    if config.quorum == 0 || config.quorum as usize > config.feeders.len() {
+        return Err(StdError::generic_err(
+            "The quorum must be between 1 and the number of feeders."
+        ));
+    }
+
+    if config.max_age == 0 {
+        return Err(StdError::generic_err("The maximum age must be greater than zero."));
+    }
+
+    CONFIG.save(deps.storage, &config)
+}
+
+/// Returns `Err` if the configuration wasn't set.
+#[inline]
+pub fn load_config(storage: &dyn Storage) -> StdResult<OracleConfig> {
+    CONFIG.load(storage)?.ok_or_else(||
+        StdError::generic_err("The oracle has not been configured.")
+    )
+}
+
+/// Verifies that `price` was signed by one of the feeders and stores it as the latest
+/// price of that feeder. Prices that are stale, from the future or not newer than the
+/// latest price of the feeder are rejected, so a signed price can only be submitted once.
+pub fn submit(deps: DepsMut, env: &Env, price: &SignedPrice) -> StdResult<()> {
+    let config = load_config(deps.storage)?;
+
+    if !config.feeders.contains(&price.pubkey) {
+        return Err(StdError::generic_err("Unknown feeder."));
+    }
+
+    let now = env.block.time.seconds();
+
+    if price.timestamp > now {
+        return Err(StdError::generic_err("The price timestamp is in the future."));
+    }
+
+    if now - price.timestamp >= config.max_age {
+        return Err(StdError::generic_err("The price is stale."));
+    }
+
+    let sign_doc = to_vec(&price.sign_doc(env))?;
+    let valid = deps.api.secp256k1_verify(
+        &sha_256(&sign_doc),
+        &price.signature,
+        &price.pubkey
+    ).map_err(|err| StdError::generic_err(err.to_string()))?;
+
+    if !valid {
+        return Err(StdError::generic_err("Invalid signature."));
+    }
+
+    let key = (&price.feed, &price.pubkey.to_base64());
+
+    if let Some(latest) = PRICES.load(deps.storage, key)? {
+        if price.timestamp <= latest.timestamp {
+            return Err(StdError::generic_err(format!(
+                "A newer price has already been submitted by the feeder: {}",
+                latest.timestamp
+            )));
+        }
+    }
+
+    PRICES.save(deps.storage, key, &FeederPrice {
+        price: price.price,
+        timestamp: price.timestamp
+    })
+}
+
+/// Returns the median of the prices of `feed` that aren't stale
+/// or [`None`] if fewer than the quorum of feeders have reported one.
+pub fn price(storage: &dyn Storage, env: &Env, feed: &str) -> StdResult<Option<OraclePrice>> {
+    let config = load_config(storage)?;
+    let now = env.block.time.seconds();
+    let feed = feed.to_string();
+
+    let mut prices = Vec::with_capacity(config.feeders.len());
+
+    for feeder in &config.feeders {
+        if let Some(price) = PRICES.load(storage, (&feed, &feeder.to_base64()))? {
+            if now.saturating_sub(price.timestamp) < config.max_age {
+                prices.push(price);
+            }
+        }
+    }
+
+    if prices.len() < config.quorum as usize {
+        return Ok(None);
+    }
+
+    let timestamp = prices.iter().map(|x| x.timestamp).min().unwrap_or_default();
+
+    let mut atomics: Vec<Uint128> = prices.iter().map(|x| x.price.atomics()).collect();
+    let median = series::median(&mut atomics).unwrap_or_default();
+
+    Ok(Some(OraclePrice {
+        price: Decimal::raw(median.u128()),
+        timestamp,
+        feeders: prices.len() as u32
+    }))
+}
+
+impl SignedPrice {
+    /// Returns the data that must be signed for this price.
+    pub fn sign_doc(&self, env: &Env) -> PriceSignDoc {
+        PriceSignDoc {
+            chain_id: env.block.chain_id.clone(),
+            contract: env.contract.address.to_string(),
+            feed: self.feed.clone(),
+            price: self.price,
+            timestamp: self.timestamp
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use k256::ecdsa::{SigningKey, Signature, signature::Signer};
+
+    use super::*;
+    use crate::cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+
+    fn feeder(seed: u8) -> (SigningKey, Binary) {
+        let key = SigningKey::from_bytes(&[seed; 32]).unwrap();
+        let pubkey = Binary::from(key.verifying_key().to_bytes().as_slice());
+
+        (key, pubkey)
+    }
+
+    fn sign(key: &SigningKey, env: &Env, price: &str, timestamp: u64) -> SignedPrice {
+        let mut price = SignedPrice {
+            feed: "SCRT/USD".into(),
+            price: price.parse().unwrap(),
+            timestamp,
+            pubkey: Binary::from(key.verifying_key().to_bytes().as_slice()),
+            signature: Binary::default()
+        };
+
+        let signature: Signature = key.sign(&to_vec(&price.sign_doc(env)).unwrap());
+        price.signature = Binary::from(signature.as_ref());
+
+        price
+    }
+
+    #[test]
+    fn medianizes_fresh_prices() {
+        let ref mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let now = env.block.time.seconds();
+
+        let (alice, alice_pubkey) = feeder(1);
+        let (bob, bob_pubkey) = feeder(2);
+        let (carol, carol_pubkey) = feeder(3);
+        let (mallory, _) = feeder(4);
+
+        admin::init(deps.as_mut(), None, &mock_info("admin", &[])).unwrap();
+
+        let config = OracleConfig {
+            feeders: vec![alice_pubkey.clone(), bob_pubkey, carol_pubkey],
+            quorum: 2,
+            max_age: 60
+        };
+
+        let set = |deps: DepsMut, config: OracleConfig| DefaultImpl::set_oracle_config(
+            deps,
+            mock_env(),
+            mock_info("admin", &[]),
+            config
+        );
+
+        assert!(set(deps.as_mut(), OracleConfig { quorum: 4, ..config.clone() }).is_err());
+        assert!(set(deps.as_mut(), OracleConfig { max_age: 0, ..config.clone() }).is_err());
+        assert!(set(deps.as_mut(), OracleConfig {
+            feeders: vec![alice_pubkey.clone(), alice_pubkey.clone()],
+            ..config.clone()
+        }).is_err());
+        set(deps.as_mut(), config).unwrap();
+
+        let submit = |deps: DepsMut, env: &Env, prices: Vec<SignedPrice>| DefaultImpl::submit_prices(
+            deps,
+            env.clone(),
+            mock_info("relayer", &[]),
+            prices
+        );
+        let price = |deps: Deps, env: &Env| DefaultImpl::oracle_price(
+            deps,
+            env.clone(),
+            "SCRT/USD".into()
+        ).unwrap();
+
+        assert!(submit(deps.as_mut(), &env, vec![sign(&mallory, &env, "100", now)]).is_err());
+        assert!(submit(deps.as_mut(), &env, vec![sign(&alice, &env, "1", now + 1)]).is_err());
+        assert!(submit(deps.as_mut(), &env, vec![sign(&alice, &env, "1", now - 60)]).is_err());
+
+        let mut tampered = sign(&alice, &env, "1", now);
+        tampered.price = Decimal::percent(200);
+        assert!(submit(deps.as_mut(), &env, vec![tampered]).is_err());
+
+        submit(deps.as_mut(), &env, vec![sign(&alice, &env, "1", now - 10)]).unwrap();
+        assert_eq!(price(deps.as_ref(), &env), None);
+
+        // Replay
+        assert!(submit(deps.as_mut(), &env, vec![sign(&alice, &env, "1", now - 10)]).is_err());
+
+        submit(deps.as_mut(), &env, vec![
+            sign(&bob, &env, "2", now),
+            sign(&carol, &env, "10", now - 5)
+        ]).unwrap();
+
+        assert_eq!(price(deps.as_ref(), &env), Some(OraclePrice {
+            price: Decimal::percent(200),
+            timestamp: now - 10,
+            feeders: 3
+        }));
+
+        // Alice's price is stale.
+        env.block.time = env.block.time.plus_seconds(50);
+        assert_eq!(price(deps.as_ref(), &env), Some(OraclePrice {
+            price: Decimal::percent(600),
+            timestamp: now - 5,
+            feeders: 2
+        }));
+
+        env.block.time = env.block.time.plus_seconds(5);
+        assert_eq!(price(deps.as_ref(), &env), None);
+    }
+}
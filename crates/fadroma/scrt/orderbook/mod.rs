@@ -0,0 +1,733 @@
+//! An order book where resting orders are kept in the private state of the contract.
+//! *Feature flag: `orderbook`*
+//!
+//! Incoming orders are matched against the book atomically, inside the same handler that
+//! places them. The only public view of the book is its aggregate depth with prices rounded
+//! to a coarse tick size which is set with [`init`]. Owners can list their own resting orders
+//! using a query permit.
+//!
+//! This component only keeps track of orders and does not move any funds. Contracts should
+//! call [`place_order`] and [`cancel_order`] from their own handlers and settle the returned
+//! [`Fill`]s, unfilled and cancelled amounts.
+//!
+//! Each order is stored under its own key and the orders at the same price are linked in
+//! the order they were placed, so that placing, filling and cancelling an order only touches
+//! a few keys regardless of the size of the book. To keep the cost of each call bounded,
+//! an order is matched against at most [`MAX_FILLS`] resting orders, orders smaller than the
+//! minimum amount set with [`init`] are rejected and each account can have at most
+//! [`MAX_ORDERS_PER_ACCOUNT`] resting orders.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    self as fadroma,
+    dsl::*,
+    prelude::*,
+    scrt::{ResponseExt, permit::Permit},
+    storage::sorted_map::SortedMap
+};
+
+crate::namespace!(pub OrdersNs, b"Tg8kXw2nRb");
+const ORDERS: ItemSpace<Order, OrdersNs, TypedKey<u64>> = ItemSpace::new();
+
+crate::namespace!(pub AccountOrdersNs, b"pQ5zHc7mLa");
+const ACCOUNT_ORDERS: ItemSpace<Vec<u64>, AccountOrdersNs, TypedKey<CanonicalAddr>> = ItemSpace::new();
+
+crate::namespace!(pub BidLevelsNs, b"Hu4cLp8zQe");
+const BID_LEVELS: SortedMap<u128, Level, BidLevelsNs> = SortedMap::new();
+
+crate::namespace!(pub AskLevelsNs, b"Wd2mRy6kVs");
+const ASK_LEVELS: SortedMap<u128, Level, AskLevelsNs> = SortedMap::new();
+
+crate::namespace!(pub BidDepthNs, b"Ks9fNb3tXa");
+const BID_DEPTH: SortedMap<u128, Uint128, BidDepthNs> = SortedMap::new();
+
+crate::namespace!(pub AskDepthNs, b"Zr5gJe1wMc");
+const ASK_DEPTH: SortedMap<u128, Uint128, AskDepthNs> = SortedMap::new();
+
+crate::namespace!(pub TickNs, b"Ve3jYs9dWo");
+const TICK: SingleItem<Uint128, TickNs> = SingleItem::new();
+
+crate::namespace!(pub MinAmountNs, b"Ya7pDq4hGn");
+const MIN_AMOUNT: SingleItem<Uint128, MinAmountNs> = SingleItem::new();
+
+crate::namespace!(pub OrderCountNs, b"bN6rFu1tKe");
+const COUNT: SingleItem<u64, OrderCountNs> = SingleItem::new();
+
+/// The maximum number of resting orders that an incoming order is matched against.
+pub const MAX_FILLS: usize = 20;
+
+/// The maximum number of resting orders that an account can have at a time.
+pub const MAX_ORDERS_PER_ACCOUNT: usize = 30;
+
+#[interface]
+pub trait SealedOrderBook {
+    type Error: fmt::Display;
+
+    /// Matches the order against the opposite side of the book and rests the
+    /// unfilled amount. Returns [`PlaceOrderResult`] as the response data.
+    #[execute]
+    fn place_order(
+        side: Side,
+        price: Uint128,
+        amount: Uint128,
+        _padding: Option<String>
+    ) -> Result<Response, Self::Error>;
+
+    /// Cancels a resting order of the sender. Returns the
+    /// cancelled [`OrderInfo`] as the response data.
+    #[execute]
+    fn cancel_order(
+        id: u64,
+        _padding: Option<String>
+    ) -> Result<Response, Self::Error>;
+
+    /// Returns the aggregate amount at each tick, best price first.
+    #[query]
+    fn depth(side: Side, limit: Option<u32>) -> Result<Vec<DepthLevel>, Self::Error>;
+
+    #[query]
+    fn orders(permit: Permit<OrderBookPermission>) -> Result<Vec<OrderInfo>, Self::Error>;
+}
+
+#[derive(
+    Serialize, Deserialize, FadromaSerialize, FadromaDeserialize,
+    JsonSchema, PartialEq, Eq, Debug, Clone, Copy
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Buy,
+    Sell
+}
+
+impl Side {
+    #[inline]
+    pub fn opposite(self) -> Self {
+        match self {
+            Self::Buy => Self::Sell,
+            Self::Sell => Self::Buy
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderBookPermission {
+    Orders
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct DepthLevel {
+    /// Bids are rounded down and asks are rounded up to the tick size.
+    pub price: Uint128,
+    pub amount: Uint128
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct OrderInfo {
+    pub id: u64,
+    pub side: Side,
+    pub price: Uint128,
+    /// The amount that has not been filled yet.
+    pub amount: Uint128
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct PlaceOrderResult {
+    /// The ID of the resting order or `None` if the order was filled in full.
+    pub id: Option<u64>,
+    pub trades: Vec<Trade>,
+    /// The amount that was neither filled nor added to the book.
+    pub unfilled: Uint128
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct Trade {
+    pub price: Uint128,
+    pub amount: Uint128
+}
+
+/// A trade against a resting order, as returned by [`place_order`].
+#[derive(PartialEq, Debug, Clone)]
+pub struct Fill {
+    pub maker: Addr,
+    pub order: u64,
+    /// The price of the resting order.
+    pub price: Uint128,
+    pub amount: Uint128
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Placement {
+    pub id: Option<u64>,
+    pub fills: Vec<Fill>,
+    /// The amount that still crossed the book after [`MAX_FILLS`] were made.
+    /// It is not added to the book and must be returned to the owner.
+    pub unfilled: Uint128
+}
+
+#[derive(FadromaSerialize, FadromaDeserialize, PartialEq, Debug, Clone)]
+struct Order {
+    owner: CanonicalAddr,
+    side: Side,
+    price: Uint128,
+    amount: Uint128,
+    /// The previous and next order at the same price, in time priority.
+    prev: Option<u64>,
+    next: Option<u64>
+}
+
+/// The first and last order at a price.
+#[derive(FadromaSerialize, FadromaDeserialize, PartialEq, Debug, Clone, Copy)]
+struct Level {
+    head: u64,
+    tail: u64
+}
+
+pub struct DefaultImpl;
+
+impl SealedOrderBook for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn place_order(
+        side: Side,
+        price: Uint128,
+        amount: Uint128,
+        _padding: Option<String>
+    ) -> StdResult<Response> {
+        let placement = place_order(deps, &info.sender, side, price, amount)?;
+
+        let result = PlaceOrderResult {
+            id: placement.id,
+            trades: placement.fills.into_iter().map(|x| Trade {
+                price: x.price,
+                amount: x.amount
+            }).collect(),
+            unfilled: placement.unfilled
+        };
+
+        Ok(Response::new()
+            .add_attribute("action", "place_order")
+            .set_data(to_binary(&result)?)
+            .pad()
+        )
+    }
+
+    #[execute]
+    fn cancel_order(
+        id: u64,
+        _padding: Option<String>
+    ) -> StdResult<Response> {
+        let order = cancel_order(deps, &info.sender, id)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "cancel_order")
+            .set_data(to_binary(&order)?)
+            .pad()
+        )
+    }
+
+    #[query]
+    fn depth(side: Side, limit: Option<u32>) -> StdResult<Vec<DepthLevel>> {
+        depth(deps.storage, side, limit.unwrap_or(30) as usize)
+    }
+
+    #[query]
+    fn orders(permit: Permit<OrderBookPermission>) -> StdResult<Vec<OrderInfo>> {
+        let owner = permit.validate(
+            deps,
+            env.contract.address.as_str(),
+            None,
+            &[OrderBookPermission::Orders]
+        )?;
+
+        orders(deps, &owner)
+    }
+}
+
+/// Sets the tick size that prices are rounded to when querying the depth of the book
+/// and the minimum amount of an order. The larger the tick is, the less the public depth
+/// reveals about individual orders. The larger the minimum amount is, the more it costs
+/// to fill the book with orders. Must only be called once, when instantiating the contract.
+pub fn init(storage: &mut dyn Storage, tick: Uint128, min_amount: Uint128) -> StdResult<()> {
+    if tick.is_zero() {
+        return Err(StdError::generic_err("The tick size cannot be zero."));
+    }
+
+    if TICK.load(storage)?.is_some() {
+        return Err(StdError::generic_err("The order book has already been initialized."));
+    }
+
+    TICK.save(storage, &tick)?;
+    MIN_AMOUNT.save(storage, &min_amount)
+}
+
+/// Matches the order against the opposite side of the book, best price first
+/// and oldest order first within the same price. The unfilled amount is added
+/// to the book unless it still crosses it after [`MAX_FILLS`] trades, in which
+/// case it is returned as [`Placement::unfilled`]. Trades happen at the price
+/// of the resting order.
+pub fn place_order(
+    deps: DepsMut,
+    owner: &Addr,
+    side: Side,
+    price: Uint128,
+    amount: Uint128
+) -> StdResult<Placement> {
+    if price.is_zero() || amount.is_zero() {
+        return Err(StdError::generic_err("The price and amount cannot be zero."));
+    }
+
+    let min_amount = MIN_AMOUNT.load_or_error(deps.storage)?;
+
+    if amount < min_amount {
+        return Err(StdError::generic_err(format!(
+            "The amount must be at least {}.",
+            min_amount
+        )));
+    }
+
+    let opposite = side.opposite();
+
+    let mut remaining = amount;
+    let mut fills = vec![];
+
+    while !remaining.is_zero() {
+        let Some((best, level)) = best_level(deps.storage, opposite)? else {
+            break;
+        };
+
+        if !crosses(side, price, best.into()) {
+            break;
+        }
+
+        if fills.len() == MAX_FILLS {
+            return Ok(Placement { id: None, fills, unfilled: remaining });
+        }
+
+        let mut order = load_order(deps.storage, level.head)?;
+
+        let filled = remaining.min(order.amount);
+        remaining -= filled;
+        order.amount -= filled;
+
+        fills.push(Fill {
+            maker: (&order.owner).humanize(deps.api)?,
+            order: level.head,
+            price: order.price,
+            amount: filled
+        });
+
+        update_depth(deps.storage, opposite, order.price, filled, false)?;
+
+        if order.amount.is_zero() {
+            unlink(deps.storage, level.head, &order)?;
+        } else {
+            ORDERS.save(deps.storage, &level.head, &order)?;
+        }
+    }
+
+    if remaining.is_zero() {
+        return Ok(Placement { id: None, fills, unfilled: remaining });
+    }
+
+    let owner = owner.canonize(deps.api)?;
+    let mut account_orders = ACCOUNT_ORDERS.load_or_default(deps.storage, &owner)?;
+
+    if account_orders.len() >= MAX_ORDERS_PER_ACCOUNT {
+        return Err(StdError::generic_err(format!(
+            "Cannot have more than {} resting orders.",
+            MAX_ORDERS_PER_ACCOUNT
+        )));
+    }
+
+    let id = COUNT.load_or_default(deps.storage)?;
+    COUNT.save(deps.storage, &(id + 1))?;
+
+    account_orders.push(id);
+    ACCOUNT_ORDERS.save(deps.storage, &owner, &account_orders)?;
+
+    // Append to the orders at the same price to preserve time priority.
+    let prev = match get_level(deps.storage, side, price)? {
+        Some(mut level) => {
+            let mut tail = load_order(deps.storage, level.tail)?;
+            tail.next = Some(id);
+            ORDERS.save(deps.storage, &level.tail, &tail)?;
+
+            let prev = level.tail;
+            level.tail = id;
+            save_level(deps.storage, side, price, &level)?;
+
+            Some(prev)
+        },
+        None => {
+            save_level(deps.storage, side, price, &Level { head: id, tail: id })?;
+
+            None
+        }
+    };
+
+    ORDERS.save(deps.storage, &id, &Order {
+        owner,
+        side,
+        price,
+        amount: remaining,
+        prev,
+        next: None
+    })?;
+
+    update_depth(deps.storage, side, price, remaining, true)?;
+
+    Ok(Placement { id: Some(id), fills, unfilled: Uint128::zero() })
+}
+
+/// Removes the resting order with the given ID if it belongs to `owner`.
+pub fn cancel_order(deps: DepsMut, owner: &Addr, id: u64) -> StdResult<OrderInfo> {
+    let owner = owner.canonize(deps.api)?;
+
+    let order = ORDERS.load(deps.storage, &id)?.ok_or_else(||
+        StdError::generic_err(format!("Order {} not found.", id))
+    )?;
+
+    if order.owner != owner {
+        return Err(StdError::generic_err("Unauthorized"));
+    }
+
+    unlink(deps.storage, id, &order)?;
+    update_depth(deps.storage, order.side, order.price, order.amount, false)?;
+
+    Ok(OrderInfo {
+        id,
+        side: order.side,
+        price: order.price,
+        amount: order.amount
+    })
+}
+
+/// Returns up to `limit` price levels of the given side of the book.
+pub fn depth(storage: &dyn Storage, side: Side, limit: usize) -> StdResult<Vec<DepthLevel>> {
+    let levels = match side {
+        Side::Buy => BID_DEPTH.range_rev(storage, .., limit)?,
+        Side::Sell => ASK_DEPTH.range(storage, .., limit)?
+    };
+
+    Ok(levels.into_iter().map(|(price, amount)| DepthLevel {
+        price: price.into(),
+        amount
+    }).collect())
+}
+
+/// Returns the resting orders of `owner`. The caller must authenticate them.
+pub fn orders(deps: Deps, owner: &str) -> StdResult<Vec<OrderInfo>> {
+    let owner = owner.canonize(deps.api)?;
+    let ids = ACCOUNT_ORDERS.load_or_default(deps.storage, &owner)?;
+
+    let mut result = Vec::with_capacity(ids.len());
+
+    for id in ids {
+        let order = load_order(deps.storage, id)?;
+
+        result.push(OrderInfo {
+            id,
+            side: order.side,
+            price: order.price,
+            amount: order.amount
+        });
+    }
+
+    Ok(result)
+}
+
+/// Removes the order from the book and from the orders of its owner.
+fn unlink(storage: &mut dyn Storage, id: u64, order: &Order) -> StdResult<()> {
+    let mut level = get_level(storage, order.side, order.price)?
+        .ok_or_else(corrupted)?;
+
+    match order.prev {
+        Some(prev) => {
+            let mut prev_order = load_order(storage, prev)?;
+            prev_order.next = order.next;
+            ORDERS.save(storage, &prev, &prev_order)?;
+        },
+        None => if let Some(next) = order.next {
+            level.head = next;
+        }
+    }
+
+    match order.next {
+        Some(next) => {
+            let mut next_order = load_order(storage, next)?;
+            next_order.prev = order.prev;
+            ORDERS.save(storage, &next, &next_order)?;
+        },
+        None => if let Some(prev) = order.prev {
+            level.tail = prev;
+        }
+    }
+
+    if order.prev.is_none() && order.next.is_none() {
+        remove_level(storage, order.side, order.price)?;
+    } else {
+        save_level(storage, order.side, order.price, &level)?;
+    }
+
+    ORDERS.remove(storage, &id);
+
+    let mut account_orders = ACCOUNT_ORDERS.load_or_default(storage, &order.owner)?;
+    account_orders.retain(|x| *x != id);
+
+    if account_orders.is_empty() {
+        ACCOUNT_ORDERS.remove(storage, &order.owner);
+
+        Ok(())
+    } else {
+        ACCOUNT_ORDERS.save(storage, &order.owner, &account_orders)
+    }
+}
+
+/// Adds or subtracts `amount` from the public depth at the tick that `price` rounds to.
+fn update_depth(
+    storage: &mut dyn Storage,
+    side: Side,
+    price: Uint128,
+    amount: Uint128,
+    add: bool
+) -> StdResult<()> {
+    let tick = TICK.load_or_error(storage)?;
+    let mut rounded = price - price % tick;
+
+    if side == Side::Sell && rounded != price {
+        rounded = rounded.checked_add(tick)?;
+    }
+
+    let price = rounded.u128();
+
+    let total = match side {
+        Side::Buy => BID_DEPTH.get(storage, price)?,
+        Side::Sell => ASK_DEPTH.get(storage, price)?
+    }.unwrap_or_default();
+
+    let total = if add {
+        total.checked_add(amount)?
+    } else {
+        total.checked_sub(amount)?
+    };
+
+    match (side, total.is_zero()) {
+        (Side::Buy, true) => BID_DEPTH.remove(storage, price).map(|_| ()),
+        (Side::Sell, true) => ASK_DEPTH.remove(storage, price).map(|_| ()),
+        (Side::Buy, false) => BID_DEPTH.insert(storage, price, &total).map(|_| ()),
+        (Side::Sell, false) => ASK_DEPTH.insert(storage, price, &total).map(|_| ())
+    }
+}
+
+/// The highest bid or the lowest ask.
+#[inline]
+fn best_level(storage: &dyn Storage, side: Side) -> StdResult<Option<(u128, Level)>> {
+    match side {
+        Side::Buy => BID_LEVELS.last(storage),
+        Side::Sell => ASK_LEVELS.first(storage)
+    }
+}
+
+#[inline]
+fn get_level(storage: &dyn Storage, side: Side, price: Uint128) -> StdResult<Option<Level>> {
+    match side {
+        Side::Buy => BID_LEVELS.get(storage, price.u128()),
+        Side::Sell => ASK_LEVELS.get(storage, price.u128())
+    }
+}
+
+#[inline]
+fn save_level(storage: &mut dyn Storage, side: Side, price: Uint128, level: &Level) -> StdResult<()> {
+    match side {
+        Side::Buy => BID_LEVELS.insert(storage, price.u128(), level),
+        Side::Sell => ASK_LEVELS.insert(storage, price.u128(), level)
+    }.map(|_| ())
+}
+
+#[inline]
+fn remove_level(storage: &mut dyn Storage, side: Side, price: Uint128) -> StdResult<()> {
+    match side {
+        Side::Buy => BID_LEVELS.remove(storage, price.u128()),
+        Side::Sell => ASK_LEVELS.remove(storage, price.u128())
+    }.map(|_| ())
+}
+
+#[inline]
+fn load_order(storage: &dyn Storage, id: u64) -> StdResult<Order> {
+    ORDERS.load(storage, &id)?.ok_or_else(corrupted)
+}
+
+#[inline]
+fn corrupted() -> StdError {
+    StdError::generic_err("The order book is corrupted.")
+}
+
+/// Whether an order on `side` at `price` can trade against `other` on the opposite side.
+#[inline]
+fn crosses(side: Side, price: Uint128, other: Uint128) -> bool {
+    match side {
+        Side::Buy => other <= price,
+        Side::Sell => other >= price
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        scrt::permit::PermitParams,
+        cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info}
+    };
+
+    #[test]
+    fn matches_orders_and_hides_book() {
+        let ref mut deps = mock_dependencies();
+        let env = mock_env();
+
+        init(deps.as_mut().storage, Uint128::new(10), Uint128::one()).unwrap();
+
+        let mut place = |sender: &str, side: Side, price: u128, amount: u128| {
+            let resp = DefaultImpl::place_order(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(sender, &[]),
+                side,
+                Uint128::new(price),
+                Uint128::new(amount),
+                None
+            ).unwrap();
+
+            from_binary::<PlaceOrderResult>(&resp.data.unwrap()).unwrap()
+        };
+
+        place("alice", Side::Sell, 101, 5);
+        place("bob", Side::Sell, 105, 5);
+        place("charlie", Side::Sell, 120, 3);
+        place("alice", Side::Sell, 101, 1);
+
+        let result = place("dave", Side::Buy, 106, 7);
+        assert_eq!(result, PlaceOrderResult {
+            id: None,
+            trades: vec![
+                Trade { price: Uint128::new(101), amount: Uint128::new(5) },
+                Trade { price: Uint128::new(101), amount: Uint128::new(1) },
+                Trade { price: Uint128::new(105), amount: Uint128::new(1) }
+            ],
+            unfilled: Uint128::zero()
+        });
+
+        let result = place("dave", Side::Buy, 110, 6);
+        assert_eq!(result.id, Some(4));
+        assert_eq!(result.trades, vec![Trade { price: Uint128::new(105), amount: Uint128::new(4) }]);
+
+        place("erin", Side::Buy, 109, 1);
+
+        let depth = |deps: Deps, side: Side| DefaultImpl::depth(deps, mock_env(), side, None).unwrap();
+        let level = |price: u128, amount: u128| DepthLevel {
+            price: Uint128::new(price),
+            amount: Uint128::new(amount)
+        };
+
+        assert_eq!(depth(deps.as_ref(), Side::Sell), vec![level(120, 3)]);
+        assert_eq!(depth(deps.as_ref(), Side::Buy), vec![level(110, 2), level(100, 1)]);
+
+        let fills = place_order(
+            deps.as_mut(),
+            &Addr::unchecked("frank"),
+            Side::Sell,
+            Uint128::new(100),
+            Uint128::new(4)
+        ).unwrap();
+        assert_eq!(fills.id, Some(6));
+        assert_eq!(
+            fills.fills.iter().map(|x| (x.maker.as_str(), x.order)).collect::<Vec<_>>(),
+            vec![("dave", 4), ("erin", 5)]
+        );
+
+        let permit = |signer: &str| Permit::new(
+            signer,
+            PermitParams::new(env.contract.address.as_str())
+                .permissions(vec![OrderBookPermission::Orders])
+        );
+
+        let own = DefaultImpl::orders(deps.as_ref(), env.clone(), permit("charlie")).unwrap();
+        assert_eq!(own, vec![OrderInfo {
+            id: 2,
+            side: Side::Sell,
+            price: Uint128::new(120),
+            amount: Uint128::new(3)
+        }]);
+        assert!(DefaultImpl::orders(deps.as_ref(), env.clone(), permit("bob")).unwrap().is_empty());
+
+        assert_eq!(
+            DefaultImpl::cancel_order(deps.as_mut(), env.clone(), mock_info("bob", &[]), 2, None).unwrap_err(),
+            StdError::generic_err("Unauthorized")
+        );
+
+        DefaultImpl::cancel_order(deps.as_mut(), env.clone(), mock_info("charlie", &[]), 2, None).unwrap();
+        assert_eq!(depth(deps.as_ref(), Side::Sell), vec![level(100, 1)]);
+        assert!(DefaultImpl::orders(deps.as_ref(), env.clone(), permit("charlie")).unwrap().is_empty());
+        assert_eq!(
+            cancel_order(deps.as_mut(), &Addr::unchecked("charlie"), 2).unwrap_err(),
+            StdError::generic_err("Order 2 not found.")
+        );
+    }
+
+    #[test]
+    fn bounds_the_work_per_call() {
+        let ref mut deps = mock_dependencies();
+
+        init(deps.as_mut().storage, Uint128::new(10), Uint128::new(100)).unwrap();
+        assert!(init(deps.as_mut().storage, Uint128::new(10), Uint128::new(100)).is_err());
+
+        let place = |deps: DepsMut, sender: &str, side: Side, price: u128, amount: u128| place_order(
+            deps,
+            &Addr::unchecked(sender),
+            side,
+            Uint128::new(price),
+            Uint128::new(amount)
+        );
+
+        assert_eq!(
+            place(deps.as_mut(), "alice", Side::Sell, 100, 99).unwrap_err(),
+            StdError::generic_err("The amount must be at least 100.")
+        );
+
+        for i in 0..MAX_ORDERS_PER_ACCOUNT {
+            let placement = place(deps.as_mut(), "alice", Side::Sell, 100 + i as u128 % 3, 100).unwrap();
+            assert_eq!(placement.id, Some(i as u64));
+        }
+
+        assert_eq!(
+            place(deps.as_mut(), "alice", Side::Sell, 100, 100).unwrap_err(),
+            StdError::generic_err(format!("Cannot have more than {} resting orders.", MAX_ORDERS_PER_ACCOUNT))
+        );
+
+        // The rest of the order still crosses the book and is not added to it.
+        let placement = place(deps.as_mut(), "bob", Side::Buy, 110, 100 * MAX_FILLS as u128 + 50).unwrap();
+        assert_eq!(placement.id, None);
+        assert_eq!(placement.fills.len(), MAX_FILLS);
+        assert_eq!(placement.unfilled, Uint128::new(50));
+
+        // Best price first, then oldest first.
+        let filled = placement.fills.iter().map(|x| x.order).collect::<Vec<_>>();
+        assert_eq!(&filled[..3], &[0, 3, 6]);
+        assert_eq!(filled[10], 1);
+
+        let remaining = orders(deps.as_ref(), "alice").unwrap();
+        assert_eq!(remaining.len(), MAX_ORDERS_PER_ACCOUNT - MAX_FILLS);
+        assert!(remaining.iter().all(|x| x.amount == Uint128::new(100)));
+
+        // The tick that an ask at the highest prices rounds up to doesn't fit.
+        assert!(place(deps.as_mut(), "charlie", Side::Sell, u128::MAX, 100).is_err());
+
+        // Once there is room, an order can rest again.
+        place(deps.as_mut(), "alice", Side::Sell, 100, 100).unwrap();
+        assert!(place(deps.as_mut(), "bob", Side::Buy, 90, 100).unwrap().id.is_some());
+    }
+}
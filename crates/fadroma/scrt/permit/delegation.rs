@@ -0,0 +1,266 @@
+//! Letting a third party, i.e. an accountant or an auditor, view the data
+//! of an account without the account having to share its viewing key or
+//! sign a permit for them.
+//!
+//! The account grants the viewer a set of permissions, which expire at the
+//! given block time, using [`grant`]. The viewer then signs a permit with those
+//! permissions using its own key and the contract validates it using
+//! [`Permit::validate_delegated`] with the address of the account whose data
+//! is being queried. The account can revoke the grant at any time using [`revoke`].
+//!
+//! # Examples
+//!
+//! ```ignore
+//! #[query]
+//! fn balance(owner: String, permit: Permit<QueryPermission>) -> Result<Uint128, Self::Error> {
+//!     let owner = permit.validate_delegated(
+//!         deps,
+//!         &env,
+//!         &owner,
+//!         None,
+//!         &[QueryPermission::Balance]
+//!     )?;
+//!
+//!     load_balance(deps, owner)
+//! }
+//! ```
+
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
+
+use crate::{
+    self as fadroma,
+    prelude::*
+};
+
+crate::namespace!(pub ViewerGrantsNs, b"Lc8pWz3nQv");
+const GRANTS: ItemSpace<
+    StoredGrant,
+    ViewerGrantsNs,
+    TypedKey2<CanonicalAddr, CanonicalAddr>
+> = ItemSpace::new();
+
+/// The maximum number of permissions that can be granted to a viewer.
+pub const MAX_GRANTED_PERMISSIONS: usize = 16;
+
+/// The permissions that an account has granted to a viewer.
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct ViewerGrant<P: Permission> {
+    pub permissions: Vec<P>,
+    /// The block time in seconds at which the grant expires.
+    pub expires_at: u64
+}
+
+#[derive(FadromaSerialize, FadromaDeserialize, PartialEq, Debug, Clone)]
+struct StoredGrant {
+    /// JSON encoded, since permissions aren't required to implement [`FadromaSerialize`].
+    permissions: Vec<Binary>,
+    expires_at: u64
+}
+
+impl<P: Permission> Permit<P> {
+    /// Validates the permit like [`Permit::validate`] and additionally checks that its
+    /// signer has either signed it for itself or has been granted all of the `expected_permissions`
+    /// by `owner` and the grant hasn't expired. Returns the address of `owner` if successful.
+    pub fn validate_delegated(
+        &self,
+        deps: Deps,
+        env: &Env,
+        owner: &str,
+        hrp: Option<&str>,
+        expected_permissions: &[P]
+    ) -> StdResult<Addr> {
+        let signer = self.validate(
+            deps,
+            env.contract.address.as_str(),
+            hrp,
+            expected_permissions
+        )?;
+
+        let owner = deps.api.addr_validate(owner)?;
+
+        if signer == owner.as_str() {
+            return Ok(owner);
+        }
+
+        let key = (
+            &owner.as_str().canonize(deps.api)?,
+            &signer.as_str().canonize(deps.api)?
+        );
+
+        let Some(grant) = GRANTS.load(deps.storage, key)? else {
+            return Err(StdError::generic_err(format!(
+                "Account {} has not granted {} access to its data.",
+                owner,
+                signer
+            )));
+        };
+
+        if grant.expires_at <= env.block.time.seconds() {
+            return Err(StdError::generic_err(format!(
+                "The access granted to {} by account {} has expired.",
+                signer,
+                owner
+            )));
+        }
+
+        for permission in expected_permissions {
+            if !grant.permissions.contains(&to_binary(permission)?) {
+                return Err(StdError::generic_err(format!(
+                    "Account {} has not granted {} the permission {}.",
+                    owner,
+                    signer,
+                    String::from_utf8_lossy(to_binary(permission)?.as_slice())
+                )));
+            }
+        }
+
+        Ok(owner)
+    }
+}
+
+/// Grants `viewer` the given permissions to the data of `owner` until `expires_at`, in seconds.
+/// Replaces any existing grant to the same viewer.
+pub fn grant<P: Permission>(
+    deps: DepsMut,
+    env: &Env,
+    owner: &Addr,
+    viewer: &str,
+    permissions: &[P],
+    expires_at: u64
+) -> StdResult<()> {
+    if permissions.is_empty() || permissions.len() > MAX_GRANTED_PERMISSIONS {
+        return Err(StdError::generic_err(format!(
+            "Between 1 and {} permissions must be granted.",
+            MAX_GRANTED_PERMISSIONS
+        )));
+    }
+
+    if expires_at <= env.block.time.seconds() {
+        return Err(StdError::generic_err("The expiration time must be in the future."));
+    }
+
+    let owner = owner.canonize(deps.api)?;
+    let viewer = viewer.canonize(deps.api)?;
+
+    if owner == viewer {
+        return Err(StdError::generic_err("Cannot grant access to yourself."));
+    }
+
+    let permissions = permissions.iter()
+        .map(to_binary)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    GRANTS.save(deps.storage, (&owner, &viewer), &StoredGrant {
+        permissions,
+        expires_at
+    })
+}
+
+/// Revokes all permissions that `owner` has granted to `viewer`.
+pub fn revoke(deps: DepsMut, owner: &Addr, viewer: &str) -> StdResult<()> {
+    let owner = owner.canonize(deps.api)?;
+    let viewer = viewer.canonize(deps.api)?;
+
+    GRANTS.remove(deps.storage, (&owner, &viewer));
+
+    Ok(())
+}
+
+/// Returns the permissions that `owner` has granted to `viewer`, even if they have expired.
+pub fn load_grant<P: Permission + DeserializeOwned>(
+    deps: Deps,
+    owner: &str,
+    viewer: &str
+) -> StdResult<Option<ViewerGrant<P>>> {
+    let owner = owner.canonize(deps.api)?;
+    let viewer = viewer.canonize(deps.api)?;
+
+    let Some(grant) = GRANTS.load(deps.storage, (&owner, &viewer))? else {
+        return Ok(None);
+    };
+
+    let permissions = grant.permissions.iter()
+        .map(from_binary)
+        .collect::<StdResult<Vec<P>>>()?;
+
+    Ok(Some(ViewerGrant {
+        permissions,
+        expires_at: grant.expires_at
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        scrt::permit::PermitParams,
+        cosmwasm_std::testing::{mock_dependencies, mock_env}
+    };
+
+    #[derive(Serialize, Deserialize, JsonSchema, Clone, PartialEq, Debug)]
+    #[serde(rename_all = "snake_case")]
+    enum QueryPermission {
+        Balance,
+        History
+    }
+
+    #[test]
+    fn viewer_can_query_with_granted_permissions() {
+        let ref mut deps = mock_dependencies();
+        let mut env = mock_env();
+
+        let owner = Addr::unchecked("owner");
+        let contract = env.contract.address.to_string();
+        let permit = |signer: &str, permissions: &[QueryPermission]| Permit::new(
+            signer,
+            PermitParams::new(&contract).permissions(permissions.to_vec())
+        );
+
+        let balance = [QueryPermission::Balance];
+        let expires_at = env.block.time.seconds() + 100;
+
+        // The owner doesn't need a grant.
+        let result = permit("owner", &balance)
+            .validate_delegated(deps.as_ref(), &env, "owner", None, &balance);
+        assert_eq!(result.unwrap(), owner);
+
+        let auditor = permit("auditor", &balance);
+        let err = auditor.validate_delegated(deps.as_ref(), &env, "owner", None, &balance);
+        assert_eq!(
+            err.unwrap_err(),
+            StdError::generic_err("Account owner has not granted auditor access to its data.")
+        );
+
+        grant(deps.as_mut(), &env, &owner, "auditor", &balance, expires_at).unwrap();
+
+        let result = auditor.validate_delegated(deps.as_ref(), &env, "owner", None, &balance);
+        assert_eq!(result.unwrap(), owner);
+
+        // Signing a permit with more permissions than granted doesn't help.
+        let history = [QueryPermission::History];
+        let err = permit("auditor", &[QueryPermission::Balance, QueryPermission::History])
+            .validate_delegated(deps.as_ref(), &env, "owner", None, &history);
+        assert_eq!(
+            err.unwrap_err(),
+            StdError::generic_err("Account owner has not granted auditor the permission \"history\".")
+        );
+
+        assert_eq!(
+            load_grant::<QueryPermission>(deps.as_ref(), "owner", "auditor").unwrap(),
+            Some(ViewerGrant { permissions: balance.to_vec(), expires_at })
+        );
+
+        env.block.time = env.block.time.plus_seconds(100);
+        let err = auditor.validate_delegated(deps.as_ref(), &env, "owner", None, &balance);
+        assert!(err.unwrap_err().to_string().contains("has expired"));
+
+        let err = grant(deps.as_mut(), &env, &owner, "auditor", &balance, expires_at);
+        assert!(err.is_err());
+
+        grant(deps.as_mut(), &env, &owner, "auditor", &balance, expires_at + 100).unwrap();
+        revoke(deps.as_mut(), &owner, "auditor").unwrap();
+
+        assert!(auditor.validate_delegated(deps.as_ref(), &env, "owner", None, &balance).is_err());
+        assert_eq!(load_grant::<QueryPermission>(deps.as_ref(), "owner", "auditor").unwrap(), None);
+    }
+}
@@ -9,6 +9,8 @@ mod permit;
 #[cfg(not(target_arch = "wasm32"))]
 mod testing;
 
+pub mod delegation;
+
 #[cfg(target_arch = "wasm32")]
 pub use permit::*;
 #[cfg(not(target_arch = "wasm32"))]
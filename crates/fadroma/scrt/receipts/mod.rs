@@ -0,0 +1,189 @@
+//! Private receipts that only the counterparty of a transaction can decrypt.
+//! *Feature flag: `receipts`*
+//!
+//! Even though contract state is encrypted on Secret Network, the response data of a
+//! transaction can be decrypted by anyone holding the key of its sender, i.e. a relayer
+//! or another contract that executed it on behalf of the user. Accounts can register an
+//! X25519 public key and the contract can then encrypt the data meant for them using
+//! [`crate::crypto::ecies`], which can only be decrypted off-chain with the corresponding
+//! secret key.
+//!
+//! Use [`seal_response`] to encrypt the data of a response or [`encrypt_for`] to encrypt
+//! arbitrary data, i.e. to be included in an attribute or a message to another contract.
+
+use std::fmt;
+
+use crate::{
+    dsl::*,
+    prelude::*,
+    crypto::{sha_256, ecies},
+    scrt::{BLOCK_SIZE, space_pad}
+};
+
+crate::namespace!(pub ReceiptKeysNs, b"pX3kR9wQvB");
+const KEYS: ItemSpace<Binary, ReceiptKeysNs, TypedKey<CanonicalAddr>> = ItemSpace::new();
+
+crate::namespace!(pub ReceiptNonceNs, b"t8GzM2hLcY");
+const NONCE: SingleItem<u64, ReceiptNonceNs> = SingleItem::new();
+
+#[interface]
+pub trait ReceiptKeys {
+    type Error: fmt::Display;
+
+    /// Registers the X25519 public key that receipts
+    /// meant for the sender will be encrypted to.
+    #[execute]
+    fn set_receipt_key(
+        key: Binary,
+        _padding: Option<String>
+    ) -> Result<Response, Self::Error>;
+
+    #[query]
+    fn receipt_key(address: String) -> Result<Option<Binary>, Self::Error>;
+}
+
+pub struct DefaultImpl;
+
+impl ReceiptKeys for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn set_receipt_key(
+        key: Binary,
+        _padding: Option<String>
+    ) -> StdResult<Response> {
+        set_key(deps, &info.sender, key)?;
+
+        Ok(Response::new().add_attribute("action", "set_receipt_key"))
+    }
+
+    #[query]
+    fn receipt_key(address: String) -> StdResult<Option<Binary>> {
+        let address = deps.api.addr_validate(&address)?;
+
+        load_key(deps, &address)
+    }
+}
+
+/// Registers the X25519 public key of `address`. Returns `Err` if `key` is invalid.
+pub fn set_key(deps: DepsMut, address: &Addr, key: Binary) -> StdResult<()> {
+    ecies::decode_key(&key)?;
+
+    KEYS.save(deps.storage, &address.canonize(deps.api)?, &key)
+}
+
+#[inline]
+pub fn load_key(deps: Deps, address: &Addr) -> StdResult<Option<Binary>> {
+    KEYS.load(deps.storage, &address.canonize(deps.api)?)
+}
+
+/// Encrypts `data` to the key registered by `recipient`.
+///
+/// Returns `Err` if `recipient` hasn't registered a key
+/// or if `env.block.random` is not available.
+pub fn encrypt_for(
+    deps: DepsMut,
+    env: &Env,
+    recipient: &Addr,
+    data: &[u8]
+) -> StdResult<Binary> {
+    let canonical = recipient.canonize(deps.api)?;
+    let Some(key) = KEYS.load(deps.storage, &canonical)? else {
+        return Err(StdError::generic_err(format!(
+            "{} hasn't registered a receipt key.",
+            recipient
+        )));
+    };
+
+    let Some(random) = &env.block.random else {
+        return Err(StdError::generic_err("Block randomness is not available."));
+    };
+
+    // The randomness may be the same across multiple calls
+    // in a block so a nonce is also needed.
+    let nonce = NONCE.load_or_default(deps.storage)?;
+    NONCE.save(deps.storage, &(nonce + 1))?;
+
+    let ephemeral_secret = sha_256(&[
+        random.as_slice(),
+        &nonce.to_be_bytes(),
+        canonical.as_slice()
+    ].concat());
+
+    ecies::encrypt(&key, data, ephemeral_secret).map(Binary)
+}
+
+/// Encrypts the data of `response`, if any, to the key registered by `recipient`.
+/// The data is padded to a multiple of [`BLOCK_SIZE`] before encrypting it, so don't
+/// call [`crate::scrt::ResponseExt::pad`] on the response since it would corrupt it.
+pub fn seal_response(
+    deps: DepsMut,
+    env: &Env,
+    recipient: &Addr,
+    mut response: Response
+) -> StdResult<Response> {
+    if let Some(mut data) = response.data.take() {
+        space_pad(&mut data.0, BLOCK_SIZE);
+        response.data = Some(encrypt_for(deps, env, recipient, &data)?);
+    }
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+
+    const SECRET: [u8; 32] = [42u8; 32];
+
+    #[test]
+    fn only_recipient_can_decrypt() {
+        let ref mut deps = mock_dependencies();
+        let env = mock_env();
+        let alice = Addr::unchecked("alice");
+
+        let register = |deps: DepsMut, sender: &str, key: &[u8]| DefaultImpl::set_receipt_key(
+            deps,
+            mock_env(),
+            mock_info(sender, &[]),
+            Binary::from(key),
+            None
+        );
+
+        assert!(register(deps.as_mut(), "alice", &[1u8; 31]).is_err());
+
+        let err = encrypt_for(deps.as_mut(), &env, &alice, b"data").unwrap_err();
+        assert_eq!(err, StdError::generic_err("alice hasn't registered a receipt key."));
+
+        let public = ecies::public_key(&SECRET);
+        register(deps.as_mut(), "alice", &public).unwrap();
+
+        let key = DefaultImpl::receipt_key(deps.as_ref(), env.clone(), "alice".into()).unwrap();
+        assert_eq!(key, Some(Binary::from(public)));
+        assert_eq!(DefaultImpl::receipt_key(deps.as_ref(), env.clone(), "bob".into()).unwrap(), None);
+
+        let data = to_binary(&Uint128::new(100)).unwrap();
+        let response = Response::new().set_data(data.clone());
+
+        let first = seal_response(deps.as_mut(), &env, &alice, response.clone()).unwrap();
+        let second = seal_response(deps.as_mut(), &env, &alice, response).unwrap();
+
+        let first = first.data.unwrap();
+        assert_ne!(first, second.data.unwrap());
+        assert_eq!(first.len(), BLOCK_SIZE + ecies::OVERHEAD);
+
+        let decrypted = ecies::decrypt(&SECRET, &first).unwrap();
+        assert_eq!(from_slice::<Uint128>(&decrypted).unwrap(), Uint128::new(100));
+        assert!(ecies::decrypt(&[1u8; 32], &first).is_err());
+
+        let response = seal_response(deps.as_mut(), &env, &alice, Response::new()).unwrap();
+        assert_eq!(response.data, None);
+
+        let env = Env {
+            block: BlockInfo { random: None, ..env.block },
+            ..env
+        };
+        assert!(encrypt_for(deps.as_mut(), &env, &alice, &data).is_err());
+    }
+}
@@ -0,0 +1,479 @@
+//! Temporary keys that can act on behalf of an account within a limited scope.
+//! *Feature flag: `session-keys`*
+//!
+//! An account registers the secp256k1 public key of a session key together with the names of
+//! the execute messages that it may be used for, a cap on the amount that it may spend and an
+//! expiration time. A game or a dapp holding the session key can then sign messages with it
+//! which anyone, i.e. a relayer, can submit wrapped in a [`SessionMsg`]. The contract verifies
+//! them with [`SessionMsg::verify`] and dispatches the inner message as if it were sent by the
+//! account itself, without the user having to hand over their actual key.
+//!
+//! What counts towards the spend cap depends entirely on the contract, which must report it
+//! using [`Session::spend`] when handling the inner message, i.e. the amount of tokens transferred.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! #[execute]
+//! fn execute_with_session(tx: SessionMsg) -> Result<Response, Self::Error> {
+//!     let session = tx.verify(deps.branch(), &env)?;
+//!
+//!     match from_binary(&tx.msg)? {
+//!         ExecuteMsg::Transfer { recipient, amount } => {
+//!             session.spend(deps.branch(), amount)?;
+//!             transfer(deps, env, session.message_info(), recipient, amount)
+//!         },
+//!         ...
+//!     }
+//! }
+//! ```
+
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, de::{self, Visitor, MapAccess, IgnoredAny}};
+
+use crate::{
+    self as fadroma,
+    dsl::*,
+    prelude::*,
+    crypto::sha_256
+};
+
+crate::namespace!(pub SessionKeysNs, b"Qm4vT8xKpE");
+const SESSIONS: ItemSpace<
+    SessionKey,
+    SessionKeysNs,
+    TypedKey2<CanonicalAddr, String>
+> = ItemSpace::new();
+
+// Kept separately so that they survive revocation. Otherwise, messages signed
+// for a revoked key could be replayed if the key is registered again.
+crate::namespace!(pub SessionNoncesNs, b"h2YcW6nJsD");
+const NONCES: ItemSpace<
+    u64,
+    SessionNoncesNs,
+    TypedKey2<CanonicalAddr, String>
+> = ItemSpace::new();
+
+/// The names of the messages of the [`SessionKeys`] interface. Session
+/// keys can never be allowed to use those in order to prevent them from
+/// registering other session keys with a larger scope.
+pub const RESERVED_MESSAGES: [&str; 2] = ["add_session_key", "revoke_session_key"];
+
+#[interface]
+pub trait SessionKeys {
+    type Error: fmt::Display;
+
+    /// Registers the secp256k1 `pubkey` as a session key of the sender.
+    /// Registering an existing key again replaces its scope and resets the amount spent.
+    #[execute]
+    fn add_session_key(
+        pubkey: Binary,
+        scope: SessionScope,
+        _padding: Option<String>
+    ) -> Result<Response, Self::Error>;
+
+    #[execute]
+    fn revoke_session_key(
+        pubkey: Binary,
+        _padding: Option<String>
+    ) -> Result<Response, Self::Error>;
+
+    #[query]
+    fn session_key(account: String, pubkey: Binary) -> Result<Option<SessionInfo>, Self::Error>;
+}
+
+/// What a session key is allowed to do.
+#[derive(
+    Serialize, Deserialize, FadromaSerialize, FadromaDeserialize,
+    JsonSchema, PartialEq, Debug, Clone
+)]
+pub struct SessionScope {
+    /// The names of the execute messages that the key can be used for, i.e. `"move_piece"`.
+    pub messages: Vec<String>,
+    /// The maximum total amount that can be spent using the key.
+    pub spend_cap: Uint128,
+    /// The block time in seconds at which the key expires.
+    pub expires_at: u64
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct SessionInfo {
+    pub scope: SessionScope,
+    pub spent: Uint128,
+    /// The nonce that the next message signed with the key must use.
+    pub nonce: u64
+}
+
+/// An execute message signed with a session key of `account`.
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct SessionMsg {
+    pub account: String,
+    pub pubkey: Binary,
+    pub nonce: u64,
+    /// The execute message to dispatch on behalf of `account`.
+    pub msg: Binary,
+    /// The signature of the SHA-256 hash of the JSON encoded [`SessionSignDoc`].
+    pub signature: Binary
+}
+
+/// The data that is signed by the session key. Binds the message to the
+/// contract and chain so that it can't be replayed anywhere else.
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct SessionSignDoc {
+    pub chain_id: String,
+    pub contract: String,
+    pub account: String,
+    pub nonce: u64,
+    pub msg: Binary
+}
+
+/// A verified [`SessionMsg`].
+#[derive(PartialEq, Debug, Clone)]
+pub struct Session {
+    /// The account that the message is executed on behalf of.
+    pub account: Addr,
+    /// The name of the inner message.
+    pub message: String,
+    pubkey: Binary
+}
+
+#[derive(FadromaSerialize, FadromaDeserialize, PartialEq, Debug, Clone)]
+struct SessionKey {
+    scope: SessionScope,
+    spent: Uint128
+}
+
+pub struct DefaultImpl;
+
+impl SessionKeys for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn add_session_key(
+        pubkey: Binary,
+        scope: SessionScope,
+        _padding: Option<String>
+    ) -> StdResult<Response> {
+        add_key(deps, &env, &info.sender, pubkey, scope)?;
+
+        Ok(Response::new().add_attribute("action", "add_session_key"))
+    }
+
+    #[execute]
+    fn revoke_session_key(
+        pubkey: Binary,
+        _padding: Option<String>
+    ) -> StdResult<Response> {
+        let account = info.sender.canonize(deps.api)?;
+        SESSIONS.remove(deps.storage, (&account, &pubkey.to_base64()));
+
+        Ok(Response::new().add_attribute("action", "revoke_session_key"))
+    }
+
+    #[query]
+    fn session_key(account: String, pubkey: Binary) -> StdResult<Option<SessionInfo>> {
+        let account = account.as_str().canonize(deps.api)?;
+        let key = (&account, &pubkey.to_base64());
+
+        let Some(session) = SESSIONS.load(deps.storage, key)? else {
+            return Ok(None);
+        };
+
+        Ok(Some(SessionInfo {
+            scope: session.scope,
+            spent: session.spent,
+            nonce: NONCES.load_or_default(deps.storage, key)?
+        }))
+    }
+}
+
+/// Registers `pubkey` as a session key of `account` with the given `scope`.
+pub fn add_key(
+    deps: DepsMut,
+    env: &Env,
+    account: &Addr,
+    pubkey: Binary,
+    scope: SessionScope
+) -> StdResult<()> {
+    if pubkey.len() != 33 {
+        return Err(StdError::generic_err("Session keys must be compressed secp256k1 public keys."));
+    }
+
+    if scope.expires_at <= env.block.time.seconds() {
+        return Err(StdError::generic_err("The session key has already expired."));
+    }
+
+    if let Some(msg) = scope.messages.iter().find(|x| RESERVED_MESSAGES.contains(&x.as_str())) {
+        return Err(StdError::generic_err(format!(
+            "Session keys cannot be allowed to use \"{}\".",
+            msg
+        )));
+    }
+
+    let account = account.canonize(deps.api)?;
+
+    SESSIONS.save(deps.storage, (&account, &pubkey.to_base64()), &SessionKey {
+        scope,
+        spent: Uint128::zero()
+    })
+}
+
+impl SessionMsg {
+    /// Returns the data that must be signed for this message.
+    pub fn sign_doc(&self, env: &Env) -> SessionSignDoc {
+        SessionSignDoc {
+            chain_id: env.block.chain_id.clone(),
+            contract: env.contract.address.to_string(),
+            account: self.account.clone(),
+            nonce: self.nonce,
+            msg: self.msg.clone()
+        }
+    }
+
+    /// Checks that the message was signed by a session key of the account that
+    /// hasn't expired and that is allowed to use the inner message. Increments
+    /// the nonce of the key so that the message can't be executed again.
+    pub fn verify(&self, deps: DepsMut, env: &Env) -> StdResult<Session> {
+        let account = deps.api.addr_validate(&self.account)?;
+        let canonical = deps.api.addr_canonicalize(account.as_str())?;
+        let key = (&canonical, &self.pubkey.to_base64());
+
+        let session = SESSIONS.load(deps.storage, key)?
+            .ok_or_else(|| StdError::generic_err("Unknown session key."))?;
+
+        if env.block.time.seconds() >= session.scope.expires_at {
+            return Err(StdError::generic_err("The session key has expired."));
+        }
+
+        let nonce = NONCES.load_or_default(deps.storage, key)?;
+
+        if self.nonce != nonce {
+            return Err(StdError::generic_err(format!(
+                "Invalid nonce. Expected {}.",
+                nonce
+            )));
+        }
+
+        let sign_doc = to_vec(&self.sign_doc(env))?;
+        let valid = deps.api.secp256k1_verify(
+            &sha_256(&sign_doc),
+            &self.signature,
+            &self.pubkey
+        ).map_err(|err| StdError::generic_err(err.to_string()))?;
+
+        if !valid {
+            return Err(StdError::generic_err("Invalid signature."));
+        }
+
+        let message = from_slice::<MessageName>(&self.msg)?.0;
+
+        if !session.scope.messages.contains(&message) {
+            return Err(StdError::generic_err(format!(
+                "The session key is not allowed to use \"{}\".",
+                message
+            )));
+        }
+
+        NONCES.save(deps.storage, key, &(nonce + 1))?;
+
+        Ok(Session {
+            account,
+            message,
+            pubkey: self.pubkey.clone()
+        })
+    }
+}
+
+impl Session {
+    /// Adds `amount` to the amount spent using the session key.
+    /// Returns `Err` if that would exceed its spend cap.
+    pub fn spend(&self, deps: DepsMut, amount: Uint128) -> StdResult<()> {
+        let account = deps.api.addr_canonicalize(self.account.as_str())?;
+        let key = (&account, &self.pubkey.to_base64());
+
+        let mut session = SESSIONS.load_or_error(deps.storage, key)?;
+        let spent = session.spent.checked_add(amount)?;
+
+        if spent > session.scope.spend_cap {
+            return Err(StdError::generic_err(format!(
+                "The session key can only spend {} more.",
+                session.scope.spend_cap - session.spent
+            )));
+        }
+
+        session.spent = spent;
+        SESSIONS.save(deps.storage, key, &session)
+    }
+
+    /// The [`MessageInfo`] to dispatch the inner message with. No funds are
+    /// attached since any funds sent along belong to whoever submitted it.
+    #[inline]
+    pub fn message_info(&self) -> MessageInfo {
+        MessageInfo {
+            sender: self.account.clone(),
+            funds: vec![]
+        }
+    }
+}
+
+/// The name of an externally tagged enum variant, ignoring its contents.
+struct MessageName(String);
+
+impl<'de> Deserialize<'de> for MessageName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(MessageNameVisitor)
+    }
+}
+
+struct MessageNameVisitor;
+
+impl<'de> Visitor<'de> for MessageNameVisitor {
+    type Value = MessageName;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an execute message")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        Ok(MessageName(value.into()))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let Some(name) = map.next_key::<&str>()? else {
+            return Err(de::Error::custom("expected exactly one message"));
+        };
+
+        map.next_value::<IgnoredAny>()?;
+
+        if map.next_key::<IgnoredAny>()?.is_some() {
+            return Err(de::Error::custom("expected exactly one message"));
+        }
+
+        Ok(MessageName(name.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use k256::ecdsa::{SigningKey, Signature, signature::Signer};
+
+    use super::*;
+    use crate::cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    enum ExecuteMsg {
+        MovePiece { to: u8 },
+        Transfer { amount: Uint128 },
+        Withdraw { }
+    }
+
+    fn sign(key: &SigningKey, env: &Env, account: &str, nonce: u64, msg: &ExecuteMsg) -> SessionMsg {
+        let mut tx = SessionMsg {
+            account: account.into(),
+            pubkey: Binary::from(key.verifying_key().to_bytes().as_slice()),
+            nonce,
+            msg: to_binary(msg).unwrap(),
+            signature: Binary::default()
+        };
+
+        let signature: Signature = key.sign(&to_vec(&tx.sign_doc(env)).unwrap());
+        tx.signature = Binary::from(signature.as_ref());
+
+        tx
+    }
+
+    #[test]
+    fn session_key_is_scoped() {
+        let ref mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let key = SigningKey::from_bytes(&[7u8; 32]).unwrap();
+        let pubkey = Binary::from(key.verifying_key().to_bytes().as_slice());
+
+        let scope = SessionScope {
+            messages: vec!["move_piece".into(), "transfer".into()],
+            spend_cap: Uint128::new(100),
+            expires_at: env.block.time.seconds() + 60
+        };
+
+        let add = |deps: DepsMut, scope: SessionScope| DefaultImpl::add_session_key(
+            deps,
+            mock_env(),
+            mock_info("alice", &[]),
+            pubkey.clone(),
+            scope,
+            None
+        );
+
+        let reserved = SessionScope { messages: vec!["add_session_key".into()], ..scope.clone() };
+        assert!(add(deps.as_mut(), reserved).is_err());
+
+        let tx = sign(&key, &env, "alice", 0, &ExecuteMsg::MovePiece { to: 1 });
+        assert_eq!(tx.verify(deps.as_mut(), &env).unwrap_err(), StdError::generic_err("Unknown session key."));
+
+        add(deps.as_mut(), scope.clone()).unwrap();
+
+        let session = tx.verify(deps.as_mut(), &env).unwrap();
+        assert_eq!(session.account, Addr::unchecked("alice"));
+        assert_eq!(session.message, "move_piece");
+        assert_eq!(session.message_info(), mock_info("alice", &[]));
+
+        // Replay
+        assert!(tx.verify(deps.as_mut(), &env).is_err());
+
+        let tx = sign(&key, &env, "alice", 1, &ExecuteMsg::Withdraw { });
+        assert_eq!(
+            tx.verify(deps.as_mut(), &env).unwrap_err(),
+            StdError::generic_err("The session key is not allowed to use \"withdraw\".")
+        );
+
+        // Tampering with the message invalidates the signature.
+        let mut tx = sign(&key, &env, "alice", 1, &ExecuteMsg::Transfer { amount: Uint128::new(60) });
+        tx.msg = to_binary(&ExecuteMsg::Transfer { amount: Uint128::new(600) }).unwrap();
+        assert!(tx.verify(deps.as_mut(), &env).is_err());
+
+        // Signed for a different account.
+        let tx = sign(&key, &env, "bob", 0, &ExecuteMsg::MovePiece { to: 1 });
+        assert!(tx.verify(deps.as_mut(), &env).is_err());
+
+        let tx = sign(&key, &env, "alice", 1, &ExecuteMsg::Transfer { amount: Uint128::new(60) });
+        let session = tx.verify(deps.as_mut(), &env).unwrap();
+        session.spend(deps.as_mut(), Uint128::new(60)).unwrap();
+        assert_eq!(
+            session.spend(deps.as_mut(), Uint128::new(41)).unwrap_err(),
+            StdError::generic_err("The session key can only spend 40 more.")
+        );
+        session.spend(deps.as_mut(), Uint128::new(40)).unwrap();
+
+        let info = DefaultImpl::session_key(deps.as_ref(), env.clone(), "alice".into(), pubkey.clone())
+            .unwrap()
+            .unwrap();
+        assert_eq!(info, SessionInfo { scope: scope.clone(), spent: Uint128::new(100), nonce: 2 });
+
+        env.block.time = env.block.time.plus_seconds(60);
+        let tx = sign(&key, &env, "alice", 2, &ExecuteMsg::MovePiece { to: 2 });
+        assert_eq!(
+            tx.verify(deps.as_mut(), &env).unwrap_err(),
+            StdError::generic_err("The session key has expired.")
+        );
+
+        DefaultImpl::revoke_session_key(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            pubkey.clone(),
+            None
+        ).unwrap();
+
+        assert_eq!(DefaultImpl::session_key(deps.as_ref(), env.clone(), "alice".into(), pubkey.clone()).unwrap(), None);
+
+        // Messages signed before the key was revoked can't be replayed after registering it again.
+        let tx = sign(&key, &env, "alice", 0, &ExecuteMsg::MovePiece { to: 1 });
+        add(deps.as_mut(), SessionScope { expires_at: env.block.time.seconds() + 60, ..scope }).unwrap();
+        assert_eq!(
+            tx.verify(deps.as_mut(), &env).unwrap_err(),
+            StdError::generic_err("Invalid nonce. Expected 2.")
+        );
+    }
+}
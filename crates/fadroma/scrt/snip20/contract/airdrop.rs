@@ -0,0 +1,321 @@
+//! Distributing tokens to a large number of recipients, i.e. for an airdrop
+//! campaign, without an external script that sends them one by one. You
+//! **MUST** implement [admin] in your contract if you want to use this module.
+//!
+//! The admin queues up the recipients, each with its own amount and memo, using
+//! [`Airdrop::add_airdrop_recipients`] which can be called as many times as needed.
+//! Then [`Airdrop::distribute_airdrop`] either mints the tokens or transfers them from
+//! the balance of the admin to at most [`MAX_AIRDROP_BATCH`] recipients per call. It
+//! continues from where the previous call stopped, so large lists are distributed by
+//! calling it repeatedly until [`AirdropStatus::remaining`] reaches zero.
+//!
+//! Each call emits a single `airdrop` event with the number of recipients and the
+//! total amount that was distributed, instead of one per recipient.
+
+use std::fmt;
+
+use serde::{Serialize, Deserialize};
+
+use crate::{
+    self as fadroma,
+    admin::{self, Admin, Mode},
+    dsl::*,
+    prelude::*,
+    storage::iterable::IterableStorage
+};
+use super::{
+    state::{Account, TokenPermission, CONSTANTS, TOTAL_SUPPLY, MINTERS},
+    safe_math::safe_add,
+    transfer_impl, mint_impl
+};
+
+crate::namespace!(pub AirdropStateNs, b"qT7dNw2LxE");
+pub const AIRDROP_STATE: SingleItem<AirdropState, AirdropStateNs> = SingleItem::new();
+
+crate::namespace!(pub AirdropRecipientsNs, b"Vm3JrK9sHc");
+
+/// The maximum number of recipients processed by a single call to [`distribute`].
+pub const MAX_AIRDROP_BATCH: u32 = 100;
+
+/// Requires the admin component in order to check for admin.
+#[interface]
+pub trait Airdrop: Admin {
+    type Error: fmt::Display;
+
+    /// Adds recipients to the end of the current airdrop.
+    #[execute]
+    fn add_airdrop_recipients(
+        recipients: Vec<AirdropRecipient>
+    ) -> Result<Response, <Self as Airdrop>::Error>;
+
+    /// Distributes to the next `limit` recipients, or [`MAX_AIRDROP_BATCH`] if not specified.
+    #[execute]
+    fn distribute_airdrop(
+        mode: AirdropMode,
+        limit: Option<u32>
+    ) -> Result<Response, <Self as Airdrop>::Error>;
+
+    /// Discards the recipients that haven't been distributed to yet.
+    #[execute]
+    fn cancel_airdrop() -> Result<Response, <Self as Airdrop>::Error>;
+
+    #[query]
+    fn airdrop_status() -> Result<AirdropStatus, <Self as Airdrop>::Error>;
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, PartialEq, Debug)]
+pub struct AirdropRecipient {
+    pub address: String,
+    pub amount: Uint128,
+    pub memo: Option<String>
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AirdropMode {
+    /// Mints new tokens. Requires minting to be enabled and the admin to be a minter.
+    Mint,
+    /// Transfers tokens from the balance of the admin.
+    Transfer
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Debug)]
+pub struct AirdropStatus {
+    /// The number of recipients in the current airdrop.
+    pub total: u64,
+    /// The number of recipients that have already been distributed to.
+    pub distributed: u64,
+    pub remaining: u64
+}
+
+#[derive(FadromaSerialize, FadromaDeserialize, Default, Clone, Copy, PartialEq, Debug)]
+pub struct AirdropState {
+    /// Each airdrop stores its recipients in a separate list, so that
+    /// a finished or cancelled one doesn't have to be cleared.
+    pub round: u64,
+    /// The index of the next recipient to distribute to.
+    pub cursor: u64
+}
+
+#[derive(FadromaSerialize, FadromaDeserialize, Clone, PartialEq, Debug)]
+struct StoredRecipient {
+    address: CanonicalAddr,
+    amount: Uint128,
+    memo: Option<String>
+}
+
+pub struct DefaultImpl;
+
+impl Admin for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn change_admin(mode: Option<Mode>) -> Result<Response, Self::Error> {
+        admin::DefaultImpl::change_admin(deps, env, info, mode)
+    }
+
+    #[query]
+    fn admin() -> Result<Option<Addr>, Self::Error> {
+        admin::DefaultImpl::admin(deps, env)
+    }
+}
+
+impl Airdrop for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn add_airdrop_recipients(recipients: Vec<AirdropRecipient>) -> StdResult<Response> {
+        add_recipients(deps, info, recipients)?;
+
+        Ok(Response::new().add_attribute("action", "add_airdrop_recipients"))
+    }
+
+    #[execute]
+    fn distribute_airdrop(mode: AirdropMode, limit: Option<u32>) -> StdResult<Response> {
+        distribute(deps, env, info, mode, limit)
+    }
+
+    #[execute]
+    fn cancel_airdrop() -> StdResult<Response> {
+        cancel(deps, info)?;
+
+        Ok(Response::new().add_attribute("action", "cancel_airdrop"))
+    }
+
+    #[query]
+    fn airdrop_status() -> StdResult<AirdropStatus> {
+        status(deps.storage)
+    }
+}
+
+/// Adds recipients to the end of the current airdrop.
+/// Requires the admin component in order to check for admin.
+#[admin::require_admin]
+pub fn add_recipients(
+    deps: DepsMut,
+    info: MessageInfo,
+    recipients: Vec<AirdropRecipient>
+) -> StdResult<()> {
+    let state = AIRDROP_STATE.load_or_default(deps.storage)?;
+    let mut list = recipients_list(&state.round);
+
+    for recipient in recipients {
+        if recipient.amount.is_zero() {
+            return Err(StdError::generic_err(format!(
+                "The airdrop amount for {} must be greater than zero.",
+                recipient.address
+            )));
+        }
+
+        list.push(deps.storage, &StoredRecipient {
+            address: recipient.address.as_str().canonize(deps.api)?,
+            amount: recipient.amount,
+            memo: recipient.memo
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Mints or transfers the tokens of the next `limit` recipients, capped at [`MAX_AIRDROP_BATCH`],
+/// and records each one in the transaction history with its memo. Once the last recipient has
+/// been distributed to, the next call to [`add_recipients`] starts a new airdrop.
+/// Requires the admin component in order to check for admin.
+#[admin::require_admin]
+pub fn distribute(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    mode: AirdropMode,
+    limit: Option<u32>
+) -> StdResult<Response> {
+    let mut state = AIRDROP_STATE.load_or_default(deps.storage)?;
+    let list = recipients_list(&state.round);
+    let total = list.len(deps.storage)?;
+
+    if state.cursor == total {
+        return Err(StdError::generic_err("There are no airdrop recipients left."));
+    }
+
+    let limit = limit.unwrap_or(MAX_AIRDROP_BATCH).clamp(1, MAX_AIRDROP_BATCH) as u64;
+    let end = total.min(state.cursor + limit);
+
+    let constants = CONSTANTS.load_or_error(deps.storage)?;
+
+    if mode == AirdropMode::Mint {
+        if !constants.token_settings.is_set(TokenPermission::Mint) {
+            return Err(StdError::generic_err(
+                "Mint functionality is not enabled for this token.",
+            ));
+        }
+
+        let minters = MINTERS.load_humanize_or_default(deps.as_ref())?;
+        if !minters.contains(&info.sender) {
+            return Err(StdError::generic_err(
+                "Minting is allowed for minter accounts only!",
+            ));
+        }
+    }
+
+    let sender = Account::of(info.sender.canonize(deps.api)?);
+    let mut total_supply = TOTAL_SUPPLY.load_or_default(deps.storage)?;
+    let mut distributed = Uint128::zero();
+
+    for index in state.cursor..end {
+        let recipient = list.get_or_error(deps.storage, index)?;
+        let account = Account::of(recipient.address);
+
+        let amount = match mode {
+            AirdropMode::Mint => {
+                let amount = safe_add(&mut total_supply, recipient.amount);
+
+                mint_impl(
+                    deps.storage,
+                    &sender,
+                    &account,
+                    amount,
+                    constants.symbol.clone(),
+                    recipient.memo,
+                    &env.block,
+                    None
+                )?;
+
+                amount
+            },
+            AirdropMode::Transfer => {
+                transfer_impl(
+                    deps.branch(),
+                    &sender,
+                    &account,
+                    recipient.amount,
+                    recipient.memo,
+                    &env.block,
+                    None
+                )?;
+
+                recipient.amount
+            }
+        };
+
+        distributed = distributed.checked_add(amount)?;
+    }
+
+    if mode == AirdropMode::Mint {
+        TOTAL_SUPPLY.save(deps.storage, &total_supply)?;
+    }
+
+    let processed = end - state.cursor;
+
+    if end == total {
+        state.round += 1;
+        state.cursor = 0;
+    } else {
+        state.cursor = end;
+    }
+
+    AIRDROP_STATE.save(deps.storage, &state)?;
+
+    let event = Event::new("airdrop")
+        .add_attribute("mode", match mode {
+            AirdropMode::Mint => "mint",
+            AirdropMode::Transfer => "transfer"
+        })
+        .add_attribute("recipients", processed.to_string())
+        .add_attribute("amount", distributed)
+        .add_attribute("remaining", (total - end).to_string());
+
+    Ok(Response::new()
+        .add_attribute("action", "distribute_airdrop")
+        .add_event(event))
+}
+
+/// Discards the recipients of the current airdrop that haven't been distributed to yet.
+/// Requires the admin component in order to check for admin.
+#[admin::require_admin]
+pub fn cancel(deps: DepsMut, info: MessageInfo) -> StdResult<()> {
+    let mut state = AIRDROP_STATE.load_or_default(deps.storage)?;
+
+    state.round += 1;
+    state.cursor = 0;
+
+    AIRDROP_STATE.save(deps.storage, &state)
+}
+
+pub fn status(storage: &dyn Storage) -> StdResult<AirdropStatus> {
+    let state = AIRDROP_STATE.load_or_default(storage)?;
+    let total = recipients_list(&state.round).len(storage)?;
+
+    Ok(AirdropStatus {
+        total,
+        distributed: state.cursor,
+        remaining: total - state.cursor
+    })
+}
+
+#[inline]
+fn recipients_list(round: &u64) -> IterableStorage<
+    StoredRecipient,
+    TypedKey2<'_, AirdropRecipientsNs, u64>
+> {
+    IterableStorage::new(TypedKey2::from((&AirdropRecipientsNs, round)))
+}
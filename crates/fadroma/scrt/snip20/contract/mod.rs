@@ -5,6 +5,7 @@ pub mod state;
 pub mod transaction_history;
 pub mod safe_math;
 pub mod decoy;
+pub mod airdrop;
 pub(crate) mod snip20;
 mod symbol_validation;
 
@@ -120,7 +120,7 @@ pub(crate) mod default_impl {
         dsl::*,
         prelude::*,
         admin::{self, Admin, Mode},
-        killswitch::{self, Killswitch, ContractStatus},
+        killswitch::{self, Killswitch, ContractStatus, Restriction},
         scrt::{
             vk::auth::VkAuth,
             snip20::{
@@ -153,9 +153,14 @@ pub(crate) mod default_impl {
         pub fn guard(msg: &ExecuteMsg) -> Result<(), StdError> {
             let status = killswitch::STORE.load_or_default(deps.storage)?;
 
+            if !matches!(msg, ExecuteMsg::SetStatus { .. } | ExecuteMsg::SetRestriction { .. }) {
+                killswitch::assert_not_restricted(deps.as_ref(), msg.name())?;
+            }
+
             if !matches!(status, ContractStatus::Operational) {
                 match msg {
-                    ExecuteMsg::SetStatus { .. } => Ok(()),
+                    ExecuteMsg::SetStatus { .. } |
+                    ExecuteMsg::SetRestriction { .. } => Ok(()),
                     ExecuteMsg::Redeem { .. } |
                     ExecuteMsg::ChangeAdmin { .. }
                         if matches!(status, ContractStatus::Paused { .. }) => Ok(()),
@@ -1646,9 +1651,23 @@ pub(crate) mod default_impl {
 
             Ok(resp.set_data(data).pad())
         }
+
+        #[execute]
+        fn set_restriction(
+            msg: String,
+            reason: Option<String>
+        ) -> Result<Response, <Self as Killswitch>::Error> {
+            // This checks if the calling address is the current admin.
+            let resp = killswitch::DefaultImpl::set_restriction(deps, env, info, msg, reason)?;
+
+            Ok(resp.pad())
+        }
     
         #[query]
         fn status() -> Result<ContractStatus<Addr>, <Self as Killswitch>::Error> { }
+
+        #[query]
+        fn restrictions() -> Result<Vec<Restriction>, <Self as Killswitch>::Error> { }
     }
 
     #[auto_impl(admin::DefaultImpl)]
@@ -4608,3 +4608,128 @@ fn token_config() {
         [uscrt.to_string(), ucosm.to_string()]
     );
 }
+
+#[test]
+fn airdrop_is_distributed_in_batches() {
+    use super::airdrop::{self, AirdropRecipient, AirdropMode, AirdropStatus};
+
+    let (init_result, mut deps) = init_helper_with_config(
+        vec![InitialBalance {
+            address: "admin".into(),
+            amount: Uint128::new(1000),
+        }],
+        false,
+        false,
+        true,
+        false,
+        0
+    );
+    assert!(init_result.is_ok());
+
+    let recipients = ["alice", "bob", "carol"].into_iter().enumerate()
+        .map(|(i, address)| AirdropRecipient {
+            address: address.into(),
+            amount: Uint128::new(100 * (i as u128 + 1)),
+            memo: Some(format!("airdrop {}", i))
+        })
+        .collect::<Vec<_>>();
+
+    let err = airdrop::add_recipients(
+        deps.as_mut(),
+        mock_info("alice", &[]),
+        recipients.clone()
+    ).unwrap_err();
+    assert_eq!(err, StdError::generic_err("Unauthorized"));
+
+    let err = airdrop::distribute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("admin", &[]),
+        AirdropMode::Transfer,
+        None
+    ).unwrap_err();
+    assert_eq!(err, StdError::generic_err("There are no airdrop recipients left."));
+
+    airdrop::add_recipients(
+        deps.as_mut(),
+        mock_info("admin", &[]),
+        recipients[..2].to_vec()
+    ).unwrap();
+    airdrop::add_recipients(
+        deps.as_mut(),
+        mock_info("admin", &[]),
+        recipients[2..].to_vec()
+    ).unwrap();
+
+    let resp = airdrop::distribute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("admin", &[]),
+        AirdropMode::Transfer,
+        Some(2)
+    ).unwrap();
+
+    let event = &resp.events[0];
+    assert_eq!(event.ty, "airdrop");
+    assert_eq!(event.attributes[1].value, "2");
+    assert_eq!(event.attributes[2].value, "300");
+    assert_eq!(event.attributes[3].value, "1");
+
+    assert_eq!(
+        airdrop::status(&deps.storage).unwrap(),
+        AirdropStatus { total: 3, distributed: 2, remaining: 1 }
+    );
+
+    airdrop::distribute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("admin", &[]),
+        AirdropMode::Mint,
+        None
+    ).unwrap();
+
+    let balance = |address: &str| {
+        let account = Account::of(deps.api.addr_canonicalize(address).unwrap());
+
+        account.balance(&deps.storage).unwrap().u128()
+    };
+
+    assert_eq!(balance("admin"), 700);
+    assert_eq!(balance("alice"), 100);
+    assert_eq!(balance("bob"), 200);
+    assert_eq!(balance("carol"), 300);
+    assert_eq!(TOTAL_SUPPLY.load_or_default(&deps.storage).unwrap(), Uint128::new(1300));
+
+    let history = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::WithPermit {
+            permit: permit("carol", [QueryPermission::History]),
+            query: QueryWithPermit::TransactionHistory {
+                page: None,
+                page_size: 10,
+                should_filter_decoys: true
+            }
+        }
+    ).unwrap();
+
+    let QueryAnswer::TransactionHistory { txs, .. } = from_binary(&history).unwrap() else {
+        panic!("Expecting QueryAnswer::TransactionHistory");
+    };
+    assert_eq!(txs[0].memo, Some("airdrop 2".into()));
+
+    // The finished airdrop is replaced by a new one.
+    assert_eq!(
+        airdrop::status(&deps.storage).unwrap(),
+        AirdropStatus { total: 0, distributed: 0, remaining: 0 }
+    );
+
+    airdrop::add_recipients(
+        deps.as_mut(),
+        mock_info("admin", &[]),
+        recipients
+    ).unwrap();
+    airdrop::cancel(deps.as_mut(), mock_info("admin", &[])).unwrap();
+
+    assert_eq!(airdrop::status(&deps.storage).unwrap().total, 0);
+}
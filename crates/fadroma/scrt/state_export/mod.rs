@@ -0,0 +1,278 @@
+//! Export of the raw contract state in chunks, i.e. in order to migrate it to a new contract
+//! or to seed [`crate::ensemble`] with it via `ContractEnsemble::import_storage`.
+//! *Feature flag: `state-export`*
+//!
+//! Secret Network doesn't support iterating over contract storage so the keys that
+//! should be exportable must be recorded when they are written. Wrap the storage in
+//! [`ExportableStorage`] in order to do that:
+//!
+//! ```
+//! use fadroma::{
+//!     cosmwasm_std::{Storage, testing::mock_dependencies},
+//!     scrt::state_export::{ExportableStorage, export_state}
+//! };
+//!
+//! let mut deps = mock_dependencies();
+//!
+//! let mut storage = ExportableStorage::new(deps.as_mut().storage);
+//! storage.set(b"config", b"{}");
+//!
+//! let page = export_state(deps.as_ref().storage, None, 10).unwrap();
+//! assert_eq!(page.items[0].key.as_slice(), b"config");
+//! assert_eq!(page.cursor, None);
+//! ```
+//!
+//! Requires the [admin](crate::admin) component since only the admin can export the state.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    admin::{self, Admin, Mode},
+    dsl::*,
+    prelude::*,
+    scrt::permit::Permit,
+    storage::iterable::{IterableStorage, IterBudget, Page}
+};
+
+crate::namespace!(pub ExportedKeysNs, b"Wc5nR1yKtA");
+crate::namespace!(pub ExportedKeysIndexNs, b"j7HqB3xZmV");
+
+/// The maximum number of entries that [`DefaultImpl`] returns in a single chunk.
+pub const MAX_CHUNK_SIZE: u32 = 100;
+
+#[interface]
+pub trait StateExport: Admin {
+    type Error: fmt::Display;
+
+    /// Returns up to `limit` entries starting at `cursor` in the order
+    /// that their keys were first written. Requires a permit from the admin.
+    #[query]
+    fn export_state(
+        cursor: Option<u64>,
+        limit: u32,
+        permit: Permit<ExportPermission>
+    ) -> Result<Page<StateEntry>, <Self as StateExport>::Error>;
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportPermission {
+    ExportState
+}
+
+/// A raw key/value pair. Keys that have been removed since
+/// they were written are exported without a value.
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct StateEntry {
+    pub key: Binary,
+    pub value: Option<Binary>
+}
+
+/// Records the keys of all values written through it so that they can be
+/// exported with [`export_state`]. Keys are recorded only once, so the overhead
+/// of overwriting an existing value is a single additional read.
+pub struct ExportableStorage<'a> {
+    storage: &'a mut dyn Storage
+}
+
+impl<'a> ExportableStorage<'a> {
+    #[inline]
+    pub fn new(storage: &'a mut dyn Storage) -> Self {
+        Self { storage }
+    }
+}
+
+impl<'a> Storage for ExportableStorage<'a> {
+    #[inline]
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.storage.get(key)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[inline]
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        self.storage.range(start, end, order)
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        // Storing a Binary can't fail unless the state is corrupted,
+        // in which case the host would abort execution as well.
+        record_key(self.storage, key).expect("Failed to record exported key.");
+
+        self.storage.set(key, value)
+    }
+
+    #[inline]
+    fn remove(&mut self, key: &[u8]) {
+        self.storage.remove(key)
+    }
+}
+
+/// Records `key` so that it is included in the exported state.
+/// Use this for values that aren't written through an [`ExportableStorage`].
+pub fn record_key(storage: &mut dyn Storage, key: &[u8]) -> StdResult<()> {
+    let index_key = [ExportedKeysIndexNs::NAMESPACE, key].concat();
+
+    if storage.get(&index_key).is_some() {
+        return Ok(());
+    }
+
+    keys().push(storage, &Binary::from(key))?;
+    storage.set(&index_key, &[1]);
+
+    Ok(())
+}
+
+/// Returns up to `limit` of the recorded entries starting at `cursor`.
+pub fn export_state(
+    storage: &dyn Storage,
+    cursor: Option<u64>,
+    limit: u32
+) -> StdResult<Page<StateEntry>> {
+    let page = keys()
+        .iter(storage)?
+        .start_at(cursor.unwrap_or_default())
+        .read_page(limit as u64, IterBudget::new(limit as u64))?;
+
+    Ok(Page {
+        items: page.items
+            .into_iter()
+            .map(|key| StateEntry {
+                value: storage.get(&key).map(Binary),
+                key
+            })
+            .collect(),
+        cursor: page.cursor
+    })
+}
+
+#[inline]
+fn keys() -> IterableStorage<Binary, ExportedKeysNs> {
+    IterableStorage::new(ExportedKeysNs)
+}
+
+pub struct DefaultImpl;
+
+impl Admin for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn change_admin(mode: Option<Mode>) -> Result<Response, Self::Error> {
+        admin::DefaultImpl::change_admin(deps, env, info, mode)
+    }
+
+    #[query]
+    fn admin() -> Result<Option<Addr>, Self::Error> {
+        admin::DefaultImpl::admin(deps, env)
+    }
+}
+
+impl StateExport for DefaultImpl {
+    type Error = StdError;
+
+    #[query]
+    fn export_state(
+        cursor: Option<u64>,
+        limit: u32,
+        permit: Permit<ExportPermission>
+    ) -> StdResult<Page<StateEntry>> {
+        let signer = permit.validate(
+            deps,
+            env.contract.address.as_str(),
+            None,
+            &[ExportPermission::ExportState]
+        )?;
+
+        let is_admin = admin::STORE.load_humanize(deps)?.is_some_and(|x| x == signer);
+
+        if !is_admin {
+            return Err(StdError::generic_err("Unauthorized"));
+        }
+
+        export_state(deps.storage, cursor, limit.min(MAX_CHUNK_SIZE))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        scrt::permit::PermitParams,
+        cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockStorage}
+    };
+
+    crate::namespace!(NumbersNs, b"numbers");
+    const NUMBERS: ItemSpace<u64, NumbersNs, TypedKey<u64>> = ItemSpace::new();
+
+    #[test]
+    fn exports_state_in_chunks() {
+        let ref mut deps = mock_dependencies();
+        let env = mock_env();
+
+        admin::init(deps.as_mut(), None, &mock_info("admin", &[])).unwrap();
+
+        let mut storage = ExportableStorage::new(&mut deps.storage);
+
+        for i in 0..5u64 {
+            NUMBERS.save(&mut storage, &i, &(i * 10)).unwrap();
+        }
+
+        // Overwriting doesn't record the key again.
+        NUMBERS.save(&mut storage, &0, &1).unwrap();
+        NUMBERS.remove(&mut storage, &4);
+
+        let permit = |signer: &str| Permit::new(
+            signer,
+            PermitParams::new(env.contract.address.as_str())
+                .permissions([ExportPermission::ExportState])
+        );
+
+        let export = |cursor: Option<u64>, limit: u32, signer: &str| DefaultImpl::export_state(
+            deps.as_ref(),
+            env.clone(),
+            cursor,
+            limit,
+            permit(signer)
+        );
+
+        assert_eq!(export(None, 2, "user").unwrap_err(), StdError::generic_err("Unauthorized"));
+
+        let mut entries = vec![];
+        let mut cursor = None;
+
+        loop {
+            let page = export(cursor, 2, "admin").unwrap();
+            assert!(page.items.len() <= 2);
+
+            entries.extend(page.items);
+            cursor = page.cursor;
+
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        assert_eq!(entries.len(), 5);
+        assert_eq!(entries[4].value, None);
+
+        // Importing the exported state reproduces it.
+        let mut imported = MockStorage::new();
+
+        for entry in entries {
+            if let Some(value) = entry.value {
+                imported.set(&entry.key, &value);
+            }
+        }
+
+        assert_eq!(NUMBERS.load(&imported, &0).unwrap(), Some(1));
+        assert_eq!(NUMBERS.load(&imported, &3).unwrap(), Some(30));
+        assert_eq!(NUMBERS.load(&imported, &4).unwrap(), None);
+    }
+}
@@ -0,0 +1,334 @@
+//! Voting where individual votes are kept in the private state of the contract.
+//! *Feature flag: `voting`*
+//!
+//! Only the number of votes cast is public while a poll is open. The tally of each option
+//! becomes queryable after the poll has ended. Otherwise, anyone could figure out how an
+//! account voted by comparing the tally before and after its transaction. Voters can look
+//! up their own vote using a query permit.
+//!
+//! The [`DefaultImpl`] lets the admin create polls and gives each account a single vote.
+//! Use [`create_poll`] and [`cast_vote`] directly to implement other rules i.e. weighing
+//! votes by token balance.
+//!
+//! Requires the [admin](crate::admin) component.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    self as fadroma,
+    admin::{self, Admin, Mode},
+    dsl::*,
+    prelude::*,
+    scrt::{ResponseExt, permit::Permit}
+};
+
+crate::namespace!(pub PollsNs, b"rT4nVb8QeK");
+const POLLS: ItemSpace<Poll, PollsNs, TypedKey<u64>> = ItemSpace::new();
+
+crate::namespace!(pub VotesNs, b"Zy1uWm3oXc");
+const VOTES: ItemSpace<Vote, VotesNs, TypedKey2<u64, CanonicalAddr>> = ItemSpace::new();
+
+crate::namespace!(pub PollCountNs, b"c7LdPq2sHf");
+const COUNT: SingleItem<u64, PollCountNs> = SingleItem::new();
+
+#[interface]
+pub trait PrivateVoting: Admin {
+    type Error: fmt::Display;
+
+    /// Creates a poll that is open for voting until the block time
+    /// (in seconds) reaches `ends_at`. The ID of the poll is returned
+    /// as the response data.
+    #[execute]
+    fn create_poll(
+        options: Vec<String>,
+        ends_at: u64
+    ) -> Result<Response, <Self as PrivateVoting>::Error>;
+
+    /// Votes for the option at the given index. Voting again replaces the previous vote.
+    #[execute]
+    fn vote(
+        poll: u64,
+        option: u32,
+        _padding: Option<String>
+    ) -> Result<Response, <Self as PrivateVoting>::Error>;
+
+    #[query]
+    fn poll(id: u64) -> Result<PollInfo, <Self as PrivateVoting>::Error>;
+
+    #[query]
+    fn vote_receipt(
+        poll: u64,
+        permit: Permit<VotingPermission>
+    ) -> Result<Option<VoteReceipt>, <Self as PrivateVoting>::Error>;
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum VotingPermission {
+    VoteReceipt
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct PollInfo {
+    pub options: Vec<String>,
+    pub ends_at: u64,
+    /// The number of accounts that have voted.
+    pub voters: u32,
+    /// The total vote weight for each option. Only available after the poll has ended.
+    pub tally: Option<Vec<Uint128>>
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+pub struct VoteReceipt {
+    pub option: u32,
+    pub weight: Uint128
+}
+
+#[derive(FadromaSerialize, FadromaDeserialize, PartialEq, Debug, Clone)]
+struct Poll {
+    options: Vec<String>,
+    ends_at: u64,
+    voters: u32,
+    tally: Vec<Uint128>
+}
+
+#[derive(FadromaSerialize, FadromaDeserialize, PartialEq, Debug, Clone)]
+struct Vote {
+    option: u32,
+    weight: Uint128
+}
+
+pub struct DefaultImpl;
+
+impl Admin for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn change_admin(mode: Option<Mode>) -> Result<Response, Self::Error> {
+        admin::DefaultImpl::change_admin(deps, env, info, mode)
+    }
+
+    #[query]
+    fn admin() -> Result<Option<Addr>, Self::Error> {
+        admin::DefaultImpl::admin(deps, env)
+    }
+}
+
+impl PrivateVoting for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn create_poll(
+        options: Vec<String>,
+        ends_at: u64
+    ) -> StdResult<Response> {
+        admin::assert(deps.as_ref(), &info)?;
+
+        if ends_at <= env.block.time.seconds() {
+            return Err(StdError::generic_err("The poll must end in the future."));
+        }
+
+        let id = create_poll(deps.storage, options, ends_at)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "create_poll")
+            .add_attribute("poll", id.to_string())
+            .set_data(to_binary(&id)?)
+            .pad()
+        )
+    }
+
+    #[execute]
+    fn vote(
+        poll: u64,
+        option: u32,
+        _padding: Option<String>
+    ) -> StdResult<Response> {
+        cast_vote(deps, &env, poll, &info.sender, option, Uint128::one())?;
+
+        Ok(Response::new()
+            .add_attribute("action", "vote")
+            .pad()
+        )
+    }
+
+    #[query]
+    fn poll(id: u64) -> StdResult<PollInfo> {
+        let poll = POLLS.load_or_error(deps.storage, &id)?;
+        let ended = env.block.time.seconds() >= poll.ends_at;
+
+        Ok(PollInfo {
+            options: poll.options,
+            ends_at: poll.ends_at,
+            voters: poll.voters,
+            tally: ended.then_some(poll.tally)
+        })
+    }
+
+    #[query]
+    fn vote_receipt(
+        poll: u64,
+        permit: Permit<VotingPermission>
+    ) -> StdResult<Option<VoteReceipt>> {
+        let voter = permit.validate(
+            deps,
+            env.contract.address.as_str(),
+            None,
+            &[VotingPermission::VoteReceipt]
+        )?;
+
+        vote_receipt(deps, poll, &voter)
+    }
+}
+
+/// Stores a new poll and returns its ID.
+pub fn create_poll(
+    storage: &mut dyn Storage,
+    options: Vec<String>,
+    ends_at: u64
+) -> StdResult<u64> {
+    if options.len() < 2 {
+        return Err(StdError::generic_err("A poll must have at least two options."));
+    }
+
+    let id = COUNT.load_or_default(storage)?;
+    COUNT.save(storage, &(id + 1))?;
+
+    POLLS.save(storage, &id, &Poll {
+        tally: vec![Uint128::zero(); options.len()],
+        options,
+        ends_at,
+        voters: 0
+    })?;
+
+    Ok(id)
+}
+
+/// Records the vote of `voter` with the given `weight`, replacing any previous vote of theirs.
+pub fn cast_vote(
+    deps: DepsMut,
+    env: &Env,
+    poll_id: u64,
+    voter: &Addr,
+    option: u32,
+    weight: Uint128
+) -> StdResult<()> {
+    let mut poll = POLLS.load_or_error(deps.storage, &poll_id)?;
+
+    if env.block.time.seconds() >= poll.ends_at {
+        return Err(StdError::generic_err("The poll has ended."));
+    }
+
+    if option as usize >= poll.options.len() {
+        return Err(StdError::generic_err("Invalid option."));
+    }
+
+    let voter = voter.canonize(deps.api)?;
+    let key = (&poll_id, &voter);
+
+    match VOTES.load(deps.storage, key)? {
+        Some(previous) => {
+            let tally = &mut poll.tally[previous.option as usize];
+            *tally = tally.checked_sub(previous.weight)?;
+        },
+        None => poll.voters += 1
+    }
+
+    let tally = &mut poll.tally[option as usize];
+    *tally = tally.checked_add(weight)?;
+
+    VOTES.save(deps.storage, key, &Vote { option, weight })?;
+    POLLS.save(deps.storage, &poll_id, &poll)
+}
+
+/// Returns the vote of `voter` if they have voted. The caller must authenticate them.
+pub fn vote_receipt(deps: Deps, poll_id: u64, voter: &str) -> StdResult<Option<VoteReceipt>> {
+    let voter = voter.canonize(deps.api)?;
+    let vote = VOTES.load(deps.storage, (&poll_id, &voter))?;
+
+    Ok(vote.map(|x| VoteReceipt {
+        option: x.option,
+        weight: x.weight
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        scrt::permit::PermitParams,
+        cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info}
+    };
+
+    #[test]
+    fn tally_is_hidden_until_poll_ends() {
+        let ref mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let ends_at = env.block.time.seconds() + 100;
+
+        admin::init(deps.as_mut(), None, &mock_info("admin", &[])).unwrap();
+
+        let options = vec!["yes".to_string(), "no".to_string()];
+        let err = DefaultImpl::create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("user", &[]),
+            options.clone(),
+            ends_at
+        ).unwrap_err();
+        assert_eq!(err, StdError::generic_err("Unauthorized"));
+
+        DefaultImpl::create_poll(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            options,
+            ends_at
+        ).unwrap();
+
+        for (voter, option) in [("alice", 0), ("bob", 1), ("charlie", 1), ("alice", 1)] {
+            DefaultImpl::vote(deps.as_mut(), env.clone(), mock_info(voter, &[]), 0, option, None).unwrap();
+        }
+
+        assert!(DefaultImpl::vote(deps.as_mut(), env.clone(), mock_info("dave", &[]), 0, 2, None).is_err());
+
+        let info = DefaultImpl::poll(deps.as_ref(), env.clone(), 0).unwrap();
+        assert_eq!(info.voters, 3);
+        assert_eq!(info.tally, None);
+
+        let permit = |signer: &str, permissions: Vec<VotingPermission>| Permit::new(
+            signer,
+            PermitParams::new(env.contract.address.as_str()).permissions(permissions)
+        );
+
+        let receipt = DefaultImpl::vote_receipt(
+            deps.as_ref(),
+            env.clone(),
+            0,
+            permit("alice", vec![VotingPermission::VoteReceipt])
+        ).unwrap();
+        assert_eq!(receipt, Some(VoteReceipt { option: 1, weight: Uint128::one() }));
+
+        assert!(DefaultImpl::vote_receipt(deps.as_ref(), env.clone(), 0, permit("alice", vec![])).is_err());
+
+        let receipt = DefaultImpl::vote_receipt(
+            deps.as_ref(),
+            env.clone(),
+            0,
+            permit("dave", vec![VotingPermission::VoteReceipt])
+        ).unwrap();
+        assert_eq!(receipt, None);
+
+        env.block.time = env.block.time.plus_seconds(100);
+
+        let info = DefaultImpl::poll(deps.as_ref(), env.clone(), 0).unwrap();
+        assert_eq!(info.tally, Some(vec![Uint128::zero(), Uint128::new(3)]));
+
+        assert_eq!(
+            DefaultImpl::vote(deps.as_mut(), env, mock_info("dave", &[]), 0, 0, None).unwrap_err(),
+            StdError::generic_err("The poll has ended.")
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use std::marker::PhantomData;
+
+use crate::{
+    bin_serde::{FadromaSerialize, FadromaDeserialize},
+    cosmwasm_std::{Storage, StdResult}
+};
+use super::{Namespace, SingleItem, not_found_error};
+
+/// Caches the value of a [`SingleItem`] so that it is only deserialized once,
+/// no matter how many times it is accessed while handling a message. Changes
+/// are kept in memory and are only written to storage by [`CachedItem::flush`],
+/// which should be called once at the end of the handler. Any changes made
+/// after the last call to it are lost.
+///
+/// # Examples
+///
+/// ```
+/// # use fadroma::{cosmwasm_std::{StdResult, testing::mock_dependencies}, storage::SingleItem};
+/// # fn main() -> StdResult<()> {
+/// # let mut deps = mock_dependencies();
+/// # let storage = deps.as_mut().storage;
+/// fadroma::namespace!(ConfigNs, b"config");
+/// const CONFIG: SingleItem::<u64, ConfigNs> = SingleItem::new();
+///
+/// CONFIG.save(storage, &1)?;
+///
+/// let mut config = CONFIG.cached();
+///
+/// assert_eq!(config.get(storage)?, Some(&1));
+/// *config.get_mut(storage)?.unwrap() += 1;
+///
+/// // Not written yet.
+/// assert_eq!(CONFIG.load(storage)?, Some(1));
+///
+/// config.flush(storage)?;
+/// assert_eq!(CONFIG.load(storage)?, Some(2));
+/// # Ok(())
+/// # }
+/// ```
+pub struct CachedItem<T: FadromaSerialize + FadromaDeserialize, N: Namespace> {
+    // None if not loaded yet.
+    value: Option<Option<T>>,
+    dirty: bool,
+    namespace_data: PhantomData<N>
+}
+
+impl<T: FadromaSerialize + FadromaDeserialize, N: Namespace> SingleItem<T, N> {
+    /// Returns a [`CachedItem`] that loads the item on first access.
+    #[inline]
+    pub fn cached(&self) -> CachedItem<T, N> {
+        CachedItem::new()
+    }
+}
+
+impl<T: FadromaSerialize + FadromaDeserialize, N: Namespace> CachedItem<T, N> {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            value: None,
+            dirty: false,
+            namespace_data: PhantomData
+        }
+    }
+
+    /// Loads the item from storage only if it hasn't been already.
+    pub fn get(&mut self, storage: &dyn Storage) -> StdResult<Option<&T>> {
+        Ok(self.load(storage)?.as_ref())
+    }
+
+    #[inline]
+    pub fn get_or_error(&mut self, storage: &dyn Storage) -> StdResult<&T> {
+        self.get(storage)?.ok_or_else(|| not_found_error::<T>())
+    }
+
+    /// Same as [`CachedItem::get`] but marks the item as
+    /// changed so that it is written by [`CachedItem::flush`].
+    pub fn get_mut(&mut self, storage: &dyn Storage) -> StdResult<Option<&mut T>> {
+        self.load(storage)?;
+        self.dirty = true;
+
+        Ok(self.value.as_mut().and_then(|x| x.as_mut()))
+    }
+
+    /// Replaces the item without loading it first.
+    #[inline]
+    pub fn set(&mut self, item: T) {
+        self.value = Some(Some(item));
+        self.dirty = true;
+    }
+
+    /// Returns `true` if the item has been changed since it was last flushed.
+    #[inline]
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Writes the item to storage if it has been changed.
+    pub fn flush(&mut self, storage: &mut dyn Storage) -> StdResult<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        if let Some(Some(item)) = &self.value {
+            super::save(storage, N::NAMESPACE, item)?;
+        }
+
+        self.dirty = false;
+
+        Ok(())
+    }
+
+    fn load(&mut self, storage: &dyn Storage) -> StdResult<&Option<T>> {
+        if self.value.is_none() {
+            self.value = Some(super::load(storage, N::NAMESPACE)?);
+        }
+
+        Ok(self.value.as_ref().unwrap())
+    }
+}
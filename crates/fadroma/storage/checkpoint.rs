@@ -0,0 +1,190 @@
+use serde::{Serialize, Deserialize};
+
+use crate::{
+    self as fadroma,
+    bin_serde::{FadromaSerialize, FadromaDeserialize},
+    cosmwasm_std::{Storage, StdResult, StdError},
+    schemars::{self, JsonSchema}
+};
+use super::{Namespace, SingleItem};
+
+/// Persists the progress of a job that processes a large number of items, i.e.
+/// distributing rewards to all accounts, so that it can be split across multiple
+/// transactions, each of which processes a bounded number of items and never exceeds
+/// the block gas limit. Items are identified by their index, i.e. in an
+/// [`IterableStorage`](super::iterable::IterableStorage), and processed in order.
+///
+/// The number of items is fixed when the job is started. Items added while it is
+/// in progress are not processed by it.
+///
+/// # Examples
+///
+/// ```
+/// # use fadroma::{
+/// #     cosmwasm_std::{StdResult, testing::mock_dependencies},
+/// #     storage::{CompositeKey, iterable::IterableStorage, checkpoint::Checkpoint}
+/// # };
+/// # fn main() -> StdResult<()> {
+/// # let mut deps = mock_dependencies();
+/// # let storage = deps.as_mut().storage;
+/// fadroma::namespace!(DistributionNs, b"distribution");
+/// const DISTRIBUTION: Checkpoint<DistributionNs> = Checkpoint::new();
+///
+/// let mut accounts = IterableStorage::<u64, _>::new(CompositeKey::new(&[b"accounts"]));
+/// # for i in 0..5 { accounts.push(storage, &i)?; }
+///
+/// let total = accounts.len(storage)?;
+/// DISTRIBUTION.start(storage, total)?;
+///
+/// let mut distributed = vec![];
+/// let progress = DISTRIBUTION.process(storage, 3, |storage, index| {
+///     distributed.push(accounts.get_or_error(storage, index)?);
+///
+///     Ok(())
+/// })?;
+///
+/// assert_eq!(distributed, vec![0, 1, 2]);
+/// assert_eq!(progress.processed, 3);
+/// assert!(!progress.is_complete());
+///
+/// // Continues from where the previous batch ended.
+/// let progress = DISTRIBUTION.process(storage, 3, |storage, index| {
+///     distributed.push(accounts.get_or_error(storage, index)?);
+///
+///     Ok(())
+/// })?;
+///
+/// assert_eq!(distributed, vec![0, 1, 2, 3, 4]);
+/// assert!(progress.is_complete());
+/// # Ok(())
+/// # }
+/// ```
+pub struct Checkpoint<N: Namespace> {
+    store: SingleItem<Progress, N>
+}
+
+/// The progress of a job tracked by a [`Checkpoint`].
+#[derive(
+    Serialize, Deserialize, FadromaSerialize, FadromaDeserialize,
+    JsonSchema, Clone, Copy, PartialEq, Debug
+)]
+pub struct Progress {
+    /// The number of items processed so far. This is also
+    /// the index of the next item that will be processed.
+    pub processed: u64,
+    pub total: u64
+}
+
+impl<N: Namespace> Checkpoint<N> {
+    #[inline]
+    pub const fn new() -> Self {
+        Self { store: SingleItem::new() }
+    }
+
+    /// Starts a new job over `total` items. Returns `Err` if
+    /// the previous job hasn't processed all of its items yet.
+    pub fn start(&self, storage: &mut dyn Storage, total: u64) -> StdResult<()> {
+        if self.progress(storage)?.is_some_and(|x| !x.is_complete()) {
+            return Err(StdError::generic_err("The previous job is still in progress."));
+        }
+
+        self.store.save(storage, &Progress { processed: 0, total })
+    }
+
+    /// Calls `process` with the index of each of the next
+    /// `batch_size` items at most and saves the progress.
+    /// Nothing is saved if `process` returns an error.
+    ///
+    /// Returns `Err` if no job was started.
+    pub fn process<F>(
+        &self,
+        storage: &mut dyn Storage,
+        batch_size: u32,
+        mut process: F
+    ) -> StdResult<Progress>
+        where F: FnMut(&mut dyn Storage, u64) -> StdResult<()>
+    {
+        let mut progress = self.progress(storage)?
+            .ok_or_else(|| StdError::generic_err("No job has been started."))?;
+
+        let end = progress.total.min(progress.processed.saturating_add(batch_size as u64));
+
+        for index in progress.processed..end {
+            process(storage, index)?;
+        }
+
+        progress.processed = end;
+        self.store.save(storage, &progress)?;
+
+        Ok(progress)
+    }
+
+    /// Returns the progress of the current or last job, if any.
+    #[inline]
+    pub fn progress(&self, storage: &dyn Storage) -> StdResult<Option<Progress>> {
+        self.store.load(storage)
+    }
+}
+
+impl Progress {
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.processed >= self.total
+    }
+
+    #[inline]
+    pub fn remaining(&self) -> u64 {
+        self.total.saturating_sub(self.processed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cosmwasm_std::testing::MockStorage;
+
+    crate::namespace!(JobNs, b"job");
+    const JOB: Checkpoint<JobNs> = Checkpoint::new();
+
+    #[test]
+    fn resumes_from_checkpoint() {
+        let ref mut storage = MockStorage::new();
+        let mut processed = vec![];
+
+        let err = JOB.process(storage, 2, |_, _| Ok(())).unwrap_err();
+        assert_eq!(err, StdError::generic_err("No job has been started."));
+
+        JOB.start(storage, 3).unwrap();
+        assert_eq!(JOB.progress(storage).unwrap(), Some(Progress { processed: 0, total: 3 }));
+
+        // A failed batch is retried from the same position.
+        let err = JOB.process(storage, 2, |_, index| if index == 1 {
+            Err(StdError::generic_err("Failed"))
+        } else {
+            Ok(())
+        });
+        assert!(err.is_err());
+        assert_eq!(JOB.progress(storage).unwrap().unwrap().processed, 0);
+
+        let progress = JOB.process(storage, 2, |_, index| {
+            processed.push(index);
+            Ok(())
+        }).unwrap();
+        assert_eq!(progress.remaining(), 1);
+
+        assert!(JOB.start(storage, 10).is_err());
+
+        JOB.process(storage, 2, |_, index| {
+            processed.push(index);
+            Ok(())
+        }).unwrap();
+
+        // Nothing left to process.
+        let progress = JOB.process(storage, 2, |_, _| unreachable!()).unwrap();
+        assert!(progress.is_complete());
+        assert_eq!(processed, vec![0, 1, 2]);
+
+        JOB.start(storage, 10).unwrap();
+        assert_eq!(JOB.progress(storage).unwrap(), Some(Progress { processed: 0, total: 10 }));
+    }
+}
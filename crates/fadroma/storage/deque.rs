@@ -0,0 +1,253 @@
+//! A double-ended queue stored under a [`Namespace`].
+
+use std::marker::PhantomData;
+
+use crate::{
+    self as fadroma,
+    bin_serde::{FadromaSerialize, FadromaDeserialize},
+    cosmwasm_std::{Storage, StdResult}
+};
+use super::Namespace;
+
+const META_KEY: &[u8] = b"meta";
+
+/// Storage type that can efficiently add and remove items on both ends, i.e.
+/// for FIFO queues of pending withdrawals or orders that are processed in batches.
+/// Each operation reads or writes a constant number of keys regardless of its length.
+///
+/// # Examples
+///
+/// ```
+/// # use fadroma::{cosmwasm_std::{StdResult, testing::mock_dependencies}, storage::deque::Deque};
+/// # fn main() -> StdResult<()> {
+/// # let mut deps = mock_dependencies();
+/// # let storage = deps.as_mut().storage;
+/// fadroma::namespace!(WithdrawalsNs, b"withdrawals");
+/// const WITHDRAWALS: Deque<u64, WithdrawalsNs> = Deque::new();
+///
+/// WITHDRAWALS.push_back(storage, &1)?;
+/// WITHDRAWALS.push_back(storage, &2)?;
+/// WITHDRAWALS.push_front(storage, &0)?;
+///
+/// assert_eq!(WITHDRAWALS.len(storage)?, 3);
+/// assert_eq!(WITHDRAWALS.peek_front(storage)?, Some(0));
+///
+/// assert_eq!(WITHDRAWALS.pop_front(storage)?, Some(0));
+/// assert_eq!(WITHDRAWALS.pop_back(storage)?, Some(2));
+/// assert_eq!(WITHDRAWALS.pop_front(storage)?, Some(1));
+/// assert_eq!(WITHDRAWALS.pop_front(storage)?, None);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Deque<T: FadromaSerialize + FadromaDeserialize, N: Namespace> {
+    namespace_data: PhantomData<N>,
+    item_data: PhantomData<T>
+}
+
+#[derive(FadromaSerialize, FadromaDeserialize, Default, Clone, Copy)]
+struct Meta {
+    /// The position of the first item. Wraps around
+    /// so that items can be pushed to the front.
+    head: u64,
+    len: u64
+}
+
+impl<T: FadromaSerialize + FadromaDeserialize, N: Namespace> Deque<T, N> {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            namespace_data: PhantomData,
+            item_data: PhantomData
+        }
+    }
+
+    #[inline]
+    pub fn len(&self, storage: &dyn Storage) -> StdResult<u64> {
+        Ok(Self::meta(storage)?.len)
+    }
+
+    #[inline]
+    pub fn is_empty(&self, storage: &dyn Storage) -> StdResult<bool> {
+        Ok(self.len(storage)? == 0)
+    }
+
+    pub fn push_back(&self, storage: &mut dyn Storage, item: &T) -> StdResult<()> {
+        let mut meta = Self::meta(storage)?;
+        super::save(storage, Self::key(meta.head.wrapping_add(meta.len)), item)?;
+
+        meta.len += 1;
+
+        Self::save_meta(storage, meta)
+    }
+
+    pub fn push_front(&self, storage: &mut dyn Storage, item: &T) -> StdResult<()> {
+        let mut meta = Self::meta(storage)?;
+        meta.head = meta.head.wrapping_sub(1);
+        meta.len += 1;
+
+        super::save(storage, Self::key(meta.head), item)?;
+
+        Self::save_meta(storage, meta)
+    }
+
+    pub fn pop_front(&self, storage: &mut dyn Storage) -> StdResult<Option<T>> {
+        let mut meta = Self::meta(storage)?;
+
+        if meta.len == 0 {
+            return Ok(None);
+        }
+
+        let key = Self::key(meta.head);
+        let item = super::load(storage, &key)?;
+        super::remove(storage, &key);
+
+        meta.head = meta.head.wrapping_add(1);
+        meta.len -= 1;
+        Self::save_meta(storage, meta)?;
+
+        Ok(item)
+    }
+
+    pub fn pop_back(&self, storage: &mut dyn Storage) -> StdResult<Option<T>> {
+        let mut meta = Self::meta(storage)?;
+
+        if meta.len == 0 {
+            return Ok(None);
+        }
+
+        meta.len -= 1;
+
+        let key = Self::key(meta.head.wrapping_add(meta.len));
+        let item = super::load(storage, &key)?;
+        super::remove(storage, &key);
+
+        Self::save_meta(storage, meta)?;
+
+        Ok(item)
+    }
+
+    /// Removes the items at the back of the queue, at most `limit` of them or all
+    /// if [`None`], without reading them. Returns the number of items left, so long
+    /// queues can be cleared over multiple transactions by calling it until it returns zero.
+    pub fn clear(&self, storage: &mut dyn Storage, limit: Option<u64>) -> StdResult<u64> {
+        let mut meta = Self::meta(storage)?;
+        let new_len = meta.len.saturating_sub(limit.unwrap_or(meta.len));
+
+        for index in new_len..meta.len {
+            super::remove(storage, Self::key(meta.head.wrapping_add(index)));
+        }
+
+        if new_len == 0 {
+            super::remove(storage, [N::NAMESPACE, META_KEY].concat());
+        } else {
+            meta.len = new_len;
+            Self::save_meta(storage, meta)?;
+        }
+
+        Ok(new_len)
+    }
+
+    #[inline]
+    pub fn peek_front(&self, storage: &dyn Storage) -> StdResult<Option<T>> {
+        self.get(storage, 0)
+    }
+
+    #[inline]
+    pub fn peek_back(&self, storage: &dyn Storage) -> StdResult<Option<T>> {
+        let len = self.len(storage)?;
+
+        if len == 0 {
+            return Ok(None);
+        }
+
+        self.get(storage, len - 1)
+    }
+
+    /// Returns the item at `index`, counting from the front.
+    pub fn get(&self, storage: &dyn Storage, index: u64) -> StdResult<Option<T>> {
+        let meta = Self::meta(storage)?;
+
+        if index >= meta.len {
+            return Ok(None);
+        }
+
+        super::load(storage, Self::key(meta.head.wrapping_add(index)))
+    }
+
+    #[inline]
+    fn meta(storage: &dyn Storage) -> StdResult<Meta> {
+        let meta = super::load(storage, [N::NAMESPACE, META_KEY].concat())?;
+
+        Ok(meta.unwrap_or_default())
+    }
+
+    #[inline]
+    fn save_meta(storage: &mut dyn Storage, meta: Meta) -> StdResult<()> {
+        super::save(storage, [N::NAMESPACE, META_KEY].concat(), &meta)
+    }
+
+    #[inline]
+    fn key(position: u64) -> Vec<u8> {
+        [N::NAMESPACE, &position.to_be_bytes()].concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cosmwasm_std::testing::MockStorage;
+
+    crate::namespace!(QueueNs, b"queue");
+    const QUEUE: Deque<u32, QueueNs> = Deque::new();
+
+    #[test]
+    fn deque_wraps_around() {
+        let ref mut storage = MockStorage::new();
+
+        assert!(QUEUE.is_empty(storage).unwrap());
+        assert_eq!(QUEUE.pop_back(storage).unwrap(), None);
+        assert_eq!(QUEUE.peek_back(storage).unwrap(), None);
+
+        // The head wraps around to u64::MAX.
+        QUEUE.push_front(storage, &2).unwrap();
+        QUEUE.push_front(storage, &1).unwrap();
+        QUEUE.push_back(storage, &3).unwrap();
+
+        assert_eq!(QUEUE.len(storage).unwrap(), 3);
+        assert_eq!(
+            (0..4).map(|i| QUEUE.get(storage, i).unwrap()).collect::<Vec<_>>(),
+            vec![Some(1), Some(2), Some(3), None]
+        );
+        assert_eq!(QUEUE.peek_back(storage).unwrap(), Some(3));
+
+        assert_eq!(QUEUE.pop_front(storage).unwrap(), Some(1));
+        assert_eq!(QUEUE.pop_front(storage).unwrap(), Some(2));
+        assert_eq!(QUEUE.pop_back(storage).unwrap(), Some(3));
+        assert!(QUEUE.is_empty(storage).unwrap());
+
+        // Only the metadata is left.
+        assert_eq!(storage.range(None, None, crate::cosmwasm_std::Order::Ascending).count(), 1);
+    }
+
+    #[test]
+    fn deque_clear() {
+        let ref mut storage = MockStorage::new();
+
+        QUEUE.push_front(storage, &1).unwrap();
+
+        for i in 2..6 {
+            QUEUE.push_back(storage, &i).unwrap();
+        }
+
+        assert_eq!(QUEUE.clear(storage, Some(3)).unwrap(), 2);
+        assert_eq!(QUEUE.peek_back(storage).unwrap(), Some(2));
+
+        QUEUE.push_back(storage, &3).unwrap();
+        assert_eq!(QUEUE.get(storage, 2).unwrap(), Some(3));
+
+        assert_eq!(QUEUE.clear(storage, None).unwrap(), 0);
+        assert!(QUEUE.is_empty(storage).unwrap());
+
+        assert_eq!(storage.range(None, None, crate::cosmwasm_std::Order::Ascending).count(), 0);
+    }
+}
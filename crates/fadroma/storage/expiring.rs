@@ -0,0 +1,380 @@
+//! Storage entries that expire at a given block height or time.
+
+use std::marker::PhantomData;
+
+use serde::{Serialize, Deserialize};
+
+use crate::{
+    self as fadroma,
+    bin_serde::{
+        self, FadromaSerialize, FadromaDeserialize,
+        FadromaSerializeExt, Serializer, Deserializer
+    },
+    cosmwasm_std::{Storage, BlockInfo, Binary, StdResult, StdError},
+    schemars::{self, JsonSchema}
+};
+use super::{
+    Namespace, Key, CompositeKey,
+    iterable::IterableStorage,
+    key_not_found_error
+};
+
+const VALUE_NS: &[u8] = b"val";
+const TRACKED_NS: &[u8] = b"tracked";
+const KEYS_NS: &[u8] = b"keys";
+const CURSOR_KEY: &[u8] = b"cursor";
+
+/// When an entry of [`ExpiringItemSpace`] expires.
+#[derive(
+    Serialize, Deserialize, FadromaSerialize, FadromaDeserialize,
+    JsonSchema, Clone, Copy, PartialEq, Debug
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Expiry {
+    /// Expires once the block height reaches the given one.
+    AtHeight(u64),
+    /// Expires once the block time reaches the given one, in seconds.
+    AtTime(u64),
+    Never
+}
+
+impl Expiry {
+    #[inline]
+    pub fn is_expired(&self, block: &BlockInfo) -> bool {
+        match self {
+            Self::AtHeight(height) => block.height >= *height,
+            Self::AtTime(time) => block.time.seconds() >= *time,
+            Self::Never => false
+        }
+    }
+}
+
+/// Storage type like [`super::ItemSpace`] whose items expire, i.e. for viewing keys,
+/// pending offers or one-time codes. Once an item has expired, it is no longer returned
+/// even though it stays in storage until it's either overwritten, removed or reclaimed
+/// by [`ExpiringItemSpace::sweep`].
+///
+/// Every key that has been saved is tracked so that it can be swept later. This costs
+/// an additional read when saving an item and a few additional writes the first time
+/// an item is saved under a given key.
+///
+/// The following namespaces are reserved by `ExpiringItemSpace`:
+///  * N + "val" + K
+///  * N + "tracked" + K
+///  * N + "keys" + n - where n is a number
+///  * N + "cursor"
+///
+/// # Examples
+///
+/// ```
+/// # use fadroma::{
+/// #     cosmwasm_std::{StdResult, testing::{mock_dependencies, mock_env}},
+/// #     storage::{TypedKey, expiring::{ExpiringItemSpace, Expiry}}
+/// # };
+/// # fn main() -> StdResult<()> {
+/// # let mut deps = mock_dependencies();
+/// # let storage = deps.as_mut().storage;
+/// let mut env = mock_env();
+///
+/// fadroma::namespace!(CodesNs, b"codes");
+/// const CODES: ExpiringItemSpace<u32, CodesNs, TypedKey<String>> = ExpiringItemSpace::new();
+///
+/// let alice = "alice".to_string();
+/// let expiry = Expiry::AtHeight(env.block.height + 10);
+/// CODES.save(storage, &alice, &1234, expiry)?;
+///
+/// assert_eq!(CODES.load(storage, &env.block, &alice)?, Some(1234));
+///
+/// env.block.height += 10;
+/// assert_eq!(CODES.load(storage, &env.block, &alice)?, None);
+///
+/// // Reclaim the storage used by expired codes.
+/// assert_eq!(CODES.sweep(storage, &env.block, 50)?, 1);
+/// # Ok(())
+/// # }
+/// ```
+pub struct ExpiringItemSpace<T: FadromaSerialize + FadromaDeserialize, N: Namespace, K: Key> {
+    namespace_data: PhantomData<N>,
+    item_data: PhantomData<T>,
+    key_data: PhantomData<K>
+}
+
+/// The expiry is stored before the item so
+/// that sweeping doesn't need to read the latter.
+struct EntryRef<'a, T> {
+    expiry: Expiry,
+    item: &'a T
+}
+
+impl<T: FadromaSerialize + FadromaDeserialize, N: Namespace, K: Key> ExpiringItemSpace<T, N, K> {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            namespace_data: PhantomData,
+            item_data: PhantomData,
+            key_data: PhantomData
+        }
+    }
+
+    /// Saves `item` under `key` until `expiry`, replacing any existing item and its expiry.
+    pub fn save(
+        &self,
+        storage: &mut dyn Storage,
+        key: impl Into<K>,
+        item: &T,
+        expiry: Expiry
+    ) -> StdResult<()> {
+        let key = Self::item_key(key);
+
+        let entry = EntryRef { expiry, item }
+            .serialize()
+            .map_err(|e| StdError::serialize_err(std::any::type_name::<T>(), e))?;
+
+        storage.set(&Self::value_key(&key), &entry);
+
+        let tracked_key = Self::tracked_key(&key);
+
+        if storage.get(&tracked_key).is_none() {
+            let segments = [N::NAMESPACE, KEYS_NS];
+            let mut keys = IterableStorage::<Binary, _>::new(CompositeKey::new(&segments));
+
+            storage.set(&tracked_key, &[1]);
+            keys.push(storage, &Binary(key))?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the item stored under `key` or [`None`] if there isn't one or it has expired.
+    pub fn load(
+        &self,
+        storage: &dyn Storage,
+        block: &BlockInfo,
+        key: impl Into<K>
+    ) -> StdResult<Option<T>> {
+        Self::load_impl(storage, block, &Self::value_key(&Self::item_key(key)))
+    }
+
+    /// Same as [`ExpiringItemSpace::load`] but returns `Err` if
+    /// there is no item under `key` or it has expired.
+    pub fn load_or_error(
+        &self,
+        storage: &dyn Storage,
+        block: &BlockInfo,
+        key: impl Into<K>
+    ) -> StdResult<T> {
+        let key = Self::value_key(&Self::item_key(key));
+        let result = Self::load_impl(storage, block, &key)?;
+
+        result.ok_or_else(|| key_not_found_error::<T>(&key))
+    }
+
+    /// Returns the expiry of the item stored under `key`, even if it has already expired.
+    pub fn expiry(&self, storage: &dyn Storage, key: impl Into<K>) -> StdResult<Option<Expiry>> {
+        let Some(bytes) = storage.get(&Self::value_key(&Self::item_key(key))) else {
+            return Ok(None);
+        };
+
+        let expiry = Deserializer::from(&bytes)
+            .deserialize()
+            .map_err(Self::parse_err)?;
+
+        Ok(Some(expiry))
+    }
+
+    /// Removes the item stored under `key`. Its key is
+    /// no longer tracked after the next [`ExpiringItemSpace::sweep`].
+    #[inline]
+    pub fn remove(&self, storage: &mut dyn Storage, key: impl Into<K>) {
+        storage.remove(&Self::value_key(&Self::item_key(key)));
+    }
+
+    /// Checks at most `limit` of the tracked keys, continuing from where the previous call
+    /// stopped, and removes the items that have expired. Returns the number of items removed.
+    /// Call it from a handler that is executed often enough or from a dedicated one in order
+    /// to reclaim the storage used by expired items over time.
+    pub fn sweep(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        limit: u32
+    ) -> StdResult<u32> {
+        let segments = [N::NAMESPACE, KEYS_NS];
+        let mut keys = IterableStorage::<Binary, _>::new(CompositeKey::new(&segments));
+        let len = keys.len(storage)?;
+
+        let mut cursor: u64 = super::load(storage, Self::cursor_key())?.unwrap_or_default();
+        let mut removed = 0;
+
+        for _ in 0..(limit as u64).min(len) {
+            let len = keys.len(storage)?;
+
+            if len == 0 {
+                break;
+            }
+
+            if cursor >= len {
+                cursor = 0;
+            }
+
+            let key = keys.get_or_error(storage, cursor)?;
+            let value_key = Self::value_key(&key.0);
+
+            let expiry = match storage.get(&value_key) {
+                Some(bytes) => Some(
+                    Deserializer::from(&bytes)
+                        .deserialize::<Expiry>()
+                        .map_err(Self::parse_err)?
+                ),
+                None => None
+            };
+
+            match expiry {
+                Some(expiry) if !expiry.is_expired(block) => {
+                    cursor += 1;
+
+                    continue;
+                },
+                Some(_) => {
+                    storage.remove(&value_key);
+                    removed += 1;
+                },
+                None => { }
+            }
+
+            // The last key is moved to the cursor, so it is checked next.
+            storage.remove(&Self::tracked_key(&key.0));
+            keys.swap_remove(storage, cursor)?;
+        }
+
+        super::save(storage, Self::cursor_key(), &cursor)?;
+
+        Ok(removed)
+    }
+
+    fn load_impl(storage: &dyn Storage, block: &BlockInfo, key: &[u8]) -> StdResult<Option<T>> {
+        let Some(bytes) = storage.get(key) else {
+            return Ok(None);
+        };
+
+        let mut de = Deserializer::from(&bytes);
+
+        let expiry: Expiry = de.deserialize().map_err(Self::parse_err)?;
+
+        if expiry.is_expired(block) {
+            return Ok(None);
+        }
+
+        let item = de.deserialize().map_err(Self::parse_err)?;
+
+        Ok(Some(item))
+    }
+
+    #[inline]
+    fn item_key(key: impl Into<K>) -> Vec<u8> {
+        let key = key.into();
+        let mut buf = Vec::with_capacity(key.size());
+        key.write_segments(&mut buf);
+
+        buf
+    }
+
+    #[inline]
+    fn value_key(key: &[u8]) -> Vec<u8> {
+        [N::NAMESPACE, VALUE_NS, key].concat()
+    }
+
+    #[inline]
+    fn tracked_key(key: &[u8]) -> Vec<u8> {
+        [N::NAMESPACE, TRACKED_NS, key].concat()
+    }
+
+    #[inline]
+    fn cursor_key() -> Vec<u8> {
+        [N::NAMESPACE, CURSOR_KEY].concat()
+    }
+
+    #[inline]
+    fn parse_err(err: bin_serde::Error) -> StdError {
+        StdError::parse_err(std::any::type_name::<T>(), err)
+    }
+}
+
+impl<'a, T: FadromaSerialize> FadromaSerialize for EntryRef<'a, T> {
+    #[inline]
+    fn size_hint(&self) -> usize {
+        self.expiry.size_hint() + self.item.size_hint()
+    }
+
+    #[inline]
+    fn to_bytes(&self, ser: &mut Serializer) -> bin_serde::Result<()> {
+        self.expiry.to_bytes(ser)?;
+        self.item.to_bytes(ser)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        storage::TypedKey,
+        cosmwasm_std::{Order, testing::{mock_dependencies, mock_env}}
+    };
+
+    crate::namespace!(OffersNs, b"offers");
+    const OFFERS: ExpiringItemSpace<u64, OffersNs, TypedKey<u64>> = ExpiringItemSpace::new();
+
+    #[test]
+    fn expired_items_are_swept() {
+        let ref mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let storage = deps.as_mut().storage;
+
+        let now = env.block.time.seconds();
+
+        OFFERS.save(storage, &1, &100, Expiry::AtTime(now + 10)).unwrap();
+        OFFERS.save(storage, &2, &200, Expiry::AtHeight(env.block.height + 1)).unwrap();
+        OFFERS.save(storage, &3, &300, Expiry::Never).unwrap();
+        OFFERS.save(storage, &4, &400, Expiry::AtTime(now + 10)).unwrap();
+
+        // Saving under the same key again doesn't track it twice.
+        OFFERS.save(storage, &1, &101, Expiry::AtTime(now + 5)).unwrap();
+
+        assert_eq!(OFFERS.load(storage, &env.block, &1).unwrap(), Some(101));
+        assert_eq!(OFFERS.expiry(storage, &1).unwrap(), Some(Expiry::AtTime(now + 5)));
+        assert_eq!(OFFERS.sweep(storage, &env.block, 10).unwrap(), 0);
+
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(5);
+
+        assert_eq!(OFFERS.load(storage, &env.block, &1).unwrap(), None);
+        assert_eq!(OFFERS.load(storage, &env.block, &2).unwrap(), None);
+        assert_eq!(OFFERS.load_or_error(storage, &env.block, &4).unwrap(), 400);
+        assert!(OFFERS.load_or_error(storage, &env.block, &2).is_err());
+
+        // Expired items are still in storage until swept.
+        assert_eq!(OFFERS.expiry(storage, &2).unwrap(), Some(Expiry::AtHeight(env.block.height)));
+
+        OFFERS.remove(storage, &4);
+
+        assert_eq!(OFFERS.sweep(storage, &env.block, 1).unwrap(), 1);
+        assert_eq!(OFFERS.sweep(storage, &env.block, 10).unwrap(), 1);
+        assert_eq!(OFFERS.expiry(storage, &1).unwrap(), None);
+        assert_eq!(OFFERS.expiry(storage, &2).unwrap(), None);
+
+        // A removed key can be saved again and is tracked once more.
+        OFFERS.save(storage, &4, &401, Expiry::AtTime(now + 6)).unwrap();
+
+        env.block.time = env.block.time.plus_seconds(1);
+        assert_eq!(OFFERS.sweep(storage, &env.block, 10).unwrap(), 1);
+
+        // Only the item that never expires and the sweep state are left.
+        let keys = storage
+            .range(None, None, Order::Ascending)
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>();
+
+        assert_eq!(OFFERS.load(storage, &env.block, &3).unwrap(), Some(300));
+        assert_eq!(keys.len(), 5);
+    }
+}
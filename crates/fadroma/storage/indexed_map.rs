@@ -0,0 +1,408 @@
+//! A map that also maintains secondary indexes over its values.
+
+use std::marker::PhantomData;
+
+use crate::{
+    bin_serde::{FadromaSerialize, FadromaDeserialize},
+    cosmwasm_std::{Storage, Binary, StdResult, StdError}
+};
+use super::{
+    Namespace, Key, CompositeKey,
+    iterable::{IterableStorage, IterBudget, Page},
+    not_found_error
+};
+
+const VALUE_NS: &[u8] = b"val";
+const INDEX_NS: &[u8] = b"idx";
+const POSITION_NS: &[u8] = b"pos";
+
+/// A secondary index of an [`IndexedMap`] that groups its values by the
+/// key returned by `index_key`. Many values can share the same index key.
+pub struct MultiIndex<V> {
+    name: &'static [u8],
+    index_key: fn(&V) -> Vec<u8>
+}
+
+/// Storage type that stores values by their primary key, like [`super::ItemSpace`],
+/// and keeps one or more [`MultiIndex`] up to date whenever a value is saved or
+/// removed. This allows looking up values by something other than their primary key,
+/// i.e. the orders of an account as well as an order by its ID.
+///
+/// Every index costs a few additional storage writes when a value is inserted or
+/// removed, or when its index key changes. Indexes whose key is unchanged by an
+/// update are not written to.
+///
+/// The following namespaces are reserved by `IndexedMap`:
+///  * N + "val" + K
+///  * N + "idx" + index name + index key
+///  * N + "pos" + index name + index key + K
+///
+/// # Examples
+///
+/// ```
+/// # use fadroma::{
+/// #     bin_serde::{FadromaSerialize, FadromaDeserialize},
+/// #     cosmwasm_std::{StdResult, testing::mock_dependencies},
+/// #     storage::{TypedKey, indexed_map::{IndexedMap, MultiIndex}}
+/// # };
+/// # fn main() -> StdResult<()> {
+/// # let mut deps = mock_dependencies();
+/// # let storage = deps.as_mut().storage;
+/// #[derive(FadromaSerialize, FadromaDeserialize, PartialEq, Debug)]
+/// struct Order {
+///     owner: String,
+///     amount: u128
+/// }
+///
+/// fadroma::namespace!(OrdersNs, b"orders");
+/// const ORDERS: IndexedMap<Order, OrdersNs, TypedKey<u64>> = IndexedMap::new(&[
+///     MultiIndex::new(b"owner", |order| order.owner.as_bytes().to_vec())
+/// ]);
+///
+/// ORDERS.save(storage, &1, &Order { owner: "alice".into(), amount: 100 })?;
+/// ORDERS.save(storage, &2, &Order { owner: "bob".into(), amount: 200 })?;
+/// ORDERS.save(storage, &3, &Order { owner: "alice".into(), amount: 300 })?;
+///
+/// let page = ORDERS.find(storage, b"owner", b"alice", None, 10)?;
+/// assert_eq!(page.items.iter().map(|x| x.amount).collect::<Vec<_>>(), vec![100, 300]);
+///
+/// // The index is updated when the owner changes.
+/// ORDERS.save(storage, &1, &Order { owner: "bob".into(), amount: 100 })?;
+/// assert_eq!(ORDERS.count(storage, b"owner", b"alice")?, 1);
+/// assert_eq!(ORDERS.count(storage, b"owner", b"bob")?, 2);
+///
+/// ORDERS.remove(storage, &2)?;
+/// assert_eq!(ORDERS.count(storage, b"owner", b"bob")?, 1);
+/// # Ok(())
+/// # }
+/// ```
+pub struct IndexedMap<
+    V: FadromaSerialize + FadromaDeserialize + 'static,
+    N: Namespace,
+    K: Key
+> {
+    indexes: &'static [MultiIndex<V>],
+    namespace_data: PhantomData<N>,
+    key_data: PhantomData<K>
+}
+
+impl<V> MultiIndex<V> {
+    /// The `name` must be unique among the indexes of a map. Changing
+    /// it later requires migrating the existing entries of the index.
+    #[inline]
+    pub const fn new(name: &'static [u8], index_key: fn(&V) -> Vec<u8>) -> Self {
+        Self { name, index_key }
+    }
+
+    #[inline]
+    pub fn name(&self) -> &'static [u8] {
+        self.name
+    }
+}
+
+impl<
+    V: FadromaSerialize + FadromaDeserialize + 'static,
+    N: Namespace,
+    K: Key
+> IndexedMap<V, N, K> {
+    #[inline]
+    pub const fn new(indexes: &'static [MultiIndex<V>]) -> Self {
+        Self {
+            indexes,
+            namespace_data: PhantomData,
+            key_data: PhantomData
+        }
+    }
+
+    /// Inserts or replaces the value under `key` and updates the indexes
+    /// whose index key has changed.
+    pub fn save(
+        &self,
+        storage: &mut dyn Storage,
+        key: impl Into<K>,
+        value: &V
+    ) -> StdResult<()> {
+        let key = Self::primary_key(key);
+        let value_key = Self::value_key(&key);
+
+        let old: Option<V> = super::load(storage, &value_key)?;
+
+        for index in self.indexes {
+            let new_index_key = (index.index_key)(value);
+
+            if let Some(old) = &old {
+                let old_index_key = (index.index_key)(old);
+
+                if old_index_key == new_index_key {
+                    continue;
+                }
+
+                Self::remove_from_index(storage, index, &old_index_key, &key)?;
+            }
+
+            Self::add_to_index(storage, index, &new_index_key, &key)?;
+        }
+
+        super::save(storage, value_key, value)
+    }
+
+    #[inline]
+    pub fn load(
+        &self,
+        storage: &dyn Storage,
+        key: impl Into<K>
+    ) -> StdResult<Option<V>> {
+        super::load(storage, Self::value_key(&Self::primary_key(key)))
+    }
+
+    #[inline]
+    pub fn load_or_error(
+        &self,
+        storage: &dyn Storage,
+        key: impl Into<K>
+    ) -> StdResult<V> {
+        let result = self.load(storage, key)?;
+
+        result.ok_or_else(|| not_found_error::<V>())
+    }
+
+    #[inline]
+    pub fn exists(
+        &self,
+        storage: &dyn Storage,
+        key: impl Into<K>
+    ) -> bool {
+        super::exists(storage, Self::value_key(&Self::primary_key(key)))
+    }
+
+    /// Removes the value under `key` from the map and all of its indexes.
+    /// Returns the removed value, if any.
+    pub fn remove(
+        &self,
+        storage: &mut dyn Storage,
+        key: impl Into<K>
+    ) -> StdResult<Option<V>> {
+        let key = Self::primary_key(key);
+        let value_key = Self::value_key(&key);
+
+        let Some(value) = super::load::<V>(storage, &value_key)? else {
+            return Ok(None);
+        };
+
+        for index in self.indexes {
+            Self::remove_from_index(storage, index, &(index.index_key)(&value), &key)?;
+        }
+
+        super::remove(storage, &value_key);
+
+        Ok(Some(value))
+    }
+
+    /// Returns the number of values whose index key is `index_key` in the index called `index`.
+    pub fn count(
+        &self,
+        storage: &dyn Storage,
+        index: &[u8],
+        index_key: &[u8]
+    ) -> StdResult<u64> {
+        let prefix = Self::bucket_prefix(self.find_index(index)?, index_key);
+
+        let segments = [prefix.as_slice()];
+
+        IterableStorage::<Binary, _>::new(CompositeKey::new(&segments)).len(storage)
+    }
+
+    /// Returns at most `limit` values whose index key is `index_key` in the index called
+    /// `index`, starting from `cursor`. Values are returned in the order they were added to
+    /// the index, except that removing a value moves the last one in its place.
+    pub fn find(
+        &self,
+        storage: &dyn Storage,
+        index: &[u8],
+        index_key: &[u8],
+        cursor: Option<u64>,
+        limit: u64
+    ) -> StdResult<Page<V>> {
+        let prefix = Self::bucket_prefix(self.find_index(index)?, index_key);
+        let segments = [prefix.as_slice()];
+        let bucket = IterableStorage::<Binary, _>::new(CompositeKey::new(&segments));
+
+        let page = bucket.iter(storage)?
+            .start_at(cursor.unwrap_or_default())
+            .read_page(limit, IterBudget::new(limit))?;
+
+        let mut items = Vec::with_capacity(page.items.len());
+
+        for key in page.items {
+            match super::load(storage, Self::value_key(key.as_slice()))? {
+                Some(value) => items.push(value),
+                None => return Err(not_found_error::<V>())
+            }
+        }
+
+        Ok(Page { items, cursor: page.cursor })
+    }
+
+    fn find_index(&self, name: &[u8]) -> StdResult<&MultiIndex<V>> {
+        self.indexes.iter()
+            .find(|x| x.name == name)
+            .ok_or_else(|| StdError::generic_err(format!(
+                "IndexedMap: no index called \"{}\".",
+                String::from_utf8_lossy(name)
+            )))
+    }
+
+    fn add_to_index(
+        storage: &mut dyn Storage,
+        index: &MultiIndex<V>,
+        index_key: &[u8],
+        key: &[u8]
+    ) -> StdResult<()> {
+        let prefix = Self::bucket_prefix(index, index_key);
+        let segments = [prefix.as_slice()];
+        let mut bucket = IterableStorage::<Binary, _>::new(CompositeKey::new(&segments));
+
+        let position = bucket.push(storage, &Binary::from(key))?;
+
+        super::save(storage, Self::position_key(index, index_key, key), &position)
+    }
+
+    fn remove_from_index(
+        storage: &mut dyn Storage,
+        index: &MultiIndex<V>,
+        index_key: &[u8],
+        key: &[u8]
+    ) -> StdResult<()> {
+        let position_key = Self::position_key(index, index_key, key);
+        let Some(position) = super::load::<u64>(storage, &position_key)? else {
+            return Ok(());
+        };
+
+        let prefix = Self::bucket_prefix(index, index_key);
+        let segments = [prefix.as_slice()];
+        let mut bucket = IterableStorage::<Binary, _>::new(CompositeKey::new(&segments));
+
+        if let Some(moved) = bucket.swap_remove(storage, position)? {
+            super::save(
+                storage,
+                Self::position_key(index, index_key, moved.as_slice()),
+                &position
+            )?;
+        }
+
+        super::remove(storage, &position_key);
+
+        Ok(())
+    }
+
+    #[inline]
+    fn primary_key(key: impl Into<K>) -> Vec<u8> {
+        let key = key.into();
+        let mut buf = Vec::with_capacity(key.size());
+        key.write_segments(&mut buf);
+
+        buf
+    }
+
+    #[inline]
+    fn value_key(key: &[u8]) -> Vec<u8> {
+        [N::NAMESPACE, VALUE_NS, key].concat()
+    }
+
+    /// The index name and key are length prefixed so
+    /// that the buckets of different keys never overlap.
+    #[inline]
+    fn bucket_prefix(index: &MultiIndex<V>, index_key: &[u8]) -> Vec<u8> {
+        [
+            N::NAMESPACE,
+            INDEX_NS,
+            &(index.name.len() as u32).to_be_bytes(),
+            index.name,
+            &(index_key.len() as u32).to_be_bytes(),
+            index_key
+        ].concat()
+    }
+
+    #[inline]
+    fn position_key(index: &MultiIndex<V>, index_key: &[u8], key: &[u8]) -> Vec<u8> {
+        [
+            N::NAMESPACE,
+            POSITION_NS,
+            &(index.name.len() as u32).to_be_bytes(),
+            index.name,
+            &(index_key.len() as u32).to_be_bytes(),
+            index_key,
+            key
+        ].concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        self as fadroma,
+        cosmwasm_std::testing::MockStorage,
+        storage::TypedKey
+    };
+
+    #[derive(FadromaSerialize, FadromaDeserialize, PartialEq, Clone, Debug)]
+    struct Order {
+        owner: String,
+        pair: u8
+    }
+
+    crate::namespace!(OrdersNs, b"orders");
+    const ORDERS: IndexedMap<Order, OrdersNs, TypedKey<u64>> = IndexedMap::new(&[
+        MultiIndex::new(b"owner", |x| x.owner.as_bytes().to_vec()),
+        MultiIndex::new(b"pair", |x| vec![x.pair])
+    ]);
+
+    fn ids(storage: &dyn Storage, index: &[u8], index_key: &[u8]) -> Vec<String> {
+        ORDERS.find(storage, index, index_key, None, 10).unwrap()
+            .items
+            .into_iter()
+            .map(|x| format!("{}/{}", x.owner, x.pair))
+            .collect()
+    }
+
+    #[test]
+    fn indexed_map_maintains_indexes() {
+        let ref mut storage = MockStorage::new();
+        let order = |owner: &str, pair| Order { owner: owner.into(), pair };
+
+        ORDERS.save(storage, &1, &order("alice", 0)).unwrap();
+        ORDERS.save(storage, &2, &order("alice", 1)).unwrap();
+        ORDERS.save(storage, &3, &order("alice", 0)).unwrap();
+        ORDERS.save(storage, &4, &order("bob", 1)).unwrap();
+
+        assert_eq!(ids(storage, b"owner", b"alice"), vec!["alice/0", "alice/1", "alice/0"]);
+        assert_eq!(ids(storage, b"pair", &[1]), vec!["alice/1", "bob/1"]);
+        assert!(ORDERS.find(storage, b"price", b"", None, 10).is_err());
+
+        // The last order moves in place of the removed one.
+        assert_eq!(ORDERS.remove(storage, &1).unwrap(), Some(order("alice", 0)));
+        assert_eq!(ORDERS.remove(storage, &1).unwrap(), None);
+        assert!(!ORDERS.exists(storage, &1));
+
+        ORDERS.save(storage, &3, &order("alice", 1)).unwrap();
+        assert_eq!(ids(storage, b"owner", b"alice"), vec!["alice/1", "alice/1"]);
+        assert_eq!(ids(storage, b"pair", &[0]), Vec::<String>::new());
+        assert_eq!(ORDERS.count(storage, b"pair", &[1]).unwrap(), 3);
+
+        let page = ORDERS.find(storage, b"pair", &[1], None, 2).unwrap();
+        assert_eq!(page.cursor, Some(2));
+
+        let page = ORDERS.find(storage, b"pair", &[1], page.cursor, 2).unwrap();
+        assert_eq!(page.items, vec![order("alice", 1)]);
+        assert_eq!(page.cursor, None);
+
+        for id in 2..5 {
+            ORDERS.remove(storage, &id).unwrap();
+        }
+
+        // Only the emptied index lengths are left.
+        assert!(storage.range(None, None, crate::cosmwasm_std::Order::Ascending)
+            .all(|(key, _)| key.ends_with(b"index")));
+    }
+}
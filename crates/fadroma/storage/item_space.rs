@@ -5,7 +5,7 @@ use crate::{
     bin_serde::{FadromaSerialize, FadromaDeserialize},
     cosmwasm_std::{Deps, DepsMut, Storage, StdResult}
 };
-use super::{Key, Namespace, not_found_error};
+use super::{Key, Namespace, key_not_found_error};
 
 /// Storage type that stores many items under the given [`Namespace`].
 /// The key can be anything that implements [`Key`] and the most suitable
@@ -82,10 +82,24 @@ impl<T: FadromaSerialize + FadromaDeserialize, N: Namespace, K: Key> ItemSpace<T
         &self,
         storage: &dyn Storage,
         key: impl Into<K>,
+    ) -> StdResult<T> {
+        let key = Self::key(key);
+        let result = super::load(storage, &key)?;
+
+        result.ok_or_else(|| key_not_found_error::<T>(&key))
+    }
+
+    /// Returns `default` if no item has been saved under the given key.
+    #[inline]
+    pub fn load_or(
+        &self,
+        storage: &dyn Storage,
+        key: impl Into<K>,
+        default: T
     ) -> StdResult<T> {
         let result = self.load(storage, key)?;
 
-        result.ok_or_else(|| not_found_error::<T>())
+        Ok(result.unwrap_or(default))
     }
 
     #[inline]
@@ -97,6 +111,61 @@ impl<T: FadromaSerialize + FadromaDeserialize, N: Namespace, K: Key> ItemSpace<T
         super::remove(storage, Self::key(key))
     }
 
+    /// Loads the item stored under `key`, passes it to `update` and saves the result.
+    /// Returns the updated item. Nothing is saved if `update` returns an error.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use fadroma::{
+    /// #     cosmwasm_std::{StdResult, StdError, testing::mock_dependencies},
+    /// #     storage::{ItemSpace, TypedKey}
+    /// # };
+    /// # fn main() -> StdResult<()> {
+    /// # let mut deps = mock_dependencies();
+    /// # let storage = deps.as_mut().storage;
+    /// fadroma::namespace!(BalancesNs, b"balances");
+    /// // Balances by account ID
+    /// const BALANCES: ItemSpace::<u64, BalancesNs, TypedKey<u64>> = ItemSpace::new();
+    /// 
+    /// BALANCES.save(storage, &1, &10)?;
+    /// 
+    /// let withdraw = |x: Option<u64>| x
+    ///     .and_then(|x| x.checked_sub(5))
+    ///     .ok_or_else(|| StdError::generic_err("Insufficient balance."));
+    /// 
+    /// assert_eq!(BALANCES.update(storage, &1, withdraw)?, 5);
+    /// assert!(BALANCES.update(storage, &2, withdraw).is_err());
+    /// assert_eq!(BALANCES.load(storage, &2)?, None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn update<F>(
+        &self,
+        storage: &mut dyn Storage,
+        key: impl Into<K>,
+        update: F
+    ) -> StdResult<T>
+        where F: FnOnce(Option<T>) -> StdResult<T>
+    {
+        let key = Self::key(key);
+
+        let item = update(super::load(storage, &key)?)?;
+        super::save(storage, key, &item)?;
+
+        Ok(item)
+    }
+
+    /// Returns `true` if an item is stored under `key`. Doesn't deserialize it.
+    #[inline]
+    pub fn exists(
+        &self,
+        storage: &dyn Storage,
+        key: impl Into<K>,
+    ) -> bool {
+        super::exists(storage, Self::key(key))
+    }
+
     #[inline]
     pub fn canonize_and_save<Input: Canonize<Output = T>>(
         &self,
@@ -110,7 +179,7 @@ impl<T: FadromaSerialize + FadromaDeserialize, N: Namespace, K: Key> ItemSpace<T
     }
 
     #[inline]
-    fn key(key: impl Into<K>) -> Vec<u8> {
+    pub(super) fn key(key: impl Into<K>) -> Vec<u8> {
         let key = key.into();
 
         let mut buf = Vec::with_capacity(N::NAMESPACE.len() + key.size());
@@ -146,9 +215,13 @@ impl<
         deps: Deps,
         key: impl Into<K>
     ) -> StdResult<<T as Humanize>::Output> {
-        let result = self.load_humanize(deps, key)?;
+        let key = Self::key(key);
+        let result: Option<T> = super::load(deps.storage, &key)?;
 
-        result.ok_or_else(|| not_found_error::<T>())
+        match result {
+            Some(item) => item.humanize(deps.api),
+            None => Err(key_not_found_error::<T>(&key))
+        }
     }
 }
 
@@ -1,9 +1,12 @@
 use std::{mem, marker::PhantomData};
 
+use serde::{Serialize, Deserialize};
+
 use crate::{
     bin_serde::{FadromaSerialize, FadromaDeserialize},
     cosmwasm_std::{Storage, Deps, DepsMut, StdResult, StdError},
-    core::{Canonize, Humanize}
+    core::{Canonize, Humanize},
+    schemars::{self, JsonSchema}
 };
 use super::{Key, not_found_error};
 
@@ -362,6 +365,65 @@ impl<T: FadromaSerialize + FadromaDeserialize, K: Key> IterableStorage<T, K> {
         Ok(result.unwrap_or(0))
     }
 
+    /// Removes the items at the end of the collection, at most `limit` of them or all
+    /// if [`None`]. Returns the number of items left, so large collections can be cleared
+    /// over multiple transactions by calling it until it returns zero.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use fadroma::storage::{iterable::IterableStorage, CompositeKey};
+    /// # use fadroma::cosmwasm_std::{StdResult, testing::mock_dependencies};
+    /// # fn main() -> StdResult<()> {
+    /// # let mut deps = mock_dependencies();
+    /// # let storage = deps.as_mut().storage;
+    /// let key = CompositeKey::new(&[b"numbers"]);
+    /// let mut iterable = IterableStorage::<u8, _>::new(key);
+    /// 
+    /// for i in 0..5 {
+    ///     iterable.push(storage, &i)?;
+    /// }
+    /// 
+    /// assert_eq!(iterable.clear(storage, Some(3))?, 2);
+    /// assert_eq!(iterable.get(storage, 1)?, Some(1));
+    /// 
+    /// assert_eq!(iterable.clear(storage, None)?, 0);
+    /// assert_eq!(iterable.len(storage)?, 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn clear(&mut self, storage: &mut dyn Storage, limit: Option<u64>) -> StdResult<u64> {
+        let len = self.len(storage)?;
+        let new_len = len.saturating_sub(limit.unwrap_or(len));
+
+        self.truncate(storage, new_len)?;
+
+        Ok(new_len)
+    }
+
+    /// Removes all items at or after `new_len` without reading them.
+    pub(super) fn truncate(&mut self, storage: &mut dyn Storage, new_len: u64) -> StdResult<()> {
+        let len = self.len(storage)?;
+
+        if new_len >= len {
+            return Ok(());
+        }
+
+        for index in new_len..len {
+            super::remove(storage, self.key(index));
+        }
+
+        if new_len == 0 {
+            super::remove(storage, self.key_len());
+        } else {
+            super::save(storage, self.key_len(), &new_len)?;
+        }
+
+        self.len = Some(new_len);
+
+        Ok(())
+    }
+
     fn increment_index(&mut self, storage: &mut dyn Storage) -> StdResult<u64> {
         let current = self.len(storage)?;
         let new = current + 1;
@@ -554,6 +616,132 @@ impl<'storage, T: FadromaDeserialize> DoubleEndedIterator for Iter<'storage, T>
 
 impl<'storage, T: FadromaDeserialize> ExactSizeIterator for Iter<'storage, T> { }
 
+/// Caps the number of storage reads that a paginated query can perform.
+/// Once the budget is exhausted, iteration stops early and a cursor
+/// pointing to the next unread item is returned alongside the results
+/// so that the caller can resume from there in a subsequent query.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct IterBudget {
+    reads: u64
+}
+
+/// A single page of results returned by a budgeted iteration.
+/// If `cursor` is [`Some`], there are more items to be read and
+/// it should be passed back to continue from where the page ended.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, PartialEq, Debug)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub cursor: Option<u64>
+}
+
+impl IterBudget {
+    /// Creates a budget that allows at most `max_reads` storage reads.
+    #[inline]
+    pub const fn new(max_reads: u64) -> Self {
+        Self { reads: max_reads }
+    }
+
+    /// Returns the number of storage reads that are still allowed.
+    #[inline]
+    pub fn remaining(&self) -> u64 {
+        self.reads
+    }
+
+    #[inline]
+    pub fn is_exhausted(&self) -> bool {
+        self.reads == 0
+    }
+
+    /// Consumes a single storage read. Returns `false` if
+    /// the budget was already exhausted.
+    #[inline]
+    pub fn consume(&mut self) -> bool {
+        if self.reads == 0 {
+            return false;
+        }
+
+        self.reads -= 1;
+
+        true
+    }
+}
+
+impl<'storage, T: FadromaDeserialize> Iter<'storage, T> {
+    /// Moves the start of the iterator to the given `cursor` without
+    /// reading from storage. Use this to resume iteration from a
+    /// cursor returned by [`Iter::read_page`].
+    #[inline]
+    pub fn start_at(mut self, cursor: u64) -> Self {
+        self.current = cursor.min(self.end);
+
+        self
+    }
+
+    /// Returns the index of the next item that will be read.
+    #[inline]
+    pub fn cursor(&self) -> u64 {
+        self.current
+    }
+
+    /// Reads at most `limit` items in order, performing no more storage
+    /// reads than the given `budget` allows. The returned [`Page`] contains
+    /// a cursor if there are any items left after the page.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use fadroma::storage::{iterable::{IterableStorage, IterBudget}, CompositeKey};
+    /// # use fadroma::cosmwasm_std::{StdResult, testing::mock_dependencies};
+    /// # fn main() -> StdResult<()> {
+    /// # let mut deps = mock_dependencies();
+    /// # let storage = deps.as_mut().storage;
+    /// let key = CompositeKey::new(&[b"numbers"]);
+    /// let mut iterable = IterableStorage::<u8, _>::new(key);
+    /// 
+    /// for i in 0..5 {
+    ///     iterable.push(storage, &i)?;
+    /// }
+    /// 
+    /// let page = iterable.iter(storage)?.read_page(10, IterBudget::new(3))?;
+    /// assert_eq!(page.items, vec![0, 1, 2]);
+    /// assert_eq!(page.cursor, Some(3));
+    /// 
+    /// let page = iterable.iter(storage)?
+    ///     .start_at(page.cursor.unwrap())
+    ///     .read_page(10, IterBudget::new(3))?;
+    /// assert_eq!(page.items, vec![3, 4]);
+    /// assert_eq!(page.cursor, None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_page(
+        mut self,
+        limit: u64,
+        mut budget: IterBudget
+    ) -> StdResult<Page<T>> {
+        let capacity = limit.min(budget.remaining()).min(self.len());
+        let mut items = Vec::with_capacity(capacity as usize);
+
+        while (items.len() as u64) < limit && self.current < self.end {
+            if !budget.consume() {
+                break;
+            }
+
+            items.push(self.load_next(self.current)?);
+            self.current += 1;
+        }
+
+        Ok(Page {
+            items,
+            cursor: if self.current < self.end {
+                Some(self.current)
+            } else {
+                None
+            }
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -768,4 +956,57 @@ mod tests {
 
         assert_eq!(storage.len(&deps.storage).unwrap(), 0);
     }
+
+    #[test]
+    fn iterable_storage_read_page() {
+        let mut deps = mock_dependencies();
+
+        let key = CompositeKey::new(&[b"numbers"]);
+        let mut storage = IterableStorage::<u8, _>::new(key);
+
+        for i in 0..10 {
+            storage.push(&mut deps.storage, &i).unwrap();
+        }
+
+        // Limit is reached before the budget.
+        let page = storage.iter(&deps.storage).unwrap()
+            .read_page(4, IterBudget::new(100))
+            .unwrap();
+        assert_eq!(page.items, vec![0, 1, 2, 3]);
+        assert_eq!(page.cursor, Some(4));
+
+        // Budget is exhausted before the limit.
+        let page = storage.iter(&deps.storage).unwrap()
+            .start_at(4)
+            .read_page(100, IterBudget::new(5))
+            .unwrap();
+        assert_eq!(page.items, vec![4, 5, 6, 7, 8]);
+        assert_eq!(page.cursor, Some(9));
+
+        let page = storage.iter(&deps.storage).unwrap()
+            .start_at(9)
+            .read_page(100, IterBudget::new(5))
+            .unwrap();
+        assert_eq!(page.items, vec![9]);
+        assert_eq!(page.cursor, None);
+
+        // Cursor past the end doesn't read anything.
+        let page = storage.iter(&deps.storage).unwrap()
+            .start_at(20)
+            .read_page(100, IterBudget::new(5))
+            .unwrap();
+        assert!(page.items.is_empty());
+        assert_eq!(page.cursor, None);
+
+        let page = storage.iter(&deps.storage).unwrap()
+            .read_page(100, IterBudget::new(0))
+            .unwrap();
+        assert!(page.items.is_empty());
+        assert_eq!(page.cursor, Some(0));
+
+        let mut budget = IterBudget::new(1);
+        assert!(budget.consume());
+        assert!(budget.is_exhausted());
+        assert!(!budget.consume());
+    }
 }
@@ -0,0 +1,104 @@
+use crate::{
+    bin_serde::{FadromaSerialize, FadromaDeserialize},
+    cosmwasm_std::{Storage, StdResult}
+};
+use super::{Key, Namespace, ItemSpace, key_not_found_error};
+
+/// A handle to the value stored under a key that is only loaded and
+/// deserialized when it is first accessed and is cached after that.
+/// Create one for each value that a handler may need up front and pass
+/// it to the branches that use it, so that the branches which don't
+/// never pay for loading it.
+///
+/// It only reads the value. Use a [`super::CachedItem`] for a single item
+/// that is also changed by the handler.
+///
+/// # Examples
+///
+/// ```
+/// # use fadroma::{
+/// #     cosmwasm_std::{CanonicalAddr, StdResult, testing::mock_dependencies},
+/// #     storage::{ItemSpace, TypedKey}
+/// # };
+/// # fn main() -> StdResult<()> {
+/// # let mut deps = mock_dependencies();
+/// # let storage = deps.as_mut().storage;
+/// fadroma::namespace!(BalancesNs, b"balances");
+/// const BALANCES: ItemSpace<u128, BalancesNs, TypedKey<CanonicalAddr>> = ItemSpace::new();
+///
+/// let alice = CanonicalAddr::from(b"alice".as_slice());
+/// BALANCES.save(storage, &alice, &100)?;
+///
+/// // Nothing is read from storage yet.
+/// let mut balance = BALANCES.lazy(&alice);
+/// assert!(!balance.is_loaded());
+///
+/// assert_eq!(balance.get(storage)?, Some(&100));
+/// assert!(balance.is_loaded());
+/// # Ok(())
+/// # }
+/// ```
+pub struct Lazy<T: FadromaDeserialize> {
+    key: Vec<u8>,
+    // None if not loaded yet.
+    value: Option<Option<T>>
+}
+
+impl<T: FadromaSerialize + FadromaDeserialize, N: Namespace, K: Key> ItemSpace<T, N, K> {
+    /// Returns a [`Lazy`] handle to the item stored under the
+    /// given key which is loaded on first access.
+    #[inline]
+    pub fn lazy(&self, key: impl Into<K>) -> Lazy<T> {
+        Lazy::new(Self::key(key))
+    }
+}
+
+impl<T: FadromaDeserialize> Lazy<T> {
+    #[inline]
+    pub fn new(key: impl Into<Vec<u8>>) -> Self {
+        Self {
+            key: key.into(),
+            value: None
+        }
+    }
+
+    /// The full key that the value is loaded from.
+    #[inline]
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// Returns `true` if the value has been loaded from storage already.
+    #[inline]
+    pub fn is_loaded(&self) -> bool {
+        self.value.is_some()
+    }
+
+    /// Loads the value from storage only if it hasn't been already.
+    pub fn get(&mut self, storage: &dyn Storage) -> StdResult<Option<&T>> {
+        if self.value.is_none() {
+            self.value = Some(super::load(storage, &self.key)?);
+        }
+
+        Ok(self.value.as_ref().unwrap().as_ref())
+    }
+
+    #[inline]
+    pub fn get_or_error(&mut self, storage: &dyn Storage) -> StdResult<&T> {
+        let key = &self.key;
+
+        if self.value.is_none() {
+            self.value = Some(super::load(storage, key)?);
+        }
+
+        self.value.as_ref().unwrap().as_ref().ok_or_else(|| key_not_found_error::<T>(key))
+    }
+
+    /// Returns the value, loading it only if it hasn't been already.
+    pub fn into_inner(self, storage: &dyn Storage) -> StdResult<Option<T>> {
+        match self.value {
+            Some(value) => Ok(value),
+            None => super::load(storage, &self.key)
+        }
+    }
+}
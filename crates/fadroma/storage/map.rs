@@ -8,7 +8,7 @@ use crate::{
 };
 use super::{
     Namespace, Key, StaticKey,
-    iterable::{IterableStorage, Iter},
+    iterable::{IterableStorage, Iter, IterBudget, Page},
     serialize, deserialize, not_found_error
 };
 
@@ -102,6 +102,12 @@ impl<
         Ok(Some(item))
     }
 
+    /// Returns `true` if a value is stored under `key`. Doesn't deserialize it.
+    #[inline]
+    pub fn exists(&self, storage: &dyn Storage, key: impl Into<K>) -> bool {
+        self.inner.exists(storage, key)
+    }
+
     /// Returns a tuple where the first member indicates whether the given value was inserted.
     /// The second member is the value itself, either loaded from storage or the `value` parameter.
     /// 
@@ -258,6 +264,55 @@ impl<
         Ok(exists)
     }
 
+    /// Removes the most recently inserted values, at most `limit` of them or all
+    /// if [`None`]. Each value costs a read in order to remove its key. Returns the
+    /// number of values left, so large maps can be cleared over multiple transactions
+    /// by calling it until it returns zero.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use fadroma::storage::{map::Map, TypedKey};
+    /// # use fadroma::cosmwasm_std::{
+    /// #     StdResult,
+    /// #     testing::mock_dependencies
+    /// # };
+    /// # fn main() -> StdResult<()> {
+    /// # let mut deps = mock_dependencies();
+    /// # let storage = deps.as_mut().storage;
+    /// fadroma::namespace!(NumbersNs, b"numbers");
+    /// let mut map = Map::<TypedKey<String>, u8, NumbersNs>::new();
+    /// 
+    /// let one = "one".to_string();
+    /// let two = "two".to_string();
+    /// 
+    /// map.insert(storage, &one, &1)?;
+    /// map.insert(storage, &two, &2)?;
+    /// 
+    /// assert_eq!(map.clear(storage, Some(1))?, 1);
+    /// assert_eq!(map.get(storage, &two)?, None);
+    /// 
+    /// assert_eq!(map.clear(storage, None)?, 0);
+    /// assert!(!map.exists(storage, &one));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn clear(&mut self, storage: &mut dyn Storage, limit: Option<u64>) -> StdResult<u64> {
+        let iterable = &mut self.inner.iterable;
+
+        let len = iterable.len(storage)?;
+        let new_len = len.saturating_sub(limit.unwrap_or(len));
+
+        for index in new_len..len {
+            let entry = iterable.get_or_error(storage, index)?;
+            storage.remove(&entry.key.0);
+        }
+
+        iterable.truncate(storage, new_len)?;
+
+        Ok(new_len)
+    }
+
     #[inline]
     fn get_impl(&self, storage: &dyn Storage, key: &K) -> StdResult<Option<V>> {
         let Ok(Some(entry)) = self.inner.get_impl(storage, key) else {
@@ -404,6 +459,12 @@ impl<
         self.get_impl(storage, &key.into())
     }
 
+    /// Returns `true` if a value is stored under `key`. Doesn't deserialize it.
+    #[inline]
+    pub fn exists(&self, storage: &dyn Storage, key: impl Into<K>) -> bool {
+        super::exists(storage, self.map_key(&key.into()))
+    }
+
     /// Returns a tuple where the first member is the index of the value if it was inserted.
     /// The second member is the value itself, either loaded from storage or the `value` parameter.
     /// 
@@ -816,6 +877,43 @@ impl<'storage, T: FadromaDeserialize> DoubleEndedIterator for MapValueIter<'stor
 
 impl<'storage, T: FadromaDeserialize> ExactSizeIterator for MapValueIter<'storage, T> { }
 
+impl<'storage, T: FadromaDeserialize> MapValueIter<'storage, T> {
+    /// See [`Iter::start_at`].
+    #[inline]
+    pub fn start_at(self, cursor: u64) -> Self {
+        Self {
+            inner: self.inner.start_at(cursor),
+            data: PhantomData
+        }
+    }
+
+    /// See [`Iter::cursor`].
+    #[inline]
+    pub fn cursor(&self) -> u64 {
+        self.inner.cursor()
+    }
+
+    /// See [`Iter::read_page`].
+    pub fn read_page(
+        self,
+        limit: u64,
+        budget: IterBudget
+    ) -> StdResult<Page<T>> {
+        let page = self.inner.read_page(limit, budget)?;
+
+        let mut items = Vec::with_capacity(page.items.len());
+
+        for entry in page.items {
+            items.push(deserialize(&entry.item.0)?);
+        }
+
+        Ok(Page {
+            items,
+            cursor: page.cursor
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1041,9 +1139,11 @@ mod tests {
 
             let value = map.get(storage, key).unwrap();
             assert_eq!(value, Some(num));
+            assert!(map.exists(storage, key));
 
             assert_eq!(map.remove(storage, key), Ok(true));
             assert_eq!(map.get(storage, key).unwrap(), None);
+            assert!(!map.exists(storage, key));
 
             assert_eq!(map.remove(storage, key), Ok(false));
         }
@@ -1054,4 +1154,62 @@ mod tests {
         let iter_next = map.values(storage).unwrap().next();
         assert!(iter_next.is_none());
     }
+
+    #[test]
+    fn map_clear() {
+        let storage = &mut mock_dependencies().storage as &mut dyn Storage;
+        let mut map = Map::<TypedKey<String>, u8, TestNs>::new();
+
+        let keys = (0..5u8).map(|x| x.to_string()).collect::<Vec<String>>();
+
+        for (i, key) in keys.iter().enumerate() {
+            map.insert(storage, key, &(i as u8)).unwrap();
+        }
+
+        assert_eq!(map.clear(storage, Some(2)), Ok(3));
+        assert_eq!(map.values(storage).unwrap().len(), 3);
+        assert!(map.exists(storage, &keys[2]));
+        assert!(!map.exists(storage, &keys[3]));
+
+        // Re-inserting a removed key appends it again.
+        map.insert(storage, &keys[4], &4).unwrap();
+        assert_eq!(map.get(storage, &keys[4]).unwrap(), Some(4));
+
+        assert_eq!(map.clear(storage, Some(10)), Ok(0));
+        assert_eq!(map.clear(storage, None), Ok(0));
+
+        for key in keys.iter() {
+            assert!(!map.exists(storage, key));
+        }
+
+        assert_eq!(storage.range(None, None, crate::cosmwasm_std::Order::Ascending).count(), 0);
+    }
+
+    #[test]
+    fn map_values_read_page() {
+        let storage = &mut mock_dependencies().storage as &mut dyn Storage;
+        let mut map = Map::<TypedKey<String>, u8, TestNs>::new();
+
+        let keys = ["one", "two", "three", "four", "five"]
+            .into_iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<String>>();
+
+        for (i, key) in keys.iter().enumerate() {
+            map.insert(storage, key, &(i as u8)).unwrap();
+        }
+
+        let page = map.values(storage).unwrap()
+            .read_page(10, IterBudget::new(2))
+            .unwrap();
+        assert_eq!(page.items, vec![0, 1]);
+        assert_eq!(page.cursor, Some(2));
+
+        let page = map.values(storage).unwrap()
+            .start_at(page.cursor.unwrap())
+            .read_page(10, IterBudget::new(10))
+            .unwrap();
+        assert_eq!(page.items, vec![2, 3, 4]);
+        assert_eq!(page.cursor, None);
+    }
 }
@@ -2,11 +2,28 @@
 
 pub mod iterable;
 pub mod map;
+pub mod checkpoint;
+pub mod deque;
+#[cfg(feature = "storage-iter")]
+pub mod expiring;
+#[cfg(feature = "storage-iter")]
+pub mod indexed_map;
+pub mod transaction;
+pub mod read_cache;
+pub mod scoped;
+pub mod series;
+pub mod sketch;
+pub mod sorted_map;
+pub mod versioned;
 
 mod single_item;
+mod cached_item;
+mod lazy;
 mod item_space;
 
 pub use single_item::*;
+pub use cached_item::*;
+pub use lazy::*;
 pub use item_space::*;
 
 use std::{any, convert::{TryFrom, TryInto}};
@@ -43,6 +60,62 @@ macro_rules! namespace {
     };
 }
 
+/// Implements [`Storage`] for a wrapper type by forwarding every
+/// method to the given field, which must itself implement [`Storage`] or
+/// be a reference to one. Useful for wrappers that only carry extra
+/// context along with the storage of a handler.
+///
+/// # Examples
+///
+/// ```
+/// use fadroma::cosmwasm_std::{Storage, testing::MockStorage};
+///
+/// struct Tagged<'a> {
+///     storage: &'a mut dyn Storage,
+///     tag: &'static str
+/// }
+///
+/// fadroma::delegate_storage!(Tagged<'a>, storage);
+///
+/// let mut storage = MockStorage::new();
+/// let mut tagged = Tagged { storage: &mut storage, tag: "admin" };
+///
+/// tagged.set(tagged.tag.as_bytes(), b"1");
+/// assert_eq!(storage.get(b"admin"), Some(b"1".to_vec()));
+/// ```
+#[macro_export]
+macro_rules! delegate_storage {
+    ($name:ident $(<$($lifetime:lifetime),+>)?, $field:tt) => {
+        impl$(<$($lifetime),+>)? $crate::cosmwasm_std::Storage for $name$(<$($lifetime),+>)? {
+            #[inline]
+            fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+                self.$field.get(key)
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            #[inline]
+            fn range<'range>(
+                &'range self,
+                start: Option<&[u8]>,
+                end: Option<&[u8]>,
+                order: $crate::cosmwasm_std::Order,
+            ) -> Box<dyn Iterator<Item = $crate::cosmwasm_std::Record> + 'range> {
+                self.$field.range(start, end, order)
+            }
+
+            #[inline]
+            fn set(&mut self, key: &[u8], value: &[u8]) {
+                self.$field.set(key, value)
+            }
+
+            #[inline]
+            fn remove(&mut self, key: &[u8]) {
+                self.$field.remove(key)
+            }
+        }
+    };
+}
+
 pub type Segments<'a> = &'a [&'a [u8]];
 
 /// Represents a namespace, usually acting as a prefix
@@ -167,6 +240,15 @@ pub fn remove(
     storage.remove(key.as_ref());
 }
 
+/// Check whether something is in the storage without deserializing it.
+#[inline]
+pub fn exists(
+    storage: &dyn Storage,
+    key: impl AsRef<[u8]>
+) -> bool {
+    storage.get(key.as_ref()).is_some()
+}
+
 /// Load something from the storage.
 #[inline]
 pub fn load<T: FadromaDeserialize> (
@@ -331,6 +413,86 @@ impl_typed_key!(TypedKey2<'a, T1, T2> [1]);
 impl_typed_key!(TypedKey3<'a, T1, T2, T3> [1, 2]);
 impl_typed_key!(TypedKey4<'a, T1, T2, T3, T4> [1, 2, 3]);
 
+macro_rules! impl_prefixed_key {
+    (
+        $(#[$meta:meta])*
+        $name:ident<$lt:lifetime, $($param:ident),+> [$($num:tt),+] $last:tt
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, PartialEq, Hash, Debug)]
+        pub struct $name<$lt, $($param: Segment + ?Sized),+>(($(&$lt $param),+));
+
+        impl<$lt, $($param: Segment + ?Sized),+> Key for $name<$lt, $($param),+> {
+            #[inline]
+            fn size(&self) -> usize {
+                $(SEGMENT_LEN_SIZE + self.0.$num.size() +)+ self.0.$last.size()
+            }
+
+            #[inline]
+            fn write_segments(&self, buf: &mut Vec<u8>) {
+                $(write_len_prefixed(self.0.$num, buf);)+
+                self.0.$last.write_segment(buf);
+            }
+        }
+
+        impl<$lt, $($param: Segment + ?Sized),+> From<($(&$lt $param),+)> for $name<$lt, $($param),+> {
+            #[inline]
+            fn from(value: ($(&$lt $param),+)) -> Self {
+                Self(value)
+            }
+        }
+    };
+}
+
+impl_prefixed_key!(
+    /// Same as [`TypedKey2`] except that every segment but the last one is prefixed
+    /// with its length as a big-endian [`u16`]. The typed keys simply concatenate their
+    /// segments, so keys with variable length segments can collide, i.e. `("ab", "c")`
+    /// and `("a", "bc")`. Prefixing the lengths makes every combination of segments
+    /// produce a distinct key. Use [`PrefixedKey3`] and [`PrefixedKey4`] for more segments.
+    ///
+    /// Since the encoding differs, switching an existing storage type from a
+    /// typed key to a prefixed one requires migrating the stored values.
+    ///
+    /// # Panics
+    ///
+    /// If any segment but the last one is longer than [`u16::MAX`] bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fadroma::storage::{Key, PrefixedKey3, TypedKey3};
+    ///
+    /// fn bytes(key: impl Key) -> Vec<u8> {
+    ///     let mut bytes = Vec::with_capacity(key.size());
+    ///     key.write_segments(&mut bytes);
+    ///
+    ///     bytes
+    /// }
+    ///
+    /// let (a, b) = (("allowance", "ab", "c"), ("allowance", "a", "bc"));
+    ///
+    /// assert_eq!(bytes(TypedKey3::from((&a.0, &a.1, &a.2))), bytes(TypedKey3::from((&b.0, &b.1, &b.2))));
+    /// assert_ne!(bytes(PrefixedKey3::from((&a.0, &a.1, &a.2))), bytes(PrefixedKey3::from((&b.0, &b.1, &b.2))));
+    ///
+    /// assert_eq!(bytes(PrefixedKey3::from((&a.0, &a.1, &a.2))), b"\x00\x09allowance\x00\x02abc");
+    /// ```
+    PrefixedKey2<'a, T1, T2> [0] 1
+);
+impl_prefixed_key!(PrefixedKey3<'a, T1, T2, T3> [0, 1] 2);
+impl_prefixed_key!(PrefixedKey4<'a, T1, T2, T3, T4> [0, 1, 2] 3);
+
+const SEGMENT_LEN_SIZE: usize = std::mem::size_of::<u16>();
+
+#[inline]
+fn write_len_prefixed(segment: &(impl Segment + ?Sized), buf: &mut Vec<u8>) {
+    let len = u16::try_from(segment.size())
+        .expect("Key segments must not be longer than u16::MAX bytes.");
+
+    buf.extend_from_slice(&len.to_be_bytes());
+    segment.write_segment(buf);
+}
+
 impl<T: Namespace> Key for T {
     #[inline]
     fn size(&self) -> usize {
@@ -434,9 +596,21 @@ fn not_found_error<T>() -> StdError {
     StdError::not_found(format!("Storage load: {}", any::type_name::<T>()))
 }
 
+/// Same as [`not_found_error`] but also includes the key that was looked up.
+/// Any non-ASCII bytes in it are escaped.
+#[inline]
+fn key_not_found_error<T>(key: &[u8]) -> StdError {
+    StdError::not_found(format!(
+        "Storage load: {} under key \"{}\"",
+        any::type_name::<T>(),
+        key.escape_ascii()
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cosmwasm_std::testing::MockStorage;
 
     #[test]
     fn typed_keys() {
@@ -455,4 +629,167 @@ mod tests {
         test(TypedKey3::from((&WORD, &WORD, &WORD)), 3);
         test(TypedKey4::from((&WORD, &WORD, &WORD, &WORD)), 4);
     }
+
+    #[test]
+    fn prefixed_keys() {
+        const WORD: &str = "test";
+
+        fn test(key: impl Key, len: usize) {
+            let mut expected = [&[0, 4], WORD.as_bytes()].concat().repeat(len - 1);
+            expected.extend_from_slice(WORD.as_bytes());
+
+            assert_eq!(key.size(), expected.len());
+
+            let mut buf = Vec::with_capacity(key.size());
+            key.write_segments(&mut buf);
+
+            assert_eq!(buf, expected);
+        }
+
+        test(PrefixedKey2::from((&WORD, &WORD)), 2);
+        test(PrefixedKey3::from((&WORD, &WORD, &WORD)), 3);
+        test(PrefixedKey4::from((&WORD, &WORD, &WORD, &WORD)), 4);
+
+        crate::namespace!(AllowancesNs, b"allowances");
+        const ALLOWANCES: ItemSpace<u64, AllowancesNs, PrefixedKey2<&str, &str>> = ItemSpace::new();
+
+        let mut storage = MockStorage::new();
+        ALLOWANCES.save(&mut storage, (&"ab", &"c"), &1).unwrap();
+
+        assert_eq!(ALLOWANCES.load(&storage, (&"a", &"bc")).unwrap(), None);
+        assert_eq!(ALLOWANCES.load(&storage, (&"ab", &"c")).unwrap(), Some(1));
+    }
+
+    #[test]
+    fn exists_without_deserializing() {
+        crate::namespace!(NumberNs, b"number");
+        const NUMBER: SingleItem<u64, NumberNs> = SingleItem::new();
+
+        crate::namespace!(NumbersNs, b"numbers");
+        const NUMBERS: ItemSpace<u64, NumbersNs, TypedKey<u8>> = ItemSpace::new();
+
+        let mut storage = MockStorage::new();
+        assert!(!NUMBER.exists(&storage));
+        assert!(!NUMBERS.exists(&storage, &1));
+
+        // Not a valid u64 so loading would fail.
+        storage.set(NumberNs::NAMESPACE, &[1]);
+        assert!(NUMBER.exists(&storage));
+        assert!(NUMBER.load(&storage).is_err());
+
+        NUMBERS.save(&mut storage, &1, &10).unwrap();
+        assert!(NUMBERS.exists(&storage, &1));
+        assert!(!NUMBERS.exists(&storage, &2));
+
+        NUMBERS.remove(&mut storage, &1);
+        assert!(!NUMBERS.exists(&storage, &1));
+    }
+
+    #[test]
+    fn load_or_and_not_found_errors() {
+        crate::namespace!(NumberNs, b"number");
+        const NUMBER: SingleItem<u64, NumberNs> = SingleItem::new();
+
+        crate::namespace!(NumbersNs, b"numbers");
+        const NUMBERS: ItemSpace<u64, NumbersNs, TypedKey<u8>> = ItemSpace::new();
+
+        let mut storage = MockStorage::new();
+        assert_eq!(NUMBER.load_or(&storage, 5).unwrap(), 5);
+        assert_eq!(NUMBERS.load_or(&storage, &1, 5).unwrap(), 5);
+
+        let err = NUMBER.load_or_error(&storage).unwrap_err();
+        assert_eq!(err, StdError::not_found("Storage load: u64 under key \"number\""));
+
+        let err = NUMBERS.load_or_error(&storage, &1).unwrap_err();
+        assert_eq!(err, StdError::not_found("Storage load: u64 under key \"numbers\\x01\""));
+
+        NUMBER.save(&mut storage, &1).unwrap();
+        NUMBERS.save(&mut storage, &1, &2).unwrap();
+
+        assert_eq!(NUMBER.load_or(&storage, 5).unwrap(), 1);
+        assert_eq!(NUMBERS.load_or(&storage, &1, 5).unwrap(), 2);
+        assert_eq!(NUMBERS.load_or_error(&storage, &1).unwrap(), 2);
+    }
+
+    #[test]
+    fn cached_item_loads_once() {
+        crate::namespace!(NumberNs, b"number");
+        const NUMBER: SingleItem<u64, NumberNs> = SingleItem::new();
+
+        let mut storage = MockStorage::new();
+        let mut number = NUMBER.cached();
+
+        assert_eq!(number.get(&storage).unwrap(), None);
+        assert_eq!(number.get_mut(&storage).unwrap(), None);
+        assert!(number.get_or_error(&storage).is_err());
+
+        // Nothing to write.
+        number.flush(&mut storage).unwrap();
+        assert!(!NUMBER.exists(&storage));
+
+        number.set(1);
+        assert!(number.is_dirty());
+
+        // The cached value is used from now on.
+        NUMBER.save(&mut storage, &5).unwrap();
+        assert_eq!(number.get(&storage).unwrap(), Some(&1));
+
+        number.flush(&mut storage).unwrap();
+        assert!(!number.is_dirty());
+        assert_eq!(NUMBER.load(&storage).unwrap(), Some(1));
+
+        let mut number = NUMBER.cached();
+        assert!(!number.is_dirty());
+        *number.get_mut(&storage).unwrap().unwrap() *= 10;
+        number.flush(&mut storage).unwrap();
+        assert_eq!(NUMBER.load(&storage).unwrap(), Some(10));
+    }
+
+    #[test]
+    fn lazy_loads_on_first_access() {
+        crate::namespace!(NumbersNs, b"numbers");
+        const NUMBERS: ItemSpace<u64, NumbersNs, TypedKey<u8>> = ItemSpace::new();
+
+        let mut storage = MockStorage::new();
+        NUMBERS.save(&mut storage, &1, &10).unwrap();
+
+        let mut number = NUMBERS.lazy(&1);
+        assert_eq!(number.key(), b"numbers\x01");
+        assert!(!number.is_loaded());
+
+        // Changes made before the first access are seen.
+        NUMBERS.save(&mut storage, &1, &20).unwrap();
+        assert_eq!(number.get(&storage).unwrap(), Some(&20));
+        assert!(number.is_loaded());
+
+        // But not the ones made after it.
+        NUMBERS.remove(&mut storage, &1);
+        assert_eq!(number.get_or_error(&storage).unwrap(), &20);
+        assert_eq!(number.into_inner(&storage).unwrap(), Some(20));
+
+        let mut missing = NUMBERS.lazy(&2);
+        let err = missing.get_or_error(&storage).unwrap_err();
+        assert_eq!(err, StdError::not_found("Storage load: u64 under key \"numbers\\x02\""));
+        assert_eq!(NUMBERS.lazy(&2).into_inner(&storage).unwrap(), None);
+    }
+
+    #[test]
+    fn delegates_storage_to_field() {
+        struct Counted(MockStorage, u32);
+        crate::delegate_storage!(Counted, 0);
+
+        let mut storage = Counted(MockStorage::new(), 0);
+        storage.1 += 1;
+
+        storage.set(b"a", b"1");
+        storage.set(b"b", b"2");
+        storage.remove(b"a");
+
+        assert_eq!(storage.get(b"b"), Some(b"2".to_vec()));
+        assert_eq!(
+            storage.range(None, None, crate::cosmwasm_std::Order::Ascending).collect::<Vec<_>>(),
+            vec![(b"b".to_vec(), b"2".to_vec())]
+        );
+        assert_eq!(storage.0.get(b"a"), None);
+    }
 }
@@ -0,0 +1,257 @@
+//! Memoizing reads so that a key which is read many times during
+//! a single message is only loaded from the underlying storage once.
+
+use std::{cell::RefCell, collections::BTreeMap};
+
+use crate::cosmwasm_std::{Storage, StdResult};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::cosmwasm_std::{Record, Order};
+
+/// Wraps a [`Storage`] and remembers the value of every key that is read
+/// through it, so that reading the same key again doesn't hit the underlying
+/// storage. Writes are applied to the underlying storage immediately and
+/// update the remembered values, so reads always see the latest state.
+///
+/// This is useful in handlers which are made up of several components that
+/// each load the same keys, i.e. the admin or the contract config, without
+/// being able to share the loaded values with each other. Use [`CachedItem`]
+/// instead when a single item is read and written many times by the same code.
+///
+/// The cache lives as long as the wrapper, so it should be created at the start
+/// of a message and dropped at the end of it. Nothing else may write to the
+/// underlying storage in the meantime.
+///
+/// [`CachedItem`]: super::CachedItem
+///
+/// # Examples
+///
+/// ```
+/// # use fadroma::{
+/// #     cosmwasm_std::{StdResult, testing::mock_dependencies},
+/// #     storage::{SingleItem, read_cache::{ReadCache, cached}}
+/// # };
+/// # fn main() -> StdResult<()> {
+/// # let mut deps = mock_dependencies();
+/// # let storage = deps.as_mut().storage;
+/// fadroma::namespace!(CountNs, b"count");
+/// const COUNT: SingleItem<u64, CountNs> = SingleItem::new();
+///
+/// COUNT.save(storage, &1)?;
+///
+/// let cache = ReadCache::read_only(storage);
+/// assert_eq!(COUNT.load(&cache)?, Some(1));
+/// // Doesn't read from the underlying storage again.
+/// assert_eq!(COUNT.load(&cache)?, Some(1));
+///
+/// cached(storage, |storage| {
+///     let count = COUNT.load_or(storage, 0)?;
+///     COUNT.save(storage, &(count + 1))?;
+///
+///     assert_eq!(COUNT.load(storage)?, Some(2));
+///
+///     Ok(())
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ReadCache<'a> {
+    storage: Inner<'a>,
+    /// [`None`] if the key doesn't exist.
+    reads: RefCell<BTreeMap<Vec<u8>, Option<Vec<u8>>>>
+}
+
+enum Inner<'a> {
+    ReadOnly(&'a dyn Storage),
+    Mutable(&'a mut dyn Storage)
+}
+
+impl<'a> ReadCache<'a> {
+    #[inline]
+    pub fn new(storage: &'a mut dyn Storage) -> Self {
+        Self::with_storage(Inner::Mutable(storage))
+    }
+
+    /// Wraps the storage of a query. Writing to the returned
+    /// cache panics, just like writing in a query does on chain.
+    #[inline]
+    pub fn read_only(storage: &'a dyn Storage) -> Self {
+        Self::with_storage(Inner::ReadOnly(storage))
+    }
+
+    /// Returns the number of keys whose value is currently remembered.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.reads.borrow().len()
+    }
+
+    /// Returns `true` if no key has been read or written yet.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.reads.borrow().is_empty()
+    }
+
+    /// Forgets all remembered values, i.e. after the
+    /// underlying storage has been written to directly.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.reads.get_mut().clear();
+    }
+
+    #[inline]
+    fn with_storage(storage: Inner<'a>) -> Self {
+        Self {
+            storage,
+            reads: RefCell::new(BTreeMap::new())
+        }
+    }
+
+    #[inline]
+    fn storage(&self) -> &dyn Storage {
+        match &self.storage {
+            Inner::ReadOnly(storage) => *storage,
+            Inner::Mutable(storage) => *storage
+        }
+    }
+
+    #[inline]
+    fn storage_mut(&mut self) -> &mut dyn Storage {
+        match &mut self.storage {
+            Inner::ReadOnly(_) => panic!("Attempted to write to a read-only ReadCache."),
+            Inner::Mutable(storage) => *storage
+        }
+    }
+}
+
+/// Runs `f` with a [`ReadCache`] over `storage`.
+#[inline]
+pub fn cached<T>(
+    storage: &mut dyn Storage,
+    f: impl FnOnce(&mut dyn Storage) -> StdResult<T>
+) -> StdResult<T> {
+    f(&mut ReadCache::new(storage))
+}
+
+impl<'a> Storage for ReadCache<'a> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        if let Some(value) = self.reads.borrow().get(key) {
+            return value.clone();
+        }
+
+        let value = self.storage().get(key);
+        self.reads.borrow_mut().insert(key.to_vec(), value.clone());
+
+        value
+    }
+
+    /// Ranges are always read from the underlying storage
+    /// since it is up to date with all the writes.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[inline]
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        self.storage().range(start, end, order)
+    }
+
+    #[inline]
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.storage_mut().set(key, value);
+        self.reads.get_mut().insert(key.to_vec(), Some(value.to_vec()));
+    }
+
+    #[inline]
+    fn remove(&mut self, key: &[u8]) {
+        self.storage_mut().remove(key);
+        self.reads.get_mut().insert(key.to_vec(), None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::cosmwasm_std::testing::MockStorage;
+
+    #[derive(Default)]
+    struct CountingStorage {
+        inner: MockStorage,
+        reads: Cell<u32>
+    }
+
+    impl Storage for CountingStorage {
+        fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+            self.reads.set(self.reads.get() + 1);
+            self.inner.get(key)
+        }
+
+        fn range<'a>(
+            &'a self,
+            start: Option<&[u8]>,
+            end: Option<&[u8]>,
+            order: Order,
+        ) -> Box<dyn Iterator<Item = Record> + 'a> {
+            self.inner.range(start, end, order)
+        }
+
+        fn set(&mut self, key: &[u8], value: &[u8]) {
+            self.inner.set(key, value);
+        }
+
+        fn remove(&mut self, key: &[u8]) {
+            self.inner.remove(key);
+        }
+    }
+
+    #[test]
+    fn repeated_reads_hit_storage_once() {
+        let ref mut storage = CountingStorage::default();
+        storage.set(b"admin", b"alice");
+
+        let mut cache = ReadCache::new(storage);
+        assert!(cache.is_empty());
+
+        for _ in 0..3 {
+            assert_eq!(cache.get(b"admin"), Some(b"alice".to_vec()));
+            assert_eq!(cache.get(b"missing"), None);
+        }
+
+        assert_eq!(cache.len(), 2);
+
+        cache.set(b"admin", b"bob");
+        cache.remove(b"missing");
+        cache.set(b"config", b"1");
+
+        assert_eq!(cache.get(b"admin"), Some(b"bob".to_vec()));
+        assert_eq!(cache.get(b"config"), Some(b"1".to_vec()));
+        assert_eq!(cache.get(b"missing"), None);
+        assert_eq!(
+            cache.range(None, None, Order::Ascending).collect::<Vec<_>>(),
+            vec![
+                (b"admin".to_vec(), b"bob".to_vec()),
+                (b"config".to_vec(), b"1".to_vec())
+            ]
+        );
+
+        cache.clear();
+        assert_eq!(cache.get(b"admin"), Some(b"bob".to_vec()));
+
+        drop(cache);
+
+        assert_eq!(storage.reads.get(), 3);
+        assert_eq!(storage.inner.get(b"admin"), Some(b"bob".to_vec()));
+        assert_eq!(storage.inner.get(b"config"), Some(b"1".to_vec()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Attempted to write to a read-only ReadCache.")]
+    fn read_only_cache_panics_on_write() {
+        let storage = MockStorage::new();
+        let mut cache = ReadCache::read_only(&storage);
+
+        cache.set(b"admin", b"alice");
+    }
+}
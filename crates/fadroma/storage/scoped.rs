@@ -0,0 +1,220 @@
+//! Namespaced views of a [`Storage`].
+
+use crate::cosmwasm_std::Storage;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::cosmwasm_std::{Record, Order};
+
+/// Wraps a [`Storage`] and transparently prefixes every key read or written through
+/// it with `scope`. This allows reusable components to use any keys they like without
+/// colliding with the keys of the contract or other components, as long as each is
+/// given a unique scope. Scopes can be nested by wrapping a `Scoped` in another.
+///
+/// The scope is prefixed with its length as a big-endian [`u16`], the same way
+/// as the `to_length_prefixed` function of CosmWasm does it, so that a scope
+/// that is a prefix of another one can't collide with it. Panics if the scope is
+/// longer than [`u16::MAX`] bytes.
+///
+/// Use [`ReadonlyScoped`] in queries.
+///
+/// # Examples
+///
+/// ```
+/// # use fadroma::{
+/// #     cosmwasm_std::{StdResult, testing::mock_dependencies},
+/// #     storage::{SingleItem, scoped::{Scoped, ReadonlyScoped}}
+/// # };
+/// # fn main() -> StdResult<()> {
+/// # let mut deps = mock_dependencies();
+/// # let storage = deps.as_mut().storage;
+/// fadroma::namespace!(ConfigNs, b"config");
+/// const CONFIG: SingleItem<u64, ConfigNs> = SingleItem::new();
+///
+/// CONFIG.save(storage, &1)?;
+/// CONFIG.save(&mut Scoped::new(storage, b"rewards"), &2)?;
+///
+/// assert_eq!(CONFIG.load(storage)?, Some(1));
+/// assert_eq!(CONFIG.load(&ReadonlyScoped::new(storage, b"rewards"))?, Some(2));
+/// # Ok(())
+/// # }
+/// ```
+pub struct Scoped<'a> {
+    storage: &'a mut dyn Storage,
+    prefix: Vec<u8>
+}
+
+/// The read-only counterpart of [`Scoped`]. Writing to it panics.
+pub struct ReadonlyScoped<'a> {
+    storage: &'a dyn Storage,
+    prefix: Vec<u8>
+}
+
+impl<'a> Scoped<'a> {
+    #[inline]
+    pub fn new(storage: &'a mut dyn Storage, scope: &[u8]) -> Self {
+        Self { storage, prefix: prefix(scope) }
+    }
+}
+
+impl<'a> ReadonlyScoped<'a> {
+    #[inline]
+    pub fn new(storage: &'a dyn Storage, scope: &[u8]) -> Self {
+        Self { storage, prefix: prefix(scope) }
+    }
+}
+
+impl<'a> Storage for Scoped<'a> {
+    #[inline]
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.storage.get(&[self.prefix.as_slice(), key].concat())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[inline]
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        range(self.storage, &self.prefix, start, end, order)
+    }
+
+    #[inline]
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.storage.set(&[self.prefix.as_slice(), key].concat(), value)
+    }
+
+    #[inline]
+    fn remove(&mut self, key: &[u8]) {
+        self.storage.remove(&[self.prefix.as_slice(), key].concat())
+    }
+}
+
+impl<'a> Storage for ReadonlyScoped<'a> {
+    #[inline]
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.storage.get(&[self.prefix.as_slice(), key].concat())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[inline]
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        range(self.storage, &self.prefix, start, end, order)
+    }
+
+    fn set(&mut self, _key: &[u8], _value: &[u8]) {
+        panic!("Cannot write to ReadonlyScoped storage.")
+    }
+
+    fn remove(&mut self, _key: &[u8]) {
+        panic!("Cannot write to ReadonlyScoped storage.")
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn range<'a>(
+    storage: &'a dyn Storage,
+    prefix: &'a [u8],
+    start: Option<&[u8]>,
+    end: Option<&[u8]>,
+    order: Order,
+) -> Box<dyn Iterator<Item = Record> + 'a> {
+    let start = [prefix, start.unwrap_or_default()].concat();
+    let end = match end {
+        Some(end) => Some([prefix, end].concat()),
+        None => scope_end(prefix)
+    };
+
+    let iter = storage.range(Some(&start), end.as_deref(), order)
+        .map(move |(key, value)| (key[prefix.len()..].to_vec(), value));
+
+    Box::new(iter)
+}
+
+/// The length prefixed scope that is prepended to every key.
+fn prefix(scope: &[u8]) -> Vec<u8> {
+    let len = u16::try_from(scope.len()).expect("The scope is longer than 65535 bytes.");
+
+    [len.to_be_bytes().as_slice(), scope].concat()
+}
+
+/// Returns the first key after all keys that start with `scope`
+/// or [`None`] if there isn't one, i.e. if it only contains `0xFF`.
+#[cfg(not(target_arch = "wasm32"))]
+fn scope_end(scope: &[u8]) -> Option<Vec<u8>> {
+    let mut end = scope.to_vec();
+
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+
+            return Some(end);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cosmwasm_std::testing::MockStorage;
+
+    #[test]
+    fn scoped_storage_is_isolated() {
+        let ref mut storage = MockStorage::new();
+        storage.set(b"a", b"0");
+        storage.set(b"\x00\x07rewardsa", b"1");
+        // Host keys that start with the scope are not visible in it.
+        storage.set(b"rewardsz", b"2");
+
+        let mut rewards = Scoped::new(storage, b"rewards");
+        assert_eq!(rewards.get(b"a"), Some(b"1".to_vec()));
+        assert_eq!(rewards.get(b"z"), None);
+
+        rewards.set(b"b", b"3");
+
+        let mut nested = Scoped::new(&mut rewards, b"b");
+        nested.set(b"c", b"4");
+        nested.remove(b"missing");
+
+        rewards.remove(b"a");
+
+        assert_eq!(
+            rewards.range(None, None, Order::Ascending).collect::<Vec<_>>(),
+            vec![(b"\x00\x01bc".to_vec(), b"4".to_vec()), (b"b".to_vec(), b"3".to_vec())]
+        );
+
+        let rewards = ReadonlyScoped::new(storage, b"rewards");
+        assert_eq!(
+            rewards.range(Some(b"\x00\x01bb"), Some(b"\x00\x02"), Order::Descending).collect::<Vec<_>>(),
+            vec![(b"\x00\x01bc".to_vec(), b"4".to_vec())]
+        );
+
+        assert_eq!(storage.get(b"a"), Some(b"0".to_vec()));
+        assert_eq!(storage.get(b"\x00\x07rewards\x00\x01bc"), Some(b"4".to_vec()));
+        assert_eq!(scope_end(b"a\xFF\xFF"), Some(b"b".to_vec()));
+        assert_eq!(scope_end(b"\xFF"), None);
+    }
+
+    #[test]
+    fn scopes_that_are_prefixes_dont_collide() {
+        let ref mut storage = MockStorage::new();
+
+        Scoped::new(storage, b"reward").set(b"s1", b"1");
+        Scoped::new(storage, b"rewards").set(b"1", b"2");
+
+        assert_eq!(ReadonlyScoped::new(storage, b"reward").get(b"s1"), Some(b"1".to_vec()));
+        assert_eq!(ReadonlyScoped::new(storage, b"rewards").get(b"1"), Some(b"2".to_vec()));
+
+        assert_eq!(
+            ReadonlyScoped::new(storage, b"reward").range(None, None, Order::Ascending).collect::<Vec<_>>(),
+            vec![(b"s1".to_vec(), b"1".to_vec())]
+        );
+    }
+}
@@ -0,0 +1,285 @@
+//! A fixed-capacity series of numbers, i.e. recent prices or volumes, together
+//! with helpers for computing statistics over it using checked integer math.
+
+use std::marker::PhantomData;
+
+use crate::{
+    self as fadroma,
+    bin_serde::{FadromaSerialize, FadromaDeserialize},
+    cosmwasm_std::{Storage, StdResult, StdError, Uint128, Uint256, Decimal}
+};
+use super::Namespace;
+
+const META_KEY: &[u8] = b"meta";
+
+/// Storage type that keeps the last `CAPACITY` values pushed to it, overwriting the oldest
+/// one once it is full. It never occupies more than `CAPACITY` + 1 keys and pushing a value
+/// only reads and writes a constant number of them. The statistics helpers read all values.
+///
+/// # Examples
+///
+/// ```
+/// # use fadroma::{
+/// #     cosmwasm_std::{StdResult, Uint128, testing::mock_dependencies},
+/// #     storage::series::Series
+/// # };
+/// # fn main() -> StdResult<()> {
+/// # let mut deps = mock_dependencies();
+/// # let storage = deps.as_mut().storage;
+/// fadroma::namespace!(PricesNs, b"prices");
+/// const PRICES: Series<PricesNs, 3> = Series::new();
+///
+/// for price in [5, 1, 3, 2] {
+///     PRICES.push(storage, Uint128::new(price))?;
+/// }
+///
+/// // The first price has been overwritten.
+/// assert_eq!(PRICES.values(storage)?, vec![Uint128::new(1), Uint128::new(3), Uint128::new(2)]);
+/// assert_eq!(PRICES.median(storage)?, Some(Uint128::new(2)));
+/// assert_eq!(PRICES.max(storage)?, Some(Uint128::new(3)));
+/// # Ok(())
+/// # }
+/// ```
+pub struct Series<N: Namespace, const CAPACITY: u32> {
+    namespace_data: PhantomData<N>
+}
+
+#[derive(FadromaSerialize, FadromaDeserialize, Default, Clone, Copy)]
+struct Meta {
+    /// The position that the next value is written to.
+    next: u32,
+    len: u32
+}
+
+impl<N: Namespace, const CAPACITY: u32> Series<N, CAPACITY> {
+    #[inline]
+    pub const fn new() -> Self {
+        assert!(CAPACITY > 0, "The capacity of a Series must be greater than zero.");
+
+        Self { namespace_data: PhantomData }
+    }
+
+    #[inline]
+    pub fn len(&self, storage: &dyn Storage) -> StdResult<u32> {
+        Ok(Self::meta(storage)?.len)
+    }
+
+    #[inline]
+    pub fn is_empty(&self, storage: &dyn Storage) -> StdResult<bool> {
+        Ok(self.len(storage)? == 0)
+    }
+
+    /// Appends `value`. Returns the oldest value if it was overwritten.
+    pub fn push(&self, storage: &mut dyn Storage, value: Uint128) -> StdResult<Option<Uint128>> {
+        let mut meta = Self::meta(storage)?;
+        let key = Self::key(meta.next);
+
+        let evicted = if meta.len == CAPACITY {
+            super::load(storage, &key)?
+        } else {
+            meta.len += 1;
+
+            None
+        };
+
+        super::save(storage, key, &value)?;
+
+        meta.next = (meta.next + 1) % CAPACITY;
+        super::save(storage, [N::NAMESPACE, META_KEY].concat(), &meta)?;
+
+        Ok(evicted)
+    }
+
+    /// Returns the most recently pushed value.
+    pub fn latest(&self, storage: &dyn Storage) -> StdResult<Option<Uint128>> {
+        let meta = Self::meta(storage)?;
+
+        if meta.len == 0 {
+            return Ok(None);
+        }
+
+        super::load(storage, Self::key((meta.next + CAPACITY - 1) % CAPACITY))
+    }
+
+    /// Returns all values, from the oldest to the most recent.
+    pub fn values(&self, storage: &dyn Storage) -> StdResult<Vec<Uint128>> {
+        let meta = Self::meta(storage)?;
+        let start = (meta.next + CAPACITY - meta.len) % CAPACITY;
+
+        let mut result = Vec::with_capacity(meta.len as usize);
+
+        for i in 0..meta.len {
+            let value = super::load(storage, Self::key((start + i) % CAPACITY))?;
+            result.push(value.unwrap_or_default());
+        }
+
+        Ok(result)
+    }
+
+    #[inline]
+    pub fn median(&self, storage: &dyn Storage) -> StdResult<Option<Uint128>> {
+        Ok(median(&mut self.values(storage)?))
+    }
+
+    #[inline]
+    pub fn mean(&self, storage: &dyn Storage) -> StdResult<Option<Uint128>> {
+        mean(&self.values(storage)?)
+    }
+
+    #[inline]
+    pub fn min(&self, storage: &dyn Storage) -> StdResult<Option<Uint128>> {
+        Ok(self.values(storage)?.into_iter().min())
+    }
+
+    #[inline]
+    pub fn max(&self, storage: &dyn Storage) -> StdResult<Option<Uint128>> {
+        Ok(self.values(storage)?.into_iter().max())
+    }
+
+    /// See [`ema`].
+    #[inline]
+    pub fn ema(&self, storage: &dyn Storage, alpha: Decimal) -> StdResult<Option<Uint128>> {
+        ema(&self.values(storage)?, alpha)
+    }
+
+    #[inline]
+    fn meta(storage: &dyn Storage) -> StdResult<Meta> {
+        let meta = super::load(storage, [N::NAMESPACE, META_KEY].concat())?;
+
+        Ok(meta.unwrap_or_default())
+    }
+
+    #[inline]
+    fn key(position: u32) -> Vec<u8> {
+        [N::NAMESPACE, &position.to_be_bytes()].concat()
+    }
+}
+
+/// Returns the median of `values`, which are sorted in place. If there is an even
+/// number of values, the mean of the two middle ones is returned, rounded down.
+///
+/// # Examples
+///
+/// ```
+/// # use fadroma::{storage::series::median, cosmwasm_std::Uint128};
+/// let mut values = [7, 1, 4, 2].map(Uint128::new);
+///
+/// assert_eq!(median(&mut values), Some(Uint128::new(3)));
+/// assert_eq!(median(&mut []), None);
+/// ```
+pub fn median(values: &mut [Uint128]) -> Option<Uint128> {
+    if values.is_empty() {
+        return None;
+    }
+
+    values.sort_unstable();
+
+    let mid = values.len() / 2;
+
+    if values.len() % 2 == 1 {
+        return Some(values[mid]);
+    }
+
+    let (a, b) = (values[mid - 1], values[mid]);
+
+    // Can't overflow unlike (a + b) / 2
+    Some(a + (b - a) / Uint128::new(2))
+}
+
+/// Returns the arithmetic mean of `values`, rounded down.
+pub fn mean(values: &[Uint128]) -> StdResult<Option<Uint128>> {
+    if values.is_empty() {
+        return Ok(None);
+    }
+
+    let mut sum = Uint256::zero();
+
+    for value in values {
+        sum = sum.checked_add((*value).into())?;
+    }
+
+    let mean = sum / Uint256::from(values.len() as u128);
+
+    // The mean can never be greater than the largest value.
+    Ok(Some(mean.try_into()?))
+}
+
+/// Returns the exponential moving average of `values`, from the oldest to the most recent,
+/// where `alpha` is the weight of each new value in `(0, 1]`. The first value is used as the
+/// initial average. Each step is rounded down.
+///
+/// # Examples
+///
+/// ```
+/// # use fadroma::{storage::series::ema, cosmwasm_std::{Uint128, Decimal, StdResult}};
+/// # fn main() -> StdResult<()> {
+/// let values = [100, 200, 200].map(Uint128::new);
+///
+/// // 100 -> 150 -> 175
+/// assert_eq!(ema(&values, Decimal::percent(50))?, Some(Uint128::new(175)));
+/// # Ok(())
+/// # }
+/// ```
+pub fn ema(values: &[Uint128], alpha: Decimal) -> StdResult<Option<Uint128>> {
+    if alpha.is_zero() || alpha > Decimal::one() {
+        return Err(StdError::generic_err(
+            "The smoothing factor must be greater than 0 and at most 1."
+        ));
+    }
+
+    let Some((first, rest)) = values.split_first() else {
+        return Ok(None);
+    };
+
+    let one = Decimal::one().atomics();
+    let alpha = alpha.atomics();
+
+    let mut average = *first;
+
+    for value in rest {
+        // Neither can overflow since alpha is at most 1.
+        let new = value.multiply_ratio(alpha, one);
+        let old = average.multiply_ratio(one - alpha, one);
+
+        average = new.checked_add(old)?;
+    }
+
+    Ok(Some(average))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cosmwasm_std::testing::MockStorage;
+
+    crate::namespace!(VolumeNs, b"volume");
+    const VOLUME: Series<VolumeNs, 4> = Series::new();
+
+    #[test]
+    fn series_overwrites_oldest() {
+        let ref mut storage = MockStorage::new();
+
+        assert_eq!(VOLUME.latest(storage).unwrap(), None);
+        assert_eq!(VOLUME.median(storage).unwrap(), None);
+        assert_eq!(VOLUME.mean(storage).unwrap(), None);
+
+        for i in 1..=4 {
+            assert_eq!(VOLUME.push(storage, Uint128::new(i * 10)).unwrap(), None);
+        }
+
+        assert_eq!(VOLUME.push(storage, Uint128::new(u128::MAX)).unwrap(), Some(Uint128::new(10)));
+        assert_eq!(VOLUME.push(storage, Uint128::new(u128::MAX)).unwrap(), Some(Uint128::new(20)));
+
+        assert_eq!(VOLUME.len(storage).unwrap(), 4);
+        assert_eq!(VOLUME.latest(storage).unwrap(), Some(Uint128::MAX));
+        assert_eq!(VOLUME.min(storage).unwrap(), Some(Uint128::new(30)));
+
+        // No overflows
+        assert_eq!(VOLUME.median(storage).unwrap(), Some(Uint128::new(40 + (u128::MAX - 40) / 2)));
+        assert_eq!(VOLUME.mean(storage).unwrap(), Some(Uint128::new(u128::MAX / 2 + 18)));
+        assert_eq!(VOLUME.ema(storage, Decimal::one()).unwrap(), Some(Uint128::MAX));
+        assert!(VOLUME.ema(storage, Decimal::zero()).is_err());
+
+        assert_eq!(storage.range(None, None, crate::cosmwasm_std::Order::Ascending).count(), 5);
+    }
+}
@@ -5,7 +5,7 @@ use crate::{
     bin_serde::{FadromaSerialize, FadromaDeserialize},
     cosmwasm_std::{Deps, DepsMut, Storage, StdResult}
 };
-use super::{Namespace, not_found_error};
+use super::{Namespace, key_not_found_error};
 
 /// Storage type that stores a single item under the given [`Namespace`].
 /// Use this when there is only "one of" something.
@@ -73,7 +73,19 @@ impl<T: FadromaSerialize + FadromaDeserialize, N: Namespace> SingleItem<T, N> {
     ) -> StdResult<T> {
         let result: Option<T> = self.load(storage)?;
 
-        result.ok_or_else(|| not_found_error::<T>())
+        result.ok_or_else(|| key_not_found_error::<T>(N::NAMESPACE))
+    }
+
+    /// Returns `default` if no item has been saved.
+    #[inline]
+    pub fn load_or(
+        &self,
+        storage: &dyn Storage,
+        default: T
+    ) -> StdResult<T> {
+        let result: Option<T> = self.load(storage)?;
+
+        Ok(result.unwrap_or(default))
     }
 
     #[inline]
@@ -84,6 +96,49 @@ impl<T: FadromaSerialize + FadromaDeserialize, N: Namespace> SingleItem<T, N> {
         super::remove(storage, N::NAMESPACE)
     }
 
+    /// Loads the item, passes it to `update` and saves the result.
+    /// Returns the updated item. Nothing is saved if `update` returns an error.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use fadroma::{cosmwasm_std::{StdResult, testing::mock_dependencies}, storage::SingleItem};
+    /// # fn main() -> StdResult<()> {
+    /// # let mut deps = mock_dependencies();
+    /// # let storage = deps.as_mut().storage;
+    /// fadroma::namespace!(CounterNs, b"counter");
+    /// const COUNTER: SingleItem::<u64, CounterNs> = SingleItem::new();
+    /// 
+    /// let increment = |x: Option<u64>| Ok(x.unwrap_or_default() + 1);
+    /// 
+    /// assert_eq!(COUNTER.update(storage, increment)?, 1);
+    /// assert_eq!(COUNTER.update(storage, increment)?, 2);
+    /// assert_eq!(COUNTER.load(storage)?, Some(2));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn update<F>(
+        &self,
+        storage: &mut dyn Storage,
+        update: F
+    ) -> StdResult<T>
+        where F: FnOnce(Option<T>) -> StdResult<T>
+    {
+        let item = update(self.load(storage)?)?;
+        self.save(storage, &item)?;
+
+        Ok(item)
+    }
+
+    /// Returns `true` if the item is set. Doesn't deserialize it.
+    #[inline]
+    pub fn exists(
+        &self,
+        storage: &dyn Storage,
+    ) -> bool {
+        super::exists(storage, N::NAMESPACE)
+    }
+
     #[inline]
     pub fn canonize_and_save<Input: Canonize<Output = T>>(
         &self,
@@ -120,7 +175,7 @@ impl<
     ) -> StdResult<<T as Humanize>::Output> {
         let result = self.load_humanize(deps)?;
 
-        result.ok_or_else(|| not_found_error::<T>())
+        result.ok_or_else(|| key_not_found_error::<T>(N::NAMESPACE))
     }
 }
 
@@ -0,0 +1,397 @@
+//! Compact probabilistic sets for when storing every item exactly would cost
+//! too much gas: [`HyperLogLog`] estimates how many unique items were added
+//! and [`BloomFilter`] tells whether an item may have been added before.
+//!
+//! Both hash the items with a fixed function that must never change, since
+//! that would make the items that were already added unrecognizable. Only
+//! integer arithmetic is used so that they can be used in contracts.
+
+use std::marker::PhantomData;
+
+use crate::cosmwasm_std::{Storage, StdResult, StdError};
+use super::{Namespace, SingleItem, ItemSpace, TypedKey};
+
+/// Estimates the number of unique items that were added to it, using a fixed
+/// `2^P` bytes of storage no matter how many there are. The standard error of
+/// the estimate is about `1.04 / sqrt(2^P)`, i.e. 6.5% for the default `P` of `8`.
+/// `P` must be between `4` and `16`.
+///
+/// Each item costs a read and only costs a write if it changes the estimate,
+/// which becomes rare as the number of items grows.
+///
+/// # Examples
+///
+/// ```
+/// # use fadroma::{
+/// #     cosmwasm_std::{StdResult, testing::mock_dependencies},
+/// #     storage::sketch::HyperLogLog
+/// # };
+/// # fn main() -> StdResult<()> {
+/// # let mut deps = mock_dependencies();
+/// # let storage = deps.as_mut().storage;
+/// fadroma::namespace!(VotersNs, b"voters");
+/// const VOTERS: HyperLogLog<VotersNs> = HyperLogLog::new();
+///
+/// for voter in ["alice", "bob", "alice", "carol"] {
+///     VOTERS.insert(storage, voter.as_bytes())?;
+/// }
+///
+/// assert_eq!(VOTERS.estimate(storage)?, 3);
+/// # Ok(())
+/// # }
+/// ```
+pub struct HyperLogLog<N: Namespace, const P: u32 = 8> {
+    namespace_data: PhantomData<N>
+}
+
+impl<N: Namespace, const P: u32> HyperLogLog<N, P> {
+    const REGISTERS: SingleItem<Vec<u8>, N> = SingleItem::new();
+
+    #[inline]
+    pub const fn new() -> Self {
+        assert!(P >= 4 && P <= 16, "The precision of a HyperLogLog must be between 4 and 16.");
+
+        Self { namespace_data: PhantomData }
+    }
+
+    /// Adds `item` to the set. Returns `true` if the estimate may have changed.
+    pub fn insert(&self, storage: &mut dyn Storage, item: &[u8]) -> StdResult<bool> {
+        let mut registers = Self::load_registers(storage)?
+            .unwrap_or_else(|| vec![0; 1 << P]);
+
+        let hash = hash(item);
+        let index = (hash >> (64 - P)) as usize;
+        // The position of the first set bit in the rest of the hash.
+        let rank = ((hash << P).leading_zeros().min(64 - P) + 1) as u8;
+
+        if registers[index] >= rank {
+            return Ok(false);
+        }
+
+        registers[index] = rank;
+        Self::REGISTERS.save(storage, &registers)?;
+
+        Ok(true)
+    }
+
+    /// Returns the estimated number of unique items that were added.
+    pub fn estimate(&self, storage: &dyn Storage) -> StdResult<u64> {
+        let Some(registers) = Self::load_registers(storage)? else {
+            return Ok(0);
+        };
+
+        Ok(estimate(&registers, P))
+    }
+
+    #[inline]
+    pub fn clear(&self, storage: &mut dyn Storage) {
+        Self::REGISTERS.remove(storage);
+    }
+
+    /// Returns an error if the stored registers weren't written by a
+    /// `HyperLogLog` with the same precision, i.e. if `P` was changed.
+    fn load_registers(storage: &dyn Storage) -> StdResult<Option<Vec<u8>>> {
+        let Some(registers) = Self::REGISTERS.load(storage)? else {
+            return Ok(None);
+        };
+
+        // No rank can exceed the number of hash bits left after the index, plus one.
+        if registers.len() != 1 << P || registers.iter().any(|x| *x as u32 > 65 - P) {
+            return Err(StdError::generic_err(format!(
+                "The stored HyperLogLog registers don't match a precision of {}.",
+                P
+            )));
+        }
+
+        Ok(Some(registers))
+    }
+}
+
+/// Tells whether an item may have been added to it. An item that was added is
+/// always recognized, but an item that wasn't may also be reported as added,
+/// and the more items there are the more likely that is. Items can't be removed.
+///
+/// The filter is split into `BLOCKS` blocks of 512 bits, each stored under
+/// its own key, and all `HASHES` bits of an item are in the same block so
+/// that each operation only reads and writes a single 64 byte block. Choose
+/// the parameters based on the expected number of items `n`:
+///
+/// | Bits per item | `BLOCKS`  | `HASHES` | False positive rate |
+/// |---------------|-----------|----------|---------------------|
+/// | 8             | `n / 64`  | 5        | ~2.5%               |
+/// | 16            | `n / 32`  | 8        | ~0.1%               |
+///
+/// # Examples
+///
+/// ```
+/// # use fadroma::{
+/// #     cosmwasm_std::{StdResult, testing::mock_dependencies},
+/// #     storage::sketch::BloomFilter
+/// # };
+/// # fn main() -> StdResult<()> {
+/// # let mut deps = mock_dependencies();
+/// # let storage = deps.as_mut().storage;
+/// fadroma::namespace!(ClaimedNs, b"claimed");
+/// // Up to ~64000 claims with a false positive rate of ~0.1%.
+/// const CLAIMED: BloomFilter<ClaimedNs, 2000, 8> = BloomFilter::new();
+///
+/// assert!(CLAIMED.insert(storage, b"alice")?);
+/// assert!(!CLAIMED.insert(storage, b"alice")?);
+///
+/// assert!(CLAIMED.contains(storage, b"alice")?);
+/// assert!(!CLAIMED.contains(storage, b"bob")?);
+/// # Ok(())
+/// # }
+/// ```
+pub struct BloomFilter<N: Namespace, const BLOCKS: u32, const HASHES: u8> {
+    namespace_data: PhantomData<N>
+}
+
+impl<N: Namespace, const BLOCKS: u32, const HASHES: u8> BloomFilter<N, BLOCKS, HASHES> {
+    const BLOCK_BYTES: usize = 64;
+    const BLOCK_DATA: ItemSpace<Vec<u8>, N, TypedKey<'static, u32>> = ItemSpace::new();
+
+    #[inline]
+    pub const fn new() -> Self {
+        assert!(BLOCKS > 0, "A BloomFilter must have at least one block.");
+        assert!(HASHES > 0, "A BloomFilter must use at least one hash.");
+
+        Self { namespace_data: PhantomData }
+    }
+
+    /// Adds `item` to the filter. Returns `false` if it may have been added already.
+    pub fn insert(&self, storage: &mut dyn Storage, item: &[u8]) -> StdResult<bool> {
+        let (block, bits) = Self::locate(item);
+
+        let mut data = Self::load_block(storage, block)?
+            .unwrap_or_else(|| vec![0; Self::BLOCK_BYTES]);
+
+        let mut added = false;
+
+        for bit in bits {
+            let (byte, mask) = (bit / 8, 1 << (bit % 8));
+
+            if data[byte] & mask == 0 {
+                data[byte] |= mask;
+                added = true;
+            }
+        }
+
+        if added {
+            Self::BLOCK_DATA.save(storage, &block, &data)?;
+        }
+
+        Ok(added)
+    }
+
+    /// Returns `true` if `item` may have been added to the filter
+    /// and `false` if it definitely wasn't.
+    pub fn contains(&self, storage: &dyn Storage, item: &[u8]) -> StdResult<bool> {
+        let (block, mut bits) = Self::locate(item);
+
+        let Some(data) = Self::load_block(storage, block)? else {
+            return Ok(false);
+        };
+
+        Ok(bits.all(|bit| data[bit / 8] & (1 << (bit % 8)) != 0))
+    }
+
+    /// Removes every block of the filter, which costs a write per block.
+    pub fn clear(&self, storage: &mut dyn Storage) {
+        for block in 0..BLOCKS {
+            Self::BLOCK_DATA.remove(storage, &block);
+        }
+    }
+
+    fn load_block(storage: &dyn Storage, block: u32) -> StdResult<Option<Vec<u8>>> {
+        let data = Self::BLOCK_DATA.load(storage, &block)?;
+
+        if data.as_ref().is_some_and(|x| x.len() != Self::BLOCK_BYTES) {
+            return Err(StdError::generic_err(format!(
+                "Block {} of the BloomFilter is not {} bytes long.",
+                block,
+                Self::BLOCK_BYTES
+            )));
+        }
+
+        Ok(data)
+    }
+
+    /// Returns the block of `item` and the positions of its bits within it.
+    fn locate(item: &[u8]) -> (u32, impl Iterator<Item = usize>) {
+        let hash = hash(item);
+        let block = ((hash as u128 * BLOCKS as u128) >> 64) as u32;
+
+        // Double hashing with an independent second hash.
+        let hash = mix(hash);
+        let (h1, h2) = (hash as u32, (hash >> 32) as u32 | 1);
+        let bits = Self::BLOCK_BYTES as u32 * 8;
+
+        let positions = (0..HASHES as u32).map(move |i|
+            (h1.wrapping_add(i.wrapping_mul(h2)) % bits) as usize
+        );
+
+        (block, positions)
+    }
+}
+
+/// The HyperLogLog estimate for `2^precision` registers.
+fn estimate(registers: &[u8], precision: u32) -> u64 {
+    // ln(2) in 32.32 fixed point.
+    const LN_2: u128 = 2_977_044_472;
+
+    let m = 1u128 << precision;
+    let zeros = registers.iter().filter(|x| **x == 0).count() as u64;
+
+    // The sum of 2^-register in 64.64 fixed point.
+    let sum: u128 = registers.iter().map(|x| 1u128 << (64 - *x as u32)).sum();
+
+    // alpha * m^2 / sum with alpha = 0.7213 / (1 + 1.079 / m).
+    let raw = ((7213 * m * m * m) << 64) / (10 * (1000 * m + 1079) * sum);
+    let raw = raw as u64;
+
+    if raw as u128 > 5 * m / 2 || zeros == 0 {
+        return raw;
+    }
+
+    // Linear counting for small cardinalities: m * ln(m / zeros).
+    let log = ((precision as u64) << 32) - log2(zeros);
+
+    ((m * log as u128 * LN_2 + (1 << 63)) >> 64) as u64
+}
+
+/// The base 2 logarithm of `x` in 32.32 fixed point. `x` must not be `0`.
+fn log2(x: u64) -> u64 {
+    let integer = 63 - x.leading_zeros() as u64;
+    let mut result = integer << 32;
+
+    // x / 2^integer in [1, 2) as 32.32 fixed point.
+    let mut y = ((x as u128) << 32) >> integer;
+
+    for bit in (0..32).rev() {
+        y = (y * y) >> 32;
+
+        if y >= 2 << 32 {
+            y >>= 1;
+            result |= 1 << bit;
+        }
+    }
+
+    result
+}
+
+/// FNV-1a followed by a finalizer so that all
+/// bits of the result depend on all bytes of `item`.
+fn hash(item: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+
+    for byte in item {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+
+    mix(hash)
+}
+
+/// The SplitMix64 finalizer.
+#[inline]
+fn mix(mut hash: u64) -> u64 {
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+
+    hash ^ (hash >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cosmwasm_std::testing::MockStorage;
+
+    crate::namespace!(SketchNs, b"sketch");
+
+    #[test]
+    fn hyperloglog_estimates_unique_items() {
+        for (count, tolerance) in [(10, 0), (100, 5), (1_000, 100), (20_000, 2_000)] {
+            let ref mut storage = MockStorage::new();
+            let sketch = HyperLogLog::<SketchNs>::new();
+
+            for i in 0..count {
+                sketch.insert(storage, format!("account{}", i).as_bytes()).unwrap();
+                // Adding the same item again changes nothing.
+                assert!(!sketch.insert(storage, format!("account{}", i).as_bytes()).unwrap());
+            }
+
+            let estimate = sketch.estimate(storage).unwrap();
+            assert!(estimate.abs_diff(count) <= tolerance, "{}: {}", count, estimate);
+
+            sketch.clear(storage);
+            assert_eq!(sketch.estimate(storage).unwrap(), 0);
+        }
+
+        // Higher precision is more accurate.
+        let ref mut storage = MockStorage::new();
+        let sketch = HyperLogLog::<SketchNs, 14>::new();
+
+        for i in 0..5_000 {
+            sketch.insert(storage, format!("account{}", i).as_bytes()).unwrap();
+        }
+
+        assert!(sketch.estimate(storage).unwrap().abs_diff(5_000) <= 150);
+    }
+
+    #[test]
+    fn bloom_filter_has_no_false_negatives() {
+        let ref mut storage = MockStorage::new();
+        let filter = BloomFilter::<SketchNs, 32, 8>::new();
+
+        // 16 bits per item. Inserting an item that wasn't added can
+        // return `false` too, when it's a false positive.
+        let added = (0..1_000)
+            .filter(|i| filter.insert(storage, format!("added{}", i).as_bytes()).unwrap())
+            .count();
+
+        assert!(added > 990, "{}", added);
+
+        for i in 0..1_000 {
+            assert!(filter.contains(storage, format!("added{}", i).as_bytes()).unwrap());
+        }
+
+        let false_positives = (0..10_000)
+            .filter(|i| filter.contains(storage, format!("other{}", i).as_bytes()).unwrap())
+            .count();
+
+        assert!(false_positives < 50, "{}", false_positives);
+
+        filter.clear(storage);
+        assert!(!filter.contains(storage, b"added0").unwrap());
+    }
+
+    #[test]
+    fn rejects_mismatched_data() {
+        let ref mut storage = MockStorage::new();
+
+        HyperLogLog::<SketchNs>::new().insert(storage, b"alice").unwrap();
+
+        let sketch = HyperLogLog::<SketchNs, 10>::new();
+        assert!(sketch.insert(storage, b"bob").is_err());
+        assert!(sketch.estimate(storage).is_err());
+
+        HyperLogLog::<SketchNs>::REGISTERS.save(storage, &vec![100; 256]).unwrap();
+        assert!(HyperLogLog::<SketchNs>::new().estimate(storage).is_err());
+
+        type Filter = BloomFilter<SketchNs, 32, 8>;
+        let (block, _) = Filter::locate(b"alice");
+
+        Filter::BLOCK_DATA.save(storage, &block, &vec![0; 10]).unwrap();
+        assert!(Filter::new().insert(storage, b"alice").is_err());
+        assert!(Filter::new().contains(storage, b"alice").is_err());
+    }
+
+    #[test]
+    fn log2_is_precise() {
+        assert_eq!(log2(1), 0);
+        assert_eq!(log2(256), 8 << 32);
+        // log2(3) = 1.5849625007, accurate to 8 decimal places.
+        assert!(((log2(3) as u128 * 10_000_000_000) >> 32).abs_diff(15_849_625_007) < 100);
+    }
+}
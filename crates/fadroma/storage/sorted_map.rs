@@ -0,0 +1,460 @@
+//! A map with numeric keys that can be traversed in order.
+
+use std::{
+    marker::PhantomData,
+    ops::{Bound, RangeBounds}
+};
+
+use crate::{
+    self as fadroma,
+    bin_serde::{FadromaSerialize, FadromaDeserialize},
+    cosmwasm_std::{Storage, StdResult, StdError}
+};
+use super::Namespace;
+
+const VALUE_NS: &[u8] = b"val";
+const PAGE_NS: &[u8] = b"page";
+const DIRECTORY_KEY: &[u8] = b"dir";
+
+/// The maximum number of keys in a page before it is split in two.
+const PAGE_CAPACITY: usize = 64;
+
+/// Storage type that stores values by a numeric key and can return them ordered by it,
+/// i.e. price levels, expiration timestamps or epochs. Since the storage of a contract
+/// cannot be iterated over, the keys are also kept sorted in pages of up to 64 keys.
+/// A small directory of the pages is loaded by every operation that changes the order or
+/// traverses it, so that only the pages within the requested range have to be loaded.
+/// Looking up a single key only reads its value.
+///
+/// Keys are encoded as big-endian [`u128`] so that the keys of the values are sorted
+/// in the same order as the numbers themselves.
+///
+/// The following namespaces are reserved by `SortedMap`:
+///  * N + "val" + K
+///  * N + "page" + page id
+///  * N + "dir"
+///
+/// # Examples
+///
+/// ```
+/// # use fadroma::{
+/// #     cosmwasm_std::{StdResult, testing::mock_dependencies},
+/// #     storage::sorted_map::SortedMap
+/// # };
+/// # fn main() -> StdResult<()> {
+/// # let mut deps = mock_dependencies();
+/// # let storage = deps.as_mut().storage;
+/// fadroma::namespace!(ExpirationsNs, b"expirations");
+/// const EXPIRATIONS: SortedMap<u64, String, ExpirationsNs> = SortedMap::new();
+///
+/// EXPIRATIONS.insert(storage, 300, &"carol".into())?;
+/// EXPIRATIONS.insert(storage, 100, &"alice".into())?;
+/// EXPIRATIONS.insert(storage, 200, &"bob".into())?;
+///
+/// assert_eq!(EXPIRATIONS.first(storage)?, Some((100, "alice".into())));
+/// assert_eq!(EXPIRATIONS.last(storage)?, Some((300, "carol".into())));
+///
+/// let expired = EXPIRATIONS.range(storage, ..=200, 10)?;
+/// assert_eq!(expired, vec![(100, "alice".into()), (200, "bob".into())]);
+///
+/// let latest = EXPIRATIONS.range_rev(storage, 150.., 1)?;
+/// assert_eq!(latest, vec![(300, "carol".into())]);
+/// # Ok(())
+/// # }
+/// ```
+pub struct SortedMap<
+    K: Copy + Into<u128> + TryFrom<u128>,
+    V: FadromaSerialize + FadromaDeserialize,
+    N: Namespace
+> {
+    key_data: PhantomData<K>,
+    value_data: PhantomData<V>,
+    namespace_data: PhantomData<N>
+}
+
+#[derive(FadromaSerialize, FadromaDeserialize, Default)]
+struct Directory {
+    /// Sorted by the smallest key of each page.
+    pages: Vec<PageRef>,
+    next_id: u64,
+    len: u64
+}
+
+#[derive(FadromaSerialize, FadromaDeserialize, Clone, Copy)]
+struct PageRef {
+    first: u128,
+    id: u64
+}
+
+impl<
+    K: Copy + Into<u128> + TryFrom<u128>,
+    V: FadromaSerialize + FadromaDeserialize,
+    N: Namespace
+> SortedMap<K, V, N> {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            key_data: PhantomData,
+            value_data: PhantomData,
+            namespace_data: PhantomData
+        }
+    }
+
+    #[inline]
+    pub fn len(&self, storage: &dyn Storage) -> StdResult<u64> {
+        Ok(Self::directory(storage)?.len)
+    }
+
+    #[inline]
+    pub fn is_empty(&self, storage: &dyn Storage) -> StdResult<bool> {
+        Ok(self.len(storage)? == 0)
+    }
+
+    #[inline]
+    pub fn get(&self, storage: &dyn Storage, key: K) -> StdResult<Option<V>> {
+        super::load(storage, Self::value_key(key.into()))
+    }
+
+    #[inline]
+    pub fn contains(&self, storage: &dyn Storage, key: K) -> bool {
+        super::exists(storage, Self::value_key(key.into()))
+    }
+
+    /// Inserts or replaces the value under `key`. Returns `true`
+    /// if a value was previously stored under it.
+    pub fn insert(&self, storage: &mut dyn Storage, key: K, value: &V) -> StdResult<bool> {
+        let key = key.into();
+        let value_key = Self::value_key(key);
+
+        if super::exists(storage, &value_key) {
+            super::save(storage, value_key, value)?;
+
+            return Ok(true);
+        }
+
+        let mut dir = Self::directory(storage)?;
+
+        match Self::find_page(&dir, key) {
+            Some(index) => {
+                let page_ref = dir.pages[index];
+                let mut page = Self::load_page(storage, page_ref.id)?;
+
+                let pos = page.binary_search(&key).unwrap_err();
+                page.insert(pos, key);
+
+                if pos == 0 {
+                    dir.pages[index].first = key;
+                }
+
+                if page.len() > PAGE_CAPACITY {
+                    let upper = page.split_off(page.len() / 2);
+                    let id = dir.next_id;
+
+                    dir.next_id += 1;
+                    dir.pages.insert(index + 1, PageRef { first: upper[0], id });
+
+                    super::save(storage, Self::page_key(id), &upper)?;
+                }
+
+                super::save(storage, Self::page_key(page_ref.id), &page)?;
+            },
+            None => {
+                let id = dir.next_id;
+
+                dir.next_id += 1;
+                dir.pages.push(PageRef { first: key, id });
+
+                super::save(storage, Self::page_key(id), &vec![key])?;
+            }
+        }
+
+        dir.len += 1;
+
+        Self::save_directory(storage, &dir)?;
+        super::save(storage, value_key, value)?;
+
+        Ok(false)
+    }
+
+    /// Removes the value under `key` and returns it, if any.
+    pub fn remove(&self, storage: &mut dyn Storage, key: K) -> StdResult<Option<V>> {
+        let key = key.into();
+        let value_key = Self::value_key(key);
+
+        let Some(value) = super::load(storage, &value_key)? else {
+            return Ok(None);
+        };
+
+        let mut dir = Self::directory(storage)?;
+        let index = Self::find_page(&dir, key).ok_or_else(Self::corrupted)?;
+
+        let page_ref = dir.pages[index];
+        let mut page = Self::load_page(storage, page_ref.id)?;
+
+        let pos = page.binary_search(&key).map_err(|_| Self::corrupted())?;
+        page.remove(pos);
+
+        if page.is_empty() {
+            dir.pages.remove(index);
+            super::remove(storage, Self::page_key(page_ref.id));
+        } else {
+            if pos == 0 {
+                dir.pages[index].first = page[0];
+            }
+
+            super::save(storage, Self::page_key(page_ref.id), &page)?;
+        }
+
+        dir.len -= 1;
+
+        Self::save_directory(storage, &dir)?;
+        super::remove(storage, value_key);
+
+        Ok(Some(value))
+    }
+
+    /// Returns the entry with the smallest key.
+    pub fn first(&self, storage: &dyn Storage) -> StdResult<Option<(K, V)>> {
+        let dir = Self::directory(storage)?;
+
+        match dir.pages.first() {
+            Some(page_ref) => self.entry(storage, page_ref.first).map(Some),
+            None => Ok(None)
+        }
+    }
+
+    /// Returns the entry with the largest key.
+    pub fn last(&self, storage: &dyn Storage) -> StdResult<Option<(K, V)>> {
+        let dir = Self::directory(storage)?;
+
+        let Some(page_ref) = dir.pages.last() else {
+            return Ok(None);
+        };
+
+        let page = Self::load_page(storage, page_ref.id)?;
+        let key = *page.last().ok_or_else(Self::corrupted)?;
+
+        self.entry(storage, key).map(Some)
+    }
+
+    /// Returns at most `limit` entries whose keys are within `range`, in ascending order.
+    /// To continue from where it stopped, call it again with the range starting after
+    /// the last returned key.
+    pub fn range(
+        &self,
+        storage: &dyn Storage,
+        range: impl RangeBounds<K>,
+        limit: usize
+    ) -> StdResult<Vec<(K, V)>> {
+        let (start, end) = Self::bounds(range);
+        let dir = Self::directory(storage)?;
+
+        let from = match start {
+            Bound::Included(x) | Bound::Excluded(x) => x,
+            Bound::Unbounded => 0
+        };
+
+        let mut result = vec![];
+
+        let Some(index) = Self::find_page(&dir, from) else {
+            return Ok(result);
+        };
+
+        for page_ref in &dir.pages[index..] {
+            for key in Self::load_page(storage, page_ref.id)? {
+                if result.len() == limit || !Self::before_end(key, end) {
+                    return Ok(result);
+                }
+
+                if Self::after_start(key, start) {
+                    result.push(self.entry(storage, key)?);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Same as [`SortedMap::range`] but in descending order. To continue from where it
+    /// stopped, call it again with the range ending before the last returned key.
+    pub fn range_rev(
+        &self,
+        storage: &dyn Storage,
+        range: impl RangeBounds<K>,
+        limit: usize
+    ) -> StdResult<Vec<(K, V)>> {
+        let (start, end) = Self::bounds(range);
+        let dir = Self::directory(storage)?;
+
+        let from = match end {
+            Bound::Included(x) | Bound::Excluded(x) => x,
+            Bound::Unbounded => u128::MAX
+        };
+
+        let mut result = vec![];
+
+        let Some(index) = Self::find_page(&dir, from) else {
+            return Ok(result);
+        };
+
+        for page_ref in dir.pages[..=index].iter().rev() {
+            for key in Self::load_page(storage, page_ref.id)?.into_iter().rev() {
+                if result.len() == limit || !Self::after_start(key, start) {
+                    return Ok(result);
+                }
+
+                if Self::before_end(key, end) {
+                    result.push(self.entry(storage, key)?);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn entry(&self, storage: &dyn Storage, key: u128) -> StdResult<(K, V)> {
+        let value = super::load(storage, Self::value_key(key))?
+            .ok_or_else(Self::corrupted)?;
+        let key = K::try_from(key).map_err(|_| Self::corrupted())?;
+
+        Ok((key, value))
+    }
+
+    /// Returns the index of the page that `key` belongs to, which is the
+    /// last one whose smallest key is not greater than it or the first page.
+    #[inline]
+    fn find_page(dir: &Directory, key: u128) -> Option<usize> {
+        if dir.pages.is_empty() {
+            return None;
+        }
+
+        Some(dir.pages.partition_point(|x| x.first <= key).saturating_sub(1))
+    }
+
+    #[inline]
+    fn bounds(range: impl RangeBounds<K>) -> (Bound<u128>, Bound<u128>) {
+        (
+            range.start_bound().map(|x| (*x).into()),
+            range.end_bound().map(|x| (*x).into())
+        )
+    }
+
+    #[inline]
+    fn after_start(key: u128, start: Bound<u128>) -> bool {
+        match start {
+            Bound::Included(x) => key >= x,
+            Bound::Excluded(x) => key > x,
+            Bound::Unbounded => true
+        }
+    }
+
+    #[inline]
+    fn before_end(key: u128, end: Bound<u128>) -> bool {
+        match end {
+            Bound::Included(x) => key <= x,
+            Bound::Excluded(x) => key < x,
+            Bound::Unbounded => true
+        }
+    }
+
+    #[inline]
+    fn directory(storage: &dyn Storage) -> StdResult<Directory> {
+        let dir = super::load(storage, [N::NAMESPACE, DIRECTORY_KEY].concat())?;
+
+        Ok(dir.unwrap_or_default())
+    }
+
+    #[inline]
+    fn save_directory(storage: &mut dyn Storage, dir: &Directory) -> StdResult<()> {
+        super::save(storage, [N::NAMESPACE, DIRECTORY_KEY].concat(), dir)
+    }
+
+    #[inline]
+    fn load_page(storage: &dyn Storage, id: u64) -> StdResult<Vec<u128>> {
+        let page = super::load(storage, Self::page_key(id))?;
+
+        Ok(page.unwrap_or_default())
+    }
+
+    #[inline]
+    fn value_key(key: u128) -> Vec<u8> {
+        [N::NAMESPACE, VALUE_NS, &key.to_be_bytes()].concat()
+    }
+
+    #[inline]
+    fn page_key(id: u64) -> Vec<u8> {
+        [N::NAMESPACE, PAGE_NS, &id.to_be_bytes()].concat()
+    }
+
+    #[inline]
+    fn corrupted() -> StdError {
+        StdError::generic_err("SortedMap: the index is out of sync with the stored values.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::cosmwasm_std::{Uint128, testing::MockStorage};
+
+    crate::namespace!(LevelsNs, b"levels");
+    const LEVELS: SortedMap<Uint128, u64, LevelsNs> = SortedMap::new();
+
+    #[test]
+    fn sorted_map_matches_btree_map() {
+        let ref mut storage = MockStorage::new();
+        let mut expected = BTreeMap::new();
+
+        // Enough keys to split pages, inserted out of order.
+        for i in 0..500u64 {
+            let key = Uint128::new((i * 7919 % 1000) as u128);
+
+            assert!(!LEVELS.insert(storage, key, &i).unwrap());
+            expected.insert(key, i);
+        }
+
+        assert!(LEVELS.insert(storage, Uint128::new(0), &1).unwrap());
+        expected.insert(Uint128::new(0), 1);
+
+        for i in (0..1000).step_by(3) {
+            let key = Uint128::new(i);
+
+            assert_eq!(LEVELS.remove(storage, key).unwrap(), expected.remove(&key));
+        }
+
+        assert_eq!(LEVELS.len(storage).unwrap(), expected.len() as u64);
+
+        let all = LEVELS.range(storage, .., usize::MAX).unwrap();
+        assert_eq!(all, expected.clone().into_iter().collect::<Vec<_>>());
+
+        let (min, max) = (Uint128::new(250), Uint128::new(750));
+
+        let range = LEVELS.range(storage, min..max, 40).unwrap();
+        assert_eq!(range, expected.range(min..max).take(40).map(|(k, v)| (*k, *v)).collect::<Vec<_>>());
+
+        let range = LEVELS.range_rev(storage, min..=max, 40).unwrap();
+        assert_eq!(range, expected.range(min..=max).rev().take(40).map(|(k, v)| (*k, *v)).collect::<Vec<_>>());
+
+        let range = LEVELS.range_rev(storage, (Bound::Excluded(min), Bound::Unbounded), usize::MAX).unwrap();
+        assert_eq!(range.len(), expected.range((Bound::Excluded(min), Bound::Unbounded)).count());
+
+        assert!(LEVELS.range(storage, Uint128::new(2000).., 10).unwrap().is_empty());
+        assert!(LEVELS.range_rev(storage, ..Uint128::new(1), 10).unwrap().is_empty());
+
+        let first = expected.iter().next().map(|(k, v)| (*k, *v));
+        let last = expected.iter().next_back().map(|(k, v)| (*k, *v));
+
+        assert_eq!(LEVELS.first(storage).unwrap(), first);
+        assert_eq!(LEVELS.last(storage).unwrap(), last);
+
+        for key in expected.keys() {
+            LEVELS.remove(storage, *key).unwrap();
+        }
+
+        assert!(LEVELS.is_empty(storage).unwrap());
+        assert_eq!(LEVELS.first(storage).unwrap(), None);
+        assert_eq!(storage.range(None, None, crate::cosmwasm_std::Order::Ascending).count(), 1);
+    }
+}
@@ -0,0 +1,250 @@
+//! Buffering writes in memory so that they can be applied or discarded all at once.
+
+use std::collections::BTreeMap;
+
+use crate::cosmwasm_std::{Storage, StdResult};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::cosmwasm_std::{Record, Order};
+
+/// Wraps a [`Storage`] and keeps all writes made through it in memory until
+/// [`Transaction::commit`] is called. Reads see the pending writes. Dropping
+/// the transaction without committing discards them.
+///
+/// A failed message already reverts every write that it made. This is
+/// useful when a handler must recover from the failure of one of its steps,
+/// i.e. when processing a batch in which some of the items may be invalid,
+/// without leaving any of the partial writes of that step behind.
+///
+/// Since only the last write to each key is kept, it can also be used to save
+/// gas in handlers which write the same key many times, i.e. a reward index that
+/// is updated by each of the steps of a handler. Run the entire handler with
+/// [`transact`] and each key is written to the underlying storage once, at the end.
+///
+/// # Examples
+///
+/// ```
+/// # use fadroma::{
+/// #     cosmwasm_std::{StdResult, StdError, testing::mock_dependencies},
+/// #     storage::{SingleItem, transaction::{Transaction, transact}}
+/// # };
+/// # fn main() -> StdResult<()> {
+/// # let mut deps = mock_dependencies();
+/// # let storage = deps.as_mut().storage;
+/// fadroma::namespace!(CountNs, b"count");
+/// const COUNT: SingleItem<u64, CountNs> = SingleItem::new();
+///
+/// let mut tx = Transaction::new(storage);
+/// COUNT.save(&mut tx, &1)?;
+/// assert_eq!(COUNT.load(&tx)?, Some(1));
+/// tx.commit();
+///
+/// let result: StdResult<()> = transact(storage, |storage| {
+///     COUNT.save(storage, &2)?;
+///
+///     Err(StdError::generic_err("Something went wrong."))
+/// });
+///
+/// assert!(result.is_err());
+/// assert_eq!(COUNT.load(storage)?, Some(1));
+/// # Ok(())
+/// # }
+/// ```
+pub struct Transaction<'a> {
+    storage: &'a mut dyn Storage,
+    /// [`None`] if the key has been removed.
+    writes: BTreeMap<Vec<u8>, Option<Vec<u8>>>
+}
+
+impl<'a> Transaction<'a> {
+    #[inline]
+    pub fn new(storage: &'a mut dyn Storage) -> Self {
+        Self {
+            storage,
+            writes: BTreeMap::new()
+        }
+    }
+
+    /// Returns `true` if nothing has been written since the transaction was created.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    /// Applies all pending writes to the underlying storage.
+    pub fn commit(self) {
+        for (key, value) in self.writes {
+            match value {
+                Some(value) => self.storage.set(&key, &value),
+                None => self.storage.remove(&key)
+            }
+        }
+    }
+
+    /// Discards all pending writes. Same as dropping the transaction.
+    #[inline]
+    pub fn rollback(self) { }
+}
+
+/// Runs `f` in a [`Transaction`] that is committed only if it returns [`Ok`].
+pub fn transact<T>(
+    storage: &mut dyn Storage,
+    f: impl FnOnce(&mut dyn Storage) -> StdResult<T>
+) -> StdResult<T> {
+    let mut tx = Transaction::new(storage);
+    let result = f(&mut tx)?;
+    tx.commit();
+
+    Ok(result)
+}
+
+impl<'a> Storage for Transaction<'a> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        match self.writes.get(key) {
+            Some(value) => value.clone(),
+            None => self.storage.get(key)
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        let mut records: BTreeMap<Vec<u8>, Vec<u8>> = self.storage
+            .range(start, end, Order::Ascending)
+            .collect();
+
+        for (key, value) in &self.writes {
+            let in_range = start.is_none_or(|x| key.as_slice() >= x) &&
+                end.is_none_or(|x| key.as_slice() < x);
+
+            if !in_range {
+                continue;
+            }
+
+            match value {
+                Some(value) => records.insert(key.clone(), value.clone()),
+                None => records.remove(key)
+            };
+        }
+
+        match order {
+            Order::Ascending => Box::new(records.into_iter()),
+            Order::Descending => Box::new(records.into_iter().rev())
+        }
+    }
+
+    #[inline]
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.writes.insert(key.to_vec(), Some(value.to_vec()));
+    }
+
+    #[inline]
+    fn remove(&mut self, key: &[u8]) {
+        self.writes.insert(key.to_vec(), None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cosmwasm_std::{StdError, testing::MockStorage};
+
+    #[test]
+    fn transaction_buffers_writes() {
+        let ref mut storage = MockStorage::new();
+        storage.set(b"a", b"1");
+        storage.set(b"b", b"2");
+
+        let mut tx = Transaction::new(storage);
+        assert!(tx.is_empty());
+
+        tx.set(b"c", b"3");
+        tx.remove(b"a");
+        tx.set(b"b", b"4");
+
+        assert_eq!(tx.get(b"a"), None);
+        assert_eq!(tx.get(b"b"), Some(b"4".to_vec()));
+        assert_eq!(
+            tx.range(Some(b"a"), Some(b"c"), Order::Descending).collect::<Vec<_>>(),
+            vec![(b"b".to_vec(), b"4".to_vec())]
+        );
+
+        tx.rollback();
+        assert_eq!(storage.get(b"a"), Some(b"1".to_vec()));
+        assert_eq!(storage.get(b"c"), None);
+
+        let result = transact(storage, |storage| {
+            storage.remove(b"a");
+            storage.set(b"c", b"3");
+
+            Ok(())
+        });
+
+        assert!(result.is_ok());
+        assert_eq!(storage.get(b"a"), None);
+        assert_eq!(storage.get(b"c"), Some(b"3".to_vec()));
+
+        let result: StdResult<()> = transact(storage, |storage| {
+            storage.set(b"a", b"1");
+
+            Err(StdError::generic_err("Failed."))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(storage.get(b"a"), None);
+    }
+
+    #[test]
+    fn repeated_writes_are_applied_once() {
+        #[derive(Default)]
+        struct CountingStorage {
+            inner: MockStorage,
+            writes: u32
+        }
+
+        impl Storage for CountingStorage {
+            fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+                self.inner.get(key)
+            }
+
+            fn range<'a>(
+                &'a self,
+                start: Option<&[u8]>,
+                end: Option<&[u8]>,
+                order: Order,
+            ) -> Box<dyn Iterator<Item = Record> + 'a> {
+                self.inner.range(start, end, order)
+            }
+
+            fn set(&mut self, key: &[u8], value: &[u8]) {
+                self.writes += 1;
+                self.inner.set(key, value);
+            }
+
+            fn remove(&mut self, key: &[u8]) {
+                self.writes += 1;
+                self.inner.remove(key);
+            }
+        }
+
+        let ref mut storage = CountingStorage::default();
+
+        transact(storage, |storage| {
+            for i in 0..5u8 {
+                storage.set(b"index", &[i]);
+            }
+
+            storage.set(b"other", b"1");
+            storage.remove(b"other");
+
+            Ok(())
+        }).unwrap();
+
+        assert_eq!(storage.writes, 2);
+        assert_eq!(storage.get(b"index"), Some(vec![4]));
+        assert_eq!(storage.get(b"other"), None);
+    }
+}
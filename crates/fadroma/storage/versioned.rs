@@ -0,0 +1,269 @@
+//! Stored values that carry the version of their schema so that they
+//! can be migrated one at a time whenever they are read.
+
+use std::ops::{Deref, DerefMut};
+
+use crate::bin_serde::{
+    self, FadromaSerialize, FadromaDeserialize,
+    Serializer, Deserializer
+};
+
+/// Implemented by types that are stored as [`Versioned`].
+pub trait Migrate: FadromaSerialize + FadromaDeserialize {
+    /// The current version of the schema. Must be incremented every
+    /// time that the layout of the type changes.
+    const VERSION: u32;
+
+    /// Converts a value that was stored with an older `version` of the
+    /// schema. The deserializer is positioned at the start of the old value.
+    fn migrate_from(version: u32, de: &mut Deserializer) -> bin_serde::Result<Self>;
+}
+
+/// Stores the schema version of `T` in front of its value. Values stored with
+/// an older version are converted by [`Migrate::migrate_from`] when they are read,
+/// which lets a contract change the layout of its state in an upgrade without
+/// having to migrate all of it at once in a single transaction.
+///
+/// The converted value is only written back when it is saved again.
+/// Values stored with a newer version than [`Migrate::VERSION`] fail to deserialize.
+/// Values that were stored before switching to [`Versioned`] don't have a version
+/// and must be migrated the usual way.
+///
+/// # Examples
+///
+/// ```
+/// # use fadroma::{
+/// #     cosmwasm_std::{StdResult, testing::mock_dependencies},
+/// #     bin_serde::{self, FadromaSerialize, FadromaDeserialize, Deserializer},
+/// #     storage::{SingleItem, versioned::{Versioned, Migrate}}
+/// # };
+/// # fn main() -> StdResult<()> {
+/// # let mut deps = mock_dependencies();
+/// # let storage = deps.as_mut().storage;
+/// # #[derive(FadromaSerialize, FadromaDeserialize)]
+/// # struct ConfigV1 { fee: u64 }
+/// # impl Migrate for ConfigV1 {
+/// #     const VERSION: u32 = 1;
+/// #     fn migrate_from(_: u32, _: &mut Deserializer) -> bin_serde::Result<Self> { unreachable!() }
+/// # }
+/// # fadroma::namespace!(ConfigNs, b"config");
+/// # SingleItem::<Versioned<ConfigV1>, ConfigNs>::new().save(storage, &ConfigV1 { fee: 10 }.into())?;
+/// #[derive(FadromaSerialize, FadromaDeserialize, PartialEq, Debug)]
+/// struct Config {
+///     fee: u64,
+///     max_fee: u64
+/// }
+///
+/// impl Migrate for Config {
+///     const VERSION: u32 = 2;
+///
+///     fn migrate_from(version: u32, de: &mut Deserializer) -> bin_serde::Result<Self> {
+///         match version {
+///             1 => {
+///                 let old: ConfigV1 = de.deserialize()?;
+///
+///                 Ok(Self { fee: old.fee, max_fee: old.fee * 2 })
+///             }
+///             _ => Err(bin_serde::Error::InvalidType)
+///         }
+///     }
+/// }
+///
+/// const CONFIG: SingleItem<Versioned<Config>, ConfigNs> = SingleItem::new();
+///
+/// // Stored as `ConfigV1 { fee: 10 }` by the previous release of the contract.
+/// let config = CONFIG.load_or_error(storage)?;
+///
+/// assert_eq!(*config, Config { fee: 10, max_fee: 20 });
+/// assert!(config.is_outdated());
+///
+/// CONFIG.save(storage, &config)?;
+/// assert!(!CONFIG.load_or_error(storage)?.is_outdated());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct Versioned<T: Migrate> {
+    value: T,
+    stored_version: u32
+}
+
+impl<T: Migrate> Versioned<T> {
+    #[inline]
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            stored_version: T::VERSION
+        }
+    }
+
+    /// The version of the schema that the value was stored with.
+    /// This is [`Migrate::VERSION`] for values that weren't read from storage.
+    #[inline]
+    pub fn stored_version(&self) -> u32 {
+        self.stored_version
+    }
+
+    /// Returns `true` if the value was converted from an older version when it
+    /// was read. Save it in order to avoid converting it on every subsequent read.
+    #[inline]
+    pub fn is_outdated(&self) -> bool {
+        self.stored_version != T::VERSION
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T: Migrate> From<T> for Versioned<T> {
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: Migrate> Deref for Versioned<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T: Migrate> DerefMut for Versioned<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<T: Migrate> FadromaSerialize for Versioned<T> {
+    #[inline]
+    fn size_hint(&self) -> usize {
+        T::VERSION.size_hint() + self.value.size_hint()
+    }
+
+    #[inline]
+    fn to_bytes(&self, ser: &mut Serializer) -> bin_serde::Result<()> {
+        // Always written as the current version since that's what the value is now.
+        T::VERSION.to_bytes(ser)?;
+        self.value.to_bytes(ser)
+    }
+}
+
+impl<T: Migrate> FadromaDeserialize for Versioned<T> {
+    fn from_bytes<'a>(de: &mut Deserializer<'a>) -> bin_serde::Result<Self> {
+        let stored_version: u32 = de.deserialize()?;
+
+        let value = if stored_version == T::VERSION {
+            de.deserialize()?
+        } else if stored_version < T::VERSION {
+            T::migrate_from(stored_version, de)?
+        } else {
+            return Err(bin_serde::Error::InvalidType);
+        };
+
+        Ok(Self { value, stored_version })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        self as fadroma,
+        storage::{ItemSpace, TypedKey},
+        cosmwasm_std::{Storage, StdError, testing::mock_dependencies}
+    };
+
+    /// Defines a previous (or future) version of `Account`.
+    macro_rules! account_version {
+        ($name:ident, $version:literal, $($field:ident: $ty:ty),+) => {
+            #[derive(FadromaSerialize, FadromaDeserialize)]
+            struct $name {
+                $($field: $ty),+
+            }
+
+            impl Migrate for $name {
+                const VERSION: u32 = $version;
+
+                fn migrate_from(_version: u32, _de: &mut Deserializer) -> bin_serde::Result<Self> {
+                    unreachable!()
+                }
+            }
+        };
+    }
+
+    account_version!(AccountV1, 1, balance: u64);
+    account_version!(AccountV2, 2, balance: u128);
+    account_version!(AccountV4, 4, balance: u128, frozen: bool, limit: u128);
+
+    #[derive(FadromaSerialize, FadromaDeserialize, PartialEq, Debug)]
+    struct Account {
+        balance: u128,
+        frozen: bool
+    }
+
+    impl Migrate for Account {
+        const VERSION: u32 = 3;
+
+        fn migrate_from(version: u32, de: &mut Deserializer) -> bin_serde::Result<Self> {
+            let balance = match version {
+                1 => de.deserialize::<AccountV1>()?.balance as u128,
+                2 => de.deserialize::<AccountV2>()?.balance,
+                _ => return Err(bin_serde::Error::InvalidType)
+            };
+
+            Ok(Self { balance, frozen: false })
+        }
+    }
+
+    crate::namespace!(AccountsNs, b"accounts");
+    const ACCOUNTS: ItemSpace<Versioned<Account>, AccountsNs, TypedKey<u64>> = ItemSpace::new();
+
+    /// Stores `value` like a release of the contract that uses version `V` of `Account` would.
+    fn store<V: Migrate>(storage: &mut dyn Storage, id: u64, value: V) {
+        ItemSpace::<Versioned<V>, AccountsNs, TypedKey<u64>>::new()
+            .save(storage, &id, &value.into())
+            .unwrap();
+    }
+
+    #[test]
+    fn values_are_migrated_on_read() {
+        let mut deps = mock_dependencies();
+        let storage = deps.as_mut().storage;
+
+        store(storage, 1, AccountV1 { balance: 100 });
+        store(storage, 2, AccountV2 { balance: 200 });
+
+        ACCOUNTS.save(storage, &3, &Account { balance: 300, frozen: true }.into()).unwrap();
+
+        let expected = [(1, 100, false, 1), (2, 200, false, 2), (3, 300, true, 3)];
+
+        for (id, balance, frozen, version) in expected {
+            let account = ACCOUNTS.load_or_error(storage, &id).unwrap();
+
+            assert_eq!(*account, Account { balance, frozen });
+            assert_eq!(account.stored_version(), version);
+        }
+
+        let mut account = ACCOUNTS.load_or_error(storage, &1).unwrap();
+        assert!(account.is_outdated());
+
+        account.frozen = true;
+        ACCOUNTS.save(storage, &1, &account).unwrap();
+
+        let account = ACCOUNTS.load_or_error(storage, &1).unwrap();
+        assert!(!account.is_outdated());
+        assert_eq!(account.into_inner(), Account { balance: 100, frozen: true });
+
+        // Written by a newer release of the contract.
+        store(storage, 4, AccountV4 { balance: 400, frozen: false, limit: 0 });
+
+        let err = ACCOUNTS.load(storage, &4).unwrap_err();
+        assert!(matches!(err, StdError::ParseErr { .. }));
+    }
+}
@@ -0,0 +1,434 @@
+//! Tiers or levels that accounts qualify for based on a score, i.e. for
+//! launchpad allocations or fee discounts. You **MUST** implement [admin]
+//! in your contract if you want to use this module.
+//!
+//! The admin configures the minimum score required for each tier using
+//! [`Tiers::set_tier_thresholds`]. Where the score comes from is up to the
+//! contract: anything that implements [`ScoreSource`] can be passed to
+//! [`current_tier`], i.e. the staked amount of the account, the volume that
+//! it has recorded in a [`StoredScore`] or a closure that queries another contract.
+//!
+//! Components that charge fees can also offer a discount per tier, configured by
+//! the admin using [`Tiers::set_tier_discounts`] and applied with [`discounted_fee`].
+
+use std::{fmt, marker::PhantomData};
+
+use crate::{
+    self as fadroma,
+    admin::{self, Admin, Mode},
+    core::math::{MathConfig, Arithmetic},
+    dsl::*,
+    prelude::*
+};
+
+crate::namespace!(pub TierThresholdsNs, b"Rk4vN8zPqD");
+pub const THRESHOLDS: SingleItem<Vec<Uint128>, TierThresholdsNs> = SingleItem::new();
+
+crate::namespace!(pub TierDiscountsNs, b"h2WcY6mKsT");
+pub const DISCOUNTS: SingleItem<Vec<u16>, TierDiscountsNs> = SingleItem::new();
+
+/// The maximum number of tiers that can be configured, excluding tier `0`.
+pub const MAX_TIERS: usize = 32;
+
+/// Discounts are expressed in basis points, so this is a 100% discount.
+pub const MAX_DISCOUNT_BPS: u16 = 10_000;
+
+/// Requires the admin component in order to check for admin.
+#[interface]
+pub trait Tiers: Admin {
+    type Error: fmt::Display;
+
+    /// Sets the minimum score of each tier, starting from tier `1`.
+    #[execute]
+    fn set_tier_thresholds(thresholds: Vec<Uint128>) -> Result<Response, <Self as Tiers>::Error>;
+
+    /// Sets the fee discount in basis points of each tier, starting from tier `0`.
+    #[execute]
+    fn set_tier_discounts(discounts_bps: Vec<u16>) -> Result<Response, <Self as Tiers>::Error>;
+
+    #[query]
+    fn tier_thresholds() -> Result<Vec<Uint128>, <Self as Tiers>::Error>;
+
+    #[query]
+    fn tier_discounts() -> Result<Vec<u16>, <Self as Tiers>::Error>;
+}
+
+/// Provides the score that determines the tier of an account.
+///
+/// Implemented for closures, so that the score can be computed
+/// on the fly, i.e. by querying a staking contract:
+///
+/// ```
+/// # use fadroma::{
+/// #     tiers::ScoreSource,
+/// #     cosmwasm_std::{Deps, Env, Addr, Uint128, StdResult, testing::{mock_dependencies, mock_env}}
+/// # };
+/// let stake = |_deps: Deps, _env: &Env, account: &Addr| -> StdResult<Uint128> {
+///     Ok(Uint128::new(account.as_str().len() as u128))
+/// };
+///
+/// let deps = mock_dependencies();
+/// let score = stake.score(deps.as_ref(), &mock_env(), &Addr::unchecked("alice"));
+///
+/// assert_eq!(score.unwrap(), Uint128::new(5));
+/// ```
+pub trait ScoreSource {
+    fn score(&self, deps: Deps, env: &Env, account: &Addr) -> StdResult<Uint128>;
+}
+
+/// A [`ScoreSource`] that is kept in storage and updated by the contract itself,
+/// i.e. when an account stakes or trades. Each instance must use its own namespace.
+pub struct StoredScore<N: Namespace> {
+    ns: PhantomData<N>
+}
+
+pub struct DefaultImpl;
+
+impl Admin for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn change_admin(mode: Option<Mode>) -> Result<Response, Self::Error> {
+        admin::DefaultImpl::change_admin(deps, env, info, mode)
+    }
+
+    #[query]
+    fn admin() -> Result<Option<Addr>, Self::Error> {
+        admin::DefaultImpl::admin(deps, env)
+    }
+}
+
+impl Tiers for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn set_tier_thresholds(thresholds: Vec<Uint128>) -> StdResult<Response> {
+        set_thresholds(deps, info, thresholds)?;
+
+        Ok(Response::new().add_attribute("action", "set_tier_thresholds"))
+    }
+
+    #[execute]
+    fn set_tier_discounts(discounts_bps: Vec<u16>) -> StdResult<Response> {
+        set_discounts(deps, info, discounts_bps)?;
+
+        Ok(Response::new().add_attribute("action", "set_tier_discounts"))
+    }
+
+    #[query]
+    fn tier_thresholds() -> StdResult<Vec<Uint128>> {
+        load_thresholds(deps.storage)
+    }
+
+    #[query]
+    fn tier_discounts() -> StdResult<Vec<u16>> {
+        DISCOUNTS.load_or_default(deps.storage)
+    }
+}
+
+impl<F> ScoreSource for F
+    where F: Fn(Deps, &Env, &Addr) -> StdResult<Uint128>
+{
+    #[inline]
+    fn score(&self, deps: Deps, env: &Env, account: &Addr) -> StdResult<Uint128> {
+        self(deps, env, account)
+    }
+}
+
+impl<N: Namespace> StoredScore<N> {
+    const SCORES: ItemSpace<Uint128, N, TypedKey<'static, CanonicalAddr>> = ItemSpace::new();
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self { ns: PhantomData }
+    }
+
+    #[inline]
+    pub fn load(&self, storage: &dyn Storage, account: &CanonicalAddr) -> StdResult<Uint128> {
+        Self::SCORES.load_or_default(storage, account)
+    }
+
+    #[inline]
+    pub fn set(
+        &self,
+        storage: &mut dyn Storage,
+        account: &CanonicalAddr,
+        score: Uint128
+    ) -> StdResult<()> {
+        if score.is_zero() {
+            Self::SCORES.remove(storage, account);
+
+            Ok(())
+        } else {
+            Self::SCORES.save(storage, account, &score)
+        }
+    }
+
+    /// Overflows according to the [`MathConfig`] of the contract.
+    pub fn add(
+        &self,
+        storage: &mut dyn Storage,
+        account: &CanonicalAddr,
+        amount: Uint128
+    ) -> StdResult<Uint128> {
+        let policy = MathConfig::load(storage)?.overflow;
+        let score = self.load(storage, account)?.add_with(amount, policy)?;
+        self.set(storage, account, score)?;
+
+        Ok(score)
+    }
+
+    /// Overflows according to the [`MathConfig`] of the contract.
+    pub fn sub(
+        &self,
+        storage: &mut dyn Storage,
+        account: &CanonicalAddr,
+        amount: Uint128
+    ) -> StdResult<Uint128> {
+        let policy = MathConfig::load(storage)?.overflow;
+        let score = self.load(storage, account)?.sub_with(amount, policy)?;
+        self.set(storage, account, score)?;
+
+        Ok(score)
+    }
+}
+
+impl<N: Namespace> ScoreSource for StoredScore<N> {
+    #[inline]
+    fn score(&self, deps: Deps, _env: &Env, account: &Addr) -> StdResult<Uint128> {
+        let account = deps.api.addr_canonicalize(account.as_str())?;
+
+        self.load(deps.storage, &account)
+    }
+}
+
+/// Sets the minimum score of each tier, starting from tier `1`. The thresholds must be
+/// strictly increasing. Requires the admin component in order to check for admin.
+#[admin::require_admin]
+pub fn set_thresholds(
+    deps: DepsMut,
+    info: MessageInfo,
+    thresholds: Vec<Uint128>
+) -> StdResult<()> {
+    if thresholds.is_empty() || thresholds.len() > MAX_TIERS {
+        return Err(StdError::generic_err(format!(
+            "Between 1 and {} tiers must be configured.",
+            MAX_TIERS
+        )));
+    }
+
+    if thresholds.windows(2).any(|x| x[0] >= x[1]) {
+        return Err(StdError::generic_err("Tier thresholds must be strictly increasing."));
+    }
+
+    THRESHOLDS.save(deps.storage, &thresholds)
+}
+
+/// Returns `Err` if the thresholds weren't set.
+#[inline]
+pub fn load_thresholds(storage: &dyn Storage) -> StdResult<Vec<Uint128>> {
+    THRESHOLDS.load(storage)?.ok_or_else(||
+        StdError::generic_err("Tiers have not been configured.")
+    )
+}
+
+/// Returns the highest tier whose threshold is reached by `score`
+/// or `0` if it's below the threshold of the first tier.
+///
+/// # Examples
+///
+/// ```
+/// # use fadroma::{tiers::tier_of, cosmwasm_std::Uint128};
+/// let thresholds = [Uint128::new(100), Uint128::new(1000)];
+///
+/// assert_eq!(tier_of(&thresholds, Uint128::new(99)), 0);
+/// assert_eq!(tier_of(&thresholds, Uint128::new(100)), 1);
+/// assert_eq!(tier_of(&thresholds, Uint128::new(5000)), 2);
+/// ```
+#[inline]
+pub fn tier_of(thresholds: &[Uint128], score: Uint128) -> u8 {
+    thresholds.partition_point(|x| *x <= score) as u8
+}
+
+/// Returns the tier that `account` currently qualifies for based on its score in `source`.
+pub fn current_tier(
+    deps: Deps,
+    env: &Env,
+    source: &dyn ScoreSource,
+    account: &Addr
+) -> StdResult<u8> {
+    let thresholds = load_thresholds(deps.storage)?;
+    let score = source.score(deps, env, account)?;
+
+    Ok(tier_of(&thresholds, score))
+}
+
+/// Sets the fee discount in basis points of each tier, starting from tier `0`. Tiers
+/// after the last one in `discounts_bps` get no discount. Requires the admin component
+/// in order to check for admin.
+#[admin::require_admin]
+pub fn set_discounts(
+    deps: DepsMut,
+    info: MessageInfo,
+    discounts_bps: Vec<u16>
+) -> StdResult<()> {
+    if discounts_bps.len() > MAX_TIERS + 1 {
+        return Err(StdError::generic_err(format!(
+            "Cannot configure discounts for more than {} tiers.",
+            MAX_TIERS + 1
+        )));
+    }
+
+    if discounts_bps.iter().any(|x| *x > MAX_DISCOUNT_BPS) {
+        return Err(StdError::generic_err(format!(
+            "A discount cannot be greater than {} basis points.",
+            MAX_DISCOUNT_BPS
+        )));
+    }
+
+    DISCOUNTS.save(deps.storage, &discounts_bps)
+}
+
+/// Returns `fee` with the discount of the tier that `account` currently qualifies for
+/// applied, rounded down. The fee is returned unchanged if no discounts have been set,
+/// without querying the score, so components can call this whether or not the contract
+/// uses tiers.
+///
+/// # Examples
+///
+/// ```
+/// # use fadroma::{
+/// #     admin,
+/// #     tiers::{self, ScoreSource},
+/// #     cosmwasm_std::{Deps, Env, Addr, Uint128, StdResult, testing::*}
+/// # };
+/// # fn main() -> StdResult<()> {
+/// # let mut deps = mock_dependencies();
+/// # let env = mock_env();
+/// # let info = mock_info("admin", &[]);
+/// # admin::init(deps.as_mut(), None, &info)?;
+/// let stake = |_: Deps, _: &Env, _: &Addr| Ok(Uint128::new(500));
+/// let alice = Addr::unchecked("alice");
+/// let fee = Uint128::new(1000);
+///
+/// assert_eq!(tiers::discounted_fee(deps.as_ref(), &env, &stake, &alice, fee)?, fee);
+///
+/// tiers::set_thresholds(deps.as_mut(), info.clone(), vec![Uint128::new(100)])?;
+/// tiers::set_discounts(deps.as_mut(), info, vec![0, 2500])?;
+///
+/// assert_eq!(tiers::discounted_fee(deps.as_ref(), &env, &stake, &alice, fee)?, Uint128::new(750));
+/// # Ok(())
+/// # }
+/// ```
+pub fn discounted_fee(
+    deps: Deps,
+    env: &Env,
+    source: &dyn ScoreSource,
+    account: &Addr,
+    fee: Uint128
+) -> StdResult<Uint128> {
+    let Some(discounts) = DISCOUNTS.load(deps.storage)? else {
+        return Ok(fee);
+    };
+
+    let tier = current_tier(deps, env, source, account)?;
+    let discount = discounts.get(tier as usize).copied().unwrap_or_default();
+
+    Ok(fee.multiply_ratio(MAX_DISCOUNT_BPS - discount, MAX_DISCOUNT_BPS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+
+    crate::namespace!(VolumeNs, b"volume");
+    const VOLUME: StoredScore<VolumeNs> = StoredScore::new();
+
+    #[test]
+    fn tiers_from_stored_score() {
+        let ref mut deps = mock_dependencies();
+        let env = mock_env();
+        let alice = Addr::unchecked("alice");
+        let canonical = deps.api.addr_canonicalize(alice.as_str()).unwrap();
+
+        admin::init(deps.as_mut(), None, &mock_info("admin", &[])).unwrap();
+
+        assert!(current_tier(deps.as_ref(), &env, &VOLUME, &alice).is_err());
+
+        let set = |deps: DepsMut, sender: &str, thresholds: &[u128]| DefaultImpl::set_tier_thresholds(
+            deps,
+            mock_env(),
+            mock_info(sender, &[]),
+            thresholds.iter().map(|x| Uint128::new(*x)).collect()
+        );
+
+        assert!(set(deps.as_mut(), "alice", &[100, 1000]).is_err());
+        assert!(set(deps.as_mut(), "admin", &[]).is_err());
+        assert!(set(deps.as_mut(), "admin", &[100, 100]).is_err());
+        assert!(set(deps.as_mut(), "admin", &[0; MAX_TIERS + 1]).is_err());
+        set(deps.as_mut(), "admin", &[100, 1000]).unwrap();
+
+        assert_eq!(current_tier(deps.as_ref(), &env, &VOLUME, &alice).unwrap(), 0);
+
+        VOLUME.add(deps.as_mut().storage, &canonical, Uint128::new(150)).unwrap();
+        assert_eq!(current_tier(deps.as_ref(), &env, &VOLUME, &alice).unwrap(), 1);
+
+        VOLUME.add(deps.as_mut().storage, &canonical, Uint128::new(850)).unwrap();
+        assert_eq!(current_tier(deps.as_ref(), &env, &VOLUME, &alice).unwrap(), 2);
+
+        VOLUME.sub(deps.as_mut().storage, &canonical, Uint128::new(1000)).unwrap();
+        assert!(VOLUME.sub(deps.as_mut().storage, &canonical, Uint128::new(1)).is_err());
+        assert_eq!(current_tier(deps.as_ref(), &env, &VOLUME, &alice).unwrap(), 0);
+        assert!(deps.storage.range(None, None, crate::cosmwasm_std::Order::Ascending)
+            .all(|(key, _)| !key.starts_with(VolumeNs::NAMESPACE)));
+
+        // Saturates instead of failing when the contract opts into it.
+        let policy = crate::core::math::OverflowPolicy::Saturating;
+        MathConfig { overflow: policy }.save(deps.as_mut().storage).unwrap();
+
+        assert_eq!(VOLUME.sub(deps.as_mut().storage, &canonical, Uint128::new(1)).unwrap(), Uint128::zero());
+    }
+
+    #[test]
+    fn discounts_fees_by_tier() {
+        let ref mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("admin", &[]);
+        let alice = Addr::unchecked("alice");
+        let canonical = deps.api.addr_canonicalize(alice.as_str()).unwrap();
+        let fee = Uint128::new(999);
+
+        admin::init(deps.as_mut(), None, &info).unwrap();
+        set_thresholds(deps.as_mut(), info.clone(), vec![Uint128::new(100), Uint128::new(1000)]).unwrap();
+
+        // Discounts are opt-in.
+        assert_eq!(discounted_fee(deps.as_ref(), &env, &VOLUME, &alice, fee).unwrap(), fee);
+
+        let set = |deps: DepsMut, sender: &str, discounts: Vec<u16>| DefaultImpl::set_tier_discounts(
+            deps,
+            mock_env(),
+            mock_info(sender, &[]),
+            discounts
+        );
+
+        assert!(set(deps.as_mut(), "alice", vec![0, 1000]).is_err());
+        assert!(set(deps.as_mut(), "admin", vec![0, MAX_DISCOUNT_BPS + 1]).is_err());
+        assert!(set(deps.as_mut(), "admin", vec![0; MAX_TIERS + 2]).is_err());
+        set(deps.as_mut(), "admin", vec![0, 1000]).unwrap();
+
+        let discounted = |deps: Deps| discounted_fee(deps, &mock_env(), &VOLUME, &alice, fee).unwrap();
+
+        assert_eq!(discounted(deps.as_ref()), fee);
+
+        VOLUME.set(deps.as_mut().storage, &canonical, Uint128::new(100)).unwrap();
+        assert_eq!(discounted(deps.as_ref()), Uint128::new(899));
+
+        // Tier 2 has no discount configured.
+        VOLUME.set(deps.as_mut().storage, &canonical, Uint128::new(1000)).unwrap();
+        assert_eq!(discounted(deps.as_ref()), fee);
+
+        set(deps.as_mut(), "admin", vec![0, 1000, MAX_DISCOUNT_BPS]).unwrap();
+        assert_eq!(discounted(deps.as_ref()), Uint128::zero());
+    }
+}
@@ -0,0 +1,374 @@
+//! A curated registry of native denominations and SNIP-20 tokens together with
+//! their symbols and decimals, so that components like AMMs or fee collectors can
+//! display and normalize amounts consistently. You **MUST** implement [admin] in
+//! your contract if you want to use this module. Set a governance contract as the
+//! admin in order to have the registry curated by governance instead.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    self as fadroma,
+    admin::{self, Admin, Mode},
+    dsl::*,
+    prelude::*,
+    storage::{map::Map, iterable::{IterBudget, Page}}
+};
+
+crate::namespace!(pub TokenRegistryNs, b"Rk4wP8dZtJ");
+
+/// The maximum number of tokens that [`DefaultImpl`] returns in a single page.
+pub const MAX_PAGE_SIZE: u32 = 50;
+
+/// The maximum number of decimals that a registered token can have.
+pub const MAX_DECIMALS: u8 = 18;
+
+/// Requires the admin component in order to check for admin.
+#[interface]
+pub trait TokenRegistry: Admin {
+    type Error: fmt::Display;
+
+    /// Registers `token` or replaces the metadata of an already registered one.
+    #[execute]
+    fn register_token(
+        token: Token,
+        metadata: TokenMetadata
+    ) -> Result<Response, <Self as TokenRegistry>::Error>;
+
+    #[execute]
+    fn remove_token(token: TokenId) -> Result<Response, <Self as TokenRegistry>::Error>;
+
+    #[query]
+    fn registered_token(
+        token: TokenId
+    ) -> Result<Option<RegisteredToken>, <Self as TokenRegistry>::Error>;
+
+    #[query]
+    fn registered_tokens(
+        cursor: Option<u64>,
+        limit: u32
+    ) -> Result<Page<RegisteredToken>, <Self as TokenRegistry>::Error>;
+}
+
+#[derive(
+    Serialize, Deserialize, FadromaSerialize, FadromaDeserialize,
+    JsonSchema, PartialEq, Debug, Clone
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Token {
+    Native { denom: String },
+    Snip20 { contract: ContractLink<String> }
+}
+
+/// Identifies a registered [`Token`].
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenId {
+    Native { denom: String },
+    Snip20 { address: String }
+}
+
+#[derive(
+    Serialize, Deserialize, FadromaSerialize, FadromaDeserialize,
+    JsonSchema, PartialEq, Debug, Clone
+)]
+pub struct TokenMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8
+}
+
+#[derive(
+    Serialize, Deserialize, FadromaSerialize, FadromaDeserialize,
+    JsonSchema, PartialEq, Debug, Clone
+)]
+pub struct RegisteredToken {
+    pub token: Token,
+    pub metadata: TokenMetadata
+}
+
+pub struct DefaultImpl;
+
+impl Admin for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn change_admin(mode: Option<Mode>) -> Result<Response, Self::Error> {
+        admin::DefaultImpl::change_admin(deps, env, info, mode)
+    }
+
+    #[query]
+    fn admin() -> Result<Option<Addr>, Self::Error> {
+        admin::DefaultImpl::admin(deps, env)
+    }
+}
+
+impl TokenRegistry for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn register_token(
+        token: Token,
+        metadata: TokenMetadata
+    ) -> StdResult<Response> {
+        let symbol = metadata.symbol.clone();
+        register(deps, info, token, metadata)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "register_token")
+            .add_attribute("symbol", symbol)
+        )
+    }
+
+    #[execute]
+    fn remove_token(token: TokenId) -> StdResult<Response> {
+        remove(deps, info, &token)?;
+
+        Ok(Response::new().add_attribute("action", "remove_token"))
+    }
+
+    #[query]
+    fn registered_token(token: TokenId) -> StdResult<Option<RegisteredToken>> {
+        load(deps, &token)
+    }
+
+    #[query]
+    fn registered_tokens(
+        cursor: Option<u64>,
+        limit: u32
+    ) -> StdResult<Page<RegisteredToken>> {
+        let limit = limit.min(MAX_PAGE_SIZE) as u64;
+
+        tokens()
+            .values(deps.storage)?
+            .start_at(cursor.unwrap_or_default())
+            .read_page(limit, IterBudget::new(limit))
+    }
+}
+
+/// Registers `token` with the given `metadata`, replacing any existing entry.
+/// Requires the admin component in order to check for admin.
+#[admin::require_admin]
+pub fn register(
+    deps: DepsMut,
+    info: MessageInfo,
+    token: Token,
+    metadata: TokenMetadata
+) -> StdResult<()> {
+    if metadata.decimals > MAX_DECIMALS {
+        return Err(StdError::generic_err(format!(
+            "Tokens can have at most {} decimals.",
+            MAX_DECIMALS
+        )));
+    }
+
+    if metadata.symbol.is_empty() {
+        return Err(StdError::generic_err("The token symbol must not be empty."));
+    }
+
+    let token = match token {
+        Token::Native { denom } if denom.is_empty() => {
+            return Err(StdError::generic_err("The denomination must not be empty."));
+        },
+        Token::Snip20 { contract } => Token::Snip20 {
+            contract: ContractLink {
+                address: normalize_address(deps.api, &contract.address)?,
+                code_hash: contract.code_hash
+            }
+        },
+        token => token
+    };
+
+    tokens().insert(
+        deps.storage,
+        &token.id().key(deps.api)?,
+        &RegisteredToken { token, metadata }
+    )?;
+
+    Ok(())
+}
+
+/// Removes `token` from the registry. Returns `Err` if it wasn't registered.
+/// Requires the admin component in order to check for admin.
+#[admin::require_admin]
+pub fn remove(deps: DepsMut, info: MessageInfo, token: &TokenId) -> StdResult<()> {
+    if !tokens().remove(deps.storage, &token.key(deps.api)?)? {
+        return Err(StdError::generic_err("The token is not registered."));
+    }
+
+    Ok(())
+}
+
+#[inline]
+pub fn load(deps: Deps, token: &TokenId) -> StdResult<Option<RegisteredToken>> {
+    tokens().get(deps.storage, &token.key(deps.api)?)
+}
+
+/// Same as [`load`] but returns `Err` if `token` is not registered.
+pub fn load_or_error(deps: Deps, token: &TokenId) -> StdResult<RegisteredToken> {
+    load(deps, token)?.ok_or_else(||
+        StdError::generic_err("The token is not registered.")
+    )
+}
+
+#[inline]
+fn tokens<'a>() -> Map<TypedKey<'a, String>, RegisteredToken, TokenRegistryNs> {
+    Map::new()
+}
+
+/// Bech32 addresses are case insensitive, so the same contract can be
+/// referred to by differently cased strings. Round trip through the
+/// canonical form so that they all map to the same entry.
+#[inline]
+fn normalize_address(api: &dyn Api, address: &str) -> StdResult<String> {
+    Ok(api.addr_humanize(&api.addr_canonicalize(address)?)?.into_string())
+}
+
+impl Token {
+    #[inline]
+    pub fn id(&self) -> TokenId {
+        match self {
+            Self::Native { denom } => TokenId::Native { denom: denom.clone() },
+            Self::Snip20 { contract } => TokenId::Snip20 { address: contract.address.clone() }
+        }
+    }
+}
+
+impl TokenId {
+    #[inline]
+    fn key(&self, api: &dyn Api) -> StdResult<String> {
+        Ok(match self {
+            Self::Native { denom } => format!("native/{}", denom),
+            Self::Snip20 { address } => format!("snip20/{}", normalize_address(api, address)?)
+        })
+    }
+}
+
+impl TokenMetadata {
+    /// Converts `amount` of this token to the same amount
+    /// expressed with `decimals`, rounding down if needed.
+    pub fn normalize(&self, amount: Uint128, decimals: u8) -> StdResult<Uint128> {
+        if decimals >= self.decimals {
+            let factor = one_token(decimals - self.decimals);
+
+            Ok(amount.checked_mul(factor.into())?)
+        } else {
+            let factor = one_token(self.decimals - decimals);
+
+            Ok(amount.checked_div(factor.into())?)
+        }
+    }
+
+    /// Formats `amount` in whole tokens followed by the symbol, i.e. `"1.5 SCRT"`.
+    pub fn display(&self, amount: Uint128) -> StdResult<String> {
+        let amount = Decimal::from_atomics(amount, self.decimals as u32)
+            .map_err(|err| StdError::generic_err(err.to_string()))?;
+
+        Ok(format!("{} {}", amount, self.symbol))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+
+    #[test]
+    fn registry_is_curated_by_admin() {
+        let ref mut deps = mock_dependencies();
+        let env = mock_env();
+
+        admin::init(deps.as_mut(), None, &mock_info("admin", &[])).unwrap();
+
+        let scrt = Token::Native { denom: "uscrt".into() };
+        let sscrt = Token::Snip20 {
+            contract: ContractLink { address: "sscrt".into(), code_hash: "hash".into() }
+        };
+        let metadata = |symbol: &str, decimals: u8| TokenMetadata {
+            name: symbol.into(),
+            symbol: symbol.into(),
+            decimals
+        };
+
+        let register = |deps: DepsMut, sender: &str, token: Token, metadata: TokenMetadata|
+            DefaultImpl::register_token(deps, mock_env(), mock_info(sender, &[]), token, metadata);
+
+        assert!(register(deps.as_mut(), "user", scrt.clone(), metadata("SCRT", 6)).is_err());
+        assert!(register(deps.as_mut(), "admin", scrt.clone(), metadata("SCRT", 19)).is_err());
+
+        register(deps.as_mut(), "admin", scrt.clone(), metadata("SCRT", 6)).unwrap();
+        register(deps.as_mut(), "admin", sscrt.clone(), metadata("sSCRT", 6)).unwrap();
+
+        // Replaces the existing entry.
+        register(deps.as_mut(), "admin", sscrt.clone(), metadata("SSCRT", 6)).unwrap();
+
+        let id = TokenId::Snip20 { address: "sscrt".into() };
+        let registered = DefaultImpl::registered_token(deps.as_ref(), env.clone(), id.clone())
+            .unwrap()
+            .unwrap();
+        assert_eq!(registered, RegisteredToken { token: sscrt, metadata: metadata("SSCRT", 6) });
+
+        let page = DefaultImpl::registered_tokens(deps.as_ref(), env.clone(), None, 10).unwrap();
+        assert_eq!(page.items.len(), 2);
+        assert_eq!(page.cursor, None);
+
+        let remove = |deps: DepsMut, sender: &str, token: TokenId|
+            DefaultImpl::remove_token(deps, mock_env(), mock_info(sender, &[]), token);
+
+        assert!(remove(deps.as_mut(), "user", id.clone()).is_err());
+        remove(deps.as_mut(), "admin", id.clone()).unwrap();
+        assert!(remove(deps.as_mut(), "admin", id.clone()).is_err());
+
+        assert_eq!(load(deps.as_ref(), &id).unwrap(), None);
+
+        let scrt = load_or_error(deps.as_ref(), &scrt.id()).unwrap();
+        assert_eq!(scrt.metadata.display(Uint128::new(1_500_000)).unwrap(), "1.5 SCRT");
+    }
+
+    #[test]
+    fn snip20_addresses_are_case_insensitive() {
+        let ref mut deps = mock_dependencies();
+        let env = mock_env();
+
+        admin::init(deps.as_mut(), None, &mock_info("admin", &[])).unwrap();
+
+        let metadata = TokenMetadata { name: "Secret SCRT".into(), symbol: "SSCRT".into(), decimals: 6 };
+        let token = Token::Snip20 {
+            contract: ContractLink { address: "SScrt".into(), code_hash: "hash".into() }
+        };
+
+        DefaultImpl::register_token(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            token,
+            metadata.clone()
+        ).unwrap();
+
+        let registered = load_or_error(deps.as_ref(), &TokenId::Snip20 { address: "sscrt".into() }).unwrap();
+        assert_eq!(registered.token, Token::Snip20 {
+            contract: ContractLink { address: "sscrt".into(), code_hash: "hash".into() }
+        });
+        assert_eq!(registered.metadata, metadata);
+
+        let id = TokenId::Snip20 { address: "SSCRT".into() };
+        assert!(load(deps.as_ref(), &id).unwrap().is_some());
+
+        DefaultImpl::remove_token(deps.as_mut(), env, mock_info("admin", &[]), id).unwrap();
+        assert_eq!(load(deps.as_ref(), &TokenId::Snip20 { address: "sscrt".into() }).unwrap(), None);
+    }
+
+    #[test]
+    fn normalizes_amounts() {
+        let metadata = TokenMetadata { name: "Token".into(), symbol: "TKN".into(), decimals: 8 };
+
+        assert_eq!(metadata.normalize(Uint128::new(123_456_789), 18).unwrap(), Uint128::new(1_234_567_890_000_000_000));
+        assert_eq!(metadata.normalize(Uint128::new(123_456_789), 6).unwrap(), Uint128::new(1_234_567));
+        assert_eq!(metadata.normalize(Uint128::new(123_456_789), 8).unwrap(), Uint128::new(123_456_789));
+        assert!(metadata.normalize(Uint128::MAX, 18).is_err());
+
+        assert_eq!(metadata.display(Uint128::new(100_000_000)).unwrap(), "1 TKN");
+        assert_eq!(metadata.display(Uint128::zero()).unwrap(), "0 TKN");
+    }
+}
@@ -0,0 +1,331 @@
+//! Per-account balances split into liquid, locked and vesting amounts, shared by
+//! components such as lockups, vesting schedules and governance deposits.
+
+use std::{fmt, marker::PhantomData};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    self as fadroma,
+    prelude::*
+};
+
+const ACCOUNT_NS: &[u8] = b"account";
+const TOTAL_NS: &[u8] = b"total";
+
+#[derive(
+    Serialize, Deserialize, FadromaSerialize, FadromaDeserialize,
+    JsonSchema, PartialEq, Debug, Clone, Copy
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Bucket {
+    /// Can be transferred or withdrawn freely.
+    Liquid,
+    /// Held by the contract, i.e. staked or deposited for a proposal.
+    Locked,
+    /// Not yet released by a vesting schedule.
+    Vesting
+}
+
+#[derive(
+    Serialize, Deserialize, FadromaSerialize, FadromaDeserialize,
+    JsonSchema, PartialEq, Default, Debug, Clone
+)]
+pub struct AccountBalance {
+    pub liquid: Uint128,
+    pub locked: Uint128,
+    pub vesting: Uint128
+}
+
+/// Tracks the [`AccountBalance`] of each account together with the totals of all
+/// accounts, which always equal the sum of the balances. Every operation is checked
+/// and fails without changing anything if it would make a bucket negative or overflow.
+/// Each instance must use its own namespace.
+///
+/// # Examples
+///
+/// ```
+/// # use fadroma::{
+/// #     tokens::balances::{Balances, Bucket},
+/// #     cosmwasm_std::{CanonicalAddr, Uint128, StdResult, testing::mock_dependencies}
+/// # };
+/// # fn main() -> StdResult<()> {
+/// # let mut deps = mock_dependencies();
+/// # let storage = deps.as_mut().storage;
+/// fadroma::namespace!(BalancesNs, b"balances");
+/// const BALANCES: Balances<BalancesNs> = Balances::new();
+///
+/// let alice = CanonicalAddr::from(b"alice".as_slice());
+///
+/// BALANCES.deposit(storage, &alice, Bucket::Liquid, Uint128::new(100))?;
+/// BALANCES.move_between(storage, &alice, Bucket::Liquid, Bucket::Locked, Uint128::new(60))?;
+///
+/// // Only 40 is liquid.
+/// assert!(BALANCES.withdraw(storage, &alice, Bucket::Liquid, Uint128::new(50)).is_err());
+///
+/// let balance = BALANCES.load(storage, &alice)?;
+/// assert_eq!(balance.liquid, Uint128::new(40));
+/// assert_eq!(balance.locked, Uint128::new(60));
+/// assert_eq!(balance.total()?, Uint128::new(100));
+/// # Ok(())
+/// # }
+/// ```
+pub struct Balances<N: Namespace> {
+    ns: PhantomData<N>
+}
+
+impl AccountBalance {
+    #[inline]
+    pub fn get(&self, bucket: Bucket) -> Uint128 {
+        match bucket {
+            Bucket::Liquid => self.liquid,
+            Bucket::Locked => self.locked,
+            Bucket::Vesting => self.vesting
+        }
+    }
+
+    /// The sum of all buckets.
+    #[inline]
+    pub fn total(&self) -> StdResult<Uint128> {
+        Ok(self.liquid.checked_add(self.locked)?.checked_add(self.vesting)?)
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.liquid.is_zero() && self.locked.is_zero() && self.vesting.is_zero()
+    }
+
+    #[inline]
+    fn get_mut(&mut self, bucket: Bucket) -> &mut Uint128 {
+        match bucket {
+            Bucket::Liquid => &mut self.liquid,
+            Bucket::Locked => &mut self.locked,
+            Bucket::Vesting => &mut self.vesting
+        }
+    }
+
+    fn add(&mut self, bucket: Bucket, amount: Uint128) -> StdResult<()> {
+        let balance = self.get_mut(bucket);
+        *balance = balance.checked_add(amount)?;
+
+        Ok(())
+    }
+
+    fn sub(&mut self, bucket: Bucket, amount: Uint128) -> StdResult<()> {
+        let balance = self.get_mut(bucket);
+
+        *balance = balance.checked_sub(amount).map_err(|_|
+            StdError::generic_err(format!(
+                "Insufficient {} balance: available {}, required {}.",
+                bucket,
+                balance,
+                amount
+            ))
+        )?;
+
+        Ok(())
+    }
+}
+
+impl<N: Namespace> Balances<N> {
+    #[inline]
+    pub const fn new() -> Self {
+        Self { ns: PhantomData }
+    }
+
+    #[inline]
+    pub fn load(&self, storage: &dyn Storage, account: &CanonicalAddr) -> StdResult<AccountBalance> {
+        Ok(storage::load(storage, Self::account_key(account))?.unwrap_or_default())
+    }
+
+    /// The sum of the balances of all accounts.
+    #[inline]
+    pub fn totals(&self, storage: &dyn Storage) -> StdResult<AccountBalance> {
+        Ok(storage::load(storage, Self::total_key())?.unwrap_or_default())
+    }
+
+    /// Adds `amount` to the `bucket` of `account`, i.e. when it
+    /// sends tokens to the contract or a vesting schedule is created.
+    pub fn deposit(
+        &self,
+        storage: &mut dyn Storage,
+        account: &CanonicalAddr,
+        bucket: Bucket,
+        amount: Uint128
+    ) -> StdResult<AccountBalance> {
+        let mut balance = self.load(storage, account)?;
+        balance.add(bucket, amount)?;
+
+        let mut totals = self.totals(storage)?;
+        totals.add(bucket, amount)?;
+
+        self.save(storage, account, &balance, &totals)?;
+
+        Ok(balance)
+    }
+
+    /// Subtracts `amount` from the `bucket` of `account`, i.e. when tokens are
+    /// sent back to it or slashed. Fails if the bucket has less than `amount`.
+    pub fn withdraw(
+        &self,
+        storage: &mut dyn Storage,
+        account: &CanonicalAddr,
+        bucket: Bucket,
+        amount: Uint128
+    ) -> StdResult<AccountBalance> {
+        let mut balance = self.load(storage, account)?;
+        balance.sub(bucket, amount)?;
+
+        let mut totals = self.totals(storage)?;
+        totals.sub(bucket, amount)?;
+
+        self.save(storage, account, &balance, &totals)?;
+
+        Ok(balance)
+    }
+
+    /// Moves `amount` from one bucket of `account` to another, i.e. to lock
+    /// liquid tokens or release vested ones. The total balance is unchanged.
+    pub fn move_between(
+        &self,
+        storage: &mut dyn Storage,
+        account: &CanonicalAddr,
+        from: Bucket,
+        to: Bucket,
+        amount: Uint128
+    ) -> StdResult<AccountBalance> {
+        let mut balance = self.load(storage, account)?;
+        balance.sub(from, amount)?;
+        balance.add(to, amount)?;
+
+        let mut totals = self.totals(storage)?;
+        totals.sub(from, amount)?;
+        totals.add(to, amount)?;
+
+        self.save(storage, account, &balance, &totals)?;
+
+        Ok(balance)
+    }
+
+    /// Moves `amount` from the `bucket` of `from` to the same bucket of `to`.
+    pub fn transfer(
+        &self,
+        storage: &mut dyn Storage,
+        from: &CanonicalAddr,
+        to: &CanonicalAddr,
+        bucket: Bucket,
+        amount: Uint128
+    ) -> StdResult<()> {
+        let mut sender = self.load(storage, from)?;
+        sender.sub(bucket, amount)?;
+
+        if from == to {
+            return Ok(());
+        }
+
+        let mut recipient = self.load(storage, to)?;
+        recipient.add(bucket, amount)?;
+
+        Self::save_account(storage, from, &sender)?;
+        Self::save_account(storage, to, &recipient)
+    }
+
+    fn save(
+        &self,
+        storage: &mut dyn Storage,
+        account: &CanonicalAddr,
+        balance: &AccountBalance,
+        totals: &AccountBalance
+    ) -> StdResult<()> {
+        Self::save_account(storage, account, balance)?;
+
+        storage::save(storage, Self::total_key(), totals)
+    }
+
+    #[inline]
+    fn save_account(
+        storage: &mut dyn Storage,
+        account: &CanonicalAddr,
+        balance: &AccountBalance
+    ) -> StdResult<()> {
+        let key = Self::account_key(account);
+
+        if balance.is_empty() {
+            storage::remove(storage, key);
+
+            Ok(())
+        } else {
+            storage::save(storage, key, balance)
+        }
+    }
+
+    #[inline]
+    fn account_key(account: &CanonicalAddr) -> Vec<u8> {
+        [N::NAMESPACE, ACCOUNT_NS, account.as_slice()].concat()
+    }
+
+    #[inline]
+    fn total_key() -> Vec<u8> {
+        [N::NAMESPACE, TOTAL_NS].concat()
+    }
+}
+
+impl fmt::Display for Bucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Liquid => f.write_str("liquid"),
+            Self::Locked => f.write_str("locked"),
+            Self::Vesting => f.write_str("vesting")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cosmwasm_std::testing::MockStorage;
+
+    crate::namespace!(BalancesNs, b"balances");
+    const BALANCES: Balances<BalancesNs> = Balances::new();
+
+    fn balance(liquid: u128, locked: u128, vesting: u128) -> AccountBalance {
+        AccountBalance {
+            liquid: Uint128::new(liquid),
+            locked: Uint128::new(locked),
+            vesting: Uint128::new(vesting)
+        }
+    }
+
+    #[test]
+    fn balances_keep_totals_in_sync() {
+        let ref mut storage = MockStorage::new();
+        let alice = CanonicalAddr::from(b"alice".as_slice());
+        let bob = CanonicalAddr::from(b"bob".as_slice());
+
+        BALANCES.deposit(storage, &alice, Bucket::Vesting, Uint128::new(100)).unwrap();
+        BALANCES.deposit(storage, &bob, Bucket::Liquid, Uint128::new(50)).unwrap();
+        assert!(BALANCES.deposit(storage, &bob, Bucket::Liquid, Uint128::MAX).is_err());
+
+        BALANCES.move_between(storage, &alice, Bucket::Vesting, Bucket::Liquid, Uint128::new(30)).unwrap();
+        BALANCES.move_between(storage, &alice, Bucket::Liquid, Bucket::Locked, Uint128::new(10)).unwrap();
+
+        let err = BALANCES.move_between(storage, &alice, Bucket::Liquid, Bucket::Locked, Uint128::new(21))
+            .unwrap_err();
+        assert_eq!(err, StdError::generic_err("Insufficient liquid balance: available 20, required 21."));
+
+        BALANCES.transfer(storage, &alice, &bob, Bucket::Liquid, Uint128::new(20)).unwrap();
+        BALANCES.transfer(storage, &bob, &bob, Bucket::Liquid, Uint128::new(70)).unwrap();
+        assert!(BALANCES.transfer(storage, &bob, &alice, Bucket::Locked, Uint128::new(1)).is_err());
+
+        assert_eq!(BALANCES.load(storage, &alice).unwrap(), balance(0, 10, 70));
+        assert_eq!(BALANCES.load(storage, &bob).unwrap(), balance(70, 0, 0));
+        assert_eq!(BALANCES.totals(storage).unwrap(), balance(70, 10, 70));
+
+        BALANCES.withdraw(storage, &bob, Bucket::Liquid, Uint128::new(70)).unwrap();
+        assert!(BALANCES.withdraw(storage, &bob, Bucket::Liquid, Uint128::new(1)).is_err());
+        assert_eq!(BALANCES.totals(storage).unwrap().total().unwrap(), Uint128::new(80));
+
+        // Empty balances are removed.
+        assert!(!storage::exists(storage, Balances::<BalancesNs>::account_key(&bob)));
+    }
+}
@@ -0,0 +1,164 @@
+//! Converting between human readable decimal strings such as `"1.5"` and
+//! integer token amounts with a fixed number of decimals, i.e. for amounts
+//! that an admin enters in the init message or in configuration handlers.
+//!
+//! Parsing is strict and doesn't depend on the locale: only ASCII digits and
+//! a single `.` are accepted. Signs, whitespace, digit group separators,
+//! exponents and more fractional digits than the precision allows are all
+//! rejected rather than silently rounded. [`format`] produces the canonical
+//! form of an amount which [`parse`] always accepts.
+//!
+//! # Examples
+//!
+//! ```
+//! use fadroma::{tokens::decimal_str, cosmwasm_std::Uint128};
+//!
+//! let amount: Uint128 = decimal_str::parse("1.5", 6).unwrap();
+//! assert_eq!(amount, Uint128::new(1_500_000));
+//! assert_eq!(decimal_str::format(amount, 6), "1.5");
+//!
+//! assert!(decimal_str::parse::<Uint128>("1.0000005", 6).is_err());
+//! assert!(decimal_str::parse::<Uint128>("1,5", 6).is_err());
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use crate::cosmwasm_std::{StdResult, StdError};
+
+/// Parses `value` into an integer amount with `decimals` digits of precision.
+/// `T` is typically [`crate::cosmwasm_std::Uint128`] or [`crate::cosmwasm_std::Uint256`].
+///
+/// The integer part must not have leading zeros and the fractional part, if
+/// present, must have between one and `decimals` digits. Trailing zeros in the
+/// fractional part are allowed. Returns an error if the amount doesn't fit in `T`.
+pub fn parse<T>(value: &str, decimals: u8) -> StdResult<T>
+where
+    T: FromStr<Err = StdError>
+{
+    let (integer, fraction) = match value.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (value, None)
+    };
+
+    if !is_digits(integer) {
+        return Err(invalid(value, "the integer part must consist of ASCII digits only"));
+    }
+
+    if integer.len() > 1 && integer.starts_with('0') {
+        return Err(invalid(value, "the integer part must not have leading zeros"));
+    }
+
+    let fraction = fraction.unwrap_or_default();
+
+    if value.len() > integer.len() && !is_digits(fraction) {
+        return Err(invalid(value, "the fractional part must consist of ASCII digits only"));
+    }
+
+    if fraction.len() > decimals as usize {
+        return Err(invalid(
+            value,
+            &format!("more than {} fractional digits", decimals)
+        ));
+    }
+
+    let mut digits = String::with_capacity(integer.len() + decimals as usize);
+    digits.push_str(integer);
+    digits.push_str(fraction);
+    digits.push_str(&"0".repeat(decimals as usize - fraction.len()));
+
+    T::from_str(&digits).map_err(|_| invalid(value, "the amount is too large"))
+}
+
+/// Formats an integer `amount` with `decimals` digits of precision as a
+/// decimal string. Trailing zeros in the fractional part are omitted, as
+/// is the `.` if there is no fractional part.
+pub fn format(amount: impl fmt::Display, decimals: u8) -> String {
+    let digits = amount.to_string();
+    let decimals = decimals as usize;
+
+    if decimals == 0 {
+        return digits;
+    }
+
+    let digits = format!("{:0>width$}", digits, width = decimals + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+
+    if fraction.is_empty() {
+        integer.to_string()
+    } else {
+        format!("{}.{}", integer, fraction)
+    }
+}
+
+#[inline]
+fn is_digits(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|x| x.is_ascii_digit())
+}
+
+#[inline]
+fn invalid(value: &str, reason: &str) -> StdError {
+    StdError::parse_err("decimal amount", format!("Invalid amount \"{}\": {}.", value, reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cosmwasm_std::{Uint128, Uint256};
+
+    #[test]
+    fn parses_valid_amounts() {
+        let cases = [
+            ("0", 6, 0),
+            ("0.000001", 6, 1),
+            ("1", 6, 1_000_000),
+            ("1.5", 6, 1_500_000),
+            ("1.500000", 6, 1_500_000),
+            ("123456.789", 3, 123_456_789),
+            ("42", 0, 42)
+        ];
+
+        for (value, decimals, expected) in cases {
+            assert_eq!(parse::<Uint128>(value, decimals).unwrap(), Uint128::new(expected), "{}", value);
+        }
+
+        let amount: Uint256 = parse("340282366920938463463.374607431768211456", 18).unwrap();
+        assert_eq!(amount, Uint256::from(u128::MAX) + Uint256::one());
+    }
+
+    #[test]
+    fn rejects_invalid_amounts() {
+        let cases = [
+            "", ".", ".5", "5.", "1..5", "1.2.3", "+1", "-1", " 1", "1 ", "1,5",
+            "1_000", "1e6", "01", "00.5", "0x10", "1.0000001", "١", "NaN"
+        ];
+
+        for value in cases {
+            assert!(parse::<Uint128>(value, 6).is_err(), "{}", value);
+        }
+
+        assert!(parse::<Uint128>("1.5", 0).is_err());
+
+        let err = parse::<Uint128>("340282366920938463463.374607431768211456", 18).unwrap_err();
+        assert!(err.to_string().contains("the amount is too large"));
+    }
+
+    #[test]
+    fn formats_canonically() {
+        let cases = [
+            (0, 6, "0"),
+            (1, 6, "0.000001"),
+            (1_500_000, 6, "1.5"),
+            (10_000_000, 6, "10"),
+            (123_456_789, 3, "123456.789"),
+            (42, 0, "42")
+        ];
+
+        for (amount, decimals, expected) in cases {
+            let amount = Uint128::new(amount);
+
+            assert_eq!(format(amount, decimals), expected);
+            assert_eq!(parse::<Uint128>(expected, decimals).unwrap(), amount);
+        }
+    }
+}
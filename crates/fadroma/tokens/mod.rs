@@ -1,5 +1,8 @@
 use crate::cosmwasm_std::{StdResult, Uint256};
 
+pub mod balances;
+pub mod decimal_str;
+
 /// Convert between tokens with different decimals.
 ///
 /// # Arguments
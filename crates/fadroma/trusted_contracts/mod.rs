@@ -0,0 +1,311 @@
+//! Restricting handlers to calls from known sibling contracts, i.e. only
+//! letting the staking contract of a protocol mint rewards. You **MUST**
+//! implement [admin] in your contract if you want to use this module.
+//!
+//! The admin registers each trusted contract under a name together with its
+//! code hash using [`TrustedContracts::add_trusted_contract`]. Handlers then
+//! call [`assert_caller_is`] with either the name of the contract that they
+//! expect or a code hash that all of the expected contracts share, i.e. for
+//! pair contracts created by a factory.
+//!
+//! The code hash of the sender of a message can't be queried on Secret Network.
+//! A code hash is checked against the one that the admin registered for the
+//! sender, so only registered contracts can ever pass either check.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    self as fadroma,
+    admin::{self, Admin, Mode},
+    dsl::*,
+    prelude::*,
+    storage::{map::Map, iterable::{IterBudget, Page}}
+};
+
+crate::namespace!(pub TrustedContractsNs, b"Hq5vN2mRcW");
+
+crate::namespace!(pub TrustedCallersNs, b"t8ZkB4xPeL");
+const CALLERS: ItemSpace<String, TrustedCallersNs, TypedKey<CanonicalAddr>> = ItemSpace::new();
+
+/// The maximum number of contracts that [`DefaultImpl`] returns in a single page.
+pub const MAX_PAGE_SIZE: u32 = 50;
+
+/// Requires the admin component in order to check for admin.
+#[interface]
+pub trait TrustedContracts: Admin {
+    type Error: fmt::Display;
+
+    /// Trusts `contract` under `name`, replacing the contract that was previously trusted under it.
+    #[execute]
+    fn add_trusted_contract(
+        name: String,
+        contract: ContractLink<String>
+    ) -> Result<Response, <Self as TrustedContracts>::Error>;
+
+    #[execute]
+    fn remove_trusted_contract(name: String) -> Result<Response, <Self as TrustedContracts>::Error>;
+
+    #[query]
+    fn trusted_contract(
+        name: String
+    ) -> Result<Option<TrustedContract>, <Self as TrustedContracts>::Error>;
+
+    #[query]
+    fn trusted_contracts(
+        cursor: Option<u64>,
+        limit: u32
+    ) -> Result<Page<TrustedContract>, <Self as TrustedContracts>::Error>;
+}
+
+#[derive(
+    Serialize, Deserialize, FadromaSerialize, FadromaDeserialize,
+    JsonSchema, PartialEq, Debug, Clone
+)]
+pub struct TrustedContract {
+    pub name: String,
+    pub contract: ContractLink<Addr>
+}
+
+/// What the sender of a message is expected to be. See [`assert_caller_is`].
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Caller<'a> {
+    /// The contract trusted under the given name.
+    Named(&'a str),
+    /// Any trusted contract with the given code hash.
+    CodeHash(&'a str)
+}
+
+pub struct DefaultImpl;
+
+impl Admin for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn change_admin(mode: Option<Mode>) -> Result<Response, Self::Error> {
+        admin::DefaultImpl::change_admin(deps, env, info, mode)
+    }
+
+    #[query]
+    fn admin() -> Result<Option<Addr>, Self::Error> {
+        admin::DefaultImpl::admin(deps, env)
+    }
+}
+
+impl TrustedContracts for DefaultImpl {
+    type Error = StdError;
+
+    #[execute]
+    fn add_trusted_contract(
+        name: String,
+        contract: ContractLink<String>
+    ) -> StdResult<Response> {
+        add(deps, info, name.clone(), contract)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "add_trusted_contract")
+            .add_attribute("name", name)
+        )
+    }
+
+    #[execute]
+    fn remove_trusted_contract(name: String) -> StdResult<Response> {
+        remove(deps, info, &name)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "remove_trusted_contract")
+            .add_attribute("name", name)
+        )
+    }
+
+    #[query]
+    fn trusted_contract(name: String) -> StdResult<Option<TrustedContract>> {
+        load(deps.storage, &name)
+    }
+
+    #[query]
+    fn trusted_contracts(
+        cursor: Option<u64>,
+        limit: u32
+    ) -> StdResult<Page<TrustedContract>> {
+        let limit = limit.min(MAX_PAGE_SIZE) as u64;
+
+        contracts()
+            .values(deps.storage)?
+            .start_at(cursor.unwrap_or_default())
+            .read_page(limit, IterBudget::new(limit))
+    }
+}
+
+/// Trusts `contract` under `name`, replacing the contract that was previously trusted under it.
+/// A contract can only be trusted under a single name.
+/// Requires the admin component in order to check for admin.
+#[admin::require_admin]
+pub fn add(
+    deps: DepsMut,
+    info: MessageInfo,
+    name: String,
+    contract: ContractLink<String>
+) -> StdResult<()> {
+    if name.is_empty() {
+        return Err(StdError::generic_err("The name must not be empty."));
+    }
+
+    if contract.code_hash.is_empty() {
+        return Err(StdError::generic_err("The code hash must not be empty."));
+    }
+
+    let address = deps.api.addr_validate(&contract.address)?;
+    let canonical = address.as_str().canonize(deps.api)?;
+
+    if let Some(existing) = CALLERS.load(deps.storage, &canonical)? {
+        if existing != name {
+            return Err(StdError::generic_err(format!(
+                "The contract is already trusted under the name \"{}\".",
+                existing
+            )));
+        }
+    }
+
+    let previous = contracts().get(deps.storage, &name)?;
+
+    if let Some(previous) = previous {
+        let previous = previous.contract.address.as_str().canonize(deps.api)?;
+        CALLERS.remove(deps.storage, &previous);
+    }
+
+    CALLERS.save(deps.storage, &canonical, &name)?;
+    contracts().insert(deps.storage, &name, &TrustedContract {
+        name: name.clone(),
+        contract: ContractLink {
+            address,
+            code_hash: contract.code_hash
+        }
+    })?;
+
+    Ok(())
+}
+
+/// Stops trusting the contract under `name`. Returns `Err` if there isn't one.
+/// Requires the admin component in order to check for admin.
+#[admin::require_admin]
+pub fn remove(deps: DepsMut, info: MessageInfo, name: &str) -> StdResult<()> {
+    let name = name.to_string();
+
+    let Some(trusted) = contracts().get(deps.storage, &name)? else {
+        return Err(StdError::generic_err("No contract is trusted under that name."));
+    };
+
+    let address = trusted.contract.address.as_str().canonize(deps.api)?;
+
+    CALLERS.remove(deps.storage, &address);
+    contracts().remove(deps.storage, &name)?;
+
+    Ok(())
+}
+
+#[inline]
+pub fn load(storage: &dyn Storage, name: &str) -> StdResult<Option<TrustedContract>> {
+    contracts().get(storage, &name.to_string())
+}
+
+/// Returns `Err` if the sender isn't a trusted contract. Otherwise, returns its entry.
+pub fn assert_trusted_caller(deps: Deps, info: &MessageInfo) -> StdResult<TrustedContract> {
+    let sender = info.sender.as_str().canonize(deps.api)?;
+
+    let trusted = match CALLERS.load(deps.storage, &sender)? {
+        Some(name) => load(deps.storage, &name)?,
+        None => None
+    };
+
+    trusted.ok_or_else(|| StdError::generic_err("Unauthorized"))
+}
+
+/// Returns `Err` if the sender isn't the expected trusted contract. Otherwise, returns its entry.
+/// Code hashes are compared case-insensitively.
+pub fn assert_caller_is(
+    deps: Deps,
+    info: &MessageInfo,
+    caller: Caller
+) -> StdResult<TrustedContract> {
+    let trusted = assert_trusted_caller(deps, info)?;
+
+    let is_expected = match caller {
+        Caller::Named(name) => trusted.name == name,
+        Caller::CodeHash(code_hash) => trusted.contract.code_hash.eq_ignore_ascii_case(code_hash)
+    };
+
+    if !is_expected {
+        return Err(StdError::generic_err("Unauthorized"));
+    }
+
+    Ok(trusted)
+}
+
+#[inline]
+fn contracts<'a>() -> Map<TypedKey<'a, String>, TrustedContract, TrustedContractsNs> {
+    Map::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+
+    #[test]
+    fn only_trusted_contracts_pass() {
+        let ref mut deps = mock_dependencies();
+        let env = mock_env();
+
+        admin::init(deps.as_mut(), None, &mock_info("admin", &[])).unwrap();
+
+        let link = |address: &str, code_hash: &str| ContractLink {
+            address: address.to_string(),
+            code_hash: code_hash.to_string()
+        };
+        let add = |deps: DepsMut, sender: &str, name: &str, contract: ContractLink<String>|
+            DefaultImpl::add_trusted_contract(deps, mock_env(), mock_info(sender, &[]), name.into(), contract);
+
+        assert!(add(deps.as_mut(), "user", "staking", link("staking", "hash")).is_err());
+
+        add(deps.as_mut(), "admin", "staking", link("staking", "ABC")).unwrap();
+        add(deps.as_mut(), "admin", "pair_a", link("pair_a", "def")).unwrap();
+        add(deps.as_mut(), "admin", "pair_b", link("pair_b", "def")).unwrap();
+
+        let err = add(deps.as_mut(), "admin", "other", link("staking", "abc")).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("The contract is already trusted under the name \"staking\".")
+        );
+
+        let check = |deps: Deps, sender: &str, caller: Caller|
+            assert_caller_is(deps, &mock_info(sender, &[]), caller).map(|x| x.name);
+
+        assert_eq!(check(deps.as_ref(), "staking", Caller::Named("staking")).unwrap(), "staking");
+        assert_eq!(check(deps.as_ref(), "staking", Caller::CodeHash("abc")).unwrap(), "staking");
+        assert_eq!(check(deps.as_ref(), "pair_b", Caller::CodeHash("def")).unwrap(), "pair_b");
+
+        assert!(check(deps.as_ref(), "pair_a", Caller::Named("staking")).is_err());
+        assert!(check(deps.as_ref(), "user", Caller::CodeHash("def")).is_err());
+
+        // Replacing the contract under a name stops trusting the previous one.
+        add(deps.as_mut(), "admin", "staking", link("staking_v2", "abc")).unwrap();
+
+        assert!(check(deps.as_ref(), "staking", Caller::Named("staking")).is_err());
+        assert!(check(deps.as_ref(), "staking_v2", Caller::Named("staking")).is_ok());
+
+        let remove = |deps: DepsMut, sender: &str, name: &str|
+            DefaultImpl::remove_trusted_contract(deps, mock_env(), mock_info(sender, &[]), name.into());
+
+        assert!(remove(deps.as_mut(), "user", "pair_a").is_err());
+        remove(deps.as_mut(), "admin", "pair_a").unwrap();
+        assert!(remove(deps.as_mut(), "admin", "pair_a").is_err());
+
+        assert!(assert_trusted_caller(deps.as_ref(), &mock_info("pair_a", &[])).is_err());
+        assert_eq!(DefaultImpl::trusted_contract(deps.as_ref(), env.clone(), "pair_a".into()).unwrap(), None);
+
+        let page = DefaultImpl::trusted_contracts(deps.as_ref(), env, None, 10).unwrap();
+        assert_eq!(page.items.iter().map(|x| x.name.as_str()).collect::<Vec<_>>(), ["staking", "pair_b"]);
+    }
+}
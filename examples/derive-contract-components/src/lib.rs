@@ -1,10 +1,14 @@
 use fadroma::dsl::*;
 
-#[contract]
+// Pad all responses to a multiple of 256 bytes so that their
+// size doesn't leak any information about the data they contain
+// and reject any messages with fields that the contract doesn't expect.
+// Messages from JavaScript clients may also use camelCase names.
+#[contract(pad, deny_unknown_fields, accept_camel_case)]
 pub mod contract {
     use fadroma::{
         admin::{self, Admin, Mode},
-        killswitch::{self, Killswitch, ContractStatus},
+        killswitch::{self, Killswitch, ContractStatus, Restriction},
         scrt::vk::auth::{self, VkAuth},
         prelude::*
     };
@@ -31,10 +35,16 @@ pub mod contract {
             // However, if the contract has been set to the "migrating" status,
             // Even the admin cannot reverse that anymore.
             if operational.is_err() && !matches!(msg, ExecuteMsg::SetStatus { .. }) {
-                Err(operational.unwrap_err())
-            } else {
-                Ok(())
+                return Err(operational.unwrap_err());
             }
+
+            // Individual messages can also be paused by name while the rest
+            // of the contract remains operational.
+            if !matches!(msg, ExecuteMsg::SetStatus { .. } | ExecuteMsg::SetRestriction { .. }) {
+                killswitch::assert_not_restricted(deps.as_ref(), msg.name())?;
+            }
+
+            Ok(())
         }
     
         #[execute]
@@ -46,14 +56,38 @@ pub mod contract {
             Ok(Response::default())
         }
     
+        /// Sets the number of the sender.
         #[execute]
-        pub fn set_number(value: u64) -> Result<Response, StdError> {
+        pub fn set_number(
+            /// Only visible to the sender.
+            value: u64
+        ) -> Result<Response, StdError> {
             let key = info.sender.canonize(deps.api)?;
             STATE.save(deps.storage, &key, &value)?;
     
             Ok(Response::default())
         }
     
+        /// Fails if the number of the sender isn't `expected`. Lets other
+        /// contracts check it as part of a transaction without a viewing key.
+        #[execute(read_only)]
+        pub fn verify_number(expected: u64) -> Result<Response, StdError> {
+            let key = info.sender.canonize(deps.api)?;
+            let value = STATE.load_or_default(deps.storage, &key)?;
+
+            if value != expected {
+                return Err(StdError::generic_err("The number doesn't match."));
+            }
+
+            Ok(Response::default())
+        }
+
+        // The old name of "set_number" which is still accepted so
+        // that existing clients don't break.
+        #[execute]
+        #[deprecated_variant(since = "0.2.0", replaced_by = "set_number")]
+        pub fn set_value(value: u64) -> Result<Response, StdError> { }
+
         #[query]
         pub fn value(address: String, vk: String) -> Result<u64, StdError> {
             let address = address.as_str().canonize(deps.api)?;
@@ -69,9 +103,18 @@ pub mod contract {
         fn set_status(
             status: ContractStatus<Addr>,
         ) -> Result<Response, <Self as Killswitch>::Error> { }
+
+        #[execute]
+        fn set_restriction(
+            msg: String,
+            reason: Option<String>
+        ) -> Result<Response, <Self as Killswitch>::Error> { }
     
         #[query]
         fn status() -> Result<ContractStatus<Addr>, <Self as Killswitch>::Error> { }
+
+        #[query]
+        fn restrictions() -> Result<Vec<Restriction>, <Self as Killswitch>::Error> { }
     }
 
     #[auto_impl(admin::DefaultImpl)]
@@ -97,7 +140,11 @@ pub mod contract {
 mod tests {
     use fadroma::{
         admin::Mode,
-        cosmwasm_std::{Addr, StdError},
+        core::schema::assert_denies_unknown_fields,
+        cosmwasm_std::{
+            Addr, StdError, from_binary, from_slice,
+            testing::{mock_dependencies, mock_env, mock_info}
+        },
         prelude::ContractLink,
         killswitch,
         ensemble::{ContractEnsemble, MockEnv, EnsembleResult, ExecuteResponse}
@@ -189,6 +236,133 @@ mod tests {
         );
     }
 
+    #[test]
+    fn restrictions() {
+        let mut suite = TestSuite::new();
+        let user = "user";
+
+        let err = suite.execute(
+            "rando",
+            &ExecuteMsg::SetRestriction {
+                msg: "set_number".into(),
+                reason: Some("".into())
+            }
+        ).unwrap_err();
+
+        assert_eq!(err.unwrap_contract_error().to_string(), "Generic error: Unauthorized");
+
+        suite.execute(
+            ADMIN,
+            &ExecuteMsg::SetRestriction {
+                msg: "set_number".into(),
+                reason: Some("Test".into())
+            }
+        ).unwrap();
+
+        let restrictions = suite.query::<Vec<killswitch::Restriction>>(
+            &QueryMsg::Restrictions { }
+        ).unwrap();
+
+        assert_eq!(restrictions, vec![killswitch::Restriction {
+            msg: "set_number".into(),
+            reason: "Test".into()
+        }]);
+
+        // Only the restricted message is paused
+        let err = suite.execute(
+            user,
+            &ExecuteMsg::SetNumber { value: 10 }
+        ).unwrap_err();
+
+        assert_eq!(
+            err.unwrap_contract_error().to_string(),
+            "Generic error: set_number is paused\nReason: Test"
+        );
+
+        suite.execute(
+            user,
+            &ExecuteMsg::SetViewingKey {
+                key: "key".into(),
+                padding: None
+            }
+        ).unwrap();
+
+        suite.execute(
+            ADMIN,
+            &ExecuteMsg::SetRestriction {
+                msg: "set_number".into(),
+                reason: None
+            }
+        ).unwrap();
+
+        suite.execute(user, &ExecuteMsg::SetNumber { value: 10 }).unwrap();
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        assert_denies_unknown_fields::<InstantiateMsg>();
+        assert_denies_unknown_fields::<ExecuteMsg>();
+        assert_denies_unknown_fields::<QueryMsg>();
+
+        let msg = r#"{ "set_number": { "value": 1, "extra": true } }"#;
+        assert!(from_slice::<ExecuteMsg>(msg.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn camel_case_is_accepted() {
+        let msg = r#"{ "setNumber": { "value": 1 } }"#;
+        let msg = from_slice::<ExecuteMsg>(msg.as_bytes()).unwrap();
+        assert!(matches!(msg, ExecuteMsg::SetNumber { value: 1 }));
+
+        let msg = r#"{ "set_viewing_key": { "key": "key" } }"#;
+        assert!(from_slice::<ExecuteMsg>(msg.as_bytes()).is_ok());
+
+        // Always emitted as snake_case.
+        let json = fadroma::cosmwasm_std::to_vec(&ExecuteMsg::SetNumber { value: 1 }).unwrap();
+        assert_eq!(json, br#"{"set_number":{"value":1}}"#);
+    }
+
+    #[test]
+    fn deprecated_variant() {
+        let mut suite = TestSuite::new();
+        let user = "user";
+        let key = "valid_key";
+
+        assert_eq!(ExecuteMsg::SetValue { value: 1 }.name(), "set_number");
+
+        suite.execute(
+            user,
+            &ExecuteMsg::SetViewingKey {
+                key: key.into(),
+                padding: None
+            }
+        ).unwrap();
+
+        suite.execute(user, &ExecuteMsg::SetValue { value: 7 }).unwrap();
+
+        let value = suite.query::<u64>(&QueryMsg::Value {
+            address: user.into(),
+            vk: key.into()
+        }).unwrap();
+
+        assert_eq!(value, 7);
+
+        // Pausing the new message also pauses its deprecated variant.
+        suite.execute(
+            ADMIN,
+            &ExecuteMsg::SetRestriction {
+                msg: "set_number".into(),
+                reason: Some("Test".into())
+            }
+        ).unwrap();
+
+        let err = suite.execute(user, &ExecuteMsg::SetValue { value: 8 }).unwrap_err();
+        assert_eq!(
+            err.unwrap_contract_error().to_string(),
+            "Generic error: set_number is paused\nReason: Test"
+        );
+    }
+
     #[test]
     fn viewing_key() {
         let mut suite = TestSuite::new();
@@ -271,6 +445,27 @@ mod tests {
         assert_eq!(value, 0);
     }
 
+    #[test]
+    fn verify_number() {
+        let mut suite = TestSuite::new();
+
+        assert_eq!(ExecuteMsg::READ_ONLY, ["verify_number"]);
+        suite.ensemble.set_read_only(0, ExecuteMsg::READ_ONLY).unwrap();
+
+        suite.execute("user", &ExecuteMsg::SetNumber { value: 10 }).unwrap();
+        suite.execute("user", &ExecuteMsg::VerifyNumber { expected: 10 }).unwrap();
+
+        let err = suite.execute(
+            "user",
+            &ExecuteMsg::VerifyNumber { expected: 5 }
+        ).unwrap_err();
+
+        assert_eq!(
+            err.unwrap_contract_error().to_string(),
+            "Generic error: The number doesn't match."
+        );
+    }
+
     #[test]
     fn change_admin() {
         let mut suite = TestSuite::new();
@@ -306,6 +501,32 @@ mod tests {
         assert_eq!(admin, new_admin);
     }
 
+    #[test]
+    fn responses_are_padded() {
+        let ref mut deps = mock_dependencies();
+
+        contract::instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADMIN, &[]),
+            InstantiateMsg { admin: None }
+        ).unwrap();
+
+        let resp = contract::query(deps.as_ref(), mock_env(), QueryMsg::Admin { }).unwrap();
+        assert_eq!(resp.len(), 256);
+
+        let admin: Option<Addr> = from_binary(&resp).unwrap();
+        assert_eq!(admin.unwrap(), ADMIN);
+
+        let resp = contract::execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("user", &[]),
+            ExecuteMsg::SetNumber { value: 10 }
+        ).unwrap();
+        assert_eq!(resp.data, None);
+    }
+
     impl TestSuite {
         fn new() -> Self {
             let mut ensemble = ContractEnsemble::new();
@@ -4,7 +4,9 @@ pub mod interface;
 
 pub use contract::*;
 
-#[contract]
+// Reject any messages larger than 1KB or nested deeper than 8
+// levels before they even get deserialized.
+#[contract(max_msg_size = 1024, max_msg_depth = 8)]
 mod contract {
     use super::*;
     use interface::Counter;
@@ -68,3 +70,71 @@ mod contract {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use fadroma::cosmwasm_std::{
+        SystemResult, ContractResult, WasmQuery, QuerierWrapper,
+        from_binary, to_binary, coin, testing::mock_dependencies
+    };
+    use fadroma::schemars::{schema_for, schema::Schema};
+    use super::*;
+    use interface::ICounter;
+
+    #[test]
+    fn client() {
+        let counter = ICounter::new(Addr::unchecked("counter"), "hash".into());
+
+        let msg = counter.clone()
+            .add_funds(coin(100, "uscrt"))
+            .add(5)
+            .unwrap();
+
+        let CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, code_hash, msg, funds }) = msg else {
+            panic!("Expecting a WasmMsg::Execute");
+        };
+
+        assert_eq!(contract_addr, "counter");
+        assert_eq!(code_hash, "hash");
+        assert_eq!(funds, vec![coin(100, "uscrt")]);
+        assert!(matches!(from_binary(&msg).unwrap(), ExecuteMsg::Add { value: 5 }));
+
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(|query| {
+            let WasmQuery::Smart { contract_addr, msg, .. } = query else {
+                panic!("Expecting a WasmQuery::Smart");
+            };
+
+            assert_eq!(contract_addr, "counter");
+            assert!(matches!(from_binary(msg).unwrap(), QueryMsg::Value { }));
+
+            SystemResult::Ok(ContractResult::Ok(to_binary(&42u64).unwrap()))
+        });
+
+        let value = counter.value(QuerierWrapper::new(&deps.querier)).unwrap();
+        assert_eq!(value, 42);
+    }
+
+    #[test]
+    fn schema_has_docs() {
+        let description = |schema: &Schema| match schema {
+            Schema::Object(x) => x.metadata.as_ref().and_then(|x| x.description.clone()),
+            Schema::Bool(_) => None
+        };
+        let property = |schema: &Schema, name: &str| match schema {
+            Schema::Object(x) => x.object.as_ref().unwrap().properties[name].clone(),
+            Schema::Bool(_) => panic!("Expecting an object schema")
+        };
+
+        let schema = schema_for!(interface::ExecuteMsg);
+        let add = &schema.schema.subschemas.unwrap().one_of.unwrap()[0];
+        assert_eq!(description(add).unwrap(), "Adds to the current value of the counter.");
+
+        let value = property(&property(add, "add"), "value");
+        assert_eq!(description(&value).unwrap(), "The amount to add.");
+
+        let schema = schema_for!(interface::QueryMsg);
+        let value = &schema.schema.subschemas.unwrap().one_of.unwrap()[0];
+        assert_eq!(description(value).unwrap(), "Returns the current value of the counter.");
+    }
+}
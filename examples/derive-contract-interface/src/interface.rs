@@ -4,16 +4,22 @@ use fadroma::{prelude::*, dsl::*};
 // generated here. This allows you to have just the definitions
 // in a separate crate so that they can be imported in multiple
 // contracts without causing cyclical crate references.
+// The "client" meta argument also generates an "ICounter"
+// struct which other contracts can use to call this one.
 
-#[interface]
+#[interface(client)]
 pub trait Counter {
     type Error: std::fmt::Display;
 
     #[init]
     fn new(initial_value: u64) -> Result<Response, Self::Error>;
 
+    /// Adds to the current value of the counter.
     #[execute]
-    fn add(value: u64) -> Result<Response, Self::Error>;
+    fn add(
+        /// The amount to add.
+        value: u64
+    ) -> Result<Response, Self::Error>;
 
     #[execute]
     fn sub(value: u64) -> Result<Response, Self::Error>;
@@ -24,6 +30,7 @@ pub trait Counter {
     #[execute]
     fn div(value: u64) -> Result<Response, Self::Error>;
 
+    /// Returns the current value of the counter.
     #[query]
     fn value() -> Result<u64, Self::Error>;
 }
@@ -80,6 +80,14 @@ pub fn execute(
                     env,
                     info,
                     status
+                ),
+            killswitch::ExecuteMsg::SetRestriction { msg, reason } =>
+                killswitch::DefaultImpl::set_restriction(
+                    deps,
+                    env,
+                    info,
+                    msg,
+                    reason
                 )
         }
     }
@@ -109,6 +117,11 @@ pub fn query(
             killswitch::QueryMsg::Status {  } => {
                 let result = killswitch::DefaultImpl::status(deps, env)?;
 
+                to_binary(&result)
+            }
+            killswitch::QueryMsg::Restrictions {  } => {
+                let result = killswitch::DefaultImpl::restrictions(deps, env)?;
+
                 to_binary(&result)
             }
         }
@@ -57,6 +57,8 @@ export abstract class BuildLocal extends Builder {
   workspace?: string
   /** Whether to skip any `git fetch` calls in the build script. */
   noFetch:    boolean = false
+  /** Whether to skip checking the imports, exports and instructions of the artifacts. */
+  noAudit:    boolean = false
   /** Name of directory where build artifacts are collected. */
   outputDir:  OpaqueDirectory
   /** Version of Rust toolchain to use. */
@@ -76,6 +78,7 @@ export abstract class BuildLocal extends Builder {
     super()
     this.workspace = options.workspace ?? this.workspace
     this.noFetch   = options.noFetch   ?? this.noFetch
+    this.noAudit   = options.noAudit   ?? this.noAudit
     this.toolchain = options.toolchain ?? this.toolchain
     this.verbose   = options.verbose   ?? this.verbose
     this.quiet     = options.quiet     ?? this.quiet
@@ -353,6 +356,7 @@ export class BuildContainer extends BuildLocal {
       _GIT_SUBDIR: gitSubdir,
       _SUBDIR:     subdir,
       _NO_FETCH:   String(this.noFetch),
+      _NO_AUDIT:   String(this.noAudit),
       _VERBOSE:    String(this.verbose),
 
       // Variables used by the tools invoked by the build script
@@ -480,6 +484,7 @@ export class BuildRaw extends BuildLocal {
       _OUTPUT:    $(workspace).in('wasm').path,
       _REGISTRY:  '',
       _TOOLCHAIN: this.toolchain,
+      _NO_AUDIT:  this.noAudit,
     }
     if ((revision ?? HEAD) !== HEAD) {
       const gitDir = this.getGitDir(source)
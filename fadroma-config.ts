@@ -112,6 +112,10 @@ export default class Config extends ConnectConfig {
     /** Don't run "git fetch" during build. */
     noFetch: this.getFlag(
       'FADROMA_NO_FETCH', ()=>false),
+    /** Don't fail the build if an artifact imports host functions that the chain
+      * doesn't provide or contains floating point instructions. */
+    noAudit: this.getFlag(
+      'FADROMA_BUILD_NO_AUDIT', ()=>false),
     /** Whether to bypass Docker and use the toolchain from the environment. */
     raw: this.getFlag(
       'FADROMA_BUILD_RAW', ()=>false),